@@ -0,0 +1,114 @@
+use crate::tokenizer::*;
+use crate::warning::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+//a suggested edit: replaces the source in span, which is empty for an insertion
+#[derive(Debug, Clone)]
+pub struct FixIt {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl fmt::Display for FixIt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.span.begin == self.span.end {
+            write!(
+                f,
+                "{}: fix-it: insert '{}'",
+                self.span.begin, self.replacement
+            )
+        } else {
+            write!(
+                f,
+                "{}: fix-it: replace with '{}'",
+                self.span.begin, self.replacement
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub fixits: Vec<FixIt>,
+    pub warning: Option<Warning>, // the option controlling a warning, or the error it became
+}
+
+impl Diagnostic {
+    pub fn error(message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            span,
+            fixits: Vec::new(),
+            warning: None,
+        }
+    }
+
+    pub fn warning(message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            message,
+            span,
+            fixits: Vec::new(),
+            warning: None,
+        }
+    }
+
+    //a warning which -W options and #pragma GCC diagnostic can disable or make an error
+    pub fn named_warning(warning: Warning, message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            warning: Some(warning),
+            ..Diagnostic::warning(message, span)
+        }
+    }
+
+    //a note follows the diagnostic it explains, such as one pointing at a previous declaration
+    pub fn note(message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Note,
+            message,
+            span,
+            fixits: Vec::new(),
+            warning: None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}",
+            self.span.begin, self.severity, self.message
+        )?;
+        match (self.severity, self.warning) {
+            (Severity::Error, Some(warning)) => write!(f, " [-Werror={}]", warning.name())?,
+            (_, Some(warning)) => write!(f, " [-W{}]", warning.name())?,
+            (_, None) => {}
+        }
+        for fixit in &self.fixits {
+            write!(f, "\n{}", fixit)?;
+        }
+        Ok(())
+    }
+}
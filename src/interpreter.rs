@@ -0,0 +1,186 @@
+pub mod builtin;
+pub mod check;
+pub mod expression;
+pub mod memory;
+pub mod statement;
+mod util;
+pub mod value;
+use crate::diagnostic::*;
+use crate::interpreter::memory::*;
+use crate::interpreter::statement::*;
+use crate::interpreter::util::*;
+use crate::interpreter::value::*;
+use crate::options::*;
+use crate::semantic::external_declaration::*;
+use crate::semantic::symbol::*;
+use crate::semantic::TypedAST;
+use crate::tokenizer::*;
+use std::io::Write;
+use std::thread;
+
+//the stack of the thread the interpreter runs on, which MAX_CALL_DEPTH nested calls fit in
+const STACK_SIZE: usize = 1 << 30;
+
+//executes the program from main over the typed tree, writing what it prints to output, and
+//returns the status it exits with. an error is a program whose behavior the interpreter
+//cannot go on with, such as an access out of the bounds of an object or a call of a
+//function which is neither defined nor a builtin. the checked execution also stops at the
+//undefined behavior the unchecked one goes on with, such as signed overflow.
+pub fn interpret(
+    typed: &TypedAST,
+    execution: Execution,
+    output: &mut (dyn Write + Send),
+) -> Result<i32, Diagnostic> {
+    thread::scope(|scope| {
+        let interpreter = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                let mut context = InterpreterContext::new(&typed.types, &typed.symbols, output);
+                context.checked = execution == Execution::Checked;
+                let result = run(&mut context, &typed.root);
+                let _ = context.output.flush();
+                result
+            })
+            .expect("cannot start the interpreter thread");
+        match interpreter.join().unwrap() {
+            Ok(status) | Err(Interruption::Exit(status)) => Ok(status),
+            Err(Interruption::Error(diagnostic)) => Err(diagnostic),
+        }
+    })
+}
+
+//the objects with static storage duration exist and are initialized before main is called.
+//main is given an argc of 1 and an argv holding the program name if it takes them.
+fn run<'a>(
+    context: &mut InterpreterContext<'a>,
+    root: &'a [TypedExternalDeclaration<'a>],
+) -> RuntimeResult<i32> {
+    let mut declarations = Vec::new();
+    for external_declaration in root {
+        match external_declaration {
+            TypedExternalDeclaration::FunctionDefinition(definition) => {
+                context.functions.insert(definition.symbol, definition);
+            }
+            TypedExternalDeclaration::Declaration(external) => declarations.extend(external),
+        }
+    }
+    for declaration in &declarations {
+        let symbol = symbol(context, declaration.symbol);
+        if symbol.kind != SymbolKind::Object
+            || symbol.definition == Definition::Declaration
+            || context.globals.contains_key(&declaration.symbol)
+        {
+            continue;
+        }
+        let size = size_of(context, symbol.ty, symbol.span)?;
+        let align = object_align(context.types, symbol);
+        let address = context.memory.allocate(AllocationKind::Static, size, align);
+        context.globals.insert(declaration.symbol, address);
+    }
+    for declaration in &declarations {
+        if let Some(initializer) = &declaration.initializer {
+            let place = Place::object(context.globals[&declaration.symbol]);
+            let ty = symbol(context, declaration.symbol).ty;
+            initialize(context, place, ty, initializer)?;
+        }
+    }
+
+    let main = context
+        .functions
+        .keys()
+        .copied()
+        .find(|id| symbol(context, *id).name == "main");
+    let main = match main {
+        Some(main) => main,
+        None => {
+            let location = Location { line: 1, column: 1 };
+            let span = Span {
+                begin: location,
+                end: location,
+            };
+            return error("undefined reference to 'main'.".to_string(), span);
+        }
+    };
+    let span = symbol(context, main).span;
+    let mut arguments = Vec::new();
+    if context.functions[&main].parameters.len() >= 2 {
+        let name = context.memory.allocate_string(b"a.out\0".to_vec());
+        let argv = context.memory.allocate(AllocationKind::Static, 16, 8);
+        if let Err(error) = context.memory.write(argv, &name.to_le_bytes()) {
+            return memory_error(error, "write", span);
+        }
+        arguments = vec![Value::Integer(1), Value::Integer(i128::from(argv))];
+    }
+
+    //falling off the end of main returns 0
+    let status = match call_function(context, main, arguments, span)? {
+        Value::Void => 0,
+        status => status.integer() as i32,
+    };
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    //the status the program exits with and what it prints
+    fn run_source(source: &str) -> (i32, String) {
+        let source = format!("int printf(const char *, ...); void exit(int);\n{}", source);
+        let (typed, diagnostics) = analyze_source(&source, Standard::C11);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let mut output = Vec::new();
+        let status = match interpret(&typed, Execution::Unchecked, &mut output) {
+            Ok(status) => status,
+            Err(diagnostic) => panic!("{:?}", diagnostic),
+        };
+        (status, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn calls_loops_and_objects() {
+        let source = "struct P { int x, y; };
+            int factorial(int n) { return n <= 1 ? 1 : n * factorial(n - 1); }
+            int main(void) {
+                int a[4] = {3, 1, 4, 1};
+                struct P p = {.y = 2};
+                int *q = a, sum = 0;
+                for (int i = 0; i < 4; i++) sum += *q++;
+                p.x = sum;
+                printf(\"%d %d %d\\n\", factorial(5), p.x, p.y);
+                return a[2];
+            }";
+        assert_eq!(run_source(source), (4, "120 9 2\n".to_string()));
+    }
+
+    #[test]
+    fn statics_are_initialized_before_main() {
+        let source = "struct P { int a, b; };
+            struct P *gp = &(struct P){5, 6};
+            int *ip = (int[]){1, 2, 3};
+            int counter(void) { static int n; return ++n; }
+            int main(void) {
+                counter();
+                printf(\"%d %d %d %d\\n\", gp->a, gp->b, ip[2], counter());
+                exit(7);
+            }";
+        assert_eq!(run_source(source), (7, "5 6 3 2\n".to_string()));
+    }
+
+    #[test]
+    fn arithmetic_converts_like_the_target() {
+        let source = "int main(void) {
+                char c = 100; c += 200;
+                unsigned char u = 250; u += 10;
+                double h = 5; h /= 2;
+                int i = 7; i *= h;
+                unsigned x = 5; x -= 10;
+                short s = 1; s <<= 20;
+                printf(\"%d %d %d %u %d %d\\n\", c, u, i, x, s, '\\xff');
+                return 0;
+            }";
+        let expected = "44 4 17 4294967291 0 -1\n";
+        assert_eq!(run_source(source), (0, expected.to_string()));
+    }
+}
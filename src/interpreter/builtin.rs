@@ -0,0 +1,428 @@
+use crate::interpreter::memory::*;
+use crate::interpreter::util::*;
+use crate::interpreter::value::*;
+use crate::tokenizer::*;
+use std::convert::TryFrom;
+
+//the largest object malloc gives, a larger request returns a null pointer
+const MAX_ALLOCATION: i128 = 1 << 30;
+
+//the functions of the C library the interpreter provides to a program which declares them
+//without defining them. None if there is no builtin of the name.
+pub fn builtin(
+    context: &mut InterpreterContext,
+    name: &str,
+    arguments: &[Value],
+    span: Span,
+) -> RuntimeResult<Option<Value>> {
+    let argument = |index: usize| match arguments.get(index) {
+        Some(argument) => Ok(argument.clone()),
+        None => error(format!("too few arguments to function '{}'.", name), span),
+    };
+    let result = match name {
+        "printf" => {
+            let count = printf(context, &argument(0)?, &arguments[1..], span)?;
+            Value::Integer(count as i128)
+        }
+        "puts" => {
+            let mut bytes = read_string(context, argument(0)?.address(), span)?;
+            bytes.push(b'\n');
+            emit(context, &bytes, span)?;
+            Value::Integer(bytes.len() as i128)
+        }
+        "putchar" => {
+            let character = argument(0)?.integer() as u8;
+            emit(context, &[character], span)?;
+            Value::Integer(i128::from(character))
+        }
+        "malloc" => malloc(context, argument(0)?.integer()),
+        "calloc" => calloc(
+            context,
+            argument(0)?.integer() * argument(1)?.integer(),
+            span,
+        )?,
+        "free" => {
+            free(context, argument(0)?.address(), span)?;
+            Value::Void
+        }
+        "memcpy" => {
+            let destination = argument(0)?.address();
+            let source = argument(1)?.address();
+            let size = argument(2)?.integer() as u64;
+            if size != 0 {
+                if let Err(error) = context.memory.read(source, size) {
+                    return memory_error(error, "read", span);
+                }
+                if let Err(error) = context.memory.copy(destination, source, size) {
+                    return memory_error(error, "write", span);
+                }
+            }
+            Value::Integer(i128::from(destination))
+        }
+        "memset" => {
+            let destination = argument(0)?.address();
+            let byte = argument(1)?.integer() as u8;
+            let size = argument(2)?.integer() as usize;
+            if size != 0 {
+                write(context, destination, &vec![byte; size], span)?;
+            }
+            Value::Integer(i128::from(destination))
+        }
+        "strlen" => {
+            let length = read_string(context, argument(0)?.address(), span)?.len();
+            Value::Integer(length as i128)
+        }
+        "exit" => return Err(Interruption::Exit(argument(0)?.integer() as i32)),
+        "abort" => return error("abort was called.".to_string(), span),
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+fn emit(context: &mut InterpreterContext, bytes: &[u8], span: Span) -> RuntimeResult<()> {
+    match context.output.write_all(bytes) {
+        Ok(()) => Ok(()),
+        Err(io_error) => error(format!("cannot write the output: {}.", io_error), span),
+    }
+}
+
+fn write(
+    context: &mut InterpreterContext,
+    address: u64,
+    bytes: &[u8],
+    span: Span,
+) -> RuntimeResult<()> {
+    match context.memory.write(address, bytes) {
+        Ok(()) => Ok(()),
+        Err(error) => memory_error(error, "write", span),
+    }
+}
+
+fn read_string(context: &InterpreterContext, address: u64, span: Span) -> RuntimeResult<Vec<u8>> {
+    match context.memory.read_string(address) {
+        Ok(bytes) => Ok(bytes),
+        Err(error) => memory_error(error, "read", span),
+    }
+}
+
+fn malloc(context: &mut InterpreterContext, size: i128) -> Value {
+    if !(0..=MAX_ALLOCATION).contains(&size) {
+        return Value::Integer(0);
+    }
+    let address = context
+        .memory
+        .allocate(AllocationKind::Heap, size as u64, 16);
+    Value::Integer(i128::from(address))
+}
+
+//the bytes of calloc are initialized, unlike those of malloc
+fn calloc(context: &mut InterpreterContext, size: i128, span: Span) -> RuntimeResult<Value> {
+    let result = malloc(context, size);
+    if result.address() != 0 {
+        write(context, result.address(), &vec![0; size as usize], span)?;
+    }
+    Ok(result)
+}
+
+//freeing a null pointer does nothing
+fn free(context: &mut InterpreterContext, address: u64, span: Span) -> RuntimeResult<()> {
+    if address == 0 {
+        return Ok(());
+    }
+    let message = match context.memory.allocation(address) {
+        Some(allocation) if allocation.kind == AllocationKind::Heap && split(address).1 == 0 => {
+            if allocation.live {
+                context.memory.release(address);
+                return Ok(());
+            }
+            "double free of a pointer."
+        }
+        _ => "free of a pointer which was not returned by malloc.",
+    };
+    error(message.to_string(), span)
+}
+
+//a conversion specification of printf, %[flags][width][.precision][length]conversion
+#[derive(Debug, Default)]
+struct Specification {
+    left: bool,      // -
+    plus: bool,      // +
+    space: bool,     // space
+    alternate: bool, // #
+    zero: bool,      // 0
+    width: usize,
+    precision: Option<usize>,
+    length: u64, // the size in bytes of the integer argument
+}
+
+//returns the number of characters written
+fn printf(
+    context: &mut InterpreterContext,
+    format: &Value,
+    arguments: &[Value],
+    span: Span,
+) -> RuntimeResult<usize> {
+    let format = read_string(context, format.address(), span)?;
+    let mut arguments = arguments.iter();
+    let mut next = || match arguments.next() {
+        Some(argument) => Ok(argument.clone()),
+        None => error(
+            "too few arguments for the format string of printf.".to_string(),
+            span,
+        ),
+    };
+
+    let mut result = Vec::new();
+    let mut bytes = format.iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            result.push(byte);
+            continue;
+        }
+
+        let mut specification = Specification {
+            length: 4,
+            ..Specification::default()
+        };
+        while let Some(flag) = bytes.next_if(|byte| b"-+ #0".contains(byte)) {
+            match flag {
+                b'-' => specification.left = true,
+                b'+' => specification.plus = true,
+                b' ' => specification.space = true,
+                b'#' => specification.alternate = true,
+                _ => specification.zero = true,
+            }
+        }
+        //a negative width taken from an argument is the - flag
+        if bytes.next_if_eq(&b'*').is_some() {
+            let width = next()?.integer();
+            specification.left |= width < 0;
+            specification.width = width.unsigned_abs() as usize;
+        } else {
+            while let Some(digit) = bytes.next_if(u8::is_ascii_digit) {
+                specification.width = specification.width * 10 + usize::from(digit - b'0');
+            }
+        }
+        if bytes.next_if_eq(&b'.').is_some() {
+            if bytes.next_if_eq(&b'*').is_some() {
+                let precision = next()?.integer();
+                specification.precision = usize::try_from(precision).ok();
+            } else {
+                let mut precision = 0;
+                while let Some(digit) = bytes.next_if(u8::is_ascii_digit) {
+                    precision = precision * 10 + usize::from(digit - b'0');
+                }
+                specification.precision = Some(precision);
+            }
+        }
+        while let Some(length) = bytes.next_if(|byte| b"hlzjtL".contains(byte)) {
+            specification.length = match (length, specification.length) {
+                (b'h', 2) => 1,
+                (b'h', _) => 2,
+                _ => 8,
+            };
+        }
+
+        let conversion = match bytes.next() {
+            Some(conversion) => conversion,
+            None => {
+                let message = "incomplete conversion specification in the format string of printf.";
+                return error(message.to_string(), span);
+            }
+        };
+        let text = match conversion {
+            b'%' => b"%".to_vec(),
+            b'd' | b'i' => {
+                let value = signed(next()?.integer(), specification.length);
+                let sign = if value < 0 {
+                    "-"
+                } else if specification.plus {
+                    "+"
+                } else if specification.space {
+                    " "
+                } else {
+                    ""
+                };
+                let digits = digits(value.unsigned_abs().to_string(), &specification);
+                pad(sign, &digits, &specification, true)
+            }
+            b'u' | b'x' | b'X' | b'o' => {
+                let value = next()?.integer();
+                let value = value as u128 & (u128::MAX >> (128 - 8 * specification.length));
+                let digits = match conversion {
+                    b'u' => value.to_string(),
+                    b'x' => format!("{:x}", value),
+                    b'X' => format!("{:X}", value),
+                    _ => format!("{:o}", value),
+                };
+                let mut digits = self::digits(digits, &specification);
+                let prefix = match conversion {
+                    b'x' if specification.alternate && value != 0 => "0x",
+                    b'X' if specification.alternate && value != 0 => "0X",
+                    b'o' if specification.alternate && !digits.starts_with('0') => {
+                        digits.insert(0, '0');
+                        ""
+                    }
+                    _ => "",
+                };
+                pad(prefix, &digits, &specification, true)
+            }
+            b'c' => {
+                let character = [next()?.integer() as u8];
+                pad_bytes(&character, &specification)
+            }
+            b's' => {
+                let address = next()?.address();
+                let mut text = Vec::new();
+                while specification.precision != Some(text.len()) {
+                    match context.memory.read(address + text.len() as u64, 1) {
+                        Ok([0]) => break,
+                        Ok(byte) => text.push(byte[0]),
+                        Err(error) => return memory_error(error, "read", span),
+                    }
+                }
+                pad_bytes(&text, &specification)
+            }
+            b'p' => match next()?.address() {
+                0 => pad("", "(nil)", &specification, false),
+                address => pad("0x", &format!("{:x}", address), &specification, false),
+            },
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                let value = next()?.floating();
+                let sign = if value.is_sign_negative() && !value.is_nan() {
+                    "-"
+                } else if specification.plus {
+                    "+"
+                } else if specification.space {
+                    " "
+                } else {
+                    ""
+                };
+                let text = floating(value.abs(), conversion, &specification);
+                let is_finite = value.is_finite();
+                pad(sign, &text, &specification, is_finite)
+            }
+            _ => {
+                let message = format!(
+                    "invalid conversion specifier '{}' in the format string of printf.",
+                    char::from(conversion)
+                );
+                return error(message, span);
+            }
+        };
+        result.extend(text);
+    }
+
+    emit(context, &result, span)?;
+    Ok(result.len())
+}
+
+//the value of an argument of a signed type of size bytes, which was promoted to int
+fn signed(value: i128, size: u64) -> i128 {
+    let width = 8 * size as u32;
+    let value = value.rem_euclid(1 << width);
+    if value >= 1 << (width - 1) {
+        value - (1 << width)
+    } else {
+        value
+    }
+}
+
+//the precision of an integer conversion is the least number of digits, and a precision
+//of 0 gives no digits for 0
+fn digits(digits: String, specification: &Specification) -> String {
+    match specification.precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(precision) if digits.len() < precision => {
+            format!("{}{}", "0".repeat(precision - digits.len()), digits)
+        }
+        _ => digits,
+    }
+}
+
+//pads to the width with spaces, or with zeros after the sign or prefix for the 0 flag,
+//which an integer conversion ignores if it has a precision
+fn pad(prefix: &str, digits: &str, specification: &Specification, numeric: bool) -> Vec<u8> {
+    let length = prefix.len() + digits.len();
+    let padding = specification.width.saturating_sub(length);
+    let zero = specification.zero && numeric && !specification.left;
+    let text = if specification.left {
+        format!("{}{}{}", prefix, digits, " ".repeat(padding))
+    } else if zero {
+        format!("{}{}{}", prefix, "0".repeat(padding), digits)
+    } else {
+        format!("{}{}{}", " ".repeat(padding), prefix, digits)
+    };
+    text.into_bytes()
+}
+
+fn pad_bytes(bytes: &[u8], specification: &Specification) -> Vec<u8> {
+    let padding = vec![b' '; specification.width.saturating_sub(bytes.len())];
+    if specification.left {
+        [bytes, &padding].concat()
+    } else {
+        [&padding, bytes].concat()
+    }
+}
+
+//a value which is not negative in the style of %f, %e or %g
+fn floating(value: f64, conversion: u8, specification: &Specification) -> String {
+    let upper = conversion.is_ascii_uppercase();
+    let text = if value.is_infinite() {
+        "inf".to_string()
+    } else if value.is_nan() {
+        "nan".to_string()
+    } else {
+        let precision = specification.precision.unwrap_or(6);
+        match conversion.to_ascii_lowercase() {
+            b'f' => {
+                let mut text = format!("{:.*}", precision, value);
+                if specification.alternate && precision == 0 {
+                    text.push('.');
+                }
+                text
+            }
+            b'e' => {
+                let mut text = exponential(value, precision);
+                if specification.alternate && precision == 0 {
+                    text.insert(1, '.');
+                }
+                text
+            }
+            //%g takes the style of %e if the exponent is less than -4 or not less than the
+            //precision, and drops the trailing zeros of the fraction unless the # flag is given
+            _ => {
+                let precision = precision.max(1);
+                let exponent = exponential(value, precision - 1);
+                let power: i32 = exponent[exponent.find('e').unwrap() + 1..].parse().unwrap();
+                let mut text = if power < -4 || power >= precision as i32 {
+                    exponent
+                } else {
+                    format!("{:.*}", (precision as i32 - 1 - power) as usize, value)
+                };
+                if !specification.alternate && text.contains('.') {
+                    let exponent = text
+                        .find('e')
+                        .map_or(String::new(), |at| text.split_off(at));
+                    text = text.trim_end_matches('0').trim_end_matches('.').to_string();
+                    text.push_str(&exponent);
+                }
+                text
+            }
+        }
+    };
+    if upper {
+        text.to_ascii_uppercase()
+    } else {
+        text
+    }
+}
+
+//the exponent has a sign and at least two digits, as in 1.500000e+01
+fn exponential(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = text.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
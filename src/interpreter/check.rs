@@ -0,0 +1,444 @@
+use crate::interpreter::memory::*;
+use crate::interpreter::util::*;
+use crate::interpreter::value::*;
+use crate::parser::expression::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+//a read or write of an object by the full expression being evaluated. the checked mode
+//keeps them to find the ones which are unsequenced.
+#[derive(Debug, Clone, Copy)]
+pub struct Access {
+    pub address: u64,
+    pub size: u64,
+    pub write: bool,
+    pub sequenced: bool, // before a sequence point of the subexpression it was made in
+}
+
+impl Access {
+    fn overlaps(&self, other: &Access) -> bool {
+        self.address < other.address + other.size && other.address < self.address + self.size
+    }
+}
+
+//the bytes an access of place reads or writes
+fn extent(context: &InterpreterContext, place: Place, ty: TypeId) -> u64 {
+    match place.bit_field {
+        Some((bit_offset, width)) => (bit_offset + width).div_ceil(8),
+        None => context.types.size_of(ty).unwrap_or(0),
+    }
+}
+
+pub fn record_access(context: &mut InterpreterContext, place: Place, ty: TypeId, write: bool) {
+    if context.checked {
+        let size = extent(context, place, ty);
+        context.accesses.push(Access {
+            address: place.address,
+            size,
+            write,
+            sequenced: false,
+        });
+    }
+}
+
+//the accesses since first happened before a sequence point, such as the one after the left
+//operand of a comma, so the side effects among them are complete when the subexpression
+//has a value
+pub fn sequence_point(context: &mut InterpreterContext, first: usize) {
+    for access in &mut context.accesses[first..] {
+        access.sequenced = true;
+    }
+}
+
+//the accesses from first to middle are unsequenced with the ones after middle, as those of
+//the two operands of an arithmetic operator are
+pub fn check_unsequenced(
+    context: &InterpreterContext,
+    first: usize,
+    middle: usize,
+    span: Span,
+) -> RuntimeResult<()> {
+    if !context.checked {
+        return Ok(());
+    }
+    //two modifications are reported before a modification and a read
+    let (before, after) = context.accesses[first..].split_at(middle - first);
+    let conflicts = before.iter().flat_map(|a| {
+        after
+            .iter()
+            .filter(move |b| (a.write || b.write) && a.overlaps(b))
+            .map(move |b| (a.write && b.write, a.address))
+    });
+    match conflicts.max_by_key(|(modifications, _)| *modifications) {
+        Some((modifications, address)) => unsequenced(context, modifications, address, span),
+        None => Ok(()),
+    }
+}
+
+//the store of an assignment or an increment to place, which comes after the values of the
+//operands evaluated since first but is unsequenced with their side effects
+pub fn check_modification(
+    context: &mut InterpreterContext,
+    first: usize,
+    place: Place,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<()> {
+    if !context.checked {
+        return Ok(());
+    }
+    let store = Access {
+        address: place.address,
+        size: extent(context, place, ty),
+        write: true,
+        sequenced: false,
+    };
+    let conflict = context.accesses[first..]
+        .iter()
+        .any(|access| access.write && !access.sequenced && access.overlaps(&store));
+    if conflict {
+        return unsequenced(context, true, place.address, span);
+    }
+    context.accesses.push(store);
+    Ok(())
+}
+
+fn unsequenced<T>(
+    context: &InterpreterContext,
+    modifications: bool,
+    address: u64,
+    span: Span,
+) -> RuntimeResult<T> {
+    let object = object_name(context, address);
+    let message = if modifications {
+        format!("multiple unsequenced modifications to {}.", object)
+    } else {
+        format!("unsequenced modification and access to {}.", object)
+    };
+    error(message, span)
+}
+
+//the name of the variable whose object holds address, as it is quoted in a message
+fn object_name(context: &InterpreterContext, address: u64) -> String {
+    let allocation = split(address).0;
+    let locals = context.frames.last().map(|frame| &frame.locals);
+    let named = locals
+        .into_iter()
+        .flatten()
+        .chain(&context.globals)
+        .find(|(_, object)| split(**object).0 == allocation);
+    match named {
+        Some((id, _)) => format!("'{}'", symbol(context, *id).name),
+        None => "an object".to_string(),
+    }
+}
+
+//the class an lvalue of the representation accesses objects as, None for a character type
+fn alias_class(representation: Representation) -> Option<AliasClass> {
+    match representation {
+        Representation::Integer(kind) if kind.rank() == 1 => None,
+        Representation::Integer(kind) => Some(AliasClass::Integer(kind)),
+        Representation::Floating(kind) => Some(AliasClass::Floating(kind)),
+        Representation::Pointer => Some(AliasClass::Pointer),
+        Representation::Bytes(_) | Representation::Void => None,
+    }
+}
+
+//a store gives the bytes it writes the effective type of the lvalue. a struct or union is
+//copied without the types of its members.
+pub fn set_effective_type(
+    context: &mut InterpreterContext,
+    place: Place,
+    representation: Representation,
+) {
+    if !context.checked || place.bit_field.is_some() {
+        return;
+    }
+    match representation {
+        Representation::Bytes(size) => context.memory.set_effective_type(place.address, size, None),
+        representation => {
+            if let Some(class) = alias_class(representation) {
+                let size = match class {
+                    AliasClass::Integer(kind) => kind.size(),
+                    AliasClass::Floating(kind) => kind.size(),
+                    AliasClass::Pointer => 8,
+                };
+                let class = Some(class);
+                context
+                    .memory
+                    .set_effective_type(place.address, size, class);
+            }
+        }
+    }
+}
+
+//a scalar which is read must have been stored to, by an lvalue whose type may read it. a
+//member of a union reads the bytes of the other members whatever their types.
+pub fn check_read(
+    context: &InterpreterContext,
+    place: Place,
+    ty: TypeId,
+    union_member: bool,
+    span: Span,
+) -> RuntimeResult<()> {
+    if !context.checked {
+        return Ok(());
+    }
+    let representation = representation(context, ty, span)?;
+    if matches!(
+        representation,
+        Representation::Bytes(_) | Representation::Void
+    ) {
+        return Ok(());
+    }
+    let size = extent(context, place, ty);
+    if !context.memory.is_initialized(place.address, size) {
+        let message = format!(
+            "use of an uninitialized value of type '{}'.",
+            context.types.display(ty)
+        );
+        return error(message, span);
+    }
+    if union_member || place.bit_field.is_some() {
+        return Ok(());
+    }
+    let class = match alias_class(representation) {
+        Some(class) => class,
+        None => return Ok(()),
+    };
+    match context.memory.effective_type(place.address, size) {
+        Some(effective) if !class.is_compatible(effective) => {
+            let message = format!(
+                "read of an object of effective type '{}' through an lvalue of type '{}' violates strict aliasing.",
+                effective.spelling(),
+                context.types.display(ty)
+            );
+            error(message, span)
+        }
+        _ => Ok(()),
+    }
+}
+
+//an integer operation in kind whose exact result is result. signed arithmetic must give a
+//representable value, and the count of a shift must be less than the width.
+pub fn check_integer_operation(
+    context: &InterpreterContext,
+    operator: BinaryOperatorKind,
+    (lhs, rhs): (i128, i128),
+    result: i128,
+    kind: IntegerKind,
+    span: Span,
+) -> RuntimeResult<()> {
+    use BinaryOperatorKind::*;
+    if !context.checked {
+        return Ok(());
+    }
+    let name = kind.spelling();
+    let width = i128::from(kind.width());
+    let fits = kind.min_value() <= result && result <= kind.max_value();
+    let signed = kind.is_signed();
+    let message = match operator {
+        LeftShift | RightShift if rhs < 0 => format!("shift exponent {} is negative.", rhs),
+        LeftShift | RightShift if rhs >= width => format!(
+            "shift exponent {} is too large for {}-bit type '{}'.",
+            rhs, width, name
+        ),
+        LeftShift if signed && lhs < 0 => format!("left shift of negative value {}.", lhs),
+        LeftShift if signed && !fits => format!(
+            "left shift of {} by {} places cannot be represented in type '{}'.",
+            lhs, rhs, name
+        ),
+        Add | Sub | Mul if signed && !fits => format!(
+            "signed integer overflow: {} {} {} cannot be represented in type '{}'.",
+            lhs,
+            operator.spelling(),
+            rhs,
+            name
+        ),
+        Div | Mod if signed && rhs == -1 && lhs == kind.min_value() => format!(
+            "division of {} by -1 cannot be represented in type '{}'.",
+            lhs, name
+        ),
+        _ => return Ok(()),
+    };
+    error(message, span)
+}
+
+pub fn check_negation(
+    context: &InterpreterContext,
+    value: i128,
+    kind: IntegerKind,
+    span: Span,
+) -> RuntimeResult<()> {
+    if context.checked && kind.is_signed() && value == kind.min_value() {
+        let message = format!(
+            "negation of {} cannot be represented in type '{}'.",
+            value,
+            kind.spelling()
+        );
+        return error(message, span);
+    }
+    Ok(())
+}
+
+//a floating value converted to an integer type must fit once truncated
+pub fn check_floating_conversion(
+    context: &InterpreterContext,
+    value: f64,
+    kind: IntegerKind,
+    span: Span,
+) -> RuntimeResult<()> {
+    if !context.checked || kind == IntegerKind::Bool {
+        return Ok(());
+    }
+    let truncated = value.trunc();
+    //the bounds are powers of two, which a double holds exactly
+    let fits = truncated >= kind.min_value() as f64 && truncated < (kind.max_value() + 1) as f64;
+    if !fits {
+        let message = format!(
+            "{} is outside the range of representable values of type '{}'.",
+            value,
+            kind.spelling()
+        );
+        return error(message, span);
+    }
+    Ok(())
+}
+
+//pointer arithmetic must keep a pointer to a live object within the object or just past
+//its end
+pub fn check_pointer_arithmetic(
+    context: &InterpreterContext,
+    address: u64,
+    offset: i128,
+    span: Span,
+) -> RuntimeResult<()> {
+    if !context.checked || offset == 0 {
+        return Ok(());
+    }
+    let allocation = match context.memory.allocation(address) {
+        Some(allocation) => allocation,
+        None if split(address).0 == 0 => {
+            return error("pointer arithmetic on a null pointer.".to_string(), span)
+        }
+        None => {
+            return error(
+                "pointer arithmetic on an invalid pointer.".to_string(),
+                span,
+            )
+        }
+    };
+    if let AllocationKind::Function(_) = allocation.kind {
+        return Ok(());
+    }
+    if !allocation.live {
+        let message = "pointer arithmetic on a pointer to an object whose lifetime has ended.";
+        return error(message.to_string(), span);
+    }
+    let size = allocation.bytes.len() as i128;
+    let result = i128::from(split(address).1) + offset;
+    if !(0..=size).contains(&result) {
+        let message = format!(
+            "pointer arithmetic moves a pointer to offset {} of an object of {} bytes.",
+            result, size
+        );
+        return error(message, span);
+    }
+    Ok(())
+}
+
+//pointers which are subtracted or compared by <, <=, > or >= must point into the same object
+pub fn check_same_object(
+    context: &InterpreterContext,
+    (lhs, rhs): (u64, u64),
+    operation: &str,
+    span: Span,
+) -> RuntimeResult<()> {
+    if context.checked && split(lhs).0 != split(rhs).0 {
+        let message = format!("{} of pointers into different objects.", operation);
+        return error(message, span);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::interpret;
+    use crate::options::*;
+    use crate::testing::*;
+
+    //the undefined behavior the checked execution of main stops at, and the source of the
+    //expression it points at
+    fn undefined(body: &str) -> (String, String) {
+        let source = format!(
+            "void *malloc(unsigned long); void free(void *);\nint main(void) {{\n{}\n}}",
+            body
+        );
+        let (typed, _) = analyze_source(&source, Standard::C11);
+        let diagnostic = match interpret(&typed, Execution::Checked, &mut Vec::new()) {
+            Ok(status) => panic!("exited with {}", status),
+            Err(diagnostic) => diagnostic,
+        };
+        let span = diagnostic.span;
+        assert_eq!(span.begin.line, span.end.line);
+        let line = source.lines().nth(span.begin.line - 1).unwrap();
+        let text = line[span.begin.column - 1..span.end.column - 1].to_string();
+        (diagnostic.message, text)
+    }
+
+    #[test]
+    fn signed_overflow() {
+        let (message, text) = undefined("int x = 2147483647; int y = 1; return x + y;");
+        assert_eq!(
+            message,
+            "signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'."
+        );
+        assert_eq!(text, "x + y");
+        let (message, _) = undefined("unsigned u = 0; u - 1; int m = -2147483647 - 1; return -m;");
+        assert_eq!(
+            message,
+            "negation of -2147483648 cannot be represented in type 'int'."
+        );
+    }
+
+    #[test]
+    fn pointer_arithmetic_out_of_bounds() {
+        let (message, text) = undefined("int a[4]; int *end = a + 4; int *p = end + 1; return 0;");
+        assert_eq!(
+            message,
+            "pointer arithmetic moves a pointer to offset 20 of an object of 16 bytes."
+        );
+        assert_eq!(text, "end + 1");
+    }
+
+    #[test]
+    fn uninitialized_values() {
+        let (message, text) = undefined("int x; int y = x; return y;");
+        assert_eq!(message, "use of an uninitialized value of type 'int'.");
+        assert_eq!(text, "x");
+    }
+
+    #[test]
+    fn use_after_free() {
+        let (message, text) = undefined("int *p = malloc(4); *p = 1; free(p); return *p;");
+        assert_eq!(message, "read of memory which was freed.");
+        assert_eq!(text, "*p");
+    }
+
+    #[test]
+    fn strict_aliasing() {
+        let (message, text) = undefined("float f = 1; int *p = (int *)&f; return *p;");
+        assert_eq!(
+            message,
+            "read of an object of effective type 'float' through an lvalue of type 'int' violates strict aliasing."
+        );
+        assert_eq!(text, "*p");
+    }
+
+    #[test]
+    fn unsequenced_modifications() {
+        let (message, text) = undefined("int i = 0; i = i++; return i;");
+        assert_eq!(message, "multiple unsequenced modifications to 'i'.");
+        assert_eq!(text, "i = i++");
+    }
+}
@@ -0,0 +1,580 @@
+use crate::interpreter::check::*;
+use crate::interpreter::memory::*;
+use crate::interpreter::statement::*;
+use crate::interpreter::util::*;
+use crate::interpreter::value::*;
+use crate::parser::expression::*;
+use crate::semantic::expression::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+//the value of expression. an array or a function designator gives its address and any
+//other lvalue the value stored in its object.
+pub fn value(
+    context: &mut InterpreterContext,
+    expression: &TypedExpression,
+) -> RuntimeResult<Value> {
+    //a member of an rvalue is an rvalue, which is read from a temporary object
+    let is_member = matches!(expression.kind, TypedExpressionKind::Member { .. });
+    if expression.category == ValueCategory::RValue && !is_member {
+        return rvalue(context, expression);
+    }
+
+    let place = place(context, expression)?;
+    if expression.category == ValueCategory::FunctionDesignator
+        || context.types.is_array(expression.ty)
+    {
+        return Ok(Value::Integer(i128::from(place.address)));
+    }
+    read(context, place, expression)
+}
+
+//the value stored in the object of an lvalue, whose read the checked mode checks and
+//records
+fn read(
+    context: &mut InterpreterContext,
+    place: Place,
+    expression: &TypedExpression,
+) -> RuntimeResult<Value> {
+    let (ty, span) = (expression.ty, expression.span);
+    let value = load(context, place, ty, span)?;
+    let union_member = is_union_member(context, expression);
+    check_read(context, place, ty, union_member, span)?;
+    record_access(context, place, ty, false);
+    Ok(value)
+}
+
+//the store of an assignment or an increment whose operands were evaluated since first
+fn modify(
+    context: &mut InterpreterContext,
+    first: usize,
+    place: Place,
+    ty: TypeId,
+    value: &Value,
+    span: Span,
+) -> RuntimeResult<()> {
+    check_modification(context, first, place, ty, span)?;
+    store(context, place, ty, value, span)
+}
+
+//whether expression designates a member of a union or a subobject of one
+fn is_union_member(context: &InterpreterContext, expression: &TypedExpression) -> bool {
+    match &expression.kind {
+        TypedExpressionKind::Member { operand, .. } => {
+            let types = context.types;
+            let is_union = types
+                .record_of(operand.ty)
+                .is_some_and(|record| types.record(record).kind == RecordKind::Union);
+            is_union || is_union_member(context, operand)
+        }
+        _ => false,
+    }
+}
+
+//the object an lvalue designates, or the function a function designator does
+pub fn place(
+    context: &mut InterpreterContext,
+    expression: &TypedExpression,
+) -> RuntimeResult<Place> {
+    let span = expression.span;
+    match &expression.kind {
+        TypedExpressionKind::Identifier(id) => {
+            Ok(Place::object(object_address(context, *id, span)?))
+        }
+        TypedExpressionKind::Unary {
+            kind: UnaryKind::Dereference,
+            operand,
+        } => Ok(Place::object(value(context, operand)?.address())),
+        TypedExpressionKind::Member { operand, index } => {
+            let base = place(context, operand)?;
+            let types = context.types;
+            let record = types.record(types.record_of(operand.ty).unwrap());
+            Ok(member_place(
+                base,
+                &record.members.as_ref().unwrap()[*index],
+            ))
+        }
+        TypedExpressionKind::String(content) => Ok(Place::object(string(context, content))),
+        //the one at file scope is initialized once, when the initializer it is in runs
+        TypedExpressionKind::CompoundLiteral {
+            initializer,
+            id: Some(id),
+        } => {
+            if let Some(address) = context.compound_literals.get(id) {
+                return Ok(Place::object(*address));
+            }
+            let size = size_of(context, expression.ty, span)?;
+            let align = context.types.align_of(expression.ty).unwrap_or(1);
+            let address = context.memory.allocate(AllocationKind::Static, size, align);
+            context.compound_literals.insert(*id, address);
+            let place = Place::object(address);
+            zero(context, address, expression.ty, span)?;
+            initialize(context, place, expression.ty, initializer)?;
+            Ok(place)
+        }
+        TypedExpressionKind::CompoundLiteral {
+            initializer,
+            id: None,
+        } => {
+            let kind = AllocationKind::Automatic;
+            let address = allocate_in_frame(context, kind, expression.ty, span)?;
+            let place = Place::object(address);
+            zero(context, address, expression.ty, span)?;
+            initialize(context, place, expression.ty, initializer)?;
+            Ok(place)
+        }
+        //an rvalue of struct or union type whose member is accessed is held in a temporary
+        _ => {
+            let value = rvalue(context, expression)?;
+            let kind = AllocationKind::Temporary;
+            let address = allocate_in_frame(context, kind, expression.ty, span)?;
+            let place = Place::object(address);
+            store(context, place, expression.ty, &value, span)?;
+            Ok(place)
+        }
+    }
+}
+
+pub fn member_place(base: Place, member: &Member) -> Place {
+    Place {
+        address: base.address + member.offset,
+        bit_field: member
+            .bit_width
+            .map(|bit_width| (member.bit_offset, bit_width)),
+    }
+}
+
+//the address of the object or function id denotes. a function is given an allocation of
+//its own the first time its address is taken, so that a pointer to it can be called.
+pub fn object_address(
+    context: &mut InterpreterContext,
+    id: SymbolId,
+    span: Span,
+) -> RuntimeResult<u64> {
+    let local = context
+        .frames
+        .last()
+        .and_then(|frame| frame.locals.get(&id));
+    if let Some(address) = local.or_else(|| context.globals.get(&id)) {
+        return Ok(*address);
+    }
+    let symbol = symbol(context, id);
+    if symbol.kind == SymbolKind::Function {
+        let address = context.memory.allocate(AllocationKind::Function(id), 0, 1);
+        context.globals.insert(id, address);
+        return Ok(address);
+    }
+    error(format!("undefined reference to '{}'.", symbol.name), span)
+}
+
+//an object which lives until the function being executed returns, or until the program
+//ends outside of functions
+fn allocate_in_frame(
+    context: &mut InterpreterContext,
+    kind: AllocationKind,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<u64> {
+    let size = size_of(context, ty, span)?;
+    let align = context.types.align_of(ty).unwrap_or(1);
+    match context.frames.last_mut() {
+        Some(frame) => {
+            let address = context.memory.allocate(kind, size, align);
+            frame.temporaries.push(address);
+            Ok(address)
+        }
+        None => Ok(context.memory.allocate(AllocationKind::Static, size, align)),
+    }
+}
+
+//the same string literal designates the same array each time it is evaluated
+fn string(context: &mut InterpreterContext, content: &str) -> u64 {
+    let key = (content.as_ptr() as usize, content.len());
+    if let Some(address) = context.strings.get(&key) {
+        return *address;
+    }
+    let mut bytes = string_bytes(content);
+    bytes.push(0);
+    let address = context.memory.allocate_string(bytes);
+    context.strings.insert(key, address);
+    address
+}
+
+//the lengths of variable length arrays, which the arrays of their types keep in the frame
+pub fn evaluate_lengths(
+    context: &mut InterpreterContext,
+    lengths: &[(LengthId, TypedExpression)],
+) -> RuntimeResult<()> {
+    for (length, expression) in lengths {
+        //the length is converted to size_t, so a negative one is a huge value
+        let value = value(context, expression)?.integer() as i64;
+        if value < 0 || (value == 0 && context.checked) {
+            let message = format!(
+                "variable length array bound evaluates to non-positive value {}.",
+                value
+            );
+            return error(message, expression.span);
+        }
+        frame(context).lengths.insert(*length, value as u64);
+    }
+    Ok(())
+}
+
+fn rvalue(context: &mut InterpreterContext, expression: &TypedExpression) -> RuntimeResult<Value> {
+    let ty = expression.ty;
+    let span = expression.span;
+    match &expression.kind {
+        TypedExpressionKind::Integer(value) => Ok(Value::Integer(*value)),
+        TypedExpressionKind::Identifier(id) => match symbol(context, *id).kind {
+            SymbolKind::EnumerationConstant(value) => Ok(Value::Integer(i128::from(value))),
+            _ => unreachable!(),
+        },
+        TypedExpressionKind::Unary { kind, operand } => unary(context, *kind, operand, ty, span),
+        TypedExpressionKind::Binary { kind, lhs, rhs } => binary(context, *kind, lhs, rhs, span),
+        TypedExpressionKind::CompoundAssignment {
+            operator,
+            lhs,
+            rhs,
+            computation,
+        } => compound_assignment(context, *operator, lhs, rhs, *computation, span),
+        TypedExpressionKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => {
+            let first = context.accesses.len();
+            let condition = value(context, condition)?;
+            sequence_point(context, first);
+            let result = match (condition.is_true(), then) {
+                (true, Some(then)) => value(context, then)?,
+                (true, None) => condition,
+                (false, _) => value(context, otherwise)?,
+            };
+            convert(context, result, ty, span)
+        }
+        TypedExpressionKind::Cast { operand, .. } => {
+            let value = value(context, operand)?;
+            convert(context, value, ty, span)
+        }
+        TypedExpressionKind::Call { callee, arguments } => call(context, callee, arguments, span),
+        TypedExpressionKind::Sizeof(ty) => {
+            Ok(Value::Integer(i128::from(size_of(context, *ty, span)?)))
+        }
+        TypedExpressionKind::Alignof(ty) => {
+            let align = context.types.align_of(*ty).unwrap_or(1);
+            Ok(Value::Integer(i128::from(align)))
+        }
+        TypedExpressionKind::Statement(body) => statement_expression(context, body, span),
+        TypedExpressionKind::VariableLengths { lengths, operand } => {
+            evaluate_lengths(context, lengths)?;
+            value(context, operand)
+        }
+        TypedExpressionKind::String(_)
+        | TypedExpressionKind::Member { .. }
+        | TypedExpressionKind::CompoundLiteral { .. } => unreachable!(),
+    }
+}
+
+fn unary(
+    context: &mut InterpreterContext,
+    kind: UnaryKind,
+    operand: &TypedExpression,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let result = match kind {
+        UnaryKind::LogicalNot => Value::Integer(i128::from(!value(context, operand)?.is_true())),
+        UnaryKind::BitwiseNot => Value::Integer(!value(context, operand)?.integer()),
+        UnaryKind::Plus => value(context, operand)?,
+        UnaryKind::Minus => match value(context, operand)? {
+            Value::Floating(value) => Value::Floating(-value),
+            value => {
+                if let Representation::Integer(kind) = representation(context, ty, span)? {
+                    check_negation(context, value.integer(), kind, span)?;
+                }
+                Value::Integer(-value.integer())
+            }
+        },
+        UnaryKind::Reference => Value::Integer(i128::from(place(context, operand)?.address)),
+        UnaryKind::Dereference => unreachable!(),
+        UnaryKind::PreIncrement
+        | UnaryKind::PreDecrement
+        | UnaryKind::PostIncrement
+        | UnaryKind::PostDecrement => return increment(context, kind, operand, span),
+    };
+    convert(context, result, ty, span)
+}
+
+//the value of a prefix operator is the one stored, which for a bit-field is the one
+//truncated to its width
+fn increment(
+    context: &mut InterpreterContext,
+    kind: UnaryKind,
+    operand: &TypedExpression,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let ty = operand.ty;
+    let first = context.accesses.len();
+    let place = place(context, operand)?;
+    let old = read(context, place, operand)?;
+    let (delta, operator) = match kind {
+        UnaryKind::PreIncrement | UnaryKind::PostIncrement => (1, BinaryOperatorKind::Add),
+        _ => (-1, BinaryOperatorKind::Sub),
+    };
+    let new = match representation(context, ty, span)? {
+        Representation::Pointer => {
+            let offset = delta * i128::from(pointee_size(context, ty, span)?);
+            check_pointer_arithmetic(context, old.address(), offset, span)?;
+            Value::Integer(old.integer() + offset)
+        }
+        Representation::Floating(_) => Value::Floating(old.floating() + delta as f64),
+        //a narrower operand is promoted, and converting the result back is no overflow
+        Representation::Integer(integer_kind) => {
+            let (old, new) = (old.integer(), old.integer() + delta);
+            if promoted(integer_kind) == integer_kind {
+                let operands = (old, 1);
+                check_integer_operation(context, operator, operands, new, integer_kind, span)?;
+            }
+            Value::Integer(new)
+        }
+        _ => Value::Integer(old.integer() + delta),
+    };
+    let new = convert(context, new, ty, span)?;
+    modify(context, first, place, ty, &new, span)?;
+    match kind {
+        UnaryKind::PreIncrement | UnaryKind::PreDecrement => load(context, place, ty, span),
+        _ => Ok(old),
+    }
+}
+
+//the size pointer arithmetic scales by
+fn pointee_size(context: &InterpreterContext, ty: TypeId, span: Span) -> RuntimeResult<u64> {
+    match context.types.scaled_pointee(ty) {
+        Some(pointee) => size_of(context, pointee, span),
+        None => Ok(1),
+    }
+}
+
+fn binary(
+    context: &mut InterpreterContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    span: Span,
+) -> RuntimeResult<Value> {
+    use BinaryOperatorKind::*;
+    match kind {
+        Comma | LogicalAnd | LogicalOr => {
+            let first = context.accesses.len();
+            let lhs_value = value(context, lhs)?;
+            sequence_point(context, first);
+            let result = match kind {
+                Comma => return value(context, rhs),
+                LogicalAnd => lhs_value.is_true() && value(context, rhs)?.is_true(),
+                _ => lhs_value.is_true() || value(context, rhs)?.is_true(),
+            };
+            Ok(Value::Integer(i128::from(result)))
+        }
+        Assign => {
+            let first = context.accesses.len();
+            let place = place(context, lhs)?;
+            let middle = context.accesses.len();
+            let value = value(context, rhs)?;
+            check_unsequenced(context, first, middle, span)?;
+            modify(context, first, place, lhs.ty, &value, span)?;
+            load(context, place, lhs.ty, span)
+        }
+        kind => {
+            let first = context.accesses.len();
+            let lhs_value = value(context, lhs)?;
+            let middle = context.accesses.len();
+            let rhs_value = value(context, rhs)?;
+            check_unsequenced(context, first, middle, span)?;
+            operate(
+                context,
+                kind,
+                (lhs_value, lhs.ty),
+                (rhs_value, rhs.ty),
+                span,
+            )
+        }
+    }
+}
+
+fn compound_assignment(
+    context: &mut InterpreterContext,
+    operator: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    computation: TypeId,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let first = context.accesses.len();
+    let place = place(context, lhs)?;
+    let old = read(context, place, lhs)?;
+    let middle = context.accesses.len();
+    let rhs_value = value(context, rhs)?;
+    check_unsequenced(context, first, middle, span)?;
+    let old = convert(context, old, computation, span)?;
+    let result = operate(
+        context,
+        operator,
+        (old, computation),
+        (rhs_value, rhs.ty),
+        span,
+    )?;
+    let result = convert(context, result, lhs.ty, span)?;
+    modify(context, first, place, lhs.ty, &result, span)?;
+    load(context, place, lhs.ty, span)
+}
+
+fn promoted(kind: IntegerKind) -> IntegerKind {
+    if kind.rank() < IntegerKind::Int.rank() {
+        IntegerKind::Int
+    } else {
+        kind
+    }
+}
+
+//an arithmetic, bitwise, shift or comparison operator applied to operands which have the
+//types the operator works on, apart from the left operand of a shift which is promoted here
+fn operate(
+    context: &InterpreterContext,
+    kind: BinaryOperatorKind,
+    (lhs, lhs_type): (Value, TypeId),
+    (rhs, rhs_type): (Value, TypeId),
+    span: Span,
+) -> RuntimeResult<Value> {
+    use BinaryOperatorKind::*;
+    let lhs_representation = representation(context, lhs_type, span)?;
+    let rhs_representation = representation(context, rhs_type, span)?;
+    let address = |value: i128| Value::Integer(wrap(value, IntegerKind::UnsignedLong));
+    match (kind, lhs_representation, rhs_representation) {
+        (Add | Sub, Representation::Pointer, Representation::Integer(_)) => {
+            let offset = rhs.integer() * i128::from(pointee_size(context, lhs_type, span)?);
+            let offset = if kind == Sub { -offset } else { offset };
+            check_pointer_arithmetic(context, lhs.address(), offset, span)?;
+            return Ok(address(lhs.integer() + offset));
+        }
+        (Add, Representation::Integer(_), Representation::Pointer) => {
+            let offset = lhs.integer() * i128::from(pointee_size(context, rhs_type, span)?);
+            check_pointer_arithmetic(context, rhs.address(), offset, span)?;
+            return Ok(address(rhs.integer() + offset));
+        }
+        (Sub, Representation::Pointer, Representation::Pointer) => {
+            let pointers = (lhs.address(), rhs.address());
+            check_same_object(context, pointers, "subtraction", span)?;
+            let size = i128::from(pointee_size(context, lhs_type, span)?);
+            let difference = (lhs.integer() - rhs.integer()) / size;
+            return Ok(Value::Integer(wrap(difference, IntegerKind::Long)));
+        }
+        _ => {}
+    }
+
+    if matches!(kind, Equal | NotEqual | LessThan | LessThanEqual) {
+        if lhs_representation == Representation::Pointer && matches!(kind, LessThan | LessThanEqual)
+        {
+            let pointers = (lhs.address(), rhs.address());
+            check_same_object(context, pointers, "comparison", span)?;
+        }
+        let result = match lhs_representation {
+            Representation::Floating(_) => {
+                let (lhs, rhs) = (lhs.floating(), rhs.floating());
+                match kind {
+                    Equal => lhs == rhs,
+                    NotEqual => lhs != rhs,
+                    LessThan => lhs < rhs,
+                    _ => lhs <= rhs,
+                }
+            }
+            _ => {
+                let (lhs, rhs) = (lhs.integer(), rhs.integer());
+                match kind {
+                    Equal => lhs == rhs,
+                    NotEqual => lhs != rhs,
+                    LessThan => lhs < rhs,
+                    _ => lhs <= rhs,
+                }
+            }
+        };
+        return Ok(Value::Integer(i128::from(result)));
+    }
+
+    match lhs_representation {
+        Representation::Floating(floating_kind) => {
+            let (lhs, rhs) = (lhs.floating(), rhs.floating());
+            let result = match kind {
+                Add => lhs + rhs,
+                Sub => lhs - rhs,
+                Mul => lhs * rhs,
+                _ => lhs / rhs,
+            };
+            let result = match floating_kind {
+                FloatingKind::Float => f64::from(result as f32),
+                _ => result,
+            };
+            Ok(Value::Floating(result))
+        }
+        Representation::Integer(integer_kind) => {
+            let (lhs, rhs) = (lhs.integer(), rhs.integer());
+            let integer_kind = promoted(integer_kind);
+            //x86-64 takes the count of a shift modulo the width of the operand
+            let count = (rhs & i128::from(integer_kind.width() - 1)) as u32;
+            let result = match kind {
+                Add => lhs.wrapping_add(rhs),
+                Sub => lhs.wrapping_sub(rhs),
+                Mul => lhs.wrapping_mul(rhs),
+                Div | Mod if rhs == 0 => return error("division by zero.".to_string(), span),
+                Div => lhs / rhs,
+                Mod => lhs % rhs,
+                LeftShift => lhs << count,
+                RightShift => lhs >> count,
+                BitwiseAnd => lhs & rhs,
+                BitwiseXor => lhs ^ rhs,
+                _ => lhs | rhs,
+            };
+            check_integer_operation(context, kind, (lhs, rhs), result, integer_kind, span)?;
+            Ok(Value::Integer(wrap(result, integer_kind)))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn call(
+    context: &mut InterpreterContext,
+    callee: &TypedExpression,
+    arguments: &[TypedExpression],
+    span: Span,
+) -> RuntimeResult<Value> {
+    let first = context.accesses.len();
+    let address = value(context, callee)?.address();
+    let function = match context.memory.allocation(address) {
+        Some(allocation) if split(address).1 == 0 => match allocation.kind {
+            AllocationKind::Function(id) => Some(id),
+            _ => None,
+        },
+        _ => None,
+    };
+    let function = match function {
+        Some(function) => function,
+        None => {
+            let message = "call through a pointer which does not point to a function.";
+            return error(message.to_string(), span);
+        }
+    };
+
+    //the callee and the arguments are unsequenced with each other, and complete before the
+    //call
+    let mut values = Vec::new();
+    let mut boundaries = vec![first, context.accesses.len()];
+    for argument in arguments {
+        values.push(value(context, argument)?);
+        boundaries.push(context.accesses.len());
+    }
+    for pair in boundaries.windows(2) {
+        check_unsequenced(context, pair[0], pair[1], span)?;
+    }
+    sequence_point(context, first);
+    call_function(context, function, values, span)
+}
@@ -0,0 +1,253 @@
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+
+//an address is the index of an allocation in its upper half and an offset into it in its
+//lower half, so that pointers are plain integers while every access can still be checked
+//against the object it points into. the allocation 0 is never used, which makes 0 the null
+//pointer.
+const OFFSET_BITS: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AllocationKind {
+    Static,    // an object with static storage duration
+    Automatic, // a local variable, a parameter or a compound literal in a function
+    Temporary, // an rvalue of struct or union type whose member is accessed
+    Heap,      // allocated by malloc or calloc
+    String,    // the array of a string literal
+    Function(SymbolId),
+}
+
+//the types an object may be read by once a value of one of them is stored in it. the
+//signed and unsigned kinds of an integer type may read each other, and all pointers are one
+//class. character types read any object and stores by them leave no type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AliasClass {
+    Integer(IntegerKind),
+    Floating(FloatingKind),
+    Pointer,
+}
+
+impl AliasClass {
+    pub fn is_compatible(self, other: AliasClass) -> bool {
+        match (self, other) {
+            (AliasClass::Integer(a), AliasClass::Integer(b)) => a.to_unsigned() == b.to_unsigned(),
+            (a, b) => a == b,
+        }
+    }
+
+    pub fn spelling(self) -> &'static str {
+        match self {
+            AliasClass::Integer(kind) => kind.spelling(),
+            AliasClass::Floating(kind) => kind.spelling(),
+            AliasClass::Pointer => "pointer",
+        }
+    }
+}
+
+//initialized and effective hold for each byte whether a value was stored in it and by which
+//type, which the checked mode looks at
+#[derive(Debug)]
+pub struct Allocation {
+    pub kind: AllocationKind,
+    pub bytes: Vec<u8>,
+    pub initialized: Vec<bool>,
+    pub effective: Vec<Option<AliasClass>>,
+    pub live: bool, // false once freed, or once the block of an automatic object ends
+}
+
+#[derive(Debug, Default)]
+pub struct Memory {
+    allocations: Vec<Allocation>,
+}
+
+//why an access to memory failed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryError {
+    Null,
+    Invalid,     // no object was ever allocated there
+    Dead,        // the lifetime of the object ended
+    Freed,       // the object was allocated by malloc and freed
+    OutOfBounds, // the access does not lie within the object
+    Function,    // the address of a function is not an object
+    ReadOnly,    // a string literal is not modifiable
+}
+
+impl MemoryError {
+    pub fn describe(self, access: &str) -> String {
+        match self {
+            MemoryError::Null => format!("{} through a null pointer.", access),
+            MemoryError::Invalid => format!("{} through an invalid pointer.", access),
+            MemoryError::Dead => format!("{} of an object whose lifetime has ended.", access),
+            MemoryError::Freed => format!("{} of memory which was freed.", access),
+            MemoryError::OutOfBounds => format!("{} out of the bounds of an object.", access),
+            MemoryError::Function => format!("{} of a function.", access),
+            MemoryError::ReadOnly => format!("{} of a string literal.", access),
+        }
+    }
+}
+
+pub fn address(allocation: usize, offset: u64) -> u64 {
+    ((allocation as u64) << OFFSET_BITS) | offset
+}
+
+//the allocation and the offset of an address
+pub fn split(address: u64) -> (usize, u64) {
+    (
+        (address >> OFFSET_BITS) as usize,
+        address & ((1 << OFFSET_BITS) - 1),
+    )
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory {
+            allocations: vec![Allocation {
+                kind: AllocationKind::Static,
+                bytes: Vec::new(),
+                initialized: Vec::new(),
+                effective: Vec::new(),
+                live: false,
+            }],
+        }
+    }
+
+    //the new object is filled with zeros, which is the value an object with static storage
+    //duration starts with and the one the other objects are given. only the former counts
+    //as initialized. an allocation starts a range of addresses of its own, so its address
+    //is a multiple of any alignment up to 2 to the OFFSET_BITS.
+    pub fn allocate(&mut self, kind: AllocationKind, size: u64, align: u64) -> u64 {
+        debug_assert!(align.is_power_of_two() && align.trailing_zeros() < OFFSET_BITS);
+        let initialized = kind == AllocationKind::Static;
+        self.push(kind, vec![0; size as usize], initialized)
+    }
+
+    //the array of a string literal, which holds its characters from the start
+    pub fn allocate_string(&mut self, bytes: Vec<u8>) -> u64 {
+        self.push(AllocationKind::String, bytes, true)
+    }
+
+    fn push(&mut self, kind: AllocationKind, bytes: Vec<u8>, initialized: bool) -> u64 {
+        self.allocations.push(Allocation {
+            kind,
+            initialized: vec![initialized; bytes.len()],
+            effective: vec![None; bytes.len()],
+            bytes,
+            live: true,
+        });
+        address(self.allocations.len() - 1, 0)
+    }
+
+    pub fn allocation(&self, address: u64) -> Option<&Allocation> {
+        match split(address) {
+            (0, _) => None,
+            (index, _) => self.allocations.get(index),
+        }
+    }
+
+    //ends the lifetime of the object at address
+    pub fn release(&mut self, address: u64) {
+        let (index, _) = split(address);
+        if let Some(allocation) = self.allocations.get_mut(index) {
+            allocation.live = false;
+            allocation.bytes = Vec::new();
+            allocation.initialized = Vec::new();
+            allocation.effective = Vec::new();
+        }
+    }
+
+    fn check(&self, address: u64, size: u64) -> Result<(usize, usize), MemoryError> {
+        let (index, offset) = split(address);
+        if address == 0 {
+            return Err(MemoryError::Null);
+        }
+        let allocation = match self.allocations.get(index) {
+            Some(allocation) if index != 0 => allocation,
+            _ => return Err(MemoryError::Invalid),
+        };
+        if let AllocationKind::Function(_) = allocation.kind {
+            return Err(MemoryError::Function);
+        }
+        if !allocation.live && allocation.kind == AllocationKind::Heap {
+            return Err(MemoryError::Freed);
+        }
+        if !allocation.live {
+            return Err(MemoryError::Dead);
+        }
+        if offset + size > allocation.bytes.len() as u64 {
+            return Err(MemoryError::OutOfBounds);
+        }
+        Ok((index, offset as usize))
+    }
+
+    pub fn read(&self, address: u64, size: u64) -> Result<&[u8], MemoryError> {
+        let (index, offset) = self.check(address, size)?;
+        Ok(&self.allocations[index].bytes[offset..offset + size as usize])
+    }
+
+    pub fn write(&mut self, address: u64, bytes: &[u8]) -> Result<(), MemoryError> {
+        let (index, offset) = self.check(address, bytes.len() as u64)?;
+        let allocation = &mut self.allocations[index];
+        if allocation.kind == AllocationKind::String {
+            return Err(MemoryError::ReadOnly);
+        }
+        allocation.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        allocation.initialized[offset..offset + bytes.len()].fill(true);
+        Ok(())
+    }
+
+    //copies the bytes with whether they are initialized and their effective types, as
+    //memcpy does
+    pub fn copy(&mut self, destination: u64, source: u64, size: u64) -> Result<(), MemoryError> {
+        let (index, offset) = self.check(source, size)?;
+        let range = offset..offset + size as usize;
+        let source = &self.allocations[index];
+        let bytes = source.bytes[range.clone()].to_vec();
+        let initialized = source.initialized[range.clone()].to_vec();
+        let effective = source.effective[range].to_vec();
+        self.write(destination, &bytes)?;
+        let (index, offset) = self.check(destination, size)?;
+        let range = offset..offset + size as usize;
+        let destination = &mut self.allocations[index];
+        destination.initialized[range.clone()].copy_from_slice(&initialized);
+        destination.effective[range].copy_from_slice(&effective);
+        Ok(())
+    }
+
+    //whether every byte of the object at address was stored to, which an accessible
+    //object is assumed to be
+    pub fn is_initialized(&self, address: u64, size: u64) -> bool {
+        match self.check(address, size) {
+            Ok((index, offset)) => self.allocations[index].initialized
+                [offset..offset + size as usize]
+                .iter()
+                .all(|initialized| *initialized),
+            Err(_) => true,
+        }
+    }
+
+    //the type of the last store to any of the bytes
+    pub fn effective_type(&self, address: u64, size: u64) -> Option<AliasClass> {
+        let (index, offset) = self.check(address, size).ok()?;
+        self.allocations[index].effective[offset..offset + size as usize]
+            .iter()
+            .find_map(|class| *class)
+    }
+
+    pub fn set_effective_type(&mut self, address: u64, size: u64, class: Option<AliasClass>) {
+        if let Ok((index, offset)) = self.check(address, size) {
+            self.allocations[index].effective[offset..offset + size as usize].fill(class);
+        }
+    }
+
+    //the bytes of the null-terminated string at address, without the null character
+    pub fn read_string(&self, address: u64) -> Result<Vec<u8>, MemoryError> {
+        let mut result = Vec::new();
+        loop {
+            let byte = self.read(address + result.len() as u64, 1)?[0];
+            if byte == 0 {
+                return Ok(result);
+            }
+            result.push(byte);
+        }
+    }
+}
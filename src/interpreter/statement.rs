@@ -0,0 +1,530 @@
+use crate::interpreter::builtin::*;
+use crate::interpreter::expression::*;
+use crate::interpreter::memory::*;
+use crate::interpreter::util::*;
+use crate::interpreter::value::*;
+use crate::semantic::constant::*;
+use crate::semantic::control_flow::*;
+use crate::semantic::declaration::*;
+use crate::semantic::expression::*;
+use crate::semantic::initializer::*;
+use crate::semantic::statement::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+use crate::util::*;
+
+//how the execution of a statement ends
+#[derive(Debug)]
+pub enum Flow<'a> {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+    Goto(&'a str),
+}
+
+//a label control jumps to. the statements are searched for it without executing anything
+//until it is found, which enters the blocks and loops around it the way a jump does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target<'a> {
+    Label(&'a str),
+    Case(i128),
+    Default,
+}
+
+//whether target is in statement. a case or default label belongs to the switch being
+//entered, not to one nested in it.
+fn contains(statement: &TypedStatement, target: Target) -> bool {
+    let found = match (statement, target) {
+        (TypedStatement::Labeled { label, .. }, Target::Label(name)) => *label == name,
+        (TypedStatement::Case { value, .. }, Target::Case(case)) => *value == case,
+        (TypedStatement::CaseRange { first, last, .. }, Target::Case(case)) => {
+            *first <= case && case <= *last
+        }
+        (TypedStatement::Default(_), Target::Default) => true,
+        (TypedStatement::Switch { .. }, Target::Case(_) | Target::Default) => return false,
+        _ => false,
+    };
+    found
+        || children(statement)
+            .into_iter()
+            .any(|child| contains(child, target))
+}
+
+//target is the label being sought, and None once it is found
+pub fn execute<'a>(
+    context: &mut InterpreterContext,
+    statement: &TypedStatement<'a>,
+    target: &mut Option<Target<'a>>,
+) -> RuntimeResult<Flow<'a>> {
+    if let Some(sought) = *target {
+        if !contains(statement, sought) {
+            return Ok(Flow::Normal);
+        }
+    }
+
+    match statement {
+        TypedStatement::Null | TypedStatement::Error => Ok(Flow::Normal),
+        TypedStatement::Expression(expression) => {
+            full_expression(context, expression)?;
+            Ok(Flow::Normal)
+        }
+        TypedStatement::If {
+            condition,
+            statement,
+            else_statement,
+        } => {
+            let taken = match *target {
+                Some(sought) => contains(statement, sought),
+                None => full_expression(context, condition)?.is_true(),
+            };
+            match (taken, else_statement) {
+                (true, _) => execute(context, statement, target),
+                (false, Some(else_statement)) => execute(context, else_statement, target),
+                (false, None) => Ok(Flow::Normal),
+            }
+        }
+        TypedStatement::Switch {
+            condition,
+            statement,
+        } => {
+            let flow = if target.is_some() {
+                execute(context, statement, target)?
+            } else {
+                let value = full_expression(context, condition)?.integer();
+                let mut case = [Target::Case(value), Target::Default]
+                    .iter()
+                    .copied()
+                    .find(|case| contains(statement, *case));
+                if case.is_none() {
+                    return Ok(Flow::Normal);
+                }
+                execute(context, statement, &mut case)?
+            };
+            match flow {
+                Flow::Break => Ok(Flow::Normal),
+                flow => Ok(flow),
+            }
+        }
+        TypedStatement::Labeled { label, statement } => {
+            if *target == Some(Target::Label(label)) {
+                *target = None;
+            }
+            execute(context, statement, target)
+        }
+        TypedStatement::Case { value, statement } => {
+            if *target == Some(Target::Case(*value)) {
+                *target = None;
+            }
+            execute(context, statement, target)
+        }
+        TypedStatement::CaseRange {
+            first,
+            last,
+            statement,
+        } => {
+            if matches!(*target, Some(Target::Case(case)) if *first <= case && case <= *last) {
+                *target = None;
+            }
+            execute(context, statement, target)
+        }
+        TypedStatement::Default(statement) => {
+            if *target == Some(Target::Default) {
+                *target = None;
+            }
+            execute(context, statement, target)
+        }
+        TypedStatement::While {
+            condition,
+            statement,
+        } => iterate(context, Some(condition), None, statement, target),
+        TypedStatement::DoWhile {
+            condition,
+            statement,
+        } => {
+            loop {
+                match execute(context, statement, target)? {
+                    Flow::Break => break,
+                    Flow::Normal | Flow::Continue => {}
+                    flow => return Ok(flow),
+                }
+                if !full_expression(context, condition)?.is_true() {
+                    break;
+                }
+            }
+            Ok(Flow::Normal)
+        }
+        //the objects declared in the initialization live until the loop ends
+        TypedStatement::For {
+            initialization,
+            condition,
+            afterthought,
+            statement,
+        } => {
+            let declarations = match initialization {
+                Some(Either::Left(declarations)) => declarations.as_slice(),
+                _ => &[],
+            };
+            let objects = enter_block(context, declarations.iter())?;
+            if target.is_none() {
+                match initialization {
+                    Some(Either::Left(declarations)) => declare(context, declarations)?,
+                    Some(Either::Right(expression)) => {
+                        full_expression(context, expression)?;
+                    }
+                    None => {}
+                }
+            }
+            let flow = iterate(
+                context,
+                condition.as_deref(),
+                afterthought.as_deref(),
+                statement,
+                target,
+            )?;
+            leave_block(context, &objects);
+            Ok(flow)
+        }
+        TypedStatement::Compound(items) => {
+            let objects = enter_block(context, block_declarations(items))?;
+            let flow = block(context, items, target)?;
+            leave_block(context, &objects);
+            Ok(flow)
+        }
+        TypedStatement::Return(expression) => {
+            let value = match expression {
+                Some(expression) => full_expression(context, expression)?,
+                None => Value::Void,
+            };
+            Ok(Flow::Return(value))
+        }
+        TypedStatement::Break => Ok(Flow::Break),
+        TypedStatement::Continue => Ok(Flow::Continue),
+        TypedStatement::Goto(label) => Ok(Flow::Goto(label)),
+        TypedStatement::Asm(_) => {
+            let function = symbol(context, context.frames.last().unwrap().function);
+            let message = format!(
+                "inline assembly in '{}' cannot be interpreted.",
+                function.name
+            );
+            error(message, function.span)
+        }
+    }
+}
+
+//a while or for loop, whose condition is not evaluated when the loop is entered by a jump
+fn iterate<'a>(
+    context: &mut InterpreterContext,
+    condition: Option<&TypedExpression>,
+    afterthought: Option<&TypedExpression>,
+    statement: &TypedStatement<'a>,
+    target: &mut Option<Target<'a>>,
+) -> RuntimeResult<Flow<'a>> {
+    loop {
+        if let (None, Some(condition)) = (*target, condition) {
+            if !full_expression(context, condition)?.is_true() {
+                break;
+            }
+        }
+        match execute(context, statement, target)? {
+            Flow::Break => break,
+            Flow::Normal | Flow::Continue => {}
+            flow => return Ok(flow),
+        }
+        if let Some(afterthought) = afterthought {
+            full_expression(context, afterthought)?;
+        }
+    }
+    Ok(Flow::Normal)
+}
+
+//an expression which is not part of another one, whose evaluation is complete before the
+//next one starts
+fn full_expression(
+    context: &mut InterpreterContext,
+    expression: &TypedExpression,
+) -> RuntimeResult<Value> {
+    context.accesses.clear();
+    value(context, expression)
+}
+
+fn block_declarations<'a, 'b>(
+    items: &'b [TypedBlockItem<'a>],
+) -> impl Iterator<Item = &'b TypedDeclaration<'a>> {
+    items.iter().flat_map(|item| match item {
+        TypedBlockItem::Declaration(declarations) => declarations.as_slice(),
+        TypedBlockItem::Statement(_) => &[],
+    })
+}
+
+//a jump to a label in the block searches for it from the first item again
+fn block<'a>(
+    context: &mut InterpreterContext,
+    items: &[TypedBlockItem<'a>],
+    target: &mut Option<Target<'a>>,
+) -> RuntimeResult<Flow<'a>> {
+    let mut position = 0;
+    while position < items.len() {
+        let flow = match &items[position] {
+            TypedBlockItem::Declaration(declarations) => {
+                if target.is_none() {
+                    declare(context, declarations)?;
+                }
+                Flow::Normal
+            }
+            TypedBlockItem::Statement(statement) => execute(context, statement, target)?,
+        };
+        position += 1;
+        match flow {
+            Flow::Normal => {}
+            Flow::Goto(label)
+                if items.iter().any(|item| match item {
+                    TypedBlockItem::Statement(statement) => {
+                        contains(statement, Target::Label(label))
+                    }
+                    TypedBlockItem::Declaration(_) => false,
+                }) =>
+            {
+                *target = Some(Target::Label(label));
+                position = 0;
+            }
+            flow => return Ok(flow),
+        }
+    }
+    Ok(Flow::Normal)
+}
+
+//allocates the objects of a block with automatic storage duration, and initializes those
+//with static storage duration the first time the block is entered. returns the automatic
+//ones, which leave_block releases.
+fn enter_block<'a, 'b>(
+    context: &mut InterpreterContext,
+    declarations: impl Iterator<Item = &'b TypedDeclaration<'a>>,
+) -> RuntimeResult<Vec<SymbolId>>
+where
+    'a: 'b,
+{
+    let mut result = Vec::new();
+    for declaration in declarations {
+        let id = declaration.symbol;
+        let symbol = symbol(context, id);
+        if symbol.kind != SymbolKind::Object || symbol.linkage != Linkage::None {
+            continue;
+        }
+        //a variable length array is allocated when its length is known
+        if context.types.is_variably_sized(symbol.ty) {
+            result.push(id);
+            continue;
+        }
+        let size = size_of(context, symbol.ty, symbol.span)?;
+        let align = object_align(context.types, symbol);
+        if has_static_storage(symbol) {
+            if !context.globals.contains_key(&id) {
+                let address = context.memory.allocate(AllocationKind::Static, size, align);
+                context.globals.insert(id, address);
+                if let Some(initializer) = &declaration.initializer {
+                    initialize(context, Place::object(address), symbol.ty, initializer)?;
+                }
+            }
+            continue;
+        }
+        let address = context
+            .memory
+            .allocate(AllocationKind::Automatic, size, align);
+        frame(context).locals.insert(id, address);
+        result.push(id);
+    }
+    Ok(result)
+}
+
+fn leave_block(context: &mut InterpreterContext, objects: &[SymbolId]) {
+    for id in objects {
+        if let Some(address) = frame(context).locals.remove(id) {
+            context.memory.release(address);
+        }
+    }
+}
+
+//evaluates the lengths of the variable length arrays of the declarations and allocates
+//those arrays, and initializes the automatic objects which have initializers. the members
+//and elements the initializer leaves out are zero.
+fn declare(
+    context: &mut InterpreterContext,
+    declarations: &[TypedDeclaration],
+) -> RuntimeResult<()> {
+    for declaration in declarations {
+        context.accesses.clear();
+        evaluate_lengths(context, &declaration.lengths)?;
+        let declared = symbol(context, declaration.symbol);
+        if declared.kind == SymbolKind::Object && context.types.is_variably_sized(declared.ty) {
+            let size = size_of(context, declared.ty, declared.span)?;
+            let align = object_align(context.types, declared);
+            let address = context
+                .memory
+                .allocate(AllocationKind::Automatic, size, align);
+            //a jump back before the declaration ends the lifetime of the array it made
+            if let Some(previous) = frame(context).locals.insert(declaration.symbol, address) {
+                context.memory.release(previous);
+            }
+        }
+
+        let initializer = match &declaration.initializer {
+            Some(initializer) => initializer,
+            None => continue,
+        };
+        let address = match frame(context).locals.get(&declaration.symbol) {
+            Some(address) => *address,
+            None => continue,
+        };
+        let symbol = symbol(context, declaration.symbol);
+        zero(context, address, symbol.ty, symbol.span)?;
+        context.accesses.clear();
+        initialize(context, Place::object(address), symbol.ty, initializer)?;
+    }
+    Ok(())
+}
+
+//fills the object at address with zeros, which are the values of the subobjects an
+//initializer leaves out
+pub fn zero(
+    context: &mut InterpreterContext,
+    address: u64,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<()> {
+    let size = size_of(context, ty, span)?;
+    match context.memory.write(address, &vec![0; size as usize]) {
+        Ok(()) => Ok(()),
+        Err(error) => memory_error(error, "write", span),
+    }
+}
+
+//stores the values of initializer into the object at place, which is already zero. a
+//string literal initializes a character array by its characters, as many as fit.
+pub fn initialize(
+    context: &mut InterpreterContext,
+    place: Place,
+    ty: TypeId,
+    initializer: &TypedInitializer,
+) -> RuntimeResult<()> {
+    for (subobjects, expression) in initializer.stores(ty) {
+        let span = expression.span;
+        if let (TypedExpressionKind::String(content), true) =
+            (&expression.kind, context.types.is_array(subobjects[0].ty))
+        {
+            let mut bytes = string_bytes(content);
+            bytes.push(0);
+            bytes.truncate(size_of(context, subobjects[0].ty, span)? as usize);
+            for subobject in subobjects {
+                let address = place.address + subobject.offset;
+                if let Err(error) = context.memory.write(address, &bytes) {
+                    return memory_error(error, "write", span);
+                }
+            }
+            continue;
+        }
+
+        //the expression of a range designator is evaluated once for all its subobjects
+        let value = value(context, expression)?;
+        for subobject in subobjects {
+            let value = convert(context, value.clone(), subobject.ty, span)?;
+            let place = Place {
+                address: place.address + subobject.offset,
+                bit_field: subobject.bit_field,
+            };
+            store(context, place, subobject.ty, &value, span)?;
+        }
+    }
+    Ok(())
+}
+
+//the value of a statement expression is that of its last expression statement, which is
+//evaluated before the objects of the block end
+pub fn statement_expression(
+    context: &mut InterpreterContext,
+    body: &TypedStatement,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let items = match body {
+        TypedStatement::Compound(items) => items,
+        _ => unreachable!(),
+    };
+    let (rest, last) = match items.split_last() {
+        Some((TypedBlockItem::Statement(TypedStatement::Expression(last)), rest)) => {
+            (rest, Some(last))
+        }
+        _ => (items.as_slice(), None),
+    };
+
+    //the statements in it are full expressions of their own
+    let accesses = std::mem::take(&mut context.accesses);
+    let objects = enter_block(context, block_declarations(items))?;
+    let result = match block(context, rest, &mut None)? {
+        Flow::Normal => match last {
+            Some(last) => full_expression(context, last)?,
+            None => Value::Void,
+        },
+        _ => {
+            let message = "a jump out of a statement expression cannot be interpreted.";
+            return error(message.to_string(), span);
+        }
+    };
+    leave_block(context, &objects);
+    context.accesses = accesses;
+    Ok(result)
+}
+
+//calls the function id defines, or the builtin of its name if it is not defined. the
+//arguments are converted to the types of the parameters.
+pub fn call_function(
+    context: &mut InterpreterContext,
+    id: SymbolId,
+    arguments: Vec<Value>,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let definition = match context.functions.get(&id) {
+        Some(definition) => *definition,
+        None => {
+            let name = assembler_name(symbol(context, id));
+            return match builtin(context, name, &arguments, span)? {
+                Some(value) => Ok(value),
+                None => error(format!("undefined reference to '{}'.", name), span),
+            };
+        }
+    };
+    if context.frames.len() >= MAX_CALL_DEPTH {
+        let message = format!("stack overflow: more than {} nested calls.", MAX_CALL_DEPTH);
+        return error(message, span);
+    }
+
+    //the execution of the function is sequenced with the expression calling it
+    let accesses = std::mem::take(&mut context.accesses);
+    context.frames.push(Frame::new(id));
+    for (parameter, argument) in definition.parameters.iter().zip(arguments) {
+        let parameter = match parameter {
+            Some(parameter) => *parameter,
+            None => continue,
+        };
+        let ty = symbol(context, parameter).ty;
+        let size = size_of(context, ty, span)?;
+        let align = context.types.align_of(ty).unwrap_or(1);
+        let address = context
+            .memory
+            .allocate(AllocationKind::Automatic, size, align);
+        frame(context).locals.insert(parameter, address);
+        let argument = convert(context, argument, ty, span)?;
+        store(context, Place::object(address), ty, &argument, span)?;
+    }
+    let flow = execute(context, &definition.body, &mut None)?;
+    let frame = context.frames.pop().unwrap();
+    for address in frame.locals.values().chain(&frame.temporaries) {
+        context.memory.release(*address);
+    }
+    context.accesses = accesses;
+
+    match flow {
+        Flow::Return(value) => Ok(value),
+        _ => Ok(Value::Void),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::diagnostic::*;
+use crate::interpreter::check::*;
+use crate::interpreter::memory::*;
+use crate::semantic::expression::*;
+use crate::semantic::external_declaration::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+use std::collections::HashMap;
+use std::io::Write;
+
+//recursion deeper than this is reported as a stack overflow instead of overflowing the
+//stack of the interpreter
+pub const MAX_CALL_DEPTH: usize = 2000;
+
+pub struct InterpreterContext<'a> {
+    pub types: &'a TypeArena,
+    pub symbols: &'a SymbolTable,
+    pub functions: HashMap<SymbolId, &'a TypedFunctionDefinition<'a>>, // the defined ones
+    pub memory: Memory,
+    pub globals: HashMap<SymbolId, u64>, // objects with static storage duration and functions
+    pub frames: Vec<Frame>,              // of the functions being executed, main first
+    pub strings: HashMap<(usize, usize), u64>, // the arrays of string literals by their source
+    pub compound_literals: HashMap<CompoundLiteralId, u64>, // the ones at file scope
+    pub output: &'a mut (dyn Write + Send),
+    pub checked: bool,         // whether undefined behavior is detected
+    pub accesses: Vec<Access>, // of objects by the full expression being evaluated, when checked
+}
+
+//the objects of a function being executed
+#[derive(Debug)]
+pub struct Frame {
+    pub function: SymbolId,
+    pub locals: HashMap<SymbolId, u64>,
+    pub temporaries: Vec<u64>, // released when the function returns
+    pub lengths: HashMap<LengthId, u64>, // of the variable length arrays declared so far
+}
+
+impl Frame {
+    pub fn new(function: SymbolId) -> Frame {
+        Frame {
+            function,
+            locals: HashMap::new(),
+            temporaries: Vec::new(),
+            lengths: HashMap::new(),
+        }
+    }
+}
+
+impl<'a> InterpreterContext<'a> {
+    pub fn new(
+        types: &'a TypeArena,
+        symbols: &'a SymbolTable,
+        output: &'a mut (dyn Write + Send),
+    ) -> InterpreterContext<'a> {
+        InterpreterContext {
+            types,
+            symbols,
+            functions: HashMap::new(),
+            memory: Memory::new(),
+            globals: HashMap::new(),
+            frames: Vec::new(),
+            strings: HashMap::new(),
+            compound_literals: HashMap::new(),
+            output,
+            checked: false,
+            accesses: Vec::new(),
+        }
+    }
+}
+
+//why execution stops before main returns
+#[derive(Debug)]
+pub enum Interruption {
+    Error(Diagnostic),
+    Exit(i32), // by a call of exit
+}
+
+pub type RuntimeResult<T> = Result<T, Interruption>;
+
+pub fn error<T>(message: String, span: Span) -> RuntimeResult<T> {
+    Err(Interruption::Error(Diagnostic::error(message, span)))
+}
+
+pub fn memory_error<T>(error: MemoryError, access: &str, span: Span) -> RuntimeResult<T> {
+    self::error(error.describe(access), span)
+}
+
+pub fn symbol<'a>(context: &InterpreterContext<'a>, id: SymbolId) -> &'a Symbol {
+    context.symbols.get(id)
+}
+
+pub fn frame<'b>(context: &'b mut InterpreterContext) -> &'b mut Frame {
+    context.frames.last_mut().unwrap()
+}
+
+//the size of a variable length array is its length, evaluated at its declaration, times
+//the size of its elements
+pub fn size_of(context: &InterpreterContext, ty: TypeId, span: Span) -> RuntimeResult<u64> {
+    let length = match context.types.kind(ty) {
+        TypeKind::Array(element, size) if context.types.is_variably_sized(ty) => {
+            let length = match size {
+                ArraySize::Constant(length) => Some(*length),
+                ArraySize::Variable(length) => context
+                    .frames
+                    .last()
+                    .and_then(|frame| frame.lengths.get(length).copied()),
+                ArraySize::Incomplete => None,
+            };
+            length.map(|length| (length, *element))
+        }
+        _ => None,
+    };
+    if let Some((length, element)) = length {
+        return Ok(length.wrapping_mul(size_of(context, element, span)?));
+    }
+
+    match context.types.size_of(ty) {
+        Some(size) => Ok(size),
+        None => error(
+            format!(
+                "cannot interpret an object of type '{}'.",
+                context.types.display(ty)
+            ),
+            span,
+        ),
+    }
+}
@@ -0,0 +1,264 @@
+use crate::interpreter::check::*;
+use crate::interpreter::util::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+//the value of an expression. an integer has the value it has in its type, a pointer is the
+//integer of its address, and a struct or union is the bytes of its object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i128),
+    Floating(f64),
+    Bytes(Vec<u8>),
+    Void,
+}
+
+//an lvalue designates the object at address, or some bits of the bytes there for a
+//bit-field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Place {
+    pub address: u64,
+    pub bit_field: Option<(u64, u64)>, // the offset of the first bit and the width
+}
+
+impl Place {
+    pub fn object(address: u64) -> Place {
+        Place {
+            address,
+            bit_field: None,
+        }
+    }
+}
+
+//how the values of a type are held in memory. long double is held as a double in its
+//first eight bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Representation {
+    Integer(IntegerKind),
+    Floating(FloatingKind),
+    Pointer,
+    Bytes(u64),
+    Void,
+}
+
+impl Value {
+    pub fn is_true(&self) -> bool {
+        match self {
+            Value::Integer(value) => *value != 0,
+            Value::Floating(value) => *value != 0.0,
+            Value::Bytes(_) | Value::Void => false,
+        }
+    }
+
+    //a floating value is truncated toward zero
+    pub fn integer(&self) -> i128 {
+        match self {
+            Value::Integer(value) => *value,
+            Value::Floating(value) => value.trunc() as i128,
+            Value::Bytes(_) | Value::Void => 0,
+        }
+    }
+
+    pub fn floating(&self) -> f64 {
+        match self {
+            Value::Integer(value) => *value as f64,
+            Value::Floating(value) => *value,
+            Value::Bytes(_) | Value::Void => 0.0,
+        }
+    }
+
+    //the address held by a pointer
+    pub fn address(&self) -> u64 {
+        self.integer() as u64
+    }
+}
+
+pub fn representation(
+    context: &InterpreterContext,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<Representation> {
+    let result = match context.types.kind(ty) {
+        TypeKind::Void => Representation::Void,
+        TypeKind::Integer(_) | TypeKind::Enum(_) => {
+            Representation::Integer(context.types.integer_kind(ty).unwrap())
+        }
+        TypeKind::Floating(kind) => Representation::Floating(*kind),
+        TypeKind::Pointer(_) | TypeKind::Array(_, _) | TypeKind::Function(_) => {
+            Representation::Pointer
+        }
+        TypeKind::Struct(_) | TypeKind::Union(_) => {
+            Representation::Bytes(size_of(context, ty, span)?)
+        }
+        TypeKind::Complex(_) => {
+            let message = "complex types are not supported by the interpreter.".to_string();
+            return error(message, span);
+        }
+    };
+    Ok(result)
+}
+
+//reduces value modulo 2 to the width of kind into its range, or to 0 or 1 for _Bool
+pub fn wrap(value: i128, kind: IntegerKind) -> i128 {
+    if kind == IntegerKind::Bool {
+        return i128::from(value != 0);
+    }
+    let modulus = 1i128 << kind.width();
+    let value = value.rem_euclid(modulus);
+    if value > kind.max_value() {
+        value - modulus
+    } else {
+        value
+    }
+}
+
+//converts value to ty as a cast does
+pub fn convert(
+    context: &InterpreterContext,
+    value: Value,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let representation = representation(context, ty, span)?;
+    if let (Representation::Integer(kind), Value::Floating(value)) = (representation, &value) {
+        check_floating_conversion(context, *value, kind, span)?;
+    }
+    let result = match representation {
+        Representation::Void => Value::Void,
+        Representation::Integer(IntegerKind::Bool) => Value::Integer(i128::from(value.is_true())),
+        Representation::Integer(kind) => Value::Integer(wrap(value.integer(), kind)),
+        Representation::Pointer => Value::Integer(wrap(value.integer(), IntegerKind::UnsignedLong)),
+        Representation::Floating(FloatingKind::Float) => {
+            Value::Floating(f64::from(value.floating() as f32))
+        }
+        Representation::Floating(_) => Value::Floating(value.floating()),
+        Representation::Bytes(_) => value,
+    };
+    Ok(result)
+}
+
+//the bytes which hold the bits of a bit-field
+fn bit_field_size(bit_offset: u64, width: u64) -> u64 {
+    (bit_offset + width).div_ceil(8)
+}
+
+fn read(
+    context: &InterpreterContext,
+    address: u64,
+    size: u64,
+    span: Span,
+) -> RuntimeResult<Vec<u8>> {
+    match context.memory.read(address, size) {
+        Ok(bytes) => Ok(bytes.to_vec()),
+        Err(error) => memory_error(error, "read", span),
+    }
+}
+
+fn write(
+    context: &mut InterpreterContext,
+    address: u64,
+    bytes: &[u8],
+    span: Span,
+) -> RuntimeResult<()> {
+    match context.memory.write(address, bytes) {
+        Ok(()) => Ok(()),
+        Err(error) => memory_error(error, "write", span),
+    }
+}
+
+//the little-endian integer in bytes
+fn to_integer(bytes: &[u8]) -> u128 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |result, byte| (result << 8) | u128::from(*byte))
+}
+
+pub fn load(
+    context: &InterpreterContext,
+    place: Place,
+    ty: TypeId,
+    span: Span,
+) -> RuntimeResult<Value> {
+    let representation = representation(context, ty, span)?;
+    if let Some((bit_offset, width)) = place.bit_field {
+        let kind = match representation {
+            Representation::Integer(kind) => kind,
+            _ => unreachable!(),
+        };
+        let size = bit_field_size(bit_offset, width);
+        let bits = to_integer(&read(context, place.address, size, span)?);
+        let value = (bits >> bit_offset) & ((1 << width) - 1);
+        //the sign bit of a signed bit-field is its highest
+        let value = if kind.is_signed() && value >> (width - 1) != 0 {
+            value as i128 - (1 << width)
+        } else {
+            value as i128
+        };
+        return Ok(Value::Integer(wrap(value, kind)));
+    }
+
+    let result = match representation {
+        Representation::Integer(kind) => {
+            let value = to_integer(&read(context, place.address, kind.size(), span)?);
+            Value::Integer(wrap(value as i128, kind))
+        }
+        Representation::Pointer => {
+            let value = to_integer(&read(context, place.address, 8, span)?);
+            Value::Integer(value as i128)
+        }
+        Representation::Floating(FloatingKind::Float) => {
+            let bytes = read(context, place.address, 4, span)?;
+            Value::Floating(f64::from(f32::from_bits(to_integer(&bytes) as u32)))
+        }
+        Representation::Floating(_) => {
+            let bytes = read(context, place.address, 8, span)?;
+            Value::Floating(f64::from_bits(to_integer(&bytes) as u64))
+        }
+        Representation::Bytes(size) => Value::Bytes(read(context, place.address, size, span)?),
+        Representation::Void => Value::Void,
+    };
+    Ok(result)
+}
+
+pub fn store(
+    context: &mut InterpreterContext,
+    place: Place,
+    ty: TypeId,
+    value: &Value,
+    span: Span,
+) -> RuntimeResult<()> {
+    let representation = representation(context, ty, span)?;
+    if let Some((bit_offset, width)) = place.bit_field {
+        let size = bit_field_size(bit_offset, width);
+        let bits = to_integer(&read(context, place.address, size, span)?);
+        let mask = ((1u128 << width) - 1) << bit_offset;
+        let bits = (bits & !mask) | (((value.integer() as u128) << bit_offset) & mask);
+        return write(
+            context,
+            place.address,
+            &bits.to_le_bytes()[..size as usize],
+            span,
+        );
+    }
+
+    let bytes = match (representation, value) {
+        (Representation::Integer(kind), value) => {
+            value.integer().to_le_bytes()[..kind.size() as usize].to_vec()
+        }
+        (Representation::Pointer, value) => value.address().to_le_bytes().to_vec(),
+        (Representation::Floating(FloatingKind::Float), value) => {
+            (value.floating() as f32).to_le_bytes().to_vec()
+        }
+        (Representation::Floating(kind), value) => {
+            let mut bytes = value.floating().to_le_bytes().to_vec();
+            bytes.resize(kind.size() as usize, 0);
+            bytes
+        }
+        (Representation::Bytes(_), Value::Bytes(bytes)) => bytes.clone(),
+        (Representation::Bytes(_), _) | (Representation::Void, _) => return Ok(()),
+    };
+    write(context, place.address, &bytes, span)?;
+    set_effective_type(context, place, representation);
+    Ok(())
+}
@@ -0,0 +1,107 @@
+pub mod builder;
+pub mod expression;
+pub mod global;
+pub mod module;
+pub mod parse;
+pub mod print;
+pub mod statement;
+mod util;
+pub mod verify;
+use crate::diagnostic::*;
+use crate::ir::global::*;
+use crate::ir::module::*;
+use crate::ir::statement::*;
+use crate::ir::util::*;
+use crate::semantic::external_declaration::*;
+use crate::semantic::initializer::*;
+use crate::semantic::symbol::*;
+use crate::semantic::TypedAST;
+use std::collections::HashMap;
+
+//lowers the typed tree to a module of the IR, in SSA form: the scalar locals whose address
+//is never taken are values, with phis where control joins, and any other object is in
+//memory. the entities used but not defined are declared, and are defined elsewhere.
+pub fn lower(typed: &TypedAST) -> Result<Module, Diagnostic> {
+    let mut context = LowerContext::new(&typed.types, &typed.symbols);
+
+    //an object declared more than once is defined once, by the declaration with an
+    //initializer if there is one
+    let mut objects = Vec::new();
+    let mut initializers: HashMap<SymbolId, &TypedInitializer> = HashMap::new();
+    let mut functions = Vec::new();
+    for external_declaration in &typed.root {
+        match external_declaration {
+            TypedExternalDeclaration::FunctionDefinition(definition) => {
+                global_name(&mut context, definition.symbol);
+                functions.push(definition);
+            }
+            TypedExternalDeclaration::Declaration(declarations) => {
+                for declaration in declarations {
+                    let id = declaration.symbol;
+                    let symbol = symbol(&context, id);
+                    if symbol.kind != SymbolKind::Object
+                        || symbol.definition == Definition::Declaration
+                    {
+                        continue;
+                    }
+                    if !objects.contains(&id) {
+                        objects.push(id);
+                    }
+                    if let Some(initializer) = &declaration.initializer {
+                        initializers.insert(id, initializer);
+                    }
+                }
+            }
+        }
+    }
+    for id in objects {
+        let name = global_name(&mut context, id);
+        let initializer = initializers.get(&id).copied();
+        define_global(&mut context, id, name, initializer)?;
+    }
+    for definition in functions {
+        function_definition(&mut context, definition)?;
+    }
+
+    let referenced = std::mem::take(&mut context.referenced);
+    for id in referenced {
+        let name = context.names[&id].clone();
+        let module = &context.module;
+        let is_defined = module.globals.iter().any(|global| global.name == name)
+            || module
+                .functions
+                .iter()
+                .any(|function| function.name == name);
+        if !is_defined {
+            declare(&mut context, id, name)?;
+        }
+    }
+    Ok(context.module)
+}
+
+fn declare(context: &mut LowerContext, id: SymbolId, name: String) -> Result<(), Diagnostic> {
+    let symbol = symbol(context, id);
+    if symbol.kind == SymbolKind::Function {
+        let (return_type, parameters, variadic) = signature(context, symbol.ty, symbol.span)?;
+        let prototype = context.types.function_type(symbol.ty).unwrap().prototype;
+        context.module.functions.push(Function {
+            name,
+            return_type,
+            parameters,
+            variadic: variadic || !prototype,
+            internal: false,
+            instructions: Vec::new(),
+            blocks: Vec::new(),
+        });
+    } else {
+        //an array of unknown size is as long as its definition makes it
+        context.module.globals.push(Global {
+            name,
+            size: context.types.size_of(symbol.ty).unwrap_or(0),
+            align: align_of(context, symbol.ty),
+            internal: false,
+            initializer: None,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,380 @@
+use crate::ir::module::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+//a local variable held in SSA values instead of memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Variable(usize);
+
+//builds a function in SSA form as it is lowered, by the algorithm of Braun et al., "Simple
+//and Efficient Construction of Static Single Assignment Form". a read of a variable takes
+//the value written last in the block, and otherwise a phi of its values in the
+//predecessors. a block is sealed once all its predecessors are known, and until then its
+//phis are left incomplete.
+#[derive(Debug)]
+pub struct FunctionBuilder {
+    pub function: Function,
+    current: BlockId,
+    allocas: usize, // at the start of the entry block
+    predecessors: Vec<Vec<BlockId>>,
+    sealed: Vec<bool>,
+    definitions: HashMap<(BlockId, Variable), Operand>,
+    incomplete: HashMap<BlockId, Vec<(Variable, ValueId)>>,
+    variables: Vec<IrType>,
+}
+
+impl FunctionBuilder {
+    //the entry block is current, and sealed since nothing jumps to it
+    pub fn new(
+        name: String,
+        return_type: IrType,
+        parameters: Vec<IrType>,
+        variadic: bool,
+        internal: bool,
+    ) -> FunctionBuilder {
+        let instructions = parameters
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| Instruction {
+                ty: *ty,
+                kind: InstructionKind::Parameter(index),
+            })
+            .collect();
+        FunctionBuilder {
+            function: Function {
+                name,
+                return_type,
+                parameters,
+                variadic,
+                internal,
+                instructions,
+                blocks: vec![Block::default()],
+            },
+            current: BlockId(0),
+            allocas: 0,
+            predecessors: vec![Vec::new()],
+            sealed: vec![true],
+            definitions: HashMap::new(),
+            incomplete: HashMap::new(),
+            variables: Vec::new(),
+        }
+    }
+
+    pub fn parameter(&self, index: usize) -> Operand {
+        Operand::Value(ValueId(index))
+    }
+
+    pub fn new_block(&mut self) -> BlockId {
+        self.function.blocks.push(Block::default());
+        self.predecessors.push(Vec::new());
+        self.sealed.push(false);
+        BlockId(self.function.blocks.len() - 1)
+    }
+
+    pub fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.function.blocks[self.current.0].terminator.is_some()
+    }
+
+    //code after a jump goes into a block nothing enters, which finish removes
+    fn open(&mut self) {
+        if self.is_terminated() {
+            let block = self.new_block();
+            self.sealed[block.0] = true;
+            self.current = block;
+        }
+    }
+
+    //the block the next instruction goes into, which is where a branch to a phi leaves from
+    pub fn insertion_block(&mut self) -> BlockId {
+        self.open();
+        self.current
+    }
+
+    fn add(&mut self, ty: IrType, kind: InstructionKind) -> ValueId {
+        self.function.instructions.push(Instruction { ty, kind });
+        ValueId(self.function.instructions.len() - 1)
+    }
+
+    pub fn append(&mut self, ty: IrType, kind: InstructionKind) -> Operand {
+        self.open();
+        let id = self.add(ty, kind);
+        self.function.blocks[self.current.0].instructions.push(id);
+        Operand::Value(id)
+    }
+
+    //a stack slot is allocated once on entry, wherever its object is declared
+    pub fn alloca(&mut self, size: u64, align: u64) -> Operand {
+        let id = self.add(IrType::Ptr, InstructionKind::Alloca { size, align });
+        self.function.blocks[0]
+            .instructions
+            .insert(self.allocas, id);
+        self.allocas += 1;
+        Operand::Value(id)
+    }
+
+    //a phi whose incoming values are given, at the start of the current block
+    pub fn phi(&mut self, ty: IrType, incoming: Vec<(BlockId, Operand)>) -> Operand {
+        self.open();
+        Operand::Value(self.insert_phi(self.current, ty, incoming))
+    }
+
+    fn insert_phi(
+        &mut self,
+        block: BlockId,
+        ty: IrType,
+        incoming: Vec<(BlockId, Operand)>,
+    ) -> ValueId {
+        let id = self.add(ty, InstructionKind::Phi(incoming));
+        let instructions = &self.function.instructions;
+        let block = &mut self.function.blocks[block.0];
+        let position = block
+            .instructions
+            .iter()
+            .take_while(|id| matches!(instructions[id.0].kind, InstructionKind::Phi(_)))
+            .count();
+        block.instructions.insert(position, id);
+        id
+    }
+
+    pub fn terminate(&mut self, terminator: Terminator) {
+        self.open();
+        self.terminate_block(self.current, terminator);
+    }
+
+    //the terminator of a block which was left open, such as the dispatch of a switch whose
+    //cases are known once its body is lowered
+    pub fn terminate_block(&mut self, block: BlockId, terminator: Terminator) {
+        for successor in terminator.successors() {
+            let predecessors = &mut self.predecessors[successor.0];
+            if !predecessors.contains(&block) {
+                predecessors.push(block);
+            }
+        }
+        self.function.blocks[block.0].terminator = Some(terminator);
+    }
+
+    //jumps to target unless the current block already ends, as one which returns does
+    pub fn jump(&mut self, target: BlockId) {
+        if !self.is_terminated() {
+            self.terminate(Terminator::Jump(target));
+        }
+    }
+
+    pub fn declare_variable(&mut self, ty: IrType) -> Variable {
+        self.variables.push(ty);
+        Variable(self.variables.len() - 1)
+    }
+
+    pub fn write_variable(&mut self, variable: Variable, value: Operand) {
+        self.open();
+        self.definitions.insert((self.current, variable), value);
+    }
+
+    pub fn read_variable(&mut self, variable: Variable) -> Operand {
+        self.open();
+        self.read(variable, self.current)
+    }
+
+    fn read(&mut self, variable: Variable, block: BlockId) -> Operand {
+        match self.definitions.get(&(block, variable)) {
+            Some(value) => value.clone(),
+            None => self.read_recursive(variable, block),
+        }
+    }
+
+    //a variable which is read before it is written is undefined
+    fn read_recursive(&mut self, variable: Variable, block: BlockId) -> Operand {
+        let ty = self.variables[variable.0];
+        let value = if !self.sealed[block.0] {
+            let phi = self.insert_phi(block, ty, Vec::new());
+            self.incomplete
+                .entry(block)
+                .or_default()
+                .push((variable, phi));
+            Operand::Value(phi)
+        } else if self.predecessors[block.0].len() == 1 {
+            let predecessor = self.predecessors[block.0][0];
+            self.read(variable, predecessor)
+        } else if self.predecessors[block.0].is_empty() {
+            Operand::Undef
+        } else {
+            //the phi is the value in the block while its operands are read, which breaks
+            //the cycles of loops
+            let phi = self.insert_phi(block, ty, Vec::new());
+            self.definitions
+                .insert((block, variable), Operand::Value(phi));
+            self.add_phi_operands(variable, block, phi)
+        };
+        self.definitions.insert((block, variable), value.clone());
+        value
+    }
+
+    fn add_phi_operands(&mut self, variable: Variable, block: BlockId, phi: ValueId) -> Operand {
+        for predecessor in self.predecessors[block.0].clone() {
+            let value = self.read(variable, predecessor);
+            if let InstructionKind::Phi(incoming) = &mut self.function.instructions[phi.0].kind {
+                incoming.push((predecessor, value));
+            }
+        }
+        self.remove_trivial_phi(phi)
+    }
+
+    //a phi whose operands are all one value, or itself, is that value. removing it can make
+    //the phis which used it trivial in turn.
+    fn remove_trivial_phi(&mut self, phi: ValueId) -> Operand {
+        let incoming = match &self.function.instructions[phi.0].kind {
+            InstructionKind::Phi(incoming) => incoming.clone(),
+            _ => return Operand::Value(phi),
+        };
+        let mut same = None;
+        for (_, value) in incoming {
+            if Some(&value) == same.as_ref() || value == Operand::Value(phi) {
+                continue;
+            }
+            if same.is_some() {
+                return Operand::Value(phi);
+            }
+            same = Some(value);
+        }
+        let same = same.unwrap_or(Operand::Undef);
+
+        let users = self.replace_uses(phi, &same);
+        for block in &mut self.function.blocks {
+            block.instructions.retain(|id| *id != phi);
+        }
+        for user in users {
+            if user != phi {
+                self.remove_trivial_phi(user);
+            }
+        }
+        same
+    }
+
+    //replaces the uses of value by replacement, and gives the phis which used it
+    fn replace_uses(&mut self, value: ValueId, replacement: &Operand) -> Vec<ValueId> {
+        let old = Operand::Value(value);
+        let mut phis = Vec::new();
+        let live: HashSet<ValueId> = self
+            .function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter().copied())
+            .collect();
+        for (index, instruction) in self.function.instructions.iter_mut().enumerate() {
+            let mut used = false;
+            for operand in instruction.kind.operands_mut() {
+                if *operand == old {
+                    *operand = replacement.clone();
+                    used = true;
+                }
+            }
+            let is_phi = matches!(instruction.kind, InstructionKind::Phi(_));
+            if used && is_phi && live.contains(&ValueId(index)) {
+                phis.push(ValueId(index));
+            }
+        }
+        for block in &mut self.function.blocks {
+            if let Some(terminator) = &mut block.terminator {
+                for operand in terminator.operands_mut() {
+                    if *operand == old {
+                        *operand = replacement.clone();
+                    }
+                }
+            }
+        }
+        for definition in self.definitions.values_mut() {
+            if *definition == old {
+                *definition = replacement.clone();
+            }
+        }
+        phis
+    }
+
+    pub fn seal(&mut self, block: BlockId) {
+        if self.sealed[block.0] {
+            return;
+        }
+        for (variable, phi) in self.incomplete.remove(&block).unwrap_or_default() {
+            self.add_phi_operands(variable, block, phi);
+        }
+        self.sealed[block.0] = true;
+    }
+
+    //seals the blocks left, such as those of labels which a goto may still jump to, and
+    //removes the blocks control never reaches
+    pub fn finish(mut self) -> Function {
+        for block in 0..self.function.blocks.len() {
+            self.seal(BlockId(block));
+        }
+        self.remove_unreachable_blocks();
+        self.function
+    }
+
+    fn remove_unreachable_blocks(&mut self) {
+        let order = self.function.reverse_postorder();
+        let mut reachable = vec![false; self.function.blocks.len()];
+        for block in &order {
+            reachable[block.0] = true;
+        }
+
+        //the phis lose the values from the blocks which go, which may leave them trivial
+        let mut phis = Vec::new();
+        for (index, block) in self.function.blocks.iter().enumerate() {
+            if !reachable[index] {
+                continue;
+            }
+            for id in &block.instructions {
+                if let InstructionKind::Phi(incoming) = &mut self.function.instructions[id.0].kind {
+                    incoming.retain(|(predecessor, _)| reachable[predecessor.0]);
+                    phis.push(*id);
+                }
+            }
+        }
+        for (index, reachable) in reachable.iter().enumerate() {
+            if !reachable {
+                self.function.blocks[index] = Block::default();
+            }
+        }
+        for phi in phis {
+            let live = self
+                .function
+                .blocks
+                .iter()
+                .any(|block| block.instructions.contains(&phi));
+            if live {
+                self.remove_trivial_phi(phi);
+            }
+        }
+
+        //the blocks which stay are in reverse postorder
+        let mut renumbered = vec![BlockId(0); reachable.len()];
+        for (index, block) in order.iter().enumerate() {
+            renumbered[block.0] = BlockId(index);
+        }
+        let mut blocks: Vec<Option<Block>> = std::mem::take(&mut self.function.blocks)
+            .into_iter()
+            .map(Some)
+            .collect();
+        for block in &order {
+            self.function.blocks.push(blocks[block.0].take().unwrap());
+        }
+        for block in &mut self.function.blocks {
+            if let Some(terminator) = &mut block.terminator {
+                for successor in terminator.successors_mut() {
+                    *successor = renumbered[successor.0];
+                }
+            }
+            for id in &block.instructions {
+                if let InstructionKind::Phi(incoming) = &mut self.function.instructions[id.0].kind {
+                    for (predecessor, _) in incoming {
+                        *predecessor = renumbered[predecessor.0];
+                    }
+                }
+            }
+        }
+    }
+}
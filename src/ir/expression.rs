@@ -0,0 +1,1207 @@
+use crate::ir::builder::*;
+use crate::ir::global::*;
+use crate::ir::module::*;
+use crate::ir::statement::*;
+use crate::ir::util::*;
+use crate::parser::expression::*;
+use crate::semantic::expression::*;
+use crate::semantic::initializer::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+//how the values of a type are computed with. the addresses of arrays, functions, structs
+//and unions are pointers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    Integer(IrType, bool), // whether it is signed
+    Bool,
+    Floating(IrType),
+    Pointer,
+    Void,
+}
+
+impl Class {
+    pub fn ir_type(self) -> IrType {
+        match self {
+            Class::Integer(ty, _) | Class::Floating(ty) => ty,
+            Class::Bool => IrType::I8,
+            Class::Pointer => IrType::Ptr,
+            Class::Void => IrType::Void,
+        }
+    }
+
+    //integers narrower than int are promoted to it, _Bool included
+    fn promoted(self) -> Class {
+        match self {
+            Class::Integer(ty, _) if ty.width() < 32 => Class::Integer(IrType::I32, true),
+            Class::Bool => Class::Integer(IrType::I32, true),
+            class => class,
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(self, Class::Integer(_, true))
+    }
+}
+
+pub fn class(context: &LowerContext, ty: TypeId, span: Span) -> LowerResult<Class> {
+    let result = match ir_type(context, ty, span)? {
+        IrType::Void => Class::Void,
+        IrType::Ptr => Class::Pointer,
+        IrType::F32 => Class::Floating(IrType::F32),
+        IrType::F64 => Class::Floating(IrType::F64),
+        _ if context.types.integer_kind(ty) == Some(IntegerKind::Bool) => Class::Bool,
+        ir_type => Class::Integer(ir_type, is_signed(context, ty)),
+    };
+    Ok(result)
+}
+
+//an object, or a local variable held in SSA values. a bit-field is accessed through the
+//storage unit of its declared type which holds it, shift bits from the lowest one.
+#[derive(Debug, Clone)]
+pub enum Place {
+    Memory(Operand, Option<BitField>),
+    Variable(Variable),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BitField {
+    unit: IrType,
+    shift: u64,
+    width: u64,
+}
+
+impl Place {
+    pub fn address(self) -> Operand {
+        match self {
+            Place::Memory(address, _) => address,
+            Place::Variable(_) => unreachable!(),
+        }
+    }
+
+    fn is_bit_field(&self) -> bool {
+        matches!(self, Place::Memory(_, Some(_)))
+    }
+}
+
+//the value of expression. an array, a function designator, a struct and a union give their
+//addresses and any other lvalue the value stored in its object.
+pub fn value(context: &mut LowerContext, expression: &TypedExpression) -> LowerResult<Operand> {
+    //a member of an rvalue is an rvalue, which is held in memory like any struct or union
+    let is_member = matches!(expression.kind, TypedExpressionKind::Member { .. });
+    if expression.category == ValueCategory::RValue && !is_member {
+        return rvalue(context, expression);
+    }
+
+    let place = place(context, expression)?;
+    if expression.category == ValueCategory::FunctionDesignator
+        || context.types.is_array(expression.ty)
+        || is_record(context, expression.ty)
+    {
+        return Ok(place.address());
+    }
+    load(context, place, expression.ty, expression.span)
+}
+
+//the object an lvalue designates, or the function a function designator does
+pub fn place(context: &mut LowerContext, expression: &TypedExpression) -> LowerResult<Place> {
+    let span = expression.span;
+    match &expression.kind {
+        TypedExpressionKind::Identifier(id) => {
+            let local = context
+                .function
+                .as_ref()
+                .and_then(|state| state.locals.get(id).cloned());
+            match local {
+                Some(Local::Variable(variable)) => Ok(Place::Variable(variable)),
+                Some(Local::Memory(address)) => Ok(Place::Memory(address, None)),
+                None => Ok(Place::Memory(
+                    Operand::Global(global_name(context, *id)),
+                    None,
+                )),
+            }
+        }
+        TypedExpressionKind::Unary {
+            kind: UnaryKind::Dereference,
+            operand,
+        } => Ok(Place::Memory(value(context, operand)?, None)),
+        TypedExpressionKind::Member { operand, index } => {
+            let base = place(context, operand)?.address();
+            let types = context.types;
+            let record = types.record(types.record_of(operand.ty).unwrap());
+            member_place(
+                context,
+                base,
+                &record.members.as_ref().unwrap()[*index],
+                span,
+            )
+        }
+        TypedExpressionKind::String(content) => Ok(Place::Memory(
+            Operand::Global(string_global(context, content)),
+            None,
+        )),
+        TypedExpressionKind::CompoundLiteral { initializer, .. } => {
+            let address = temporary(context, expression.ty, span)?;
+            initialize(context, address.clone(), expression.ty, initializer, span)?;
+            Ok(Place::Memory(address, None))
+        }
+        //a struct or union rvalue, such as the result of a call
+        _ => Ok(Place::Memory(rvalue(context, expression)?, None)),
+    }
+}
+
+fn member_place(
+    context: &mut LowerContext,
+    base: Operand,
+    member: &Member,
+    span: Span,
+) -> LowerResult<Place> {
+    let subobject = Subobject {
+        offset: member.offset,
+        bit_field: member
+            .bit_width
+            .map(|bit_width| (member.bit_offset, bit_width)),
+        ty: member.ty,
+    };
+    subobject_place(context, base, subobject, span)
+}
+
+//the subobject of the object at base
+pub fn subobject_place(
+    context: &mut LowerContext,
+    base: Operand,
+    subobject: Subobject,
+    span: Span,
+) -> LowerResult<Place> {
+    let (bit_offset, width) = match subobject.bit_field {
+        Some(bit_field) => bit_field,
+        None => return Ok(Place::Memory(offset(context, base, subobject.offset), None)),
+    };
+    let unit = ir_type(context, subobject.ty, span)?;
+    let size = u64::from(unit.width() / 8);
+    let bit = subobject.offset * 8 + bit_offset;
+    let start = bit / (size * 8) * size;
+    let shift = bit - start * 8;
+    if shift + width > size * 8 {
+        let message =
+            "a bit-field which straddles the storage unit of its type cannot be lowered to the IR.";
+        return error(message.to_string(), span);
+    }
+    let address = offset(context, base, start);
+    Ok(Place::Memory(
+        address,
+        Some(BitField { unit, shift, width }),
+    ))
+}
+
+//the address bytes after address
+fn offset(context: &mut LowerContext, address: Operand, bytes: u64) -> Operand {
+    if bytes == 0 {
+        return address;
+    }
+    let offset = Operand::Integer(bytes as i64);
+    builder(context).append(
+        IrType::Ptr,
+        InstructionKind::PointerAdd {
+            base: address,
+            offset,
+        },
+    )
+}
+
+//an object the function allocates on entry, for the value of an expression
+pub fn temporary(context: &mut LowerContext, ty: TypeId, span: Span) -> LowerResult<Operand> {
+    let size = size_of(context, ty, span)?;
+    let align = align_of(context, ty);
+    Ok(builder(context).alloca(size, align))
+}
+
+//the size of ty as an i64, which for a variable length array is computed from the length
+//evaluated at its declaration
+pub fn variable_size(context: &mut LowerContext, ty: TypeId, span: Span) -> LowerResult<Operand> {
+    let (element, size) = match context.types.kind(ty) {
+        TypeKind::Array(element, size) if context.types.is_variably_sized(ty) => (*element, *size),
+        _ => return Ok(Operand::Integer(size_of(context, ty, span)? as i64)),
+    };
+    let length = match size {
+        ArraySize::Constant(length) => Operand::Integer(length as i64),
+        ArraySize::Variable(length) => match state(context).lengths.get(&length) {
+            Some(variable) => {
+                let variable = *variable;
+                builder(context).read_variable(variable)
+            }
+            //a length which is not evaluated yet, as in a parameter
+            None => {
+                let message = format!(
+                    "cannot lower an object of type '{}'.",
+                    context.types.display(ty)
+                );
+                return error(message, span);
+            }
+        },
+        ArraySize::Incomplete => unreachable!(),
+    };
+    let element = variable_size(context, element, span)?;
+    Ok(multiply(context, length, element))
+}
+
+//the product of two i64 values, folded when both are constants
+fn multiply(context: &mut LowerContext, lhs: Operand, rhs: Operand) -> Operand {
+    match (lhs, rhs) {
+        (Operand::Integer(lhs), Operand::Integer(rhs)) => Operand::Integer(lhs.wrapping_mul(rhs)),
+        (value, Operand::Integer(1)) | (Operand::Integer(1), value) => value,
+        (lhs, rhs) => append_binary(builder(context), BinaryOperator::Mul, IrType::I64, lhs, rhs),
+    }
+}
+
+//the value stored in place, of type ty. the sign bit of a signed bit-field is its highest.
+pub fn load(
+    context: &mut LowerContext,
+    place: Place,
+    ty: TypeId,
+    span: Span,
+) -> LowerResult<Operand> {
+    let ir_type = ir_type(context, ty, span)?;
+    let signed = is_signed(context, ty);
+    let builder = builder(context);
+    let (address, bit_field) = match place {
+        Place::Variable(variable) => return Ok(builder.read_variable(variable)),
+        Place::Memory(_, _) if ir_type == IrType::Void => return Ok(Operand::Undef),
+        Place::Memory(address, None) => {
+            return Ok(builder.append(ir_type, InstructionKind::Load(address)))
+        }
+        Place::Memory(address, Some(bit_field)) => (address, bit_field),
+    };
+    let BitField { unit, shift, width } = bit_field;
+    let bits = u64::from(unit.width());
+    let value = builder.append(unit, InstructionKind::Load(address));
+    let value = if signed {
+        let value = binary_instruction(
+            builder,
+            BinaryOperator::Shl,
+            unit,
+            value,
+            bits - width - shift,
+        );
+        binary_instruction(builder, BinaryOperator::AShr, unit, value, bits - width)
+    } else {
+        let value = binary_instruction(builder, BinaryOperator::LShr, unit, value, shift);
+        let mask = integer(unit, (1i128 << width) - 1);
+        append_binary(builder, BinaryOperator::And, unit, value, mask)
+    };
+    Ok(value)
+}
+
+//stores value in place. a struct or union is copied from the address which is its value.
+pub fn store(
+    context: &mut LowerContext,
+    place: Place,
+    ty: TypeId,
+    value: Operand,
+    span: Span,
+) -> LowerResult<()> {
+    if is_record(context, ty) {
+        let size = size_of(context, ty, span)?;
+        let kind = InstructionKind::Copy {
+            destination: place.address(),
+            source: value,
+            size,
+        };
+        builder(context).append(IrType::Void, kind);
+        return Ok(());
+    }
+    let ir_type = ir_type(context, ty, span)?;
+    let builder = builder(context);
+    let (address, bit_field) = match place {
+        Place::Variable(variable) => {
+            builder.write_variable(variable, value);
+            return Ok(());
+        }
+        Place::Memory(address, None) => {
+            let kind = InstructionKind::Store {
+                ty: ir_type,
+                value,
+                address,
+            };
+            builder.append(IrType::Void, kind);
+            return Ok(());
+        }
+        Place::Memory(address, Some(bit_field)) => (address, bit_field),
+    };
+
+    //the other bits of the storage unit are kept
+    let BitField { unit, shift, width } = bit_field;
+    let mask = (1i128 << width) - 1;
+    let old = builder.append(unit, InstructionKind::Load(address.clone()));
+    let old = append_binary(
+        builder,
+        BinaryOperator::And,
+        unit,
+        old,
+        integer(unit, !(mask << shift)),
+    );
+    let value = append_binary(
+        builder,
+        BinaryOperator::And,
+        unit,
+        value,
+        integer(unit, mask),
+    );
+    let value = binary_instruction(builder, BinaryOperator::Shl, unit, value, shift);
+    let value = append_binary(builder, BinaryOperator::Or, unit, old, value);
+    let kind = InstructionKind::Store {
+        ty: unit,
+        value,
+        address,
+    };
+    builder.append(IrType::Void, kind);
+    Ok(())
+}
+
+//an operation on integer constants is folded to its result, unless it is a division by zero
+//or a shift by too much, which are left for when the program runs
+fn append_binary(
+    builder: &mut FunctionBuilder,
+    operator: BinaryOperator,
+    ty: IrType,
+    lhs: Operand,
+    rhs: Operand,
+) -> Operand {
+    if let (Operand::Integer(lhs), Operand::Integer(rhs)) = (&lhs, &rhs) {
+        let (lhs, rhs) = (i128::from(*lhs), i128::from(*rhs));
+        let width = i128::from(ty.width());
+        let unsigned = |value: i128| value & ((1 << width) - 1);
+        let shift = (0..width).contains(&rhs);
+        let result = match operator {
+            BinaryOperator::Add => Some(lhs + rhs),
+            BinaryOperator::Sub => Some(lhs - rhs),
+            BinaryOperator::Mul => Some(lhs * rhs),
+            BinaryOperator::And => Some(lhs & rhs),
+            BinaryOperator::Or => Some(lhs | rhs),
+            BinaryOperator::Xor => Some(lhs ^ rhs),
+            BinaryOperator::SDiv if rhs != 0 => Some(lhs / rhs),
+            BinaryOperator::SRem if rhs != 0 => Some(lhs % rhs),
+            BinaryOperator::UDiv if rhs != 0 => Some(unsigned(lhs) / unsigned(rhs)),
+            BinaryOperator::URem if rhs != 0 => Some(unsigned(lhs) % unsigned(rhs)),
+            BinaryOperator::Shl if shift => Some(lhs << rhs),
+            BinaryOperator::LShr if shift => Some(unsigned(lhs) >> rhs),
+            BinaryOperator::AShr if shift => Some(lhs >> rhs),
+            _ => None,
+        };
+        if let Some(result) = result {
+            return integer(ty, result);
+        }
+    }
+    builder.append(ty, InstructionKind::Binary { operator, lhs, rhs })
+}
+
+//an operation by a constant, which is left out when it does nothing, as a shift by 0 does
+fn binary_instruction(
+    builder: &mut FunctionBuilder,
+    operator: BinaryOperator,
+    ty: IrType,
+    value: Operand,
+    constant: u64,
+) -> Operand {
+    if constant == 0 {
+        return value;
+    }
+    append_binary(
+        builder,
+        operator,
+        ty,
+        value,
+        integer(ty, i128::from(constant)),
+    )
+}
+
+//the lengths of variable length arrays, each held in a variable the arrays of their types
+//read
+pub fn evaluate_lengths(
+    context: &mut LowerContext,
+    lengths: &[(LengthId, TypedExpression)],
+) -> LowerResult<()> {
+    for (length, expression) in lengths {
+        let value = value(context, expression)?;
+        let variable = match state(context).lengths.get(length) {
+            Some(variable) => *variable,
+            None => builder(context).declare_variable(IrType::I64),
+        };
+        builder(context).write_variable(variable, value);
+        state(context).lengths.insert(*length, variable);
+    }
+    Ok(())
+}
+
+fn rvalue(context: &mut LowerContext, expression: &TypedExpression) -> LowerResult<Operand> {
+    let ty = expression.ty;
+    let span = expression.span;
+    match &expression.kind {
+        TypedExpressionKind::Integer(value) => Ok(integer(ir_type(context, ty, span)?, *value)),
+        TypedExpressionKind::Identifier(id) => match symbol(context, *id).kind {
+            SymbolKind::EnumerationConstant(value) => {
+                Ok(integer(ir_type(context, ty, span)?, i128::from(value)))
+            }
+            _ => unreachable!(),
+        },
+        TypedExpressionKind::Unary { kind, operand } => unary(context, *kind, operand, ty, span),
+        TypedExpressionKind::Binary { kind, lhs, rhs } => binary(context, *kind, lhs, rhs, span),
+        TypedExpressionKind::CompoundAssignment {
+            operator,
+            lhs,
+            rhs,
+            computation,
+        } => compound_assignment(context, *operator, lhs, rhs, *computation, span),
+        TypedExpressionKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => conditional(context, condition, then.as_deref(), otherwise, ty, span),
+        TypedExpressionKind::Cast { operand, .. } => {
+            let is_bool = |ty| context.types.integer_kind(ty) == Some(IntegerKind::Bool);
+            if is_bool(ty) && !is_bool(operand.ty) {
+                let truth = truth(context, operand)?;
+                return Ok(cast_instruction(
+                    context,
+                    CastOperator::ZExt,
+                    IrType::I1,
+                    truth,
+                    IrType::I8,
+                ));
+            }
+            let value = value(context, operand)?;
+            convert(context, value, operand.ty, ty, span)
+        }
+        TypedExpressionKind::Call { callee, arguments } => call(context, callee, arguments, span),
+        TypedExpressionKind::Sizeof(operand) => match variable_size(context, *operand, span)? {
+            Operand::Integer(size) => Ok(integer(ir_type(context, ty, span)?, i128::from(size))),
+            size => Ok(size),
+        },
+        TypedExpressionKind::Alignof(operand) => {
+            let align = align_of(context, *operand);
+            Ok(integer(ir_type(context, ty, span)?, i128::from(align)))
+        }
+        TypedExpressionKind::Statement(body) => statement_expression(context, body),
+        TypedExpressionKind::VariableLengths { lengths, operand } => {
+            evaluate_lengths(context, lengths)?;
+            value(context, operand)
+        }
+        TypedExpressionKind::String(_)
+        | TypedExpressionKind::Member { .. }
+        | TypedExpressionKind::CompoundLiteral { .. } => unreachable!(),
+    }
+}
+
+fn unary(
+    context: &mut LowerContext,
+    kind: UnaryKind,
+    operand: &TypedExpression,
+    ty: TypeId,
+    span: Span,
+) -> LowerResult<Operand> {
+    let result_type = ir_type(context, ty, span)?;
+    match kind {
+        UnaryKind::LogicalNot => {
+            let zero = negation(context, operand)?;
+            Ok(cast_instruction(
+                context,
+                CastOperator::ZExt,
+                IrType::I1,
+                zero,
+                result_type,
+            ))
+        }
+        UnaryKind::BitwiseNot => {
+            let value = value(context, operand)?;
+            let ones = integer(result_type, -1);
+            Ok(append_binary(
+                builder(context),
+                BinaryOperator::Xor,
+                result_type,
+                value,
+                ones,
+            ))
+        }
+        UnaryKind::Plus => {
+            let value = value(context, operand)?;
+            convert(context, value, operand.ty, ty, span)
+        }
+        UnaryKind::Minus => {
+            let value = value(context, operand)?;
+            let (operator, zero) = match value {
+                Operand::Integer(value) => return Ok(integer(result_type, -i128::from(value))),
+                Operand::Floating(value) => return Ok(Operand::Floating(-value)),
+                _ if result_type.is_floating() => (BinaryOperator::FSub, Operand::Floating(-0.0)),
+                _ => (BinaryOperator::Sub, Operand::Integer(0)),
+            };
+            Ok(append_binary(
+                builder(context),
+                operator,
+                result_type,
+                zero,
+                value,
+            ))
+        }
+        UnaryKind::Reference => Ok(place(context, operand)?.address()),
+        UnaryKind::Dereference => unreachable!(),
+        UnaryKind::PreIncrement
+        | UnaryKind::PreDecrement
+        | UnaryKind::PostIncrement
+        | UnaryKind::PostDecrement => increment(context, kind, operand, span),
+    }
+}
+
+//the value of a prefix operator is the one stored, which for a bit-field is the one
+//truncated to its width
+fn increment(
+    context: &mut LowerContext,
+    kind: UnaryKind,
+    operand: &TypedExpression,
+    span: Span,
+) -> LowerResult<Operand> {
+    let ty = operand.ty;
+    let place = place(context, operand)?;
+    let old = load(context, place.clone(), ty, span)?;
+    let delta = match kind {
+        UnaryKind::PreIncrement | UnaryKind::PostIncrement => 1,
+        _ => -1,
+    };
+    let new = match class(context, ty, span)? {
+        Class::Pointer => {
+            let size = pointee_size(context, ty, span)?;
+            let offset = multiply(context, Operand::Integer(delta), size);
+            let kind = InstructionKind::PointerAdd {
+                base: old.clone(),
+                offset,
+            };
+            builder(context).append(IrType::Ptr, kind)
+        }
+        Class::Floating(ir_type) => {
+            let delta = Operand::Floating(delta as f64);
+            append_binary(
+                builder(context),
+                BinaryOperator::FAdd,
+                ir_type,
+                old.clone(),
+                delta,
+            )
+        }
+        //a narrower operand is promoted, and the result converted back
+        class => {
+            let promoted = class.promoted();
+            let value = cast(context, old.clone(), class, promoted);
+            let ir_type = promoted.ir_type();
+            let delta = integer(ir_type, i128::from(delta));
+            let value = append_binary(builder(context), BinaryOperator::Add, ir_type, value, delta);
+            cast(context, value, promoted, class)
+        }
+    };
+    let is_bit_field = place.is_bit_field();
+    store(context, place.clone(), ty, new.clone(), span)?;
+    match kind {
+        UnaryKind::PreIncrement | UnaryKind::PreDecrement if is_bit_field => {
+            load(context, place, ty, span)
+        }
+        UnaryKind::PreIncrement | UnaryKind::PreDecrement => Ok(new),
+        _ => Ok(old),
+    }
+}
+
+//the size pointer arithmetic scales by
+fn pointee_size(context: &mut LowerContext, ty: TypeId, span: Span) -> LowerResult<Operand> {
+    match context.types.scaled_pointee(ty) {
+        Some(pointee) => variable_size(context, pointee, span),
+        None => Ok(Operand::Integer(1)),
+    }
+}
+
+fn binary(
+    context: &mut LowerContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    span: Span,
+) -> LowerResult<Operand> {
+    use BinaryOperatorKind::*;
+    match kind {
+        Comma => {
+            value(context, lhs)?;
+            value(context, rhs)
+        }
+        LogicalAnd | LogicalOr => {
+            let result = short_circuit(context, kind, lhs, rhs)?;
+            let result =
+                cast_instruction(context, CastOperator::ZExt, IrType::I1, result, IrType::I32);
+            Ok(result)
+        }
+        Assign => {
+            let place = place(context, lhs)?;
+            let value = value(context, rhs)?;
+            assign(context, place, lhs.ty, value, span)
+        }
+        kind => {
+            let lhs_value = value(context, lhs)?;
+            let rhs_value = value(context, rhs)?;
+            let result = operate(
+                context,
+                kind,
+                (lhs_value, lhs.ty),
+                (rhs_value, rhs.ty),
+                span,
+            )?;
+            Ok(result.0)
+        }
+    }
+}
+
+fn compound_assignment(
+    context: &mut LowerContext,
+    operator: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    computation: TypeId,
+    span: Span,
+) -> LowerResult<Operand> {
+    let place = place(context, lhs)?;
+    let old = load(context, place.clone(), lhs.ty, span)?;
+    let rhs_value = value(context, rhs)?;
+    let old = convert(context, old, lhs.ty, computation, span)?;
+    let (result, result_class) = operate(
+        context,
+        operator,
+        (old, computation),
+        (rhs_value, rhs.ty),
+        span,
+    )?;
+    let lhs_class = class(context, lhs.ty, span)?;
+    let result = cast(context, result, result_class, lhs_class);
+    assign(context, place, lhs.ty, result, span)
+}
+
+//the value of an assignment is the one stored, which a bit-field is read back for
+fn assign(
+    context: &mut LowerContext,
+    place: Place,
+    ty: TypeId,
+    value: Operand,
+    span: Span,
+) -> LowerResult<Operand> {
+    store(context, place.clone(), ty, value.clone(), span)?;
+    if place.is_bit_field() {
+        return load(context, place, ty, span);
+    }
+    if is_record(context, ty) {
+        return Ok(place.address());
+    }
+    Ok(value)
+}
+
+//an arithmetic, bitwise, shift or comparison operator applied to operands which have the
+//types the operator works on, apart from the left operand of a shift which is promoted
+//here. gives the result and how it is computed with.
+fn operate(
+    context: &mut LowerContext,
+    kind: BinaryOperatorKind,
+    (lhs, lhs_type): (Operand, TypeId),
+    (rhs, rhs_type): (Operand, TypeId),
+    span: Span,
+) -> LowerResult<(Operand, Class)> {
+    use BinaryOperatorKind::*;
+    let lhs_class = class(context, lhs_type, span)?;
+    let rhs_class = class(context, rhs_type, span)?;
+    match (kind, lhs_class, rhs_class) {
+        (Add | Sub, Class::Pointer, Class::Integer(_, _) | Class::Bool) => {
+            let size = pointee_size(context, lhs_type, span)?;
+            let offset = scale(context, rhs, rhs_class, size);
+            let offset = match kind {
+                Sub => negate(context, offset),
+                _ => offset,
+            };
+            return Ok((pointer_add(context, lhs, offset), Class::Pointer));
+        }
+        (Add, Class::Integer(_, _) | Class::Bool, Class::Pointer) => {
+            let size = pointee_size(context, rhs_type, span)?;
+            let offset = scale(context, lhs, lhs_class, size);
+            return Ok((pointer_add(context, rhs, offset), Class::Pointer));
+        }
+        (Sub, Class::Pointer, Class::Pointer) => {
+            let size = pointee_size(context, lhs_type, span)?;
+            let lhs = cast_instruction(
+                context,
+                CastOperator::PtrToInt,
+                IrType::Ptr,
+                lhs,
+                IrType::I64,
+            );
+            let rhs = cast_instruction(
+                context,
+                CastOperator::PtrToInt,
+                IrType::Ptr,
+                rhs,
+                IrType::I64,
+            );
+            let builder = builder(context);
+            let difference = append_binary(builder, BinaryOperator::Sub, IrType::I64, lhs, rhs);
+            let difference = match size {
+                Operand::Integer(1) => difference,
+                size => append_binary(builder, BinaryOperator::SDiv, IrType::I64, difference, size),
+            };
+            return Ok((difference, Class::Integer(IrType::I64, true)));
+        }
+        _ => {}
+    }
+
+    if matches!(kind, Equal | NotEqual | LessThan | LessThanEqual) {
+        let result = compare(context, kind, (lhs, lhs_class), (rhs, rhs_class));
+        let result = cast_instruction(context, CastOperator::ZExt, IrType::I1, result, IrType::I32);
+        return Ok((result, Class::Integer(IrType::I32, true)));
+    }
+
+    if let Class::Floating(ty) = lhs_class {
+        let operator = match kind {
+            Add => BinaryOperator::FAdd,
+            Sub => BinaryOperator::FSub,
+            Mul => BinaryOperator::FMul,
+            _ => BinaryOperator::FDiv,
+        };
+        let rhs = cast(context, rhs, rhs_class, lhs_class);
+        return Ok((
+            append_binary(builder(context), operator, ty, lhs, rhs),
+            lhs_class,
+        ));
+    }
+
+    let class = lhs_class.promoted();
+    let signed = class.is_signed();
+    let operator = match kind {
+        Add => BinaryOperator::Add,
+        Sub => BinaryOperator::Sub,
+        Mul => BinaryOperator::Mul,
+        Div if signed => BinaryOperator::SDiv,
+        Div => BinaryOperator::UDiv,
+        Mod if signed => BinaryOperator::SRem,
+        Mod => BinaryOperator::URem,
+        LeftShift => BinaryOperator::Shl,
+        RightShift if signed => BinaryOperator::AShr,
+        RightShift => BinaryOperator::LShr,
+        BitwiseAnd => BinaryOperator::And,
+        BitwiseXor => BinaryOperator::Xor,
+        _ => BinaryOperator::Or,
+    };
+    let lhs = cast(context, lhs, lhs_class, class);
+    let rhs = cast(context, rhs, rhs_class, class);
+    let ty = class.ir_type();
+    Ok((
+        append_binary(builder(context), operator, ty, lhs, rhs),
+        class,
+    ))
+}
+
+//the i1 result of a comparison of operands converted to a common type
+fn compare(
+    context: &mut LowerContext,
+    kind: BinaryOperatorKind,
+    (lhs, lhs_class): (Operand, Class),
+    (rhs, rhs_class): (Operand, Class),
+) -> Operand {
+    use BinaryOperatorKind::*;
+    let class = lhs_class.promoted();
+    let predicate = match (class, kind) {
+        (Class::Floating(_), Equal) => Predicate::FEq,
+        (Class::Floating(_), NotEqual) => Predicate::FNe,
+        (Class::Floating(_), LessThan) => Predicate::FLt,
+        (Class::Floating(_), _) => Predicate::FLe,
+        (_, Equal) => Predicate::Eq,
+        (_, NotEqual) => Predicate::Ne,
+        (class, LessThan) if class.is_signed() => Predicate::Slt,
+        (_, LessThan) => Predicate::Ult,
+        (class, _) if class.is_signed() => Predicate::Sle,
+        (_, _) => Predicate::Ule,
+    };
+    let lhs = cast(context, lhs, lhs_class, class);
+    let rhs = cast(context, rhs, rhs_class, class);
+    let kind = InstructionKind::Compare {
+        predicate,
+        ty: class.ir_type(),
+        lhs,
+        rhs,
+    };
+    builder(context).append(IrType::I1, kind)
+}
+
+//an integer as an i64 count of bytes, size for each
+fn scale(context: &mut LowerContext, value: Operand, class: Class, size: Operand) -> Operand {
+    let value = cast(
+        context,
+        value,
+        class,
+        Class::Integer(IrType::I64, class.is_signed()),
+    );
+    multiply(context, value, size)
+}
+
+fn negate(context: &mut LowerContext, value: Operand) -> Operand {
+    match value {
+        Operand::Integer(value) => Operand::Integer(value.wrapping_neg()),
+        value => {
+            let zero = Operand::Integer(0);
+            append_binary(
+                builder(context),
+                BinaryOperator::Sub,
+                IrType::I64,
+                zero,
+                value,
+            )
+        }
+    }
+}
+
+fn pointer_add(context: &mut LowerContext, base: Operand, offset: Operand) -> Operand {
+    if offset == Operand::Integer(0) {
+        return base;
+    }
+    builder(context).append(IrType::Ptr, InstructionKind::PointerAdd { base, offset })
+}
+
+//&& and || evaluate their right operand only when the left one does not decide the result,
+//which is a phi of where control comes from
+fn short_circuit(
+    context: &mut LowerContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+) -> LowerResult<Operand> {
+    let right = builder(context).new_block();
+    let end = builder(context).new_block();
+    let condition = truth(context, lhs)?;
+    let left = builder(context).insertion_block();
+    let (decided, terminator) = match kind {
+        BinaryOperatorKind::LogicalAnd => (0, branch(condition, right, end)),
+        _ => (1, branch(condition, end, right)),
+    };
+    builder(context).terminate(terminator);
+    builder(context).seal(right);
+
+    builder(context).switch_to(right);
+    let value = truth(context, rhs)?;
+    let from_right = builder(context).insertion_block();
+    builder(context).jump(end);
+    builder(context).seal(end);
+
+    builder(context).switch_to(end);
+    let incoming = vec![(left, Operand::Integer(decided)), (from_right, value)];
+    Ok(builder(context).phi(IrType::I1, incoming))
+}
+
+pub fn branch(condition: Operand, then: BlockId, otherwise: BlockId) -> Terminator {
+    Terminator::Branch {
+        condition,
+        then,
+        otherwise,
+    }
+}
+
+//the value of `condition ? then : otherwise` comes from the branch which was taken. the
+//condition is the value of `condition ?: otherwise` when it is true.
+fn conditional(
+    context: &mut LowerContext,
+    condition: &TypedExpression,
+    then: Option<&TypedExpression>,
+    otherwise: &TypedExpression,
+    ty: TypeId,
+    span: Span,
+) -> LowerResult<Operand> {
+    let then_block = builder(context).new_block();
+    let otherwise_block = builder(context).new_block();
+    let end = builder(context).new_block();
+    //the condition of `condition ?: otherwise` is its value too
+    let (truth, condition_value) = match then {
+        Some(_) => (truth(context, condition)?, Operand::Undef),
+        None => {
+            let value = value(context, condition)?;
+            let class = class(context, condition.ty, span)?;
+            (compare_zero(context, value.clone(), class, false), value)
+        }
+    };
+    builder(context).terminate(branch(truth, then_block, otherwise_block));
+    builder(context).seal(then_block);
+    builder(context).seal(otherwise_block);
+
+    let mut incoming = Vec::new();
+    for (block, operand) in [(then_block, then), (otherwise_block, Some(otherwise))]
+        .iter()
+        .copied()
+    {
+        builder(context).switch_to(block);
+        let value = match operand {
+            Some(operand) => {
+                let value = value(context, operand)?;
+                convert(context, value, operand.ty, ty, span)?
+            }
+            None => convert(context, condition_value.clone(), condition.ty, ty, span)?,
+        };
+        incoming.push((builder(context).insertion_block(), value));
+        builder(context).jump(end);
+    }
+    builder(context).seal(end);
+
+    builder(context).switch_to(end);
+    let ir_type = ir_type(context, ty, span)?;
+    if ir_type == IrType::Void {
+        return Ok(Operand::Undef);
+    }
+    Ok(builder(context).phi(ir_type, incoming))
+}
+
+//whether the value of expression is nonzero, as an i1
+pub fn truth(context: &mut LowerContext, expression: &TypedExpression) -> LowerResult<Operand> {
+    use BinaryOperatorKind::*;
+    //the operators whose result is a truth value give it as the i1 itself
+    let span = expression.span;
+    match &expression.kind {
+        TypedExpressionKind::Binary {
+            kind: kind @ (LogicalAnd | LogicalOr),
+            lhs,
+            rhs,
+        } => return short_circuit(context, *kind, lhs, rhs),
+        TypedExpressionKind::Binary {
+            kind: kind @ (Equal | NotEqual | LessThan | LessThanEqual),
+            lhs,
+            rhs,
+        } => {
+            let lhs_value = value(context, lhs)?;
+            let rhs_value = value(context, rhs)?;
+            let lhs_class = class(context, lhs.ty, span)?;
+            let rhs_class = class(context, rhs.ty, span)?;
+            return Ok(compare(
+                context,
+                *kind,
+                (lhs_value, lhs_class),
+                (rhs_value, rhs_class),
+            ));
+        }
+        TypedExpressionKind::Unary {
+            kind: UnaryKind::LogicalNot,
+            operand,
+        } => return negation(context, operand),
+        _ => {}
+    }
+    let value = value(context, expression)?;
+    let class = class(context, expression.ty, expression.span)?;
+    Ok(compare_zero(context, value, class, false))
+}
+
+//whether the value of expression is zero, as an i1
+fn negation(context: &mut LowerContext, expression: &TypedExpression) -> LowerResult<Operand> {
+    let truth = truth(context, expression)?;
+    let one = Operand::Integer(1);
+    Ok(append_binary(
+        builder(context),
+        BinaryOperator::Xor,
+        IrType::I1,
+        truth,
+        one,
+    ))
+}
+
+//whether value is zero when equal, and otherwise whether it is not. a floating value which
+//is a NaN is not zero.
+fn compare_zero(context: &mut LowerContext, value: Operand, class: Class, equal: bool) -> Operand {
+    let is_zero = match &value {
+        Operand::Integer(value) => Some(*value == 0),
+        Operand::Floating(value) => Some(*value == 0.0),
+        Operand::Null => Some(true),
+        Operand::Global(_) => Some(false),
+        _ => None,
+    };
+    if let Some(is_zero) = is_zero {
+        return Operand::Integer(i64::from(is_zero == equal));
+    }
+    let (predicate, zero) = match (class, equal) {
+        (Class::Floating(_), true) => (Predicate::FEq, Operand::Floating(0.0)),
+        (Class::Floating(_), false) => (Predicate::FNe, Operand::Floating(0.0)),
+        (Class::Pointer, true) => (Predicate::Eq, Operand::Null),
+        (Class::Pointer, false) => (Predicate::Ne, Operand::Null),
+        (_, true) => (Predicate::Eq, Operand::Integer(0)),
+        (_, false) => (Predicate::Ne, Operand::Integer(0)),
+    };
+    let kind = InstructionKind::Compare {
+        predicate,
+        ty: class.ir_type(),
+        lhs: value,
+        rhs: zero,
+    };
+    builder(context).append(IrType::I1, kind)
+}
+
+//converts value of type from to type to, as a cast does
+pub fn convert(
+    context: &mut LowerContext,
+    value: Operand,
+    from: TypeId,
+    to: TypeId,
+    span: Span,
+) -> LowerResult<Operand> {
+    let from = class(context, from, span)?;
+    let to = class(context, to, span)?;
+    Ok(cast(context, value, from, to))
+}
+
+pub fn cast(context: &mut LowerContext, value: Operand, from: Class, to: Class) -> Operand {
+    use CastOperator::*;
+    if from == to {
+        return value;
+    }
+    let from = match (from, to) {
+        (_, Class::Void) | (Class::Void, _) => return Operand::Undef,
+        (from, Class::Bool) => {
+            let truth = compare_zero(context, value, from, false);
+            return cast_instruction(context, ZExt, IrType::I1, truth, IrType::I8);
+        }
+        (Class::Bool, _) => Class::Integer(IrType::I8, false),
+        (from, _) => from,
+    };
+    match (from, to) {
+        (Class::Integer(from, signed), Class::Integer(to, _)) => {
+            resize(context, value, from, signed, to)
+        }
+        (Class::Integer(from, true), Class::Floating(to)) => {
+            cast_instruction(context, SiToFp, from, value, to)
+        }
+        (Class::Integer(from, false), Class::Floating(to)) => {
+            cast_instruction(context, UiToFp, from, value, to)
+        }
+        (Class::Floating(from), Class::Integer(to, true)) => {
+            cast_instruction(context, FpToSi, from, value, to)
+        }
+        (Class::Floating(from), Class::Integer(to, false)) => {
+            cast_instruction(context, FpToUi, from, value, to)
+        }
+        (Class::Floating(from), Class::Floating(to)) if from.width() < to.width() => {
+            cast_instruction(context, FpExt, from, value, to)
+        }
+        (Class::Floating(from), Class::Floating(to)) => {
+            cast_instruction(context, FpTrunc, from, value, to)
+        }
+        (Class::Pointer, Class::Integer(to, signed)) => {
+            let value = cast_instruction(context, PtrToInt, IrType::Ptr, value, IrType::I64);
+            resize(context, value, IrType::I64, signed, to)
+        }
+        (Class::Integer(_, _), Class::Pointer) if value == Operand::Integer(0) => Operand::Null,
+        (Class::Integer(from, signed), Class::Pointer) => {
+            let value = resize(context, value, from, signed, IrType::I64);
+            cast_instruction(context, IntToPtr, IrType::I64, value, IrType::Ptr)
+        }
+        _ => value,
+    }
+}
+
+//an integer of type from as one of type to, extended by its sign when it is signed
+fn resize(
+    context: &mut LowerContext,
+    value: Operand,
+    from: IrType,
+    signed: bool,
+    to: IrType,
+) -> Operand {
+    let operator = match from.width().cmp(&to.width()) {
+        std::cmp::Ordering::Equal => return value,
+        std::cmp::Ordering::Greater => CastOperator::Trunc,
+        std::cmp::Ordering::Less if signed => CastOperator::SExt,
+        std::cmp::Ordering::Less => CastOperator::ZExt,
+    };
+    cast_instruction(context, operator, from, value, to)
+}
+
+//the cast of a constant is folded
+pub fn cast_instruction(
+    context: &mut LowerContext,
+    operator: CastOperator,
+    from: IrType,
+    value: Operand,
+    to: IrType,
+) -> Operand {
+    use CastOperator::*;
+    let unsigned = |value: i64| match from {
+        IrType::I1 => i128::from(value & 1),
+        _ => i128::from(value) & ((1i128 << from.width()) - 1),
+    };
+    let round = |value: f64| match to {
+        IrType::F32 => f64::from(value as f32),
+        _ => value,
+    };
+    match (operator, &value) {
+        (Trunc | SExt, Operand::Integer(value)) => integer(to, i128::from(*value)),
+        (ZExt, Operand::Integer(value)) => integer(to, unsigned(*value)),
+        (SiToFp, Operand::Integer(value)) => Operand::Floating(round(*value as f64)),
+        (UiToFp, Operand::Integer(value)) => Operand::Floating(round(unsigned(*value) as f64)),
+        (FpExt | FpTrunc, Operand::Floating(value)) => Operand::Floating(round(*value)),
+        (_, Operand::Undef) => Operand::Undef,
+        _ => builder(context).append(
+            to,
+            InstructionKind::Cast {
+                operator,
+                from,
+                value,
+            },
+        ),
+    }
+}
+
+//a struct or union is returned through the address of an object the caller allocates, and
+//is passed as the address of a copy
+fn call(
+    context: &mut LowerContext,
+    callee: &TypedExpression,
+    arguments: &[TypedExpression],
+    span: Span,
+) -> LowerResult<Operand> {
+    let types = context.types;
+    let function_type = types
+        .function_type(callee.ty)
+        .or_else(|| types.function_type(types.pointee(callee.ty)?))
+        .unwrap();
+    let return_type = function_type.return_type;
+    let callee = value(context, callee)?;
+
+    let mut values = Vec::new();
+    let result = if is_record(context, return_type) {
+        let result = temporary(context, return_type, span)?;
+        values.push((IrType::Ptr, result.clone()));
+        Some(result)
+    } else {
+        None
+    };
+    for argument in arguments {
+        let value = value(context, argument)?;
+        if is_record(context, argument.ty) {
+            let copy = temporary(context, argument.ty, span)?;
+            let size = size_of(context, argument.ty, span)?;
+            let kind = InstructionKind::Copy {
+                destination: copy.clone(),
+                source: value,
+                size,
+            };
+            builder(context).append(IrType::Void, kind);
+            values.push((IrType::Ptr, copy));
+        } else {
+            values.push((ir_type(context, argument.ty, argument.span)?, value));
+        }
+    }
+
+    let ty = match result {
+        Some(_) => IrType::Void,
+        None => ir_type(context, return_type, span)?,
+    };
+    let kind = InstructionKind::Call {
+        callee,
+        arguments: values,
+    };
+    let value = builder(context).append(ty, kind);
+    match result {
+        Some(result) => Ok(result),
+        None if ty == IrType::Void => Ok(Operand::Undef),
+        None => Ok(value),
+    }
+}
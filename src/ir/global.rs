@@ -0,0 +1,305 @@
+use crate::ir::module::*;
+use crate::ir::util::*;
+use crate::semantic::constant::*;
+use crate::semantic::expression::*;
+use crate::semantic::initializer::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+//a run of zero bytes at least this long in static data becomes a piece of its own
+const ZERO_RUN: usize = 8;
+
+//the name of the global or function id denotes. an entity with linkage keeps its name in
+//the source, or the one its asm label gives, and is recorded as referenced so that it is
+//declared if it is not defined.
+pub fn global_name(context: &mut LowerContext, id: SymbolId) -> String {
+    if let Some(name) = context.names.get(&id) {
+        return name.clone();
+    }
+    let name = assembler_name(symbol(context, id)).to_string();
+    context.taken.insert(name.clone());
+    context.names.insert(id, name.clone());
+    context.referenced.push(id);
+    name
+}
+
+//the global holding the characters of a string literal and a null character. the same
+//literal is the same global each time it is lowered.
+pub fn string_global(context: &mut LowerContext, content: &str) -> String {
+    let key = (content.as_ptr() as usize, content.len());
+    if let Some(name) = context.strings.get(&key) {
+        return name.clone();
+    }
+    let name = unique_name(context, format!(".str.{}", context.strings.len()));
+    let mut bytes = string_bytes(content);
+    bytes.push(0);
+    context.module.globals.push(Global {
+        name: name.clone(),
+        size: bytes.len() as u64,
+        align: 1,
+        internal: true,
+        initializer: Some(vec![Piece::Bytes(bytes)]),
+    });
+    context.strings.insert(key, name.clone());
+    name
+}
+
+//the global of a compound literal at file scope, which is defined when an address constant
+//first refers to it. expression is the one the address constant was folded from, which
+//contains the compound literal.
+fn compound_literal_global(
+    context: &mut LowerContext,
+    id: CompoundLiteralId,
+    expression: &TypedExpression,
+) -> LowerResult<String> {
+    if let Some(name) = context.compound_literals.get(&id) {
+        return Ok(name.clone());
+    }
+    let literal = match find_compound_literal(expression, id) {
+        Some(literal) => literal,
+        None => return not_constant(expression.span),
+    };
+    let initializer = match &literal.kind {
+        TypedExpressionKind::CompoundLiteral { initializer, .. } => initializer,
+        _ => unreachable!(),
+    };
+    let number = context.compound_literals.len();
+    let name = unique_name(context, format!(".compoundliteral.{}", number));
+    context.compound_literals.insert(id, name.clone());
+    let (ty, span) = (literal.ty, literal.span);
+    let pieces = static_data(context, ty, Some(initializer), span)?;
+    context.module.globals.push(Global {
+        name: name.clone(),
+        size: size_of(context, ty, span)?,
+        align: align_of(context, ty),
+        internal: true,
+        initializer: Some(pieces),
+    });
+    Ok(name)
+}
+
+//the compound literal among the operands an address constant is made of
+fn find_compound_literal<'e, 'a>(
+    expression: &'e TypedExpression<'a>,
+    id: CompoundLiteralId,
+) -> Option<&'e TypedExpression<'a>> {
+    match &expression.kind {
+        TypedExpressionKind::CompoundLiteral {
+            id: Some(found), ..
+        } if *found == id => Some(expression),
+        TypedExpressionKind::Unary { operand, .. }
+        | TypedExpressionKind::Cast { operand, .. }
+        | TypedExpressionKind::Member { operand, .. } => find_compound_literal(operand, id),
+        TypedExpressionKind::Binary { lhs, rhs, .. } => {
+            find_compound_literal(lhs, id).or_else(|| find_compound_literal(rhs, id))
+        }
+        TypedExpressionKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => find_compound_literal(condition, id)
+            .or_else(|| {
+                then.as_ref()
+                    .and_then(|then| find_compound_literal(then, id))
+            })
+            .or_else(|| find_compound_literal(otherwise, id)),
+        _ => None,
+    }
+}
+
+//defines the global of an object with static storage duration, whose initializer must be
+//made of constant expressions
+pub fn define_global(
+    context: &mut LowerContext,
+    id: SymbolId,
+    name: String,
+    initializer: Option<&TypedInitializer>,
+) -> LowerResult<()> {
+    let symbol = symbol(context, id);
+    let size = size_of(context, symbol.ty, symbol.span)?;
+    let (align, internal) = (
+        object_align(context.types, symbol),
+        symbol.linkage != Linkage::External,
+    );
+    let pieces = static_data(context, symbol.ty, initializer, symbol.span)?;
+    context.module.globals.push(Global {
+        name,
+        size,
+        align,
+        internal,
+        initializer: Some(pieces),
+    });
+    Ok(())
+}
+
+//the contents of an object of type ty with static storage duration
+fn static_data(
+    context: &mut LowerContext,
+    ty: TypeId,
+    initializer: Option<&TypedInitializer>,
+    span: Span,
+) -> LowerResult<Vec<Piece>> {
+    let size = size_of(context, ty, span)?;
+    let mut data = StaticData {
+        bytes: vec![0; size as usize],
+        addresses: Vec::new(),
+    };
+    if let Some(initializer) = initializer {
+        for (subobjects, expression) in initializer.stores(ty) {
+            for subobject in subobjects {
+                data.initialize(context, subobject, expression)?;
+            }
+        }
+    }
+    Ok(data.pieces())
+}
+
+//the bytes of a global being initialized, and the addresses in them which are only known
+//once the program is linked
+struct StaticData {
+    bytes: Vec<u8>,
+    addresses: Vec<(u64, String, i64)>, // the offset, the global and the offset into it
+}
+
+impl StaticData {
+    fn initialize(
+        &mut self,
+        context: &mut LowerContext,
+        subobject: Subobject,
+        expression: &TypedExpression,
+    ) -> LowerResult<()> {
+        let span = expression.span;
+        let types = context.types;
+        if let (TypedExpressionKind::String(content), true) =
+            (&expression.kind, types.is_array(subobject.ty))
+        {
+            let mut bytes = string_bytes(content);
+            bytes.push(0);
+            bytes.truncate(size_of(context, subobject.ty, span)? as usize);
+            self.write(subobject.offset, &bytes);
+            return Ok(());
+        }
+        let constant = match evaluate(context, expression)? {
+            Some(constant) if !types.is_record(subobject.ty) => constant,
+            _ => return not_constant(span),
+        };
+
+        if let Some((bit_offset, width)) = subobject.bit_field {
+            let value = match constant {
+                Constant::Integer(value) => value,
+                Constant::Floating(value) => value.trunc() as i128,
+                Constant::Address(_) => return not_constant(span),
+            };
+            self.write_bits(subobject.offset * 8 + bit_offset, width, value);
+            return Ok(());
+        }
+        let size = size_of(context, subobject.ty, span)?;
+        match (ir_type(context, subobject.ty, span)?, constant) {
+            (IrType::F32, constant) => {
+                let value = floating_value(constant, span)? as f32;
+                self.write(subobject.offset, &value.to_le_bytes());
+            }
+            (IrType::F64, constant) => {
+                let value = floating_value(constant, span)?;
+                self.write(subobject.offset, &value.to_le_bytes());
+            }
+            (_, Constant::Address(address)) if size == 8 => {
+                let name = match address.base {
+                    AddressBase::Symbol(id) => global_name(context, id),
+                    AddressBase::String(content) => string_global(context, content),
+                    AddressBase::CompoundLiteral(id) => {
+                        compound_literal_global(context, id, expression)?
+                    }
+                };
+                self.write(subobject.offset, &[0; 8]);
+                let offset = subobject.offset;
+                self.addresses.push((offset, name, address.offset));
+            }
+            (_, Constant::Address(_)) => return not_constant(span),
+            (_, Constant::Integer(value)) => {
+                self.write(subobject.offset, &value.to_le_bytes()[..size as usize]);
+            }
+            (_, Constant::Floating(value)) => {
+                let value = value.trunc() as i128;
+                self.write(subobject.offset, &value.to_le_bytes()[..size as usize]);
+            }
+        }
+        Ok(())
+    }
+
+    //bytes replace the ones at offset, and the addresses they overlap
+    fn write(&mut self, offset: u64, bytes: &[u8]) {
+        let end = offset + bytes.len() as u64;
+        self.addresses
+            .retain(|(address, _, _)| *address + 8 <= offset || end <= *address);
+        self.bytes[offset as usize..end as usize].copy_from_slice(bytes);
+    }
+
+    //the width bits starting at the bit offset, the lowest bit of the first byte being 0
+    fn write_bits(&mut self, offset: u64, width: u64, value: i128) {
+        let first = (offset / 8) as usize;
+        let last = ((offset + width).div_ceil(8)) as usize;
+        let mut bytes = self.bytes[first..last].to_vec();
+        for bit in 0..width {
+            let position = (offset - first as u64 * 8 + bit) as usize;
+            let mask = 1 << (position % 8);
+            if (value >> bit) & 1 != 0 {
+                bytes[position / 8] |= mask;
+            } else {
+                bytes[position / 8] &= !mask;
+            }
+        }
+        self.write(first as u64, &bytes);
+    }
+
+    fn pieces(mut self) -> Vec<Piece> {
+        self.addresses.sort_by_key(|(offset, _, _)| *offset);
+        let mut result = Vec::new();
+        let mut position = 0;
+        for (offset, name, addend) in self.addresses {
+            push_bytes(&mut result, &self.bytes[position..offset as usize]);
+            result.push(Piece::Address(name, addend));
+            position = offset as usize + 8;
+        }
+        push_bytes(&mut result, &self.bytes[position..]);
+        result
+    }
+}
+
+//bytes as pieces, the long runs of zeros apart
+fn push_bytes(pieces: &mut Vec<Piece>, bytes: &[u8]) {
+    let mut start = 0;
+    while start < bytes.len() {
+        let zeros = bytes[start..].iter().take_while(|byte| **byte == 0).count();
+        if zeros >= ZERO_RUN || start + zeros == bytes.len() && zeros > 0 {
+            pieces.push(Piece::Zero(zeros as u64));
+            start += zeros;
+            continue;
+        }
+        //the other bytes go on until a long run of zeros
+        let mut end = start + zeros.max(1);
+        while end < bytes.len() {
+            let zeros = bytes[end..].iter().take_while(|byte| **byte == 0).count();
+            if zeros >= ZERO_RUN || end + zeros == bytes.len() {
+                break;
+            }
+            end += zeros.max(1);
+        }
+        pieces.push(Piece::Bytes(bytes[start..end].to_vec()));
+        start = end;
+    }
+}
+
+fn floating_value(constant: Constant, span: Span) -> LowerResult<f64> {
+    match constant {
+        Constant::Integer(value) => Ok(value as f64),
+        Constant::Floating(value) => Ok(value),
+        Constant::Address(_) => not_constant(span),
+    }
+}
+
+fn not_constant<T>(span: Span) -> LowerResult<T> {
+    let message = "initializer element is not a compile-time constant.";
+    error(message.to_string(), span)
+}
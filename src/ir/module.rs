@@ -0,0 +1,417 @@
+//the types of IR values. integers carry no sign, the operations which care about it say
+//which they mean. a struct or union lives in memory and is handled by its address, and
+//long double is computed as a double, as the interpreter does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IrType {
+    Void,
+    I1,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    Ptr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValueId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub usize);
+
+//an integer constant is held sign-extended from the width of the type it is used with,
+//except that an i1 is 0 or 1
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Value(ValueId),
+    Integer(i64),
+    Floating(f64),
+    Null,
+    Undef,
+    Global(String), // the address of a global object or a function
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    UDiv,
+    SRem,
+    URem,
+    Shl,
+    LShr,
+    AShr,
+    And,
+    Or,
+    Xor,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+}
+
+//the floating predicates are false when an operand is a NaN, except for fne
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predicate {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+    FEq,
+    FNe,
+    FLt,
+    FLe,
+    FGt,
+    FGe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastOperator {
+    Trunc,
+    ZExt,
+    SExt,
+    FpTrunc,
+    FpExt,
+    FpToSi,
+    FpToUi,
+    SiToFp,
+    UiToFp,
+    PtrToInt,
+    IntToPtr,
+}
+
+//an instruction defines the value of its id, of type ty, which is Void for one which only
+//has an effect
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub ty: IrType,
+    pub kind: InstructionKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructionKind {
+    Parameter(usize), // defined on entry, in no block
+    Alloca {
+        size: u64,
+        align: u64,
+    },
+    //an object whose size is known only where it is made, for a variable length array. it
+    //is allocated each time it is reached, not in the entry block.
+    DynamicAlloca {
+        size: Operand, // an i64 count of bytes
+        align: u64,
+    },
+    Load(Operand),
+    Store {
+        ty: IrType,
+        value: Operand,
+        address: Operand,
+    },
+    Binary {
+        operator: BinaryOperator,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Compare {
+        predicate: Predicate,
+        ty: IrType, // of the operands
+        lhs: Operand,
+        rhs: Operand,
+    },
+    Cast {
+        operator: CastOperator,
+        from: IrType,
+        value: Operand,
+    },
+    PointerAdd {
+        base: Operand,
+        offset: Operand, // an i64 count of bytes
+    },
+    Call {
+        callee: Operand,
+        arguments: Vec<(IrType, Operand)>,
+    },
+    //the value coming from each predecessor of the block
+    Phi(Vec<(BlockId, Operand)>),
+    Copy {
+        destination: Operand,
+        source: Operand,
+        size: u64,
+    },
+    Zero {
+        address: Operand,
+        size: u64,
+    },
+    //inline assembly, kept as it is written. an output is the address its result is stored
+    //to, and an input the value it reads, or the address of its object for a memory
+    //constraint. there is no x86-64 backend yet, so nothing emits the template; this is
+    //what one would substitute the operands of into it.
+    Asm {
+        template: String,
+        outputs: Vec<(String, Operand)>, // each with its constraint
+        inputs: Vec<(String, IrType, Operand)>,
+        clobbers: Vec<String>,
+        volatile: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        condition: Operand, // an i1
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Switch {
+        ty: IrType,
+        value: Operand,
+        default: BlockId,
+        cases: Vec<(i64, BlockId)>,
+    },
+    Return(Option<(IrType, Operand)>),
+    Unreachable,
+}
+
+//the phis of a block come before its other instructions. a block of a well-formed function
+//always has a terminator.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Block {
+    pub instructions: Vec<ValueId>,
+    pub terminator: Option<Terminator>,
+}
+
+//a function without blocks is only declared. instructions holds every value the blocks
+//refer to by id, starting with the parameters. a struct or union argument is passed by the
+//address of a copy the callee owns, and one is returned through a pointer the caller passes
+//before the other arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub return_type: IrType,
+    pub parameters: Vec<IrType>,
+    pub variadic: bool,
+    pub internal: bool, // has internal linkage
+    pub instructions: Vec<Instruction>,
+    pub blocks: Vec<Block>, // the entry block first
+}
+
+//an object with static storage duration. one without an initializer is defined elsewhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub size: u64,
+    pub align: u64,
+    pub internal: bool,
+    pub initializer: Option<Vec<Piece>>,
+}
+
+//the bytes of an initializer in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Bytes(Vec<u8>),
+    Zero(u64),
+    Address(String, i64), // eight bytes holding the address of a global plus an offset
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+impl IrType {
+    pub fn is_integer(self) -> bool {
+        matches!(
+            self,
+            IrType::I1 | IrType::I8 | IrType::I16 | IrType::I32 | IrType::I64
+        )
+    }
+
+    pub fn is_floating(self) -> bool {
+        matches!(self, IrType::F32 | IrType::F64)
+    }
+
+    //in bits, 0 for void
+    pub fn width(self) -> u32 {
+        match self {
+            IrType::Void => 0,
+            IrType::I1 => 1,
+            IrType::I8 => 8,
+            IrType::I16 => 16,
+            IrType::I32 | IrType::F32 => 32,
+            IrType::I64 | IrType::F64 | IrType::Ptr => 64,
+        }
+    }
+}
+
+impl BinaryOperator {
+    pub fn is_floating(self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::FAdd
+                | BinaryOperator::FSub
+                | BinaryOperator::FMul
+                | BinaryOperator::FDiv
+        )
+    }
+}
+
+impl Predicate {
+    pub fn is_floating(self) -> bool {
+        matches!(
+            self,
+            Predicate::FEq
+                | Predicate::FNe
+                | Predicate::FLt
+                | Predicate::FLe
+                | Predicate::FGt
+                | Predicate::FGe
+        )
+    }
+}
+
+impl InstructionKind {
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            InstructionKind::Parameter(_) | InstructionKind::Alloca { .. } => Vec::new(),
+            InstructionKind::DynamicAlloca { size, .. } => vec![size],
+            InstructionKind::Load(address) => vec![address],
+            InstructionKind::Store { value, address, .. } => vec![value, address],
+            InstructionKind::Binary { lhs, rhs, .. }
+            | InstructionKind::Compare { lhs, rhs, .. } => {
+                vec![lhs, rhs]
+            }
+            InstructionKind::Cast { value, .. } => vec![value],
+            InstructionKind::PointerAdd { base, offset } => vec![base, offset],
+            InstructionKind::Call { callee, arguments } => std::iter::once(callee)
+                .chain(arguments.iter_mut().map(|(_, argument)| argument))
+                .collect(),
+            InstructionKind::Phi(incoming) => incoming.iter_mut().map(|(_, value)| value).collect(),
+            InstructionKind::Copy {
+                destination,
+                source,
+                ..
+            } => vec![destination, source],
+            InstructionKind::Zero { address, .. } => vec![address],
+            InstructionKind::Asm {
+                outputs, inputs, ..
+            } => outputs
+                .iter_mut()
+                .map(|(_, address)| address)
+                .chain(inputs.iter_mut().map(|(_, _, value)| value))
+                .collect(),
+        }
+    }
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Switch { default, cases, .. } => std::iter::once(*default)
+                .chain(cases.iter().map(|(_, target)| *target))
+                .collect(),
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Switch { default, cases, .. } => std::iter::once(default)
+                .chain(cases.iter_mut().map(|(_, target)| target))
+                .collect(),
+            Terminator::Return(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Switch { value, .. } => vec![value],
+            Terminator::Return(Some((_, value))) => vec![value],
+            Terminator::Jump(_) | Terminator::Return(None) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+}
+
+impl Function {
+    pub fn is_declaration(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn instruction(&self, id: ValueId) -> &Instruction {
+        &self.instructions[id.0]
+    }
+
+    //the blocks each block is entered from, each once however many edges there are
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut result = vec![Vec::new(); self.blocks.len()];
+        for (index, block) in self.blocks.iter().enumerate() {
+            let successors = match &block.terminator {
+                Some(terminator) => terminator.successors(),
+                None => continue,
+            };
+            for successor in successors {
+                let predecessors: &mut Vec<BlockId> = match result.get_mut(successor.0) {
+                    Some(predecessors) => predecessors,
+                    None => continue,
+                };
+                if !predecessors.contains(&BlockId(index)) {
+                    predecessors.push(BlockId(index));
+                }
+            }
+        }
+        result
+    }
+
+    //the blocks reachable from the entry, each after the blocks it is only entered from
+    //unless a loop goes back to it. the first successor of a block comes before the others.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let successors = |block: BlockId| match self.blocks.get(block.0) {
+            Some(Block {
+                terminator: Some(terminator),
+                ..
+            }) => terminator.successors(),
+            _ => Vec::new(),
+        };
+        let mut visited = vec![false; self.blocks.len()];
+        let mut postorder = Vec::new();
+        let mut stack = Vec::new();
+        if !self.blocks.is_empty() {
+            visited[0] = true;
+            stack.push((BlockId(0), successors(BlockId(0))));
+        }
+        while let Some((block, pending)) = stack.last_mut() {
+            match pending.pop() {
+                Some(successor) if successor.0 < visited.len() && !visited[successor.0] => {
+                    visited[successor.0] = true;
+                    stack.push((successor, successors(successor)));
+                }
+                Some(_) => {}
+                None => {
+                    postorder.push(*block);
+                    stack.pop();
+                }
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
+}
@@ -0,0 +1,937 @@
+use crate::diagnostic::*;
+use crate::ir::module::*;
+use crate::ir::print::*;
+use crate::tokenizer::{Location, Span};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+//reads a module in the textual form it is printed in, so that the IR of a test can be
+//written by hand. a value or a block may be named by any word, and a value may be used
+//before it is defined, as a phi uses the values of the blocks which follow. nothing is
+//checked beyond what reading the module needs, which is left to verify.
+pub fn parse_module(source: &str) -> Result<Module, Diagnostic> {
+    let lines = tokenize(source)?;
+    let end = Location {
+        line: source.lines().count() + 1,
+        column: 1,
+    };
+    let mut parser = Parser {
+        lines,
+        line: 0,
+        position: 0,
+        end,
+        values: HashMap::new(),
+        instructions: Vec::new(),
+        labels: HashMap::new(),
+    };
+    parser.module()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    location: Location,
+}
+
+impl Token<'_> {
+    fn span(&self) -> Span {
+        let end = Location {
+            line: self.location.line,
+            column: self.location.column + self.text.len(),
+        };
+        Span {
+            begin: self.location,
+            end,
+        }
+    }
+}
+
+fn is_word_character(character: char) -> bool {
+    character.is_ascii_alphanumeric() || "_.%@$-".contains(character)
+}
+
+//the tokens of each line which has any. a comment goes from ';' to the end of the line,
+//and a string from '"' to the next one.
+fn tokenize(source: &str) -> Result<Vec<Vec<Token<'_>>>, Diagnostic> {
+    let mut lines = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let mut tokens = Vec::new();
+        let mut characters = line.char_indices().peekable();
+        while let Some((start, character)) = characters.next() {
+            let location = Location {
+                line: index + 1,
+                column: start + 1,
+            };
+            let mut end = start + character.len_utf8();
+            if character == ';' {
+                break;
+            } else if character == '"' {
+                match characters.find(|(_, character)| *character == '"') {
+                    Some((next, _)) => end = next + 1,
+                    None => {
+                        let message = "missing terminating '\"' character.".to_string();
+                        let span = Span {
+                            begin: location,
+                            end: location,
+                        };
+                        return Err(Diagnostic::error(message, span));
+                    }
+                }
+            } else if character.is_whitespace() {
+                continue;
+            } else if is_word_character(character) {
+                while let Some((next, character)) = characters.peek().copied() {
+                    if !is_word_character(character) {
+                        break;
+                    }
+                    end = next + character.len_utf8();
+                    characters.next();
+                }
+            } else if !",:=()[]{}+".contains(character) {
+                let message = format!("unexpected character '{}'.", character);
+                let span = Span {
+                    begin: location,
+                    end: location,
+                };
+                return Err(Diagnostic::error(message, span));
+            }
+            let text = &line[start..end];
+            tokens.push(Token { text, location });
+        }
+        if !tokens.is_empty() {
+            lines.push(tokens);
+        }
+    }
+    Ok(lines)
+}
+
+fn lookup<T: Copy>(table: &[(T, &str)], text: &str) -> Option<T> {
+    let entry = table.iter().find(|(_, spelling)| *spelling == text);
+    entry.map(|(value, _)| *value)
+}
+
+struct Parser<'a> {
+    lines: Vec<Vec<Token<'a>>>,
+    line: usize,
+    position: usize, // of the next token in the line
+    end: Location,   // of the source
+    //the values of the function being read, with where each was first used until it is
+    //defined
+    values: HashMap<&'a str, (ValueId, Option<Token<'a>>)>,
+    instructions: Vec<Instruction>,
+    labels: HashMap<&'a str, BlockId>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.lines.get(self.line)?.get(self.position).copied()
+    }
+
+    //where the next token is, or where the line ends if it has no more
+    fn span(&self) -> Span {
+        if let Some(token) = self.peek() {
+            return token.span();
+        }
+        let location = match self.lines.get(self.line).and_then(|line| line.last()) {
+            Some(token) => token.span().end,
+            None => self.end,
+        };
+        Span {
+            begin: location,
+            end: location,
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, Diagnostic> {
+        Err(Diagnostic::error(message, self.span()))
+    }
+
+    //what was found instead of what was expected
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("'{}'", token.text),
+            None => "the end of the line".to_string(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, Diagnostic> {
+        match self.peek() {
+            Some(token) => {
+                self.position += 1;
+                Ok(token)
+            }
+            None => self.error("unexpected end of the line.".to_string()),
+        }
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.peek().is_some_and(|token| token.text == text);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), Diagnostic> {
+        if !self.eat(text) {
+            return self.error(format!("expected '{}', found {}.", text, self.found()));
+        }
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), Diagnostic> {
+        if self.peek().is_some() {
+            return self.error(format!(
+                "expected the end of the line, found {}.",
+                self.found()
+            ));
+        }
+        self.line += 1;
+        self.position = 0;
+        Ok(())
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T, Diagnostic> {
+        let found = self.found();
+        match self.peek().map(|token| token.text.parse()) {
+            Some(Ok(number)) => {
+                self.position += 1;
+                Ok(number)
+            }
+            _ => self.error(format!("expected a number, found {}.", found)),
+        }
+    }
+
+    fn ty(&mut self) -> Result<IrType, Diagnostic> {
+        let found = self.found();
+        match self.peek().and_then(|token| lookup(&TYPES, token.text)) {
+            Some(ty) => {
+                self.position += 1;
+                Ok(ty)
+            }
+            None => self.error(format!("expected a type, found {}.", found)),
+        }
+    }
+
+    //a string as print writes it, with the escapes replaced by the bytes they stand for
+    fn string(&mut self) -> Result<String, Diagnostic> {
+        let found = self.found();
+        let text = match self.peek().map(|token| token.text) {
+            Some(text) if text.len() >= 2 && text.starts_with('"') => &text[1..text.len() - 1],
+            _ => return self.error(format!("expected a string, found {}.", found)),
+        };
+        let mut bytes = Vec::new();
+        let mut rest = text;
+        while let Some(position) = rest.find('\\') {
+            bytes.extend_from_slice(&rest.as_bytes()[..position]);
+            let escape = rest.get(position + 1..position + 3);
+            match escape.and_then(|digits| u8::from_str_radix(digits, 16).ok()) {
+                Some(byte) => bytes.push(byte),
+                None => return self.error(format!("invalid escape in the string {}.", found)),
+            }
+            rest = &rest[position + 3..];
+        }
+        bytes.extend_from_slice(rest.as_bytes());
+        match String::from_utf8(bytes) {
+            Ok(string) => {
+                self.position += 1;
+                Ok(string)
+            }
+            Err(_) => self.error(format!("the string {} is not valid UTF-8.", found)),
+        }
+    }
+
+    //[item, ...], each item read by read
+    fn list<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, Diagnostic>,
+    ) -> Result<Vec<T>, Diagnostic> {
+        self.expect("[")?;
+        let mut items = Vec::new();
+        if !self.eat("]") {
+            loop {
+                items.push(read(self)?);
+                if self.eat("]") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(items)
+    }
+
+    fn global_name(&mut self) -> Result<String, Diagnostic> {
+        match self.peek().and_then(|token| token.text.strip_prefix('@')) {
+            Some(name) if !name.is_empty() => {
+                self.position += 1;
+                Ok(name.to_string())
+            }
+            _ => self.error(format!("expected a global name, found {}.", self.found())),
+        }
+    }
+
+    fn module(&mut self) -> Result<Module, Diagnostic> {
+        let mut module = Module::default();
+        while let Some(token) = self.peek() {
+            match token.text {
+                "global" => module.globals.push(self.global()?),
+                "declare" => module.functions.push(self.declaration()?),
+                "define" => module.functions.push(self.definition()?),
+                _ => {
+                    let message = "expected 'global', 'declare' or 'define'";
+                    return self.error(format!("{}, found {}.", message, self.found()));
+                }
+            }
+        }
+        Ok(module)
+    }
+
+    //global [internal ]@name, size N, align N[ = [piece, ...]]
+    fn global(&mut self) -> Result<Global, Diagnostic> {
+        self.expect("global")?;
+        let internal = self.eat("internal");
+        let name = self.global_name()?;
+        self.expect(",")?;
+        self.expect("size")?;
+        let size = self.number()?;
+        self.expect(",")?;
+        self.expect("align")?;
+        let align = self.number()?;
+        let mut initializer = None;
+        if self.eat("=") {
+            self.expect("[")?;
+            let mut pieces = Vec::new();
+            if !self.eat("]") {
+                loop {
+                    pieces.push(self.piece()?);
+                    if self.eat("]") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            initializer = Some(pieces);
+        }
+        self.end_line()?;
+        Ok(Global {
+            name,
+            size,
+            align,
+            internal,
+            initializer,
+        })
+    }
+
+    fn piece(&mut self) -> Result<Piece, Diagnostic> {
+        let token = self.next()?;
+        match token.text {
+            "bytes" => {
+                let mut bytes = Vec::new();
+                while let Some(token) = self.peek() {
+                    if token.text == "," || token.text == "]" {
+                        break;
+                    }
+                    match u8::from_str_radix(token.text, 16) {
+                        Ok(byte) if token.text.len() == 2 => bytes.push(byte),
+                        _ => {
+                            let message = format!("expected a byte, found '{}'.", token.text);
+                            return self.error(message);
+                        }
+                    }
+                    self.position += 1;
+                }
+                Ok(Piece::Bytes(bytes))
+            }
+            "zero" => Ok(Piece::Zero(self.number()?)),
+            "address" => {
+                let name = self.global_name()?;
+                let offset = if self.eat("+") {
+                    self.number()?
+                } else if self.eat("-") {
+                    (self.number::<u64>()? as i64).wrapping_neg()
+                } else {
+                    0
+                };
+                Ok(Piece::Address(name, offset))
+            }
+            text => {
+                let message = "expected 'bytes', 'zero' or 'address'";
+                self.position -= 1;
+                self.error(format!("{}, found '{}'.", message, text))
+            }
+        }
+    }
+
+    //declare T @name(T, ...)
+    fn declaration(&mut self) -> Result<Function, Diagnostic> {
+        self.expect("declare")?;
+        let return_type = self.ty()?;
+        let name = self.global_name()?;
+        self.expect("(")?;
+        let mut parameters = Vec::new();
+        let mut variadic = false;
+        if !self.eat(")") {
+            loop {
+                if self.eat("...") {
+                    variadic = true;
+                    self.expect(")")?;
+                    break;
+                }
+                parameters.push(self.ty()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        self.end_line()?;
+        Ok(Function {
+            name,
+            return_type,
+            parameters,
+            variadic,
+            internal: false,
+            instructions: Vec::new(),
+            blocks: Vec::new(),
+        })
+    }
+
+    //define [internal ]T @name(T %a, ...) { followed by the blocks, each a label and its
+    //instructions, and a line with }
+    fn definition(&mut self) -> Result<Function, Diagnostic> {
+        self.values.clear();
+        self.instructions.clear();
+        self.expect("define")?;
+        let internal = self.eat("internal");
+        let return_type = self.ty()?;
+        let name = self.global_name()?;
+        self.expect("(")?;
+        let mut parameters = Vec::new();
+        let mut variadic = false;
+        if !self.eat(")") {
+            loop {
+                if self.eat("...") {
+                    variadic = true;
+                    self.expect(")")?;
+                    break;
+                }
+                let ty = self.ty()?;
+                let kind = InstructionKind::Parameter(parameters.len());
+                parameters.push(ty);
+                let name = self.value_name()?;
+                self.define(name, Instruction { ty, kind })?;
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        self.expect("{")?;
+        self.end_line()?;
+
+        let mut blocks = self.labels()?;
+        let mut current: Option<usize> = None;
+        loop {
+            let token = match self.peek() {
+                Some(token) => token,
+                None => return self.error("expected '}' at the end of the function.".to_string()),
+            };
+            let line = &self.lines[self.line];
+            if token.text == "}" {
+                self.position += 1;
+                self.end_line()?;
+                break;
+            } else if line.len() == 2 && line[1].text == ":" {
+                current = Some(self.labels[token.text].0);
+                self.line += 1;
+                continue;
+            }
+            let block = match current {
+                Some(block) if blocks[block].terminator.is_none() => block,
+                Some(_) => {
+                    let message = "expected a block label after the terminator";
+                    return self.error(format!("{}, found {}.", message, self.found()));
+                }
+                None => {
+                    let message = "expected a block label";
+                    return self.error(format!("{}, found {}.", message, self.found()));
+                }
+            };
+            match token.text {
+                "jump" | "branch" | "switch" | "ret" | "unreachable" => {
+                    blocks[block].terminator = Some(self.terminator()?);
+                }
+                _ => {
+                    let id = self.instruction()?;
+                    blocks[block].instructions.push(id);
+                }
+            }
+            self.end_line()?;
+        }
+
+        for (name, (_, first_use)) in &self.values {
+            if let Some(token) = first_use {
+                let message = format!("use of undefined value '{}'.", name);
+                return Err(Diagnostic::error(message, token.span()));
+            }
+        }
+        if blocks.is_empty() {
+            return self.error(format!("expected a block in the definition of @{}.", name));
+        }
+        Ok(Function {
+            name,
+            return_type,
+            parameters,
+            variadic,
+            internal,
+            instructions: std::mem::take(&mut self.instructions),
+            blocks,
+        })
+    }
+
+    //the blocks of the function the lines which follow define, which are numbered by the
+    //order of their labels
+    fn labels(&mut self) -> Result<Vec<Block>, Diagnostic> {
+        self.labels.clear();
+        for line in &self.lines[self.line..] {
+            if line[0].text == "}" {
+                break;
+            }
+            if line.len() == 2 && line[1].text == ":" {
+                let id = BlockId(self.labels.len());
+                if self.labels.insert(line[0].text, id).is_some() {
+                    let message = format!("redefinition of block '{}'.", line[0].text);
+                    return Err(Diagnostic::error(message, line[0].span()));
+                }
+            }
+        }
+        Ok(vec![Block::default(); self.labels.len()])
+    }
+
+    fn block(&mut self) -> Result<BlockId, Diagnostic> {
+        let found = self.found();
+        match self.peek().and_then(|token| self.labels.get(token.text)) {
+            Some(block) => {
+                let block = *block;
+                self.position += 1;
+                Ok(block)
+            }
+            None if self.peek().is_some() => {
+                self.error(format!("use of undefined block {}.", found))
+            }
+            None => self.error(format!("expected a block, found {}.", found)),
+        }
+    }
+
+    fn value_name(&mut self) -> Result<Token<'a>, Diagnostic> {
+        match self.peek() {
+            Some(token) if token.text.len() > 1 && token.text.starts_with('%') => {
+                self.position += 1;
+                Ok(token)
+            }
+            _ => self.error(format!("expected a value name, found {}.", self.found())),
+        }
+    }
+
+    //the value named by token is the one instruction defines, which takes the id it was
+    //given if it has been used already
+    fn define(
+        &mut self,
+        token: Token<'a>,
+        instruction: Instruction,
+    ) -> Result<ValueId, Diagnostic> {
+        match self.values.get(token.text).copied() {
+            Some((id, Some(_))) => {
+                self.instructions[id.0] = instruction;
+                self.values.insert(token.text, (id, None));
+                Ok(id)
+            }
+            Some((_, None)) => {
+                let message = format!("redefinition of value '{}'.", token.text);
+                Err(Diagnostic::error(message, token.span()))
+            }
+            None => {
+                let id = self.push(instruction);
+                self.values.insert(token.text, (id, None));
+                Ok(id)
+            }
+        }
+    }
+
+    fn push(&mut self, instruction: Instruction) -> ValueId {
+        self.instructions.push(instruction);
+        ValueId(self.instructions.len() - 1)
+    }
+
+    //an operand used as a value of type ty
+    fn operand(&mut self, ty: IrType) -> Result<Operand, Diagnostic> {
+        let token = self.next()?;
+        let text = token.text;
+        if text.len() > 1 && text.starts_with('%') {
+            if let Some((id, _)) = self.values.get(text) {
+                return Ok(Operand::Value(*id));
+            }
+            //a placeholder until the value is defined
+            let id = self.push(Instruction {
+                ty: IrType::Void,
+                kind: InstructionKind::Phi(Vec::new()),
+            });
+            self.values.insert(text, (id, Some(token)));
+            return Ok(Operand::Value(id));
+        }
+        let operand = match text {
+            "null" => Some(Operand::Null),
+            "undef" => Some(Operand::Undef),
+            _ if text.len() > 1 && text.starts_with('@') => {
+                Some(Operand::Global(text[1..].to_string()))
+            }
+            _ if ty.is_floating() => text.parse().ok().map(Operand::Floating),
+            _ => text.parse().ok().map(Operand::Integer),
+        };
+        match operand {
+            Some(operand) => Ok(operand),
+            None => {
+                self.position -= 1;
+                self.error(format!("expected an operand, found '{}'.", text))
+            }
+        }
+    }
+
+    //[%name = ]operation ..., where a value has a name exactly when it is not void
+    fn instruction(&mut self) -> Result<ValueId, Diagnostic> {
+        let name = match self.peek() {
+            Some(token) if token.text.starts_with('%') => {
+                let name = self.value_name()?;
+                self.expect("=")?;
+                Some(name)
+            }
+            _ => None,
+        };
+        let operation = self.next()?;
+        let (ty, kind) = match operation.text {
+            //`alloca i64 %size` is a dynamic one
+            "alloca" if self.peek().map(|token| token.text) == Some("i64") => {
+                self.position += 1;
+                let size = self.operand(IrType::I64)?;
+                self.expect(",")?;
+                self.expect("align")?;
+                let align = self.number()?;
+                (IrType::Ptr, InstructionKind::DynamicAlloca { size, align })
+            }
+            "alloca" => {
+                let size = self.number()?;
+                self.expect(",")?;
+                self.expect("align")?;
+                let align = self.number()?;
+                (IrType::Ptr, InstructionKind::Alloca { size, align })
+            }
+            "load" => {
+                let ty = self.ty()?;
+                self.expect(",")?;
+                (ty, InstructionKind::Load(self.operand(IrType::Ptr)?))
+            }
+            "store" => {
+                let ty = self.ty()?;
+                let value = self.operand(ty)?;
+                self.expect(",")?;
+                let address = self.operand(IrType::Ptr)?;
+                (IrType::Void, InstructionKind::Store { ty, value, address })
+            }
+            "cmp" => {
+                let found = self.found();
+                let predicate = match self
+                    .peek()
+                    .and_then(|token| lookup(&PREDICATES, token.text))
+                {
+                    Some(predicate) => predicate,
+                    None => return self.error(format!("expected a predicate, found {}.", found)),
+                };
+                self.position += 1;
+                let ty = self.ty()?;
+                let lhs = self.operand(ty)?;
+                self.expect(",")?;
+                let rhs = self.operand(ty)?;
+                let kind = InstructionKind::Compare {
+                    predicate,
+                    ty,
+                    lhs,
+                    rhs,
+                };
+                (IrType::I1, kind)
+            }
+            "ptradd" => {
+                let base = self.operand(IrType::Ptr)?;
+                self.expect(",")?;
+                let offset = self.operand(IrType::I64)?;
+                (IrType::Ptr, InstructionKind::PointerAdd { base, offset })
+            }
+            "call" => {
+                let ty = self.ty()?;
+                let callee = self.operand(IrType::Ptr)?;
+                self.expect("(")?;
+                let mut arguments = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        let ty = self.ty()?;
+                        arguments.push((ty, self.operand(ty)?));
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                (ty, InstructionKind::Call { callee, arguments })
+            }
+            "phi" => {
+                let ty = self.ty()?;
+                let mut incoming = Vec::new();
+                if self.peek().is_some() {
+                    loop {
+                        self.expect("[")?;
+                        let block = self.block()?;
+                        self.expect(",")?;
+                        incoming.push((block, self.operand(ty)?));
+                        self.expect("]")?;
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                }
+                (ty, InstructionKind::Phi(incoming))
+            }
+            "copy" => {
+                let destination = self.operand(IrType::Ptr)?;
+                self.expect(",")?;
+                let source = self.operand(IrType::Ptr)?;
+                self.expect(",")?;
+                let size = self.number()?;
+                let kind = InstructionKind::Copy {
+                    destination,
+                    source,
+                    size,
+                };
+                (IrType::Void, kind)
+            }
+            "zero" => {
+                let address = self.operand(IrType::Ptr)?;
+                self.expect(",")?;
+                let size = self.number()?;
+                (IrType::Void, InstructionKind::Zero { address, size })
+            }
+            "asm" => {
+                let volatile = self.eat("volatile");
+                let template = self.string()?;
+                self.expect(",")?;
+                let outputs = self.list(|parser| {
+                    let constraint = parser.string()?;
+                    Ok((constraint, parser.operand(IrType::Ptr)?))
+                })?;
+                self.expect(",")?;
+                let inputs = self.list(|parser| {
+                    let constraint = parser.string()?;
+                    let ty = parser.ty()?;
+                    Ok((constraint, ty, parser.operand(ty)?))
+                })?;
+                self.expect(",")?;
+                let clobbers = self.list(|parser| parser.string())?;
+                let kind = InstructionKind::Asm {
+                    template,
+                    outputs,
+                    inputs,
+                    clobbers,
+                    volatile,
+                };
+                (IrType::Void, kind)
+            }
+            text => {
+                if let Some(operator) = lookup(&BINARY_OPERATORS, text) {
+                    let ty = self.ty()?;
+                    let lhs = self.operand(ty)?;
+                    self.expect(",")?;
+                    let rhs = self.operand(ty)?;
+                    (ty, InstructionKind::Binary { operator, lhs, rhs })
+                } else if let Some(operator) = lookup(&CAST_OPERATORS, text) {
+                    let from = self.ty()?;
+                    let value = self.operand(from)?;
+                    self.expect("to")?;
+                    let ty = self.ty()?;
+                    let kind = InstructionKind::Cast {
+                        operator,
+                        from,
+                        value,
+                    };
+                    (ty, kind)
+                } else {
+                    let message = format!("unknown instruction '{}'.", text);
+                    return Err(Diagnostic::error(message, operation.span()));
+                }
+            }
+        };
+
+        let instruction = Instruction { ty, kind };
+        match name {
+            Some(name) if ty != IrType::Void => self.define(name, instruction),
+            None if ty == IrType::Void => Ok(self.push(instruction)),
+            Some(name) => {
+                let message = format!("'{}' names a value of type void.", name.text);
+                Err(Diagnostic::error(message, name.span()))
+            }
+            None => {
+                let message = format!("the value of '{}' has no name.", operation.text);
+                Err(Diagnostic::error(message, operation.span()))
+            }
+        }
+    }
+
+    fn terminator(&mut self) -> Result<Terminator, Diagnostic> {
+        let terminator = match self.next()?.text {
+            "jump" => Terminator::Jump(self.block()?),
+            "branch" => {
+                let condition = self.operand(IrType::I1)?;
+                self.expect(",")?;
+                let then = self.block()?;
+                self.expect(",")?;
+                let otherwise = self.block()?;
+                Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                }
+            }
+            "switch" => {
+                let ty = self.ty()?;
+                let value = self.operand(ty)?;
+                self.expect(",")?;
+                let default = self.block()?;
+                self.expect("[")?;
+                let mut cases = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        let case = self.number()?;
+                        self.expect(":")?;
+                        cases.push((case, self.block()?));
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Terminator::Switch {
+                    ty,
+                    value,
+                    default,
+                    cases,
+                }
+            }
+            "ret" if self.eat("void") => Terminator::Return(None),
+            "ret" => {
+                let ty = self.ty()?;
+                Terminator::Return(Some((ty, self.operand(ty)?)))
+            }
+            _ => Terminator::Unreachable,
+        };
+        Ok(terminator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::lower;
+    use crate::ir::verify::verify;
+    use crate::options::*;
+    use crate::testing::*;
+
+    //the printed IR of the C source, which must verify
+    fn lowered(source: &str) -> String {
+        let (typed, diagnostics) = analyze_source(source, Standard::Gnu11);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let module = lower(&typed).unwrap();
+        assert_eq!(verify(&module), Vec::<String>::new());
+        module.to_string()
+    }
+
+    fn round_trip(source: &str) {
+        let printed = lowered(source);
+        let module = match parse_module(&printed) {
+            Ok(module) => module,
+            Err(diagnostic) => panic!("{}\n{}", diagnostic, printed),
+        };
+        assert_eq!(verify(&module), Vec::<String>::new());
+        assert_eq!(module.to_string(), printed);
+    }
+
+    #[test]
+    fn control_flow_round_trips() {
+        round_trip(
+            "int g(int a, int b) { return a && b ? a : a || b; }
+            int main(void) {
+                int x = 0;
+                for (int i = 0; i < 3; i++) x += i;
+                while (x > 10) x--;
+                do x++; while (x < 2);
+                switch (x) { case 1: x = 2; __attribute__((fallthrough)); case 3 ... 5: break; default: x = 0; }
+                if (x) goto end;
+                x = g(x, 1);
+            end:
+                return x;
+            }",
+        );
+    }
+
+    #[test]
+    fn globals_strings_and_memory_round_trip() {
+        round_trip(
+            "struct S { char name[8]; int n : 5; } s = {\"a\\\"b\\n\", 3};
+            const char *p = \"quote \\\" and \\\\\";
+            int main(void) {
+                int n = s.n, a[n];
+                a[0] = p[1];
+                s.n = a[0];
+                return sizeof a;
+            }",
+        );
+    }
+
+    #[test]
+    fn asm_round_trips() {
+        round_trip(
+            "int g;
+            int main(void) {
+                int x = 1;
+                asm volatile(\"addl %[in], %0\\n\" : \"+r\"(x), \"=m\"(g) : [in] \"r\"(2) : \"cc\");
+                return x;
+            }",
+        );
+    }
+
+    #[test]
+    fn values_may_be_used_before_they_are_defined() {
+        let source = "define i32 @f(i32 %a) {
+            entry:
+              jump loop
+            loop:
+              %i = phi i32 [entry, 0], [loop, %next]
+              %next = add i32 %i, 1
+              %done = cmp eq i32 %next, %a
+              branch %done, exit, loop
+            exit:
+              ret i32 %next
+            }";
+        let module = parse_module(source).unwrap();
+        assert_eq!(verify(&module), Vec::<String>::new());
+        let printed = module.to_string();
+        assert!(
+            printed.contains("%1 = phi i32 [bb0, 0], [bb1, %2]"),
+            "{}",
+            printed
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let error = parse_module("define i32 @f() {\nbb0:\n  br bb0\n}").unwrap_err();
+        assert_eq!(error.message, "unknown instruction 'br'.");
+        assert_eq!((error.span.begin.line, error.span.begin.column), (3, 3));
+    }
+}
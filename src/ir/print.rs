@@ -0,0 +1,412 @@
+use crate::ir::module::*;
+use std::collections::HashMap;
+use std::fmt;
+
+//the textual form of the IR, which the parser reads back. the values of a function are
+//numbered in order, the parameters first, and the blocks by their index:
+//
+//  global internal @.str.0, size 3, align 1 = [bytes 68 69 00]
+//  declare i32 @puts(ptr)
+//  define i32 @main(i32 %0) {
+//  bb0:
+//    %1 = cmp slt i32 %0, 2
+//    branch %1, bb1, bb2
+//  ...
+//  }
+//
+//a string is written in double quotes, with a character other than a printable ASCII one,
+//a double quote and a backslash as a backslash and two hexadecimal digits, as in
+//`asm volatile "nop\0A", ["=r" %2], ["r" i32 %1], ["cc"]`.
+
+pub const TYPES: [(IrType, &str); 9] = [
+    (IrType::Void, "void"),
+    (IrType::I1, "i1"),
+    (IrType::I8, "i8"),
+    (IrType::I16, "i16"),
+    (IrType::I32, "i32"),
+    (IrType::I64, "i64"),
+    (IrType::F32, "f32"),
+    (IrType::F64, "f64"),
+    (IrType::Ptr, "ptr"),
+];
+
+pub const BINARY_OPERATORS: [(BinaryOperator, &str); 17] = [
+    (BinaryOperator::Add, "add"),
+    (BinaryOperator::Sub, "sub"),
+    (BinaryOperator::Mul, "mul"),
+    (BinaryOperator::SDiv, "sdiv"),
+    (BinaryOperator::UDiv, "udiv"),
+    (BinaryOperator::SRem, "srem"),
+    (BinaryOperator::URem, "urem"),
+    (BinaryOperator::Shl, "shl"),
+    (BinaryOperator::LShr, "lshr"),
+    (BinaryOperator::AShr, "ashr"),
+    (BinaryOperator::And, "and"),
+    (BinaryOperator::Or, "or"),
+    (BinaryOperator::Xor, "xor"),
+    (BinaryOperator::FAdd, "fadd"),
+    (BinaryOperator::FSub, "fsub"),
+    (BinaryOperator::FMul, "fmul"),
+    (BinaryOperator::FDiv, "fdiv"),
+];
+
+pub const PREDICATES: [(Predicate, &str); 16] = [
+    (Predicate::Eq, "eq"),
+    (Predicate::Ne, "ne"),
+    (Predicate::Slt, "slt"),
+    (Predicate::Sle, "sle"),
+    (Predicate::Sgt, "sgt"),
+    (Predicate::Sge, "sge"),
+    (Predicate::Ult, "ult"),
+    (Predicate::Ule, "ule"),
+    (Predicate::Ugt, "ugt"),
+    (Predicate::Uge, "uge"),
+    (Predicate::FEq, "feq"),
+    (Predicate::FNe, "fne"),
+    (Predicate::FLt, "flt"),
+    (Predicate::FLe, "fle"),
+    (Predicate::FGt, "fgt"),
+    (Predicate::FGe, "fge"),
+];
+
+pub const CAST_OPERATORS: [(CastOperator, &str); 11] = [
+    (CastOperator::Trunc, "trunc"),
+    (CastOperator::ZExt, "zext"),
+    (CastOperator::SExt, "sext"),
+    (CastOperator::FpTrunc, "fptrunc"),
+    (CastOperator::FpExt, "fpext"),
+    (CastOperator::FpToSi, "fptosi"),
+    (CastOperator::FpToUi, "fptoui"),
+    (CastOperator::SiToFp, "sitofp"),
+    (CastOperator::UiToFp, "uitofp"),
+    (CastOperator::PtrToInt, "ptrtoint"),
+    (CastOperator::IntToPtr, "inttoptr"),
+];
+
+fn spelling<T: PartialEq + Copy>(table: &[(T, &'static str)], value: T) -> &'static str {
+    table.iter().find(|(entry, _)| *entry == value).unwrap().1
+}
+
+impl fmt::Display for IrType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", spelling(&TYPES, *self))
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", spelling(&BINARY_OPERATORS, *self))
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", spelling(&PREDICATES, *self))
+    }
+}
+
+impl fmt::Display for CastOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", spelling(&CAST_OPERATORS, *self))
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+//the number each value of function is printed with
+pub fn numbering(function: &Function) -> HashMap<ValueId, usize> {
+    let mut result: HashMap<ValueId, usize> = (0..function.parameters.len())
+        .map(|index| (ValueId(index), index))
+        .collect();
+    for block in &function.blocks {
+        for id in &block.instructions {
+            if function.instruction(*id).ty != IrType::Void {
+                result.insert(*id, result.len());
+            }
+        }
+    }
+    result
+}
+
+struct Printer<'a> {
+    function: &'a Function,
+    names: HashMap<ValueId, usize>,
+}
+
+impl Printer<'_> {
+    fn operand(&self, operand: &Operand) -> String {
+        match operand {
+            Operand::Value(id) => match self.names.get(id) {
+                Some(number) => format!("%{}", number),
+                None => format!("%<dead {}>", id.0), // an instruction in no block
+            },
+            Operand::Integer(value) => value.to_string(),
+            Operand::Floating(value) => format!("{:?}", value),
+            Operand::Null => "null".to_string(),
+            Operand::Undef => "undef".to_string(),
+            Operand::Global(name) => format!("@{}", name),
+        }
+    }
+
+    fn typed(&self, ty: IrType, operand: &Operand) -> String {
+        format!("{} {}", ty, self.operand(operand))
+    }
+
+    fn instruction(&self, id: ValueId) -> String {
+        let instruction = self.function.instruction(id);
+        let ty = instruction.ty;
+        let body = match &instruction.kind {
+            InstructionKind::Parameter(index) => format!("parameter {}", index),
+            InstructionKind::Alloca { size, align } => format!("alloca {}, align {}", size, align),
+            InstructionKind::DynamicAlloca { size, align } => {
+                format!("alloca {}, align {}", self.typed(IrType::I64, size), align)
+            }
+            InstructionKind::Load(address) => {
+                format!("load {}, {}", ty, self.operand(address))
+            }
+            InstructionKind::Store { ty, value, address } => format!(
+                "store {}, {}",
+                self.typed(*ty, value),
+                self.operand(address)
+            ),
+            InstructionKind::Binary { operator, lhs, rhs } => format!(
+                "{} {}, {}",
+                operator,
+                self.typed(ty, lhs),
+                self.operand(rhs)
+            ),
+            InstructionKind::Compare {
+                predicate,
+                ty,
+                lhs,
+                rhs,
+            } => format!(
+                "cmp {} {}, {}",
+                predicate,
+                self.typed(*ty, lhs),
+                self.operand(rhs)
+            ),
+            InstructionKind::Cast {
+                operator,
+                from,
+                value,
+            } => format!("{} {} to {}", operator, self.typed(*from, value), ty),
+            InstructionKind::PointerAdd { base, offset } => {
+                format!("ptradd {}, {}", self.operand(base), self.operand(offset))
+            }
+            InstructionKind::Call { callee, arguments } => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|(ty, argument)| self.typed(*ty, argument))
+                    .collect();
+                format!(
+                    "call {} {}({})",
+                    ty,
+                    self.operand(callee),
+                    arguments.join(", ")
+                )
+            }
+            InstructionKind::Phi(incoming) => {
+                let incoming: Vec<String> = incoming
+                    .iter()
+                    .map(|(block, value)| format!("[{}, {}]", block, self.operand(value)))
+                    .collect();
+                format!("phi {} {}", ty, incoming.join(", "))
+            }
+            InstructionKind::Copy {
+                destination,
+                source,
+                size,
+            } => format!(
+                "copy {}, {}, {}",
+                self.operand(destination),
+                self.operand(source),
+                size
+            ),
+            InstructionKind::Zero { address, size } => {
+                format!("zero {}, {}", self.operand(address), size)
+            }
+            InstructionKind::Asm {
+                template,
+                outputs,
+                inputs,
+                clobbers,
+                volatile,
+            } => {
+                let outputs: Vec<String> = outputs
+                    .iter()
+                    .map(|(constraint, address)| {
+                        format!("{} {}", quoted(constraint), self.operand(address))
+                    })
+                    .collect();
+                let inputs: Vec<String> = inputs
+                    .iter()
+                    .map(|(constraint, ty, value)| {
+                        format!("{} {}", quoted(constraint), self.typed(*ty, value))
+                    })
+                    .collect();
+                let clobbers: Vec<String> =
+                    clobbers.iter().map(|clobber| quoted(clobber)).collect();
+                format!(
+                    "asm {}{}, [{}], [{}], [{}]",
+                    if *volatile { "volatile " } else { "" },
+                    quoted(template),
+                    outputs.join(", "),
+                    inputs.join(", "),
+                    clobbers.join(", ")
+                )
+            }
+        };
+        match self.names.get(&id) {
+            Some(number) if ty != IrType::Void => format!("%{} = {}", number, body),
+            _ => body,
+        }
+    }
+
+    fn terminator(&self, terminator: &Option<Terminator>) -> String {
+        let terminator = match terminator {
+            Some(terminator) => terminator,
+            None => return "; no terminator".to_string(),
+        };
+        match terminator {
+            Terminator::Jump(target) => format!("jump {}", target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => format!(
+                "branch {}, {}, {}",
+                self.operand(condition),
+                then,
+                otherwise
+            ),
+            Terminator::Switch {
+                ty,
+                value,
+                default,
+                cases,
+            } => {
+                let cases: Vec<String> = cases
+                    .iter()
+                    .map(|(value, target)| format!("{}: {}", value, target))
+                    .collect();
+                format!(
+                    "switch {}, {} [{}]",
+                    self.typed(*ty, value),
+                    default,
+                    cases.join(", ")
+                )
+            }
+            Terminator::Return(Some((ty, value))) => format!("ret {}", self.typed(*ty, value)),
+            Terminator::Return(None) => "ret void".to_string(),
+            Terminator::Unreachable => "unreachable".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let internal = if self.internal { "internal " } else { "" };
+        write!(
+            f,
+            "global {}@{}, size {}, align {}",
+            internal, self.name, self.size, self.align
+        )?;
+        if let Some(pieces) = &self.initializer {
+            let pieces: Vec<String> = pieces
+                .iter()
+                .map(|piece| match piece {
+                    Piece::Bytes(bytes) => {
+                        let bytes: Vec<String> =
+                            bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                        format!("bytes {}", bytes.join(" "))
+                    }
+                    Piece::Zero(size) => format!("zero {}", size),
+                    Piece::Address(name, 0) => format!("address @{}", name),
+                    Piece::Address(name, offset) if *offset < 0 => {
+                        format!("address @{} - {}", name, offset.unsigned_abs())
+                    }
+                    Piece::Address(name, offset) => format!("address @{} + {}", name, offset),
+                })
+                .collect();
+            write!(f, " = [{}]", pieces.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| match self.is_declaration() {
+                true => ty.to_string(),
+                false => format!("{} %{}", ty, index),
+            })
+            .collect();
+        if self.variadic {
+            parameters.push("...".to_string());
+        }
+        let parameters = parameters.join(", ");
+        if self.is_declaration() {
+            return write!(
+                f,
+                "declare {} @{}({})",
+                self.return_type, self.name, parameters
+            );
+        }
+
+        let internal = if self.internal { "internal " } else { "" };
+        writeln!(
+            f,
+            "define {}{} @{}({}) {{",
+            internal, self.return_type, self.name, parameters
+        )?;
+        let printer = Printer {
+            function: self,
+            names: numbering(self),
+        };
+        for (index, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "{}:", BlockId(index))?;
+            for id in &block.instructions {
+                writeln!(f, "  {}", printer.instruction(*id))?;
+            }
+            writeln!(f, "  {}", printer.terminator(&block.terminator))?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for global in &self.globals {
+            writeln!(f, "{}", global)?;
+        }
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+//text written as a string of the textual form
+pub fn quoted(text: &str) -> String {
+    let mut result = String::from("\"");
+    for byte in text.bytes() {
+        match byte {
+            b' '..=b'~' if byte != b'"' && byte != b'\\' => result.push(byte as char),
+            _ => result.push_str(&format!("\\{:02X}", byte)),
+        }
+    }
+    result.push('"');
+    result
+}
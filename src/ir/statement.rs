@@ -0,0 +1,799 @@
+use crate::ir::builder::*;
+use crate::ir::expression::*;
+use crate::ir::global::*;
+use crate::ir::module::*;
+use crate::ir::util::*;
+use crate::parser::asm::*;
+use crate::semantic::constant::*;
+use crate::semantic::declaration::*;
+use crate::semantic::expression::*;
+use crate::semantic::external_declaration::*;
+use crate::semantic::initializer::*;
+use crate::semantic::statement::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+use crate::util::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+//the return type, the parameters and whether the function is variadic. a function declared
+//without a prototype may be called with any arguments.
+pub fn signature(
+    context: &LowerContext,
+    ty: TypeId,
+    span: Span,
+) -> LowerResult<(IrType, Vec<IrType>, bool)> {
+    let function = context.types.function_type(ty).unwrap();
+    let mut parameters = Vec::new();
+    let return_type = if is_record(context, function.return_type) {
+        parameters.push(IrType::Ptr);
+        IrType::Void
+    } else {
+        ir_type(context, function.return_type, span)?
+    };
+    for parameter in &function.parameters {
+        parameters.push(ir_type(context, *parameter, span)?);
+    }
+    Ok((return_type, parameters, function.variadic))
+}
+
+pub fn function_definition(
+    context: &mut LowerContext,
+    definition: &TypedFunctionDefinition,
+) -> LowerResult<()> {
+    let symbol = symbol(context, definition.symbol);
+    let span = symbol.span;
+    let (return_type, parameters, variadic) = signature(context, symbol.ty, span)?;
+    let name = global_name(context, definition.symbol);
+    let internal = symbol.linkage == Linkage::Internal;
+    let function = FunctionBuilder::new(name, return_type, parameters, variadic, internal);
+    let mut addressed = HashSet::new();
+    walk_statement(&definition.body, &mut |expression| {
+        if let TypedExpressionKind::Unary {
+            kind: UnaryKind::Reference,
+            operand,
+        } = &expression.kind
+        {
+            if let TypedExpressionKind::Identifier(id) = operand.kind {
+                addressed.insert(id);
+            }
+        }
+    });
+    context.function = Some(FunctionState {
+        builder: function,
+        symbol: definition.symbol,
+        locals: HashMap::new(),
+        addressed,
+        labels: HashMap::new(),
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        switches: Vec::new(),
+        result: None,
+        lengths: HashMap::new(),
+    });
+
+    //a struct or union is returned through the first parameter
+    let result_type = context.types.function_type(symbol.ty).unwrap().return_type;
+    let mut index = 0;
+    if is_record(context, result_type) {
+        state(context).result = Some(builder(context).parameter(0));
+        index = 1;
+    }
+    for parameter in &definition.parameters {
+        let argument = builder(context).parameter(index);
+        index += 1;
+        if let Some(parameter) = parameter {
+            let ty = self::symbol(context, *parameter).ty;
+            if is_record(context, ty) {
+                state(context)
+                    .locals
+                    .insert(*parameter, Local::Memory(argument));
+            } else {
+                let local = local(context, *parameter)?;
+                let place = match &local {
+                    Local::Variable(variable) => Place::Variable(*variable),
+                    Local::Memory(address) => Place::Memory(address.clone(), None),
+                };
+                store(context, place, ty, argument, span)?;
+            }
+        }
+    }
+
+    statement(context, &definition.body)?;
+
+    //falling off the end of main returns 0
+    if !builder(context).is_terminated() {
+        let value = match return_type {
+            IrType::Void => None,
+            IrType::I32 if symbol.name == "main" => Some((IrType::I32, Operand::Integer(0))),
+            ty => Some((ty, Operand::Undef)),
+        };
+        builder(context).terminate(Terminator::Return(value));
+    }
+    let state = context.function.take().unwrap();
+    context.module.functions.push(state.builder.finish());
+    Ok(())
+}
+
+//a scalar local whose address is never taken is held in SSA values, and any other in an
+//object the function allocates
+fn local(context: &mut LowerContext, id: SymbolId) -> LowerResult<Local> {
+    let symbol = symbol(context, id);
+    let types = context.types;
+    let is_promoted = types.is_scalar(symbol.ty)
+        && !types.qualifiers(symbol.ty).is_volatile
+        && !state(context).addressed.contains(&id);
+    let local = if is_promoted {
+        let ty = ir_type(context, symbol.ty, symbol.span)?;
+        Local::Variable(builder(context).declare_variable(ty))
+    } else {
+        let size = size_of(context, symbol.ty, symbol.span)?;
+        let align = object_align(context.types, symbol);
+        Local::Memory(builder(context).alloca(size, align))
+    };
+    state(context).locals.insert(id, local.clone());
+    Ok(local)
+}
+
+fn statement(context: &mut LowerContext, statement: &TypedStatement) -> LowerResult<()> {
+    match statement {
+        TypedStatement::Null | TypedStatement::Error => {}
+        TypedStatement::Expression(expression) => {
+            value(context, expression)?;
+        }
+        TypedStatement::If {
+            condition,
+            statement,
+            else_statement,
+        } => {
+            let then = builder(context).new_block();
+            let end = builder(context).new_block();
+            let otherwise = match else_statement {
+                Some(_) => builder(context).new_block(),
+                None => end,
+            };
+            let condition = truth(context, condition)?;
+            builder(context).terminate(branch(condition, then, otherwise));
+            builder(context).seal(then);
+            builder(context).switch_to(then);
+            self::statement(context, statement)?;
+            builder(context).jump(end);
+            if let Some(else_statement) = else_statement {
+                builder(context).seal(otherwise);
+                builder(context).switch_to(otherwise);
+                self::statement(context, else_statement)?;
+                builder(context).jump(end);
+            }
+            builder(context).seal(end);
+            builder(context).switch_to(end);
+        }
+        TypedStatement::Switch {
+            condition,
+            statement,
+        } => switch(context, condition, statement)?,
+        TypedStatement::Labeled { label, statement } => {
+            let block = label_block(context, label);
+            enter(context, block);
+            self::statement(context, statement)?;
+        }
+        TypedStatement::Case { value, statement } => {
+            let block = case_block(context);
+            let switch = state(context).switches.last_mut().unwrap();
+            switch.cases.push((*value, *value, block));
+            self::statement(context, statement)?;
+        }
+        TypedStatement::CaseRange {
+            first,
+            last,
+            statement,
+        } => {
+            let block = case_block(context);
+            let switch = state(context).switches.last_mut().unwrap();
+            switch.cases.push((*first, *last, block));
+            self::statement(context, statement)?;
+        }
+        TypedStatement::Default(statement) => {
+            let block = case_block(context);
+            state(context).switches.last_mut().unwrap().default = Some(block);
+            self::statement(context, statement)?;
+        }
+        TypedStatement::While {
+            condition,
+            statement,
+        } => {
+            let header = builder(context).new_block();
+            let body = builder(context).new_block();
+            let end = builder(context).new_block();
+            enter(context, header);
+            let condition = truth(context, condition)?;
+            builder(context).terminate(branch(condition, body, end));
+            builder(context).seal(body);
+            builder(context).switch_to(body);
+            loop_body(context, statement, end, header)?;
+            builder(context).jump(header);
+            builder(context).seal(header);
+            builder(context).seal(end);
+            builder(context).switch_to(end);
+        }
+        TypedStatement::DoWhile {
+            condition,
+            statement,
+        } => {
+            let body = builder(context).new_block();
+            let latch = builder(context).new_block();
+            let end = builder(context).new_block();
+            enter(context, body);
+            loop_body(context, statement, end, latch)?;
+            builder(context).jump(latch);
+            builder(context).seal(latch);
+            builder(context).switch_to(latch);
+            let condition = truth(context, condition)?;
+            builder(context).terminate(branch(condition, body, end));
+            builder(context).seal(body);
+            builder(context).seal(end);
+            builder(context).switch_to(end);
+        }
+        TypedStatement::For {
+            initialization,
+            condition,
+            afterthought,
+            statement,
+        } => {
+            match initialization {
+                Some(Either::Left(declarations)) => declare(context, declarations)?,
+                Some(Either::Right(expression)) => {
+                    value(context, expression)?;
+                }
+                None => {}
+            }
+            let header = builder(context).new_block();
+            let body = builder(context).new_block();
+            let latch = builder(context).new_block();
+            let end = builder(context).new_block();
+            enter(context, header);
+            match condition {
+                Some(condition) => {
+                    let condition = truth(context, condition)?;
+                    builder(context).terminate(branch(condition, body, end));
+                }
+                None => builder(context).jump(body),
+            }
+            builder(context).seal(body);
+            builder(context).switch_to(body);
+            loop_body(context, statement, end, latch)?;
+            builder(context).jump(latch);
+            builder(context).seal(latch);
+            builder(context).switch_to(latch);
+            if let Some(afterthought) = afterthought {
+                value(context, afterthought)?;
+            }
+            builder(context).jump(header);
+            builder(context).seal(header);
+            builder(context).seal(end);
+            builder(context).switch_to(end);
+        }
+        TypedStatement::Compound(items) => {
+            for item in items {
+                block_item(context, item)?;
+            }
+        }
+        TypedStatement::Return(expression) => {
+            let value = match expression {
+                Some(expression) => Some((expression, value(context, expression)?)),
+                None => None,
+            };
+            let return_type = builder(context).function.return_type;
+            let value = match (value, state(context).result.clone()) {
+                (Some((expression, value)), Some(result)) => {
+                    let place = Place::Memory(result, None);
+                    store(context, place, expression.ty, value, expression.span)?;
+                    None
+                }
+                _ if return_type == IrType::Void => None,
+                (Some((expression, value)), None) => {
+                    let function = state(context).symbol;
+                    let function = symbol(context, function).ty;
+                    let function = context.types.function_type(function).unwrap();
+                    let value = convert(
+                        context,
+                        value,
+                        expression.ty,
+                        function.return_type,
+                        expression.span,
+                    )?;
+                    Some((return_type, value))
+                }
+                (None, _) => Some((return_type, Operand::Undef)),
+            };
+            builder(context).terminate(Terminator::Return(value));
+        }
+        TypedStatement::Break => {
+            let target = *state(context).breaks.last().unwrap();
+            builder(context).jump(target);
+        }
+        TypedStatement::Continue => {
+            let target = *state(context).continues.last().unwrap();
+            builder(context).jump(target);
+        }
+        TypedStatement::Goto(label) => {
+            let target = label_block(context, label);
+            builder(context).jump(target);
+        }
+        TypedStatement::Asm(asm) => asm_statement(context, asm)?,
+    }
+    Ok(())
+}
+
+//an output which is not an object in memory, as a local held in a variable or a
+//bit-field, is given a temporary object, whose value is stored to it after the asm. so is
+//an input with a memory constraint which is not one.
+fn asm_statement(context: &mut LowerContext, asm: &TypedAsmStatement) -> LowerResult<()> {
+    if !asm.labels.is_empty() {
+        let function = state(context).symbol;
+        let function = symbol(context, function);
+        let message = format!(
+            "asm goto in '{}' cannot be lowered to the IR.",
+            function.name
+        );
+        return error(message, function.span);
+    }
+
+    let mut outputs = Vec::new();
+    let mut results = Vec::new();
+    for operand in &asm.outputs {
+        let (expression, span) = (&operand.expression, operand.expression.span);
+        let address = match place(context, expression)? {
+            Place::Memory(address, None) => address,
+            place => {
+                let address = temporary(context, expression.ty, span)?;
+                if operand.constraint.starts_with('+') {
+                    let value = load(context, place.clone(), expression.ty, span)?;
+                    let memory = Place::Memory(address.clone(), None);
+                    store(context, memory, expression.ty, value, span)?;
+                }
+                results.push((place, address.clone(), expression));
+                address
+            }
+        };
+        outputs.push((operand.constraint.to_string(), address));
+    }
+    let mut inputs = Vec::new();
+    for operand in &asm.inputs {
+        let (expression, span) = (&operand.expression, operand.expression.span);
+        let input = if is_memory_constraint(operand.constraint) {
+            let address = match place(context, expression)? {
+                Place::Memory(address, None) => address,
+                place => {
+                    let value = load(context, place, expression.ty, span)?;
+                    let address = temporary(context, expression.ty, span)?;
+                    let memory = Place::Memory(address.clone(), None);
+                    store(context, memory, expression.ty, value, span)?;
+                    address
+                }
+            };
+            (IrType::Ptr, address)
+        } else {
+            (
+                ir_type(context, expression.ty, span)?,
+                value(context, expression)?,
+            )
+        };
+        inputs.push((operand.constraint.to_string(), input.0, input.1));
+    }
+
+    let template: Vec<u8> = asm
+        .template
+        .iter()
+        .flat_map(|piece| string_bytes(piece))
+        .collect();
+    let names: Vec<Option<&str>> = asm
+        .outputs
+        .iter()
+        .chain(asm.inputs.iter())
+        .map(|operand| operand.name)
+        .collect();
+    let kind = InstructionKind::Asm {
+        template: numbered_template(&String::from_utf8_lossy(&template), &names),
+        outputs,
+        inputs,
+        clobbers: asm
+            .clobbers
+            .iter()
+            .map(|clobber| clobber.to_string())
+            .collect(),
+        volatile: asm.qualifiers.contains(&AsmQualifier::Volatile),
+    };
+    builder(context).append(IrType::Void, kind);
+
+    for (place, address, expression) in results {
+        let span = expression.span;
+        let value = load(context, Place::Memory(address, None), expression.ty, span)?;
+        store(context, place, expression.ty, value, span)?;
+    }
+    Ok(())
+}
+
+//the operands of the IR have no names, so `%[name]` in the template, after any modifier, is
+//replaced by the number of the operand. the parser has checked that each name is defined.
+fn numbered_template(template: &str, names: &[Option<&str>]) -> String {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        result.push(c);
+        if c != '%' {
+            continue;
+        }
+        if let Some(modifier) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            result.push(modifier);
+        }
+        if chars.next_if_eq(&'[').is_some() {
+            let name: String = chars.by_ref().take_while(|c| *c != ']').collect();
+            match names.iter().position(|other| *other == Some(name.as_str())) {
+                Some(number) => result.push_str(&number.to_string()),
+                None => result.push_str(&format!("[{}]", name)),
+            }
+        } else if let Some(c) = chars.next_if_eq(&'%') {
+            result.push(c);
+        }
+    }
+    result
+}
+
+//control falls through into block, which the code after goes into
+fn enter(context: &mut LowerContext, block: BlockId) {
+    builder(context).jump(block);
+    builder(context).switch_to(block);
+}
+
+fn loop_body(
+    context: &mut LowerContext,
+    statement: &TypedStatement,
+    break_target: BlockId,
+    continue_target: BlockId,
+) -> LowerResult<()> {
+    state(context).breaks.push(break_target);
+    state(context).continues.push(continue_target);
+    self::statement(context, statement)?;
+    state(context).breaks.pop();
+    state(context).continues.pop();
+    Ok(())
+}
+
+//a label is a block a goto may jump to from anywhere in the function, so it is only sealed
+//once the whole function is lowered
+fn label_block(context: &mut LowerContext, label: &str) -> BlockId {
+    if let Some(block) = state(context).labels.get(label) {
+        return *block;
+    }
+    let block = builder(context).new_block();
+    state(context).labels.insert(label.to_string(), block);
+    block
+}
+
+//a case or default label is entered from the dispatch of its switch, and falls through
+//from the statement before it
+fn case_block(context: &mut LowerContext) -> BlockId {
+    let block = builder(context).new_block();
+    enter(context, block);
+    block
+}
+
+//the body is lowered first, from a block nothing enters, and the dispatch jumps to the case
+//labels found in it. a case range is tested before the other cases.
+fn switch(
+    context: &mut LowerContext,
+    condition: &TypedExpression,
+    body: &TypedStatement,
+) -> LowerResult<()> {
+    let value = value(context, condition)?;
+    let ty = ir_type(context, condition.ty, condition.span)?;
+    let dispatch = builder(context).insertion_block();
+    let start = builder(context).new_block();
+    let end = builder(context).new_block();
+    builder(context).seal(start);
+    builder(context).switch_to(start);
+    state(context).switches.push(Switch::default());
+    state(context).breaks.push(end);
+    statement(context, body)?;
+    builder(context).jump(end);
+    state(context).breaks.pop();
+    let switch = state(context).switches.pop().unwrap();
+
+    builder(context).switch_to(dispatch);
+    let default = switch.default.unwrap_or(end);
+    let mut cases = Vec::new();
+    for (first, last, block) in &switch.cases {
+        if first == last {
+            if let Operand::Integer(value) = integer(ty, *first) {
+                cases.push((value, *block));
+            }
+            continue;
+        }
+        let next = builder(context).new_block();
+        let builder = builder(context);
+        let kind = InstructionKind::Binary {
+            operator: BinaryOperator::Sub,
+            lhs: value.clone(),
+            rhs: integer(ty, *first),
+        };
+        let offset = builder.append(ty, kind);
+        let kind = InstructionKind::Compare {
+            predicate: Predicate::Ule,
+            ty,
+            lhs: offset,
+            rhs: integer(ty, last - first),
+        };
+        let condition = builder.append(IrType::I1, kind);
+        builder.terminate(branch(condition, *block, next));
+        builder.seal(next);
+        builder.switch_to(next);
+    }
+    builder(context).terminate(Terminator::Switch {
+        ty,
+        value,
+        default,
+        cases,
+    });
+    for (_, _, block) in &switch.cases {
+        builder(context).seal(*block);
+    }
+    builder(context).seal(default);
+    builder(context).seal(end);
+    builder(context).switch_to(end);
+    Ok(())
+}
+
+fn block_item(context: &mut LowerContext, item: &TypedBlockItem) -> LowerResult<()> {
+    match item {
+        TypedBlockItem::Declaration(declarations) => declare(context, declarations),
+        TypedBlockItem::Statement(statement) => self::statement(context, statement),
+    }
+}
+
+//an object declared with static is a global named after the function unless an asm label
+//names it, and one declared with extern is the global of its name
+fn declare(context: &mut LowerContext, declarations: &[TypedDeclaration]) -> LowerResult<()> {
+    for declaration in declarations {
+        evaluate_lengths(context, &declaration.lengths)?;
+
+        let id = declaration.symbol;
+        let symbol = symbol(context, id);
+        if symbol.kind != SymbolKind::Object || symbol.linkage != Linkage::None {
+            continue;
+        }
+        //a variable length array is allocated each time its declaration is reached
+        if context.types.is_variably_sized(symbol.ty) {
+            let size = variable_size(context, symbol.ty, symbol.span)?;
+            let align = object_align(context.types, symbol);
+            let kind = InstructionKind::DynamicAlloca { size, align };
+            let address = builder(context).append(IrType::Ptr, kind);
+            state(context).locals.insert(id, Local::Memory(address));
+            continue;
+        }
+        if has_static_storage(symbol) {
+            let name = match &symbol.asm_label {
+                Some(label) => label.clone(),
+                None => format!("{}.{}", builder(context).function.name, symbol.name),
+            };
+            let name = unique_name(context, name);
+            context.names.insert(id, name.clone());
+            define_global(context, id, name, declaration.initializer.as_ref())?;
+            continue;
+        }
+
+        let local = local(context, id)?;
+        let initializer = match &declaration.initializer {
+            Some(initializer) => initializer,
+            None => continue,
+        };
+        match local {
+            Local::Memory(address) => {
+                initialize(context, address, symbol.ty, initializer, symbol.span)?
+            }
+            //the last value of a scalar initializer is the one it keeps
+            Local::Variable(variable) => {
+                let mut result = None;
+                for (_, expression) in initializer.stores(symbol.ty) {
+                    let value = value(context, expression)?;
+                    result = Some(convert(
+                        context,
+                        value,
+                        expression.ty,
+                        symbol.ty,
+                        symbol.span,
+                    )?);
+                }
+                let ty = ir_type(context, symbol.ty, symbol.span)?;
+                let value = result.unwrap_or(match ty {
+                    IrType::F32 | IrType::F64 => Operand::Floating(0.0),
+                    IrType::Ptr => Operand::Null,
+                    _ => Operand::Integer(0),
+                });
+                builder(context).write_variable(variable, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+//stores the values of initializer into the object at address. an array, and an object
+//initialized by a list, is zeroed first, which is the value of the subobjects left out.
+pub fn initialize(
+    context: &mut LowerContext,
+    address: Operand,
+    ty: TypeId,
+    initializer: &TypedInitializer,
+    span: Span,
+) -> LowerResult<()> {
+    if matches!(initializer, TypedInitializer::List(_)) || context.types.is_array(ty) {
+        let size = size_of(context, ty, span)?;
+        let kind = InstructionKind::Zero {
+            address: address.clone(),
+            size,
+        };
+        builder(context).append(IrType::Void, kind);
+    }
+    for (subobjects, expression) in initializer.stores(ty) {
+        let span = expression.span;
+
+        //a string literal initializes an array by as many of its characters as fit
+        if let (TypedExpressionKind::String(content), true) =
+            (&expression.kind, context.types.is_array(subobjects[0].ty))
+        {
+            let length = size_of(context, expression.ty, span)?;
+            let size = size_of(context, subobjects[0].ty, span)?.min(length);
+            let source = Operand::Global(string_global(context, content));
+            for subobject in subobjects {
+                let place = subobject_place(context, address.clone(), subobject, span)?;
+                let kind = InstructionKind::Copy {
+                    destination: place.address(),
+                    source: source.clone(),
+                    size,
+                };
+                builder(context).append(IrType::Void, kind);
+            }
+            continue;
+        }
+        //the expression of a range designator is evaluated once for all its subobjects
+        let value = value(context, expression)?;
+        for subobject in subobjects {
+            let place = subobject_place(context, address.clone(), subobject, span)?;
+            let value = convert(context, value.clone(), expression.ty, subobject.ty, span)?;
+            store(context, place, subobject.ty, value, span)?;
+        }
+    }
+    Ok(())
+}
+
+//the value of a statement expression is that of its last expression statement
+pub fn statement_expression(
+    context: &mut LowerContext,
+    body: &TypedStatement,
+) -> LowerResult<Operand> {
+    let items = match body {
+        TypedStatement::Compound(items) => items,
+        _ => unreachable!(),
+    };
+    let (rest, last) = match items.split_last() {
+        Some((TypedBlockItem::Statement(TypedStatement::Expression(last)), rest)) => {
+            (rest, Some(last))
+        }
+        _ => (items.as_slice(), None),
+    };
+    for item in rest {
+        block_item(context, item)?;
+    }
+    match last {
+        Some(last) => value(context, last),
+        None => Ok(Operand::Undef),
+    }
+}
+
+//calls visit with each expression in statement, and the expressions in those
+pub fn walk_statement(statement: &TypedStatement, visit: &mut dyn FnMut(&TypedExpression)) {
+    match statement {
+        TypedStatement::Expression(expression) => walk_expression(expression, visit),
+        TypedStatement::If { condition, .. }
+        | TypedStatement::Switch { condition, .. }
+        | TypedStatement::While { condition, .. }
+        | TypedStatement::DoWhile { condition, .. } => walk_expression(condition, visit),
+        TypedStatement::For {
+            initialization,
+            condition,
+            afterthought,
+            ..
+        } => {
+            match initialization {
+                Some(Either::Left(declarations)) => walk_declarations(declarations, visit),
+                Some(Either::Right(expression)) => walk_expression(expression, visit),
+                None => {}
+            }
+            for expression in condition.iter().chain(afterthought) {
+                walk_expression(expression, visit);
+            }
+        }
+        TypedStatement::Compound(items) => {
+            for item in items {
+                if let TypedBlockItem::Declaration(declarations) = item {
+                    walk_declarations(declarations, visit);
+                }
+            }
+        }
+        TypedStatement::Return(Some(expression)) => walk_expression(expression, visit),
+        TypedStatement::Asm(asm) => {
+            for operand in asm.outputs.iter().chain(&asm.inputs) {
+                walk_expression(&operand.expression, visit);
+            }
+        }
+        _ => {}
+    }
+    for child in crate::semantic::control_flow::children(statement) {
+        walk_statement(child, visit);
+    }
+}
+
+fn walk_declarations(declarations: &[TypedDeclaration], visit: &mut dyn FnMut(&TypedExpression)) {
+    for declaration in declarations {
+        if let Some(initializer) = &declaration.initializer {
+            walk_initializer(initializer, visit);
+        }
+    }
+}
+
+fn walk_initializer(initializer: &TypedInitializer, visit: &mut dyn FnMut(&TypedExpression)) {
+    match initializer {
+        TypedInitializer::Expression(expression) => walk_expression(expression, visit),
+        TypedInitializer::List(items) => {
+            for item in items {
+                walk_expression(&item.expression, visit);
+            }
+        }
+    }
+}
+
+fn walk_expression(expression: &TypedExpression, visit: &mut dyn FnMut(&TypedExpression)) {
+    visit(expression);
+    match &expression.kind {
+        TypedExpressionKind::Unary { operand, .. }
+        | TypedExpressionKind::Cast { operand, .. }
+        | TypedExpressionKind::Member { operand, .. } => walk_expression(operand, visit),
+        TypedExpressionKind::Binary { lhs, rhs, .. }
+        | TypedExpressionKind::CompoundAssignment { lhs, rhs, .. } => {
+            walk_expression(lhs, visit);
+            walk_expression(rhs, visit);
+        }
+        TypedExpressionKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => {
+            walk_expression(condition, visit);
+            if let Some(then) = then {
+                walk_expression(then, visit);
+            }
+            walk_expression(otherwise, visit);
+        }
+        TypedExpressionKind::Call { callee, arguments } => {
+            walk_expression(callee, visit);
+            for argument in arguments {
+                walk_expression(argument, visit);
+            }
+        }
+        TypedExpressionKind::CompoundLiteral { initializer, .. } => {
+            walk_initializer(initializer, visit)
+        }
+        TypedExpressionKind::Statement(body) => walk_statement(body, visit),
+        TypedExpressionKind::VariableLengths { lengths, operand } => {
+            for (_, length) in lengths {
+                walk_expression(length, visit);
+            }
+            walk_expression(operand, visit);
+        }
+        TypedExpressionKind::Integer(_)
+        | TypedExpressionKind::String(_)
+        | TypedExpressionKind::Identifier(_)
+        | TypedExpressionKind::Sizeof(_)
+        | TypedExpressionKind::Alignof(_) => {}
+    }
+}
@@ -0,0 +1,174 @@
+use crate::diagnostic::*;
+use crate::ir::builder::*;
+use crate::ir::module::*;
+use crate::semantic::constant::*;
+use crate::semantic::expression::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+pub type LowerResult<T> = Result<T, Diagnostic>;
+
+pub struct LowerContext<'a> {
+    pub types: &'a TypeArena,
+    pub symbols: &'a SymbolTable,
+    pub module: Module,
+    pub names: HashMap<SymbolId, String>, // of the globals and functions lowered or referred to
+    pub taken: HashSet<String>,           // the names given to globals and functions
+    pub referenced: Vec<SymbolId>,        // declared and used, which may be defined elsewhere
+    pub strings: HashMap<(usize, usize), String>, // the globals of string literals by their source
+    pub compound_literals: HashMap<CompoundLiteralId, String>, // the globals of those at file scope
+    pub function: Option<FunctionState>,  // being lowered
+}
+
+//how a function being lowered holds its locals, and where its jumps go
+pub struct FunctionState {
+    pub builder: FunctionBuilder,
+    pub symbol: SymbolId,
+    pub locals: HashMap<SymbolId, Local>,
+    pub addressed: HashSet<SymbolId>, // the locals whose address is taken, which stay in memory
+    pub labels: HashMap<String, BlockId>,
+    pub breaks: Vec<BlockId>,
+    pub continues: Vec<BlockId>,
+    pub switches: Vec<Switch>,
+    pub result: Option<Operand>, // the address a struct or union is returned to
+    pub lengths: HashMap<LengthId, Variable>, // of the variable length arrays, as i64 values
+}
+
+#[derive(Debug, Clone)]
+pub enum Local {
+    Variable(Variable),
+    Memory(Operand), // the address of the object
+}
+
+//the labels of the switch statement being lowered
+#[derive(Debug, Default)]
+pub struct Switch {
+    pub cases: Vec<(i128, i128, BlockId)>, // the first and last values of each case
+    pub default: Option<BlockId>,
+}
+
+impl<'a> LowerContext<'a> {
+    pub fn new(types: &'a TypeArena, symbols: &'a SymbolTable) -> LowerContext<'a> {
+        LowerContext {
+            types,
+            symbols,
+            module: Module::default(),
+            names: HashMap::new(),
+            taken: HashSet::new(),
+            referenced: Vec::new(),
+            strings: HashMap::new(),
+            compound_literals: HashMap::new(),
+            function: None,
+        }
+    }
+}
+
+impl ConstantContext for LowerContext<'_> {
+    fn types(&self) -> &TypeArena {
+        self.types
+    }
+
+    fn symbols(&self) -> &SymbolTable {
+        self.symbols
+    }
+}
+
+pub fn error<T>(message: String, span: Span) -> LowerResult<T> {
+    Err(Diagnostic::error(message, span))
+}
+
+pub fn symbol<'a>(context: &LowerContext<'a>, id: SymbolId) -> &'a Symbol {
+    context.symbols.get(id)
+}
+
+pub fn state<'b>(context: &'b mut LowerContext) -> &'b mut FunctionState {
+    context.function.as_mut().unwrap()
+}
+
+pub fn builder<'b>(context: &'b mut LowerContext) -> &'b mut FunctionBuilder {
+    &mut state(context).builder
+}
+
+//name itself if it is free, and otherwise with the first number after it which makes it so
+pub fn unique_name(context: &mut LowerContext, name: String) -> String {
+    let mut result = name.clone();
+    let mut number = 1;
+    while context.taken.contains(&result) {
+        result = format!("{}.{}", name, number);
+        number += 1;
+    }
+    context.taken.insert(result.clone());
+    result
+}
+
+pub fn size_of(context: &LowerContext, ty: TypeId, span: Span) -> LowerResult<u64> {
+    match context.types.size_of(ty) {
+        Some(size) => Ok(size),
+        None => error(
+            format!(
+                "cannot lower an object of type '{}'.",
+                context.types.display(ty)
+            ),
+            span,
+        ),
+    }
+}
+
+pub fn align_of(context: &LowerContext, ty: TypeId) -> u64 {
+    context.types.align_of(ty).unwrap_or(1)
+}
+
+//the IR type of the values of ty. an array, a function, a struct and a union are all
+//handled by address.
+pub fn ir_type(context: &LowerContext, ty: TypeId, span: Span) -> LowerResult<IrType> {
+    let result = match context.types.kind(ty) {
+        TypeKind::Void => IrType::Void,
+        TypeKind::Integer(_) | TypeKind::Enum(_) => {
+            integer_type(context.types.integer_kind(ty).unwrap())
+        }
+        TypeKind::Floating(FloatingKind::Float) => IrType::F32,
+        TypeKind::Floating(_) => IrType::F64,
+        TypeKind::Pointer(_)
+        | TypeKind::Array(_, _)
+        | TypeKind::Function(_)
+        | TypeKind::Struct(_)
+        | TypeKind::Union(_) => IrType::Ptr,
+        TypeKind::Complex(_) => {
+            let message = "complex types cannot be lowered to the IR.".to_string();
+            return error(message, span);
+        }
+    };
+    Ok(result)
+}
+
+pub fn integer_type(kind: IntegerKind) -> IrType {
+    match kind.size() {
+        1 => IrType::I8,
+        2 => IrType::I16,
+        4 => IrType::I32,
+        _ => IrType::I64,
+    }
+}
+
+//value as the constant of an integer type, sign-extended from its width. an i1 is 0 or 1.
+pub fn integer(ty: IrType, value: i128) -> Operand {
+    if ty == IrType::I1 {
+        return Operand::Integer((value & 1) as i64);
+    }
+    let shift = 128 - ty.width();
+    Operand::Integer(((value << shift) >> shift) as i64)
+}
+
+pub fn is_record(context: &LowerContext, ty: TypeId) -> bool {
+    context.types.is_record(ty)
+}
+
+pub fn is_signed(context: &LowerContext, ty: TypeId) -> bool {
+    context
+        .types
+        .integer_kind(ty)
+        .is_some_and(|kind| kind.is_signed())
+}
@@ -0,0 +1,694 @@
+use crate::ir::module::*;
+use crate::ir::print::*;
+use std::collections::{HashMap, HashSet};
+
+//checks that a module is well formed: the names are defined once, each function is in SSA
+//form with every value defined before any path reaches a use of it, and the instructions
+//are applied to operands of the types they take. gives a message for each problem found.
+pub fn verify(module: &Module) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut names = HashSet::new();
+    let globals = module.globals.iter().map(|global| &global.name);
+    let functions = module.functions.iter().map(|function| &function.name);
+    for name in globals.chain(functions) {
+        if !names.insert(name) {
+            errors.push(format!("@{} is defined more than once.", name));
+        }
+    }
+    for global in &module.globals {
+        verify_global(module, global, &mut errors);
+    }
+    for function in &module.functions {
+        if !function.is_declaration() {
+            let mut verifier = Verifier {
+                module,
+                function,
+                names: numbering(function),
+                definitions: HashMap::new(),
+                dominators: dominators(function),
+                errors: &mut errors,
+            };
+            verifier.function();
+        }
+    }
+    errors
+}
+
+fn verify_global(module: &Module, global: &Global, errors: &mut Vec<String>) {
+    if !global.align.is_power_of_two() {
+        let message = "has an alignment which is not a power of 2";
+        errors.push(format!("@{} {}.", global.name, message));
+    }
+    let pieces = match &global.initializer {
+        Some(pieces) => pieces,
+        None => return,
+    };
+    let mut size = 0;
+    for piece in pieces {
+        size += match piece {
+            Piece::Bytes(bytes) => bytes.len() as u64,
+            Piece::Zero(size) => *size,
+            Piece::Address(name, _) => {
+                if !is_defined(module, name) {
+                    let message = "is initialized with the address of";
+                    errors.push(format!(
+                        "@{} {} @{}, which is not defined.",
+                        global.name, message, name
+                    ));
+                }
+                8
+            }
+        };
+    }
+    if size != global.size {
+        let message = format!(
+            "@{} is initialized with {} bytes, but its size is {}.",
+            global.name, size, global.size
+        );
+        errors.push(message);
+    }
+}
+
+fn is_defined(module: &Module, name: &str) -> bool {
+    module.globals.iter().any(|global| global.name == name)
+        || module
+            .functions
+            .iter()
+            .any(|function| function.name == name)
+}
+
+//the immediate dominator of each block reachable from the entry, which is its own. found as
+//by Cooper, Harvey and Kennedy, in "A Simple, Fast Dominance Algorithm".
+fn dominators(function: &Function) -> Vec<Option<BlockId>> {
+    let order = function.reverse_postorder();
+    let predecessors = function.predecessors();
+    let mut position = vec![usize::MAX; function.blocks.len()];
+    for (index, block) in order.iter().enumerate() {
+        position[block.0] = index;
+    }
+    let mut idom = vec![None; function.blocks.len()];
+    idom[0] = Some(BlockId(0));
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in order.iter().skip(1) {
+            let mut dominator = None;
+            for predecessor in &predecessors[block.0] {
+                if idom[predecessor.0].is_none() {
+                    continue;
+                }
+                dominator = match dominator {
+                    None => Some(*predecessor),
+                    Some(mut other) => {
+                        let mut predecessor = *predecessor;
+                        while predecessor != other {
+                            while position[predecessor.0] > position[other.0] {
+                                predecessor = idom[predecessor.0].unwrap();
+                            }
+                            while position[other.0] > position[predecessor.0] {
+                                other = idom[other.0].unwrap();
+                            }
+                        }
+                        Some(other)
+                    }
+                };
+            }
+            if idom[block.0] != dominator {
+                idom[block.0] = dominator;
+                changed = true;
+            }
+        }
+    }
+    idom
+}
+
+struct Verifier<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    names: HashMap<ValueId, usize>,
+    definitions: HashMap<ValueId, (BlockId, usize)>, // the block and position of each value
+    dominators: Vec<Option<BlockId>>,
+    errors: &'a mut Vec<String>,
+}
+
+impl Verifier<'_> {
+    fn report(&mut self, block: Option<BlockId>, message: String) {
+        let name = &self.function.name;
+        match block {
+            Some(block) => self
+                .errors
+                .push(format!("in @{}, {}: {}.", name, block, message)),
+            None => self.errors.push(format!("in @{}: {}.", name, message)),
+        }
+    }
+
+    fn value_name(&self, id: ValueId) -> String {
+        match self.names.get(&id) {
+            Some(number) => format!("%{}", number),
+            None => format!("value #{}", id.0),
+        }
+    }
+
+    fn function(&mut self) {
+        let function = self.function;
+        for (index, ty) in function.parameters.iter().enumerate() {
+            let is_parameter = function.instructions.get(index).is_some_and(|instruction| {
+                instruction.kind == InstructionKind::Parameter(index) && instruction.ty == *ty
+            });
+            if !is_parameter || *ty == IrType::Void {
+                self.report(None, format!("parameter {} is not defined as one", index));
+            }
+        }
+
+        //each value is defined once, in a single place
+        for (index, block) in function.blocks.iter().enumerate() {
+            for (position, id) in block.instructions.iter().enumerate() {
+                let is_parameter = match function.instructions.get(id.0) {
+                    Some(instruction) => {
+                        matches!(instruction.kind, InstructionKind::Parameter(_))
+                    }
+                    None => {
+                        let message = format!("value #{} does not exist", id.0);
+                        self.report(Some(BlockId(index)), message);
+                        continue;
+                    }
+                };
+                if is_parameter {
+                    let message = format!("{} is a parameter in a block", self.value_name(*id));
+                    self.report(Some(BlockId(index)), message);
+                } else if self.definitions.contains_key(id) {
+                    let message = format!("{} is defined more than once", self.value_name(*id));
+                    self.report(Some(BlockId(index)), message);
+                } else {
+                    self.definitions.insert(*id, (BlockId(index), position));
+                }
+            }
+        }
+
+        let predecessors = function.predecessors();
+        for (index, block) in function.blocks.iter().enumerate() {
+            let block_id = BlockId(index);
+            let mut phis_done = false;
+            for (position, id) in block.instructions.iter().enumerate() {
+                let instruction = match function.instructions.get(id.0) {
+                    Some(instruction) => instruction,
+                    None => continue,
+                };
+                if let InstructionKind::Phi(incoming) = &instruction.kind {
+                    if phis_done {
+                        let message = "a phi follows an instruction which is not one";
+                        self.report(Some(block_id), message.to_string());
+                    }
+                    self.phi(block_id, &predecessors[index], incoming);
+                } else {
+                    phis_done = true;
+                }
+                self.instruction(block_id, position, *id, instruction);
+            }
+            match &block.terminator {
+                Some(terminator) => self.terminator(block_id, terminator),
+                None => self.report(Some(block_id), "the block has no terminator".to_string()),
+            }
+        }
+        if !predecessors.is_empty() && !predecessors[0].is_empty() {
+            let message = "the entry block is branched to".to_string();
+            self.report(Some(BlockId(0)), message);
+        }
+    }
+
+    //a phi has a value for each predecessor, and only for them
+    fn phi(&mut self, block: BlockId, predecessors: &[BlockId], incoming: &[(BlockId, Operand)]) {
+        let mut seen = HashSet::new();
+        for (predecessor, _) in incoming {
+            if !predecessors.contains(predecessor) {
+                let message = format!(
+                    "a phi has a value from {}, which is not a predecessor",
+                    predecessor
+                );
+                self.report(Some(block), message);
+            } else if !seen.insert(*predecessor) {
+                let message = format!("a phi has more than one value from {}", predecessor);
+                self.report(Some(block), message);
+            }
+        }
+        for predecessor in predecessors {
+            if !seen.contains(predecessor) {
+                let message = format!("a phi has no value from the predecessor {}", predecessor);
+                self.report(Some(block), message);
+            }
+        }
+    }
+
+    //an operand used at position in block, which is the end of the block for the terminator
+    //and for the value a phi takes from it
+    fn operand(&mut self, (block, position): (BlockId, usize), operand: &Operand, ty: IrType) {
+        let function = self.function;
+        let found = match operand {
+            Operand::Value(id) => {
+                let instruction = match function.instructions.get(id.0) {
+                    Some(instruction) => instruction,
+                    None => {
+                        let message = format!("value #{} does not exist", id.0);
+                        return self.report(Some(block), message);
+                    }
+                };
+                let is_parameter = matches!(instruction.kind, InstructionKind::Parameter(_));
+                match self.definitions.get(id).copied() {
+                    _ if is_parameter => {}
+                    None => {
+                        let message = format!("{} is used but in no block", self.value_name(*id));
+                        self.report(Some(block), message);
+                    }
+                    Some((definition, defined_at)) => {
+                        let dominated = match definition == block {
+                            true => defined_at < position,
+                            false => self.dominates(definition, block),
+                        };
+                        if !dominated {
+                            let message = format!(
+                                "{} is used where its definition does not dominate",
+                                self.value_name(*id)
+                            );
+                            self.report(Some(block), message);
+                        }
+                    }
+                }
+                instruction.ty
+            }
+            Operand::Integer(value) => {
+                let canonical = match ty {
+                    IrType::I1 => *value == 0 || *value == 1,
+                    ty if ty.is_integer() => {
+                        let unused = 64 - ty.width();
+                        (*value << unused) >> unused == *value
+                    }
+                    _ => true,
+                };
+                if !canonical {
+                    let message = format!("the constant {} is out of the range of {}", value, ty);
+                    self.report(Some(block), message);
+                }
+                match ty.is_integer() {
+                    true => ty,
+                    false => return self.mismatch(block, operand, ty, "an integer constant"),
+                }
+            }
+            Operand::Floating(_) if ty.is_floating() => ty,
+            Operand::Floating(_) => {
+                return self.mismatch(block, operand, ty, "a floating constant")
+            }
+            Operand::Null => IrType::Ptr,
+            Operand::Undef if ty != IrType::Void => ty,
+            Operand::Undef => return self.mismatch(block, operand, ty, "an undefined value"),
+            Operand::Global(name) => {
+                if !is_defined(self.module, name) {
+                    self.report(Some(block), format!("@{} is not defined", name));
+                }
+                IrType::Ptr
+            }
+        };
+        if found != ty {
+            let found = format!("of type {}", found);
+            self.mismatch(block, operand, ty, &found);
+        }
+    }
+
+    fn mismatch(&mut self, block: BlockId, operand: &Operand, ty: IrType, found: &str) {
+        let operand = match operand {
+            Operand::Value(id) => self.value_name(*id),
+            Operand::Integer(value) => value.to_string(),
+            Operand::Floating(value) => format!("{:?}", value),
+            Operand::Null => "null".to_string(),
+            Operand::Undef => "undef".to_string(),
+            Operand::Global(name) => format!("@{}", name),
+        };
+        let message = format!("{} is used as {} but is {}", operand, ty, found);
+        self.report(Some(block), message);
+    }
+
+    //whether every path from the entry to block goes through dominator. there is no path to
+    //a block which cannot be reached, so a use in one is not checked.
+    fn dominates(&self, dominator: BlockId, block: BlockId) -> bool {
+        let mut block = block;
+        if self.dominators.get(block.0).copied().flatten().is_none() {
+            return true;
+        }
+        loop {
+            if block == dominator {
+                return true;
+            }
+            match self.dominators[block.0] {
+                Some(idom) if idom != block => block = idom,
+                _ => return false,
+            }
+        }
+    }
+
+    fn instruction(
+        &mut self,
+        block: BlockId,
+        position: usize,
+        id: ValueId,
+        instruction: &Instruction,
+    ) {
+        let at = (block, position);
+        let ty = instruction.ty;
+        let result = |expected: bool| if expected { None } else { Some(ty) };
+        let wrong_result = match &instruction.kind {
+            InstructionKind::Parameter(_) => None,
+            InstructionKind::Alloca { align, .. } => {
+                if !align.is_power_of_two() {
+                    let message = "an alloca has an alignment which is not a power of 2";
+                    self.report(Some(block), message.to_string());
+                }
+                result(ty == IrType::Ptr)
+            }
+            InstructionKind::DynamicAlloca { size, align } => {
+                if !align.is_power_of_two() {
+                    let message = "an alloca has an alignment which is not a power of 2";
+                    self.report(Some(block), message.to_string());
+                }
+                self.operand(at, size, IrType::I64);
+                result(ty == IrType::Ptr)
+            }
+            InstructionKind::Load(address) => {
+                self.operand(at, address, IrType::Ptr);
+                result(ty != IrType::Void)
+            }
+            InstructionKind::Store {
+                ty: stored,
+                value,
+                address,
+            } => {
+                if *stored == IrType::Void {
+                    self.report(Some(block), "a store of void".to_string());
+                } else {
+                    self.operand(at, value, *stored);
+                }
+                self.operand(at, address, IrType::Ptr);
+                result(ty == IrType::Void)
+            }
+            InstructionKind::Binary { operator, lhs, rhs } => {
+                self.operand(at, lhs, ty);
+                self.operand(at, rhs, ty);
+                match operator.is_floating() {
+                    true => result(ty.is_floating()),
+                    false => result(ty.is_integer()),
+                }
+            }
+            InstructionKind::Compare {
+                predicate,
+                ty: operands,
+                lhs,
+                rhs,
+            } => {
+                let valid = match predicate.is_floating() {
+                    true => operands.is_floating(),
+                    false => operands.is_integer() || *operands == IrType::Ptr,
+                };
+                if !valid {
+                    let message = format!("cmp {} cannot compare {} values", predicate, operands);
+                    self.report(Some(block), message);
+                } else {
+                    self.operand(at, lhs, *operands);
+                    self.operand(at, rhs, *operands);
+                }
+                result(ty == IrType::I1)
+            }
+            InstructionKind::Cast {
+                operator,
+                from,
+                value,
+            } => {
+                if !is_valid_cast(*operator, *from, ty) {
+                    let message = format!("{} cannot convert {} to {}", operator, from, ty);
+                    self.report(Some(block), message);
+                } else {
+                    self.operand(at, value, *from);
+                }
+                None
+            }
+            InstructionKind::PointerAdd { base, offset } => {
+                self.operand(at, base, IrType::Ptr);
+                self.operand(at, offset, IrType::I64);
+                result(ty == IrType::Ptr)
+            }
+            InstructionKind::Call { callee, arguments } => {
+                self.operand(at, callee, IrType::Ptr);
+                for (argument_type, argument) in arguments {
+                    self.operand(at, argument, *argument_type);
+                }
+                self.call(block, ty, callee, arguments);
+                None
+            }
+            InstructionKind::Phi(incoming) => {
+                for (predecessor, value) in incoming {
+                    if predecessor.0 < self.function.blocks.len() {
+                        let end = self.function.blocks[predecessor.0].instructions.len();
+                        self.operand((*predecessor, end), value, ty);
+                    }
+                }
+                result(ty != IrType::Void)
+            }
+            InstructionKind::Copy {
+                destination,
+                source,
+                ..
+            } => {
+                self.operand(at, destination, IrType::Ptr);
+                self.operand(at, source, IrType::Ptr);
+                result(ty == IrType::Void)
+            }
+            InstructionKind::Zero { address, .. } => {
+                self.operand(at, address, IrType::Ptr);
+                result(ty == IrType::Void)
+            }
+            InstructionKind::Asm {
+                outputs, inputs, ..
+            } => {
+                for (_, address) in outputs {
+                    self.operand(at, address, IrType::Ptr);
+                }
+                for (_, input, value) in inputs {
+                    if *input == IrType::Void {
+                        self.report(Some(block), "an asm input of type void".to_string());
+                    } else {
+                        self.operand(at, value, *input);
+                    }
+                }
+                result(ty == IrType::Void)
+            }
+        };
+        if let Some(ty) = wrong_result {
+            let message = format!("{} cannot be of type {}", self.value_name(id), ty);
+            self.report(Some(block), message);
+        }
+    }
+
+    //a call of a function the module names agrees with its signature
+    fn call(
+        &mut self,
+        block: BlockId,
+        ty: IrType,
+        callee: &Operand,
+        arguments: &[(IrType, Operand)],
+    ) {
+        let name = match callee {
+            Operand::Global(name) => name,
+            _ => return,
+        };
+        let callee = match self
+            .module
+            .functions
+            .iter()
+            .find(|function| function.name == *name)
+        {
+            Some(function) => function,
+            None => {
+                if self
+                    .module
+                    .globals
+                    .iter()
+                    .any(|global| global.name == *name)
+                {
+                    self.report(
+                        Some(block),
+                        format!("@{} is called but is not a function", name),
+                    );
+                }
+                return;
+            }
+        };
+        let count = callee.parameters.len();
+        let arity = match callee.variadic {
+            true => arguments.len() >= count,
+            false => arguments.len() == count,
+        };
+        let types = callee
+            .parameters
+            .iter()
+            .zip(arguments)
+            .all(|(parameter, (argument, _))| parameter == argument);
+        if !arity || !types || callee.return_type != ty {
+            let message = format!("a call of @{} does not agree with its signature", name);
+            self.report(Some(block), message);
+        }
+    }
+
+    fn terminator(&mut self, block: BlockId, terminator: &Terminator) {
+        let end = (block, self.function.blocks[block.0].instructions.len());
+        for successor in terminator.successors() {
+            if successor.0 >= self.function.blocks.len() {
+                self.report(Some(block), format!("{} does not exist", successor));
+            }
+        }
+        match terminator {
+            Terminator::Jump(_) | Terminator::Unreachable => {}
+            Terminator::Branch { condition, .. } => self.operand(end, condition, IrType::I1),
+            Terminator::Switch {
+                ty, value, cases, ..
+            } => {
+                if !ty.is_integer() {
+                    self.report(Some(block), format!("a switch on a {} value", ty));
+                    return;
+                }
+                self.operand(end, value, *ty);
+                let mut seen = HashSet::new();
+                for (case, _) in cases {
+                    self.operand(end, &Operand::Integer(*case), *ty);
+                    if !seen.insert(*case) {
+                        self.report(Some(block), format!("the case {} is duplicated", case));
+                    }
+                }
+            }
+            Terminator::Return(value) => {
+                let return_type = self.function.return_type;
+                match value {
+                    None if return_type == IrType::Void => {}
+                    Some((ty, value)) if *ty == return_type && *ty != IrType::Void => {
+                        self.operand(end, value, *ty);
+                    }
+                    _ => {
+                        let message = format!("a return does not give a {} value", return_type);
+                        self.report(Some(block), message);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_valid_cast(operator: CastOperator, from: IrType, to: IrType) -> bool {
+    match operator {
+        CastOperator::Trunc => from.is_integer() && to.is_integer() && from.width() > to.width(),
+        CastOperator::ZExt | CastOperator::SExt => {
+            from.is_integer() && to.is_integer() && from.width() < to.width()
+        }
+        CastOperator::FpTrunc => from == IrType::F64 && to == IrType::F32,
+        CastOperator::FpExt => from == IrType::F32 && to == IrType::F64,
+        CastOperator::FpToSi | CastOperator::FpToUi => from.is_floating() && to.is_integer(),
+        CastOperator::SiToFp | CastOperator::UiToFp => from.is_integer() && to.is_floating(),
+        CastOperator::PtrToInt => from == IrType::Ptr && to.is_integer(),
+        CastOperator::IntToPtr => from.is_integer() && to == IrType::Ptr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::parse::parse_module;
+
+    fn errors(source: &str) -> Vec<String> {
+        verify(&parse_module(source).unwrap())
+    }
+
+    #[test]
+    fn uses_must_be_dominated_by_their_definitions() {
+        let source = "define i32 @f(i32 %0) {
+            bb0:
+              %1 = cmp ne i32 %0, 0
+              branch %1, bb1, bb2
+            bb1:
+              %2 = add i32 %0, 1
+              jump bb2
+            bb2:
+              ret i32 %2
+            }";
+        assert_eq!(
+            errors(source),
+            ["in @f, bb2: %2 is used where its definition does not dominate."]
+        );
+    }
+
+    #[test]
+    fn phis_take_one_value_from_each_predecessor() {
+        let source = "define i32 @f(i32 %0) {
+            bb0:
+              %1 = cmp ne i32 %0, 0
+              branch %1, bb1, bb2
+            bb1:
+              jump bb2
+            bb2:
+              %2 = phi i32 [bb0, 0], [bb0, 1]
+              ret i32 %2
+            }";
+        assert_eq!(
+            errors(source),
+            [
+                "in @f, bb2: a phi has more than one value from bb0.",
+                "in @f, bb2: a phi has no value from the predecessor bb1.",
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_end_in_a_terminator_and_globals_are_defined() {
+        let source = "define i32 @f() {
+            bb0:
+              %0 = add i32 1, 2
+              ret i32 %0
+            bb1:
+              %1 = call i32 @g()
+            }";
+        assert_eq!(
+            errors(source),
+            [
+                "in @f, bb1: @g is not defined.",
+                "in @f, bb1: the block has no terminator.",
+            ]
+        );
+    }
+
+    #[test]
+    fn operands_have_the_types_their_instructions_take() {
+        let source = "define void @f(i32 %0) {
+            bb0:
+              store i32 %0, %0
+              switch i32 %0, bb0 [1: bb0, 1: bb0]
+            }";
+        assert_eq!(
+            errors(source),
+            [
+                "in @f, bb0: %0 is used as ptr but is of type i32.",
+                "in @f, bb0: the case 1 is duplicated.",
+                "in @f, bb0: the entry block is branched to.",
+            ]
+        );
+    }
+
+    #[test]
+    fn asm_outputs_are_addresses() {
+        let source = "define void @f(i32 %0) {
+            bb0:
+              asm \"\", [\"=r\" %0], [\"r\" void undef], []
+              ret void
+            }";
+        assert_eq!(
+            errors(source),
+            [
+                "in @f, bb0: %0 is used as ptr but is of type i32.",
+                "in @f, bb0: an asm input of type void.",
+            ]
+        );
+    }
+}
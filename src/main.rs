@@ -1,33 +1,160 @@
+#![allow(clippy::needless_lifetimes)]
+#![allow(clippy::upper_case_acronyms)]
+#![allow(clippy::enum_variant_names)]
+
+mod diagnostic;
+mod interpreter;
+mod ir;
+mod options;
 mod parser;
+mod semantic;
+#[cfg(test)]
+mod testing;
 mod tokenizer;
 mod util;
+mod warning;
+use crate::diagnostic::Severity;
+use crate::interpreter::interpret;
+use crate::ir::lower;
+use crate::ir::module::Module;
+use crate::ir::parse::parse_module;
+use crate::ir::verify::verify;
+use crate::options::*;
 use crate::parser::parse;
+use crate::semantic::analyze;
 use crate::tokenizer::tokenize;
+use crate::warning::*;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
 
 fn main() {
-    compile("if (1 > 0) { (1 + 2 + 3 - 1) * 2; }");
+    let options = match parse_arguments(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            println!("Error: {}", message);
+            process::exit(1);
+        }
+    };
+
+    //a module of the IR in its textual form is read back and checked, which tests the IR
+    //without a C program to lower
+    let succeeded = match &options.input {
+        Some(path) if path.ends_with(".ir") => match fs::read_to_string(path) {
+            Ok(source) => match parse_module(&source) {
+                Ok(module) => emit(&module),
+                Err(diagnostic) => {
+                    println!("{}", diagnostic);
+                    false
+                }
+            },
+            Err(error) => {
+                println!("Error: cannot read {}: {}", path, error);
+                false
+            }
+        },
+        Some(path) => match fs::read_to_string(path) {
+            Ok(source) => compile(&source, &options),
+            Err(error) => {
+                println!("Error: cannot read {}: {}", path, error);
+                false
+            }
+        },
+        None => compile(
+            "typedef int T; T x; int main(T a) { if (1 > 0) { (1 + 2 + 3 - 1) * 2; } }",
+            &options,
+        ),
+    };
+    if !succeeded {
+        process::exit(1);
+    }
 }
 
-fn compile(source: &str) {
-    let tokens = match tokenize(source) {
+//whether the source compiled without errors
+fn compile(source: &str, options: &Options) -> bool {
+    let (tokens, pragmas) = match tokenize(source, options.standard) {
         Ok(result) => result,
         Err(message) => {
             println!("Tokenization Error: {}", message);
-            return;
+            return false;
         }
     };
 
-    for token in &tokens {
-        println!("{:?}", token);
+    let dump = options.run.is_none() && !options.emit_ir;
+    if dump {
+        for token in &tokens {
+            println!("{:?}", token);
+        }
     }
 
-    let ast = match parse(&tokens) {
-        Ok(result) => result,
-        Err(message) => {
-            println!("Parse Error: {}", message);
-            return;
+    //the warnings about #pragma GCC diagnostic itself come with those of the parser
+    let (control, mut diagnostics) = diagnostic_control(&options.warnings, &pragmas);
+    let (ast, parse_diagnostics) = parse(&tokens, options);
+    diagnostics.extend(parse_diagnostics);
+    let diagnostics = filter_diagnostics(&control, diagnostics);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return false;
+    }
+
+    if dump {
+        println!("{:?}", ast);
+    }
+
+    let (typed, diagnostics) = analyze(&ast, &pragmas, options);
+    let diagnostics = filter_diagnostics(&control, diagnostics);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return false;
+    }
+
+    //a program which fails at run time exits with 1 after its output
+    if let Some(execution) = options.run {
+        match interpret(&typed, execution, &mut io::stdout()) {
+            Ok(status) => process::exit(status),
+            Err(diagnostic) => {
+                println!("{}", diagnostic);
+                process::exit(1);
+            }
         }
-    };
+    }
 
-    println!("{:?}", ast)
+    if options.emit_ir {
+        return match lower(&typed) {
+            Ok(module) => emit(&module),
+            Err(diagnostic) => {
+                println!("{}", diagnostic);
+                false
+            }
+        };
+    }
+
+    println!("{:?}", typed.root);
+    for (_, symbol) in typed.symbols.iter() {
+        println!("{}: {}", symbol.name, typed.types.display(symbol.ty));
+    }
+    true
+}
+
+//prints a module which verifies, and otherwise what is wrong with it. whether it verifies.
+fn emit(module: &Module) -> bool {
+    let errors = verify(module);
+    if errors.is_empty() {
+        print!("{}", module);
+    }
+    for error in &errors {
+        println!("IR Verification Error: {}", error);
+    }
+    errors.is_empty()
 }
@@ -0,0 +1,70 @@
+use crate::warning::*;
+
+//language standards in the order they were published, so that `>=` means "at least"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Standard {
+    C89,
+    C99,
+    C11,
+    Gnu11, // C11 with GNU extensions
+}
+
+//how -run executes the program
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Execution {
+    Unchecked, // -run
+    Checked,   // -run=checked, which stops at the first undefined behavior
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub standard: Standard,
+    pub input: Option<String>,
+    pub warnings: WarningOptions,
+    pub run: Option<Execution>, // interprets the program instead of printing its trees
+    pub emit_ir: bool,          // prints the IR the program is lowered to instead
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            standard: Standard::C11,
+            input: None,
+            warnings: WarningOptions::default(),
+            run: None,
+            emit_ir: false,
+        }
+    }
+}
+
+//the arguments without the program name
+pub fn parse_arguments<I: Iterator<Item = String>>(arguments: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    for argument in arguments {
+        if let Some(standard) = argument.strip_prefix("-std=") {
+            options.standard = match standard {
+                "c89" | "c90" => Standard::C89,
+                "c99" => Standard::C99,
+                "c11" => Standard::C11,
+                "gnu11" => Standard::Gnu11,
+                _ => return Err(format!("unknown standard '{}'.", standard)),
+            };
+        } else if let Some(option) = argument.strip_prefix("-W") {
+            options.warnings.apply(option)?;
+        } else if argument == "-run" {
+            options.run = Some(Execution::Unchecked);
+        } else if argument == "-run=checked" {
+            options.run = Some(Execution::Checked);
+        } else if argument == "-emit-ir" {
+            options.emit_ir = true;
+        } else if argument.starts_with('-') {
+            return Err(format!("unknown option '{}'.", argument));
+        } else if options.input.is_some() {
+            return Err("only one input file is supported.".to_string());
+        } else {
+            options.input = Some(argument);
+        }
+    }
+
+    Ok(options)
+}
@@ -1,19 +1,86 @@
-mod declaration;
-mod expression;
-mod statement;
+pub mod asm;
+pub mod declaration;
+pub mod expression;
+pub mod external_declaration;
+pub mod statement;
 mod util;
-use crate::parser::declaration::*;
-use crate::parser::statement::*;
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::parser::external_declaration::*;
 use crate::parser::util::*;
 use crate::tokenizer::*;
 
 #[derive(Debug)]
 pub struct AST<'a> {
-    root: StatementNode<'a>,
+    pub root: TranslationUnit<'a>,
 }
 
-pub fn parse<'a>(tokens: &'a [Token<'a>]) -> Result<AST<'a>, String> {
-    let mut context = ParseContext::new(tokens);
-    let node = statement(&mut context)?;
-    Ok(AST { root: node })
+//a syntax error does not stop parsing, the broken part becomes an error node and
+//parsing resumes at the next synchronization point
+pub fn parse<'a>(tokens: &'a [Token<'a>], options: &Options) -> (AST<'a>, Vec<Diagnostic>) {
+    let mut context = ParseContext::new(tokens, options.standard);
+    let root = translation_unit(&mut context);
+    (AST { root }, context.into_diagnostics())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        let (_, diagnostics) = parse_source(source, Standard::C11);
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    //the replacements of the fix-its offered
+    fn fixits(source: &str) -> Vec<String> {
+        let (_, diagnostics) = parse_source(source, Standard::C11);
+        diagnostics
+            .into_iter()
+            .flat_map(|diagnostic| diagnostic.fixits)
+            .map(|fixit| fixit.replacement)
+            .collect()
+    }
+
+    #[test]
+    fn struct_member_recovery() {
+        let errors = errors("struct S { int a b; int c; }; int x;");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].ends_with("but found 'b'."), "{:?}", errors);
+    }
+
+    #[test]
+    fn old_style_parameter_declarations() {
+        let errors = errors("int g(a, b) int a; char *a; int b = 1; { return a; }");
+        assert_eq!(
+            errors,
+            [
+                "redefinition of parameter 'a'.",
+                "parameter 'b' is initialized."
+            ]
+        );
+    }
+
+    #[test]
+    fn forgotten_terminators_are_inserted() {
+        assert_eq!(fixits("int y\nint z;"), [";"]);
+        assert_eq!(fixits("int f(void) { return (1 + 2; }"), [")"]);
+        //nothing was forgotten before a construct which has not begun
+        assert!(fixits("int x;\n]").is_empty());
+        assert!(fixits("int f(void) { ; ) }").is_empty());
+    }
+
+    #[test]
+    fn expected_sets_leave_out_gnu_keywords_in_iso_c() {
+        let errors = errors("int y\nint z;");
+        assert_eq!(
+            errors,
+            ["expected one of '[', '(', '{', '=', ',' or ';' but found 'int'."]
+        );
+    }
 }
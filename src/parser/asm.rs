@@ -0,0 +1,338 @@
+use crate::diagnostic::*;
+use crate::parser::expression::*;
+use crate::parser::util::*;
+use crate::tokenizer::*;
+
+//asm qualifiers ( template : outputs : inputs : clobbers : labels );, GNU extension
+#[derive(Debug)]
+pub struct AsmStatement<'a> {
+    pub qualifiers: Vec<AsmQualifier>,
+    pub template: Vec<&'a str>, // adjacent string literals, to be concatenated
+    pub outputs: Vec<AsmOperand<'a>>,
+    pub inputs: Vec<AsmOperand<'a>>,
+    pub clobbers: Vec<&'a str>,
+    pub labels: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsmQualifier {
+    Volatile,
+    Inline,
+    Goto,
+}
+
+//[name] "constraint" (expression)
+#[derive(Debug)]
+pub struct AsmOperand<'a> {
+    pub name: Option<&'a str>,
+    pub constraint: &'a str,
+    pub expression: Expression<'a>,
+}
+
+//constraint letters of x86-64 and the machine independent ones
+const CONSTRAINTS: &str = "rmoVigInsEFXpabcdSDAqQRUfxyvtuIJKLMNGCeZl";
+
+//constraint modifiers which may appear anywhere after the leading `=` or `+`
+const CONSTRAINT_MODIFIERS: &str = "&%*#?!,";
+
+//the constraint letters which allow an operand only in memory
+const MEMORY_CONSTRAINTS: &str = "moV";
+
+const REGISTERS: [&str; 84] = [
+    "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15", "eax", "ebx", "ecx", "edx", "esi", "edi", "ebp", "esp", "r8d", "r9d", "r10d",
+    "r11d", "r12d", "r13d", "r14d", "r15d", "ax", "bx", "cx", "dx", "si", "di", "bp", "sp", "al",
+    "bl", "cl", "dl", "ah", "bh", "ch", "dh", "sil", "dil", "bpl", "spl", "xmm0", "xmm1", "xmm2",
+    "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8", "xmm9", "xmm10", "xmm11", "xmm12", "xmm13",
+    "xmm14", "xmm15", "st", "st(1)", "st(2)", "st(3)", "st(4)", "st(5)", "st(6)", "st(7)", "mm0",
+    "mm1", "mm2", "mm3", "mm4", "mm5", "mm6", "mm7",
+];
+
+//parser body
+pub(super) fn asm_statement<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<AsmStatement<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Asm).is_none() {
+        return Ok(None);
+    }
+    require_gnu(context, "asm statement", span);
+
+    let mut qualifiers = Vec::new();
+    loop {
+        let token = peek_token(context);
+        let qualifier = if consume_keyword(context, KeywordKind::Volatile).is_some() {
+            AsmQualifier::Volatile
+        } else if consume_keyword(context, KeywordKind::Inline).is_some() {
+            AsmQualifier::Inline
+        } else if consume_keyword(context, KeywordKind::Goto).is_some() {
+            AsmQualifier::Goto
+        } else {
+            break;
+        };
+        if qualifiers.contains(&qualifier) {
+            return Err(format!("duplicate asm qualifier {}.", token));
+        }
+        qualifiers.push(qualifier);
+    }
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let template = string_literals(context)?;
+
+    //each section is optional, but only from the end
+    let mut outputs = Vec::new();
+    let mut inputs = Vec::new();
+    let mut clobbers = Vec::new();
+    let mut labels = Vec::new();
+    let extended = peek_punctuator(context, PunctuatorKind::Colon);
+    if consume_punctuator(context, PunctuatorKind::Colon).is_some() {
+        outputs = asm_operands(context)?;
+        if consume_punctuator(context, PunctuatorKind::Colon).is_some() {
+            inputs = asm_operands(context)?;
+            if consume_punctuator(context, PunctuatorKind::Colon).is_some() {
+                clobbers = asm_clobbers(context)?;
+                if qualifiers.contains(&AsmQualifier::Goto)
+                    && consume_punctuator(context, PunctuatorKind::Colon).is_some()
+                {
+                    labels = asm_labels(context)?;
+                }
+            }
+        }
+    }
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
+
+    let result = AsmStatement {
+        qualifiers,
+        template,
+        outputs,
+        inputs,
+        clobbers,
+        labels,
+    };
+    if extended {
+        validate(context, &result, span);
+    }
+
+    Ok(Some(result))
+}
+
+fn string_literals<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<&'b str>, String> {
+    let mut result = Vec::new();
+    while let TokenKind::Literal(Literal::String(content)) = peek_token(context) {
+        consume_token(context);
+        result.push(*content);
+    }
+    if result.is_empty() {
+        return Err(expected(context, "string literal"));
+    }
+
+    Ok(result)
+}
+
+fn asm_operands<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<AsmOperand<'b>>, String> {
+    let mut result = Vec::new();
+    if !matches!(
+        peek_token(context),
+        TokenKind::Literal(Literal::String(_))
+            | TokenKind::Punctuator(PunctuatorKind::LeftSquareBracket)
+    ) {
+        return Ok(result);
+    }
+
+    loop {
+        let name = if consume_punctuator(context, PunctuatorKind::LeftSquareBracket).is_some() {
+            let name = expect_identifier(context)?;
+            expect_punctuator(context, PunctuatorKind::RightSquareBracket)?;
+            Some(name)
+        } else {
+            None
+        };
+        let constraint = string_literal(context)?;
+        expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+        let expression = expression(context)?;
+        expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+        result.push(AsmOperand {
+            name,
+            constraint,
+            expression,
+        });
+
+        if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+fn asm_clobbers<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<&'b str>, String> {
+    let mut result = Vec::new();
+    if !matches!(peek_token(context), TokenKind::Literal(Literal::String(_))) {
+        return Ok(result);
+    }
+
+    loop {
+        let span = peek_span(context);
+        let clobber = string_literal(context)?;
+        let register = clobber.strip_prefix('%').unwrap_or(clobber);
+        if !matches!(clobber, "memory" | "cc") && !REGISTERS.contains(&register) {
+            let message = format!("unknown register name '{}' in asm.", clobber);
+            report(context, Diagnostic::error(message, span));
+        }
+        result.push(clobber);
+
+        if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+fn asm_labels<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<&'b str>, String> {
+    let mut result = vec![expect_identifier(context)?];
+    while consume_punctuator(context, PunctuatorKind::Commma).is_some() {
+        result.push(expect_identifier(context)?);
+    }
+
+    Ok(result)
+}
+
+fn string_literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<&'b str, String> {
+    match peek_token(context) {
+        TokenKind::Literal(Literal::String(content)) => {
+            consume_token(context);
+            Ok(content)
+        }
+        _ => Err(expected(context, "string literal")),
+    }
+}
+
+//checks the constraints and the operand references of the template, reporting every problem
+//at the asm keyword
+fn validate(context: &mut ParseContext, statement: &AsmStatement, span: Span) {
+    let mut messages = Vec::new();
+
+    for output in &statement.outputs {
+        let constraint = output.constraint;
+        if !constraint.starts_with('=') && !constraint.starts_with('+') {
+            messages.push(format!(
+                "output operand constraint '{}' lacks '=' or '+'.",
+                constraint
+            ));
+        } else if let Some(message) = invalid_constraint(&constraint[1..], None) {
+            messages.push(message);
+        }
+    }
+    for input in &statement.inputs {
+        let constraint = input.constraint;
+        if constraint.contains('=') || constraint.contains('+') {
+            messages.push(format!(
+                "input operand constraint '{}' contains '=' or '+'.",
+                constraint
+            ));
+        } else if constraint.contains('&') {
+            messages.push(format!(
+                "input operand constraint '{}' contains '&'.",
+                constraint
+            ));
+        } else if let Some(message) = invalid_constraint(constraint, Some(statement.outputs.len()))
+        {
+            messages.push(message);
+        }
+    }
+
+    let operands: Vec<&AsmOperand> = statement
+        .outputs
+        .iter()
+        .chain(statement.inputs.iter())
+        .collect();
+    let mut names: Vec<&str> = Vec::new();
+    for name in operands.iter().filter_map(|operand| operand.name) {
+        if names.contains(&name) {
+            messages.push(format!("duplicate asm operand name '{}'.", name));
+        }
+        names.push(name);
+    }
+
+    //labels are numbered after the operands in `asm goto`
+    let count = operands.len() + statement.labels.len();
+    let template = statement.template.concat();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        //an operand modifier such as the `k` of `%k0` may come first
+        if chars.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            chars.next();
+        }
+        match chars.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                let mut number = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    number = number * 10 + digit as usize;
+                    chars.next();
+                }
+                if number >= count {
+                    messages.push(format!(
+                        "invalid operand number %{} in asm template.",
+                        number
+                    ));
+                }
+            }
+            Some('[') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if !names.contains(&name.as_str()) {
+                    messages.push(format!("undefined asm operand name '{}'.", name));
+                }
+            }
+            _ => {
+                chars.next();
+            }
+        }
+    }
+
+    for message in messages {
+        report(context, Diagnostic::error(message, span));
+    }
+}
+
+//an input may also name the output it shares a location with by its number
+fn invalid_constraint(constraint: &str, outputs: Option<usize>) -> Option<String> {
+    if constraint.is_empty() {
+        return Some("empty asm operand constraint.".to_string());
+    }
+    for c in constraint.chars() {
+        match (c.to_digit(10), outputs) {
+            (Some(number), Some(outputs)) if number as usize >= outputs => {
+                return Some(format!(
+                    "matching constraint '{}' does not refer to an output operand.",
+                    constraint
+                ));
+            }
+            (Some(_), Some(_)) => {}
+            _ if CONSTRAINTS.contains(c) || CONSTRAINT_MODIFIERS.contains(c) => {}
+            _ => {
+                return Some(format!(
+                    "invalid character '{}' in asm constraint '{}'.",
+                    c, constraint
+                ))
+            }
+        }
+    }
+
+    None
+}
+
+//whether the operand of constraint can only be in memory, which makes it an object
+//rather than a value
+pub fn is_memory_constraint(constraint: &str) -> bool {
+    let mut letters = constraint
+        .chars()
+        .filter(|c| !"=+".contains(*c) && !CONSTRAINT_MODIFIERS.contains(*c))
+        .peekable();
+    letters.peek().is_some() && letters.all(|c| MEMORY_CONSTRAINTS.contains(c))
+}
@@ -1,12 +1,23 @@
+use crate::diagnostic::*;
+use crate::options::*;
 use crate::parser::expression::*;
 use crate::parser::util::*;
 use crate::tokenizer::*;
 use crate::util::*;
+use crate::warning::*;
 
 #[derive(Debug)]
 pub struct Declaration<'a> {
-    specifiers: NonEmptyVec<DeclarationSpecifier<'a>>,
-    declarators: Vec<InitDeclarator<'a>>,
+    pub specifiers: NonEmptyVec<DeclarationSpecifier<'a>>,
+    pub declarators: Vec<InitDeclarator<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct StaticAssertDeclaration<'a> {
+    pub condition: Expression<'a>,
+    pub message: &'a str,
+    pub span: Span, // of the _Static_assert keyword
 }
 
 #[derive(Debug)]
@@ -15,7 +26,15 @@ pub enum DeclarationSpecifier<'a> {
     TypeSpecifier(TypeSpecifier<'a>),
     TypeQualifier(TypeQualifier),
     FunctionSpecifier(FunctionSpecifier),
-    AlignmentSpecifier(Either<Type, Expression<'a>>),
+    AlignmentSpecifier(Either<TypeName<'a>, Expression<'a>>),
+    Attributes(Vec<Attribute<'a>>),
+}
+
+//`name` or `name(arguments)` in `__attribute__((...))`, GNU extension
+#[derive(Debug)]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    pub arguments: Vec<Expression<'a>>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -41,10 +60,11 @@ pub enum TypeSpecifier<'a> {
     Unsigned,
     Bool,
     Complex,
-    AtomicTypeSpecifier,
+    AtomicTypeSpecifier(TypeName<'a>),
     StructOrUnionSpecifier(StructOrUnionSpecifier<'a>),
     EnumSpecifier(EnumSpecifier<'a>),
     TypedefName(&'a str),
+    Typeof(Either<TypeName<'a>, Expression<'a>>), // GNU extension
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -63,8 +83,10 @@ pub enum FunctionSpecifier {
 
 #[derive(Debug)]
 pub struct StructOrUnionSpecifier<'a> {
-    kind: StructOrUnion,
-    name_or_menbers: EitherOrBoth<&'a str, NonEmptyVec<&'a str>>,
+    pub kind: StructOrUnion,
+    pub attributes: Vec<Attribute<'a>>,
+    pub name_or_menbers: EitherOrBoth<&'a str, NonEmptyVec<StructDeclaration<'a>>>,
+    pub span: Span, // of the struct or union keyword
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -75,42 +97,82 @@ pub enum StructOrUnion {
 
 #[derive(Debug)]
 pub struct StructDeclaration<'a> {
-    specifier_qualifier_list: NonEmptyVec<SpecifierOrQualifier<'a>>,
-    declarators: Vec<StructDeclarator<'a>>,
+    pub specifier_qualifier_list: NonEmptyVec<SpecifierOrQualifier<'a>>,
+    pub declarators: Vec<StructDeclarator<'a>>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub enum SpecifierOrQualifier<'a> {
     TypeSpecifier(TypeSpecifier<'a>),
     TypeQualifier(TypeQualifier),
+    AlignmentSpecifier(Either<TypeName<'a>, Expression<'a>>, Span), // only of members
 }
 
 pub type StructDeclarator<'a> = EitherOrBoth<Declarator<'a>, Expression<'a>>;
 
 #[derive(Debug)]
 pub struct EnumSpecifier<'a> {
-    name_or_menbers: EitherOrBoth<&'a str, NonEmptyVec<&'a str>>,
+    #[allow(dead_code)] // accepted, but packed enumerations are not implemented
+    pub attributes: Vec<Attribute<'a>>,
+    pub name_or_menbers: EitherOrBoth<&'a str, NonEmptyVec<Enumerator<'a>>>,
+    pub span: Span, // of the enum keyword
+}
+
+#[derive(Debug)]
+pub struct Enumerator<'a> {
+    pub name: &'a str,
+    pub value: Option<Expression<'a>>,
+    pub span: Span, // of the name
 }
 
 #[derive(Debug)]
 pub struct InitDeclarator<'a> {
-    declarator: Declarator<'a>,
-    initializer: Expression<'a>,
+    pub declarator: Declarator<'a>,
+    pub asm_label: Option<&'a str>, // the assembler name given by `asm("name")`, GNU extension
+    pub initializer: Option<Initializer<'a>>,
+}
+
+#[derive(Debug)]
+pub enum Initializer<'a> {
+    Expression(Expression<'a>),
+    List(Vec<InitializerListItem<'a>>),
+}
+
+//an item without designators initializes the member or element that follows the
+//previously initialized one
+#[derive(Debug)]
+pub struct InitializerListItem<'a> {
+    pub designators: Vec<Designator<'a>>,
+    pub initializer: Initializer<'a>,
+}
+
+#[derive(Debug)]
+pub enum Designator<'a> {
+    Member(&'a str),                       // .member
+    Index(Expression<'a>),                 // [index]
+    Range(Expression<'a>, Expression<'a>), // [first ... last], GNU extension
 }
 
 #[derive(Debug)]
 pub struct Declarator<'a> {
-    pointer: Pointer,
-    identifier: DirectDeclarator<'a>,
+    pub pointer: Pointer<'a>,
+    pub identifier: DirectDeclarator<'a>,
+    pub attributes: Vec<Attribute<'a>>,
 }
 
-pub type Pointer = Vec<Star>;
+pub type Pointer<'a> = Vec<Star<'a>>;
 
-pub type Star = Vec<TypeQualifier>;
+//the qualifiers and GNU attributes after a `*` apply to the pointer type it makes
+#[derive(Debug)]
+pub struct Star<'a> {
+    pub qualifiers: Vec<TypeQualifier>,
+    pub attributes: Vec<Attribute<'a>>,
+}
 
 #[derive(Debug)]
 pub enum DirectDeclarator<'a> {
-    Identifier(&'a str),
+    Identifier(&'a str, Span),
     Declarator(Box<Declarator<'a>>),
     Array(
         Box<DirectDeclarator<'a>>,
@@ -125,27 +187,30 @@ pub enum DirectDeclarator<'a> {
 
 #[derive(Debug)]
 pub struct ParameterTypeList<'a> {
-    parameter_list: NonEmptyVec<ParameterDeclaration<'a>>,
-    variable_parameter: bool,
+    pub parameter_list: NonEmptyVec<ParameterDeclaration<'a>>,
+    pub variable_parameter: bool,
 }
 
 #[derive(Debug)]
 pub struct ParameterDeclaration<'a> {
-    specifier: DeclarationSpecifier<'a>,
-    declarator: Either<Declarator<'a>, AbstractDeclarator<'a>>,
+    pub specifiers: NonEmptyVec<DeclarationSpecifier<'a>>,
+    pub declarator: Option<Either<Declarator<'a>, AbstractDeclarator<'a>>>,
+    pub span: Span,
 }
 
 pub type IdentifierList<'a> = Vec<&'a str>;
 
+#[derive(Debug)]
 pub struct TypeName<'a> {
-    specifier_qualifier_list: NonEmptyVec<SpecifierOrQualifier<'a>>,
-    declarator: Option<AbstractDeclarator<'a>>,
+    pub specifier_qualifier_list: NonEmptyVec<SpecifierOrQualifier<'a>>,
+    pub declarator: Option<AbstractDeclarator<'a>>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct AbstractDeclarator<'a> {
-    pointer: Pointer,
-    declarator: DirectAbstarctDeclarator<'a>,
+    pub pointer: Pointer<'a>,
+    pub declarator: Option<DirectAbstarctDeclarator<'a>>,
 }
 
 #[derive(Debug)]
@@ -158,55 +223,317 @@ pub enum DirectAbstarctDeclarator<'a> {
     ),
     Function(
         Option<Box<DirectAbstarctDeclarator<'a>>>,
-        ParameterTypeList<'a>,
+        Option<ParameterTypeList<'a>>,
     ),
 }
 
+impl<'a> Declarator<'a> {
+    pub fn identifier(&self) -> &'a str {
+        self.identifier.identifier()
+    }
+
+    pub fn span(&self) -> Span {
+        self.identifier.span()
+    }
+
+    //parameters of the function declarator that is applied to the identifier first
+    pub fn function_parameters(
+        &self,
+    ) -> Option<&Either<ParameterTypeList<'a>, IdentifierList<'a>>> {
+        self.identifier.function_parameters()
+    }
+}
+
+impl<'a> DirectDeclarator<'a> {
+    pub fn identifier(&self) -> &'a str {
+        match self {
+            DirectDeclarator::Identifier(identifier, _) => identifier,
+            DirectDeclarator::Declarator(declarator) => declarator.identifier(),
+            DirectDeclarator::Array(declarator, _, _) => declarator.identifier(),
+            DirectDeclarator::Function(declarator, _) => declarator.identifier(),
+        }
+    }
+
+    //the span of the identifier
+    pub fn span(&self) -> Span {
+        match self {
+            DirectDeclarator::Identifier(_, span) => *span,
+            DirectDeclarator::Declarator(declarator) => declarator.span(),
+            DirectDeclarator::Array(declarator, _, _) => declarator.span(),
+            DirectDeclarator::Function(declarator, _) => declarator.span(),
+        }
+    }
+
+    fn function_parameters(&self) -> Option<&Either<ParameterTypeList<'a>, IdentifierList<'a>>> {
+        match self {
+            DirectDeclarator::Identifier(_, _) => None,
+            DirectDeclarator::Declarator(declarator) => declarator.function_parameters(),
+            DirectDeclarator::Array(declarator, _, _) => declarator.function_parameters(),
+            DirectDeclarator::Function(declarator, parameters) => {
+                declarator.function_parameters().or(Some(parameters))
+            }
+        }
+    }
+}
+
+impl<'a> ParameterTypeList<'a> {
+    pub fn parameters(&self) -> &[ParameterDeclaration<'a>] {
+        &self.parameter_list
+    }
+}
+
+impl<'a> ParameterDeclaration<'a> {
+    pub fn identifier(&self) -> Option<&'a str> {
+        match &self.declarator {
+            Some(Either::Left(declarator)) => Some(declarator.identifier()),
+            _ => None,
+        }
+    }
+}
+
 //parser body
-pub fn declaration<'a, 'b>(
+pub(super) fn declaration<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<Declaration<'b>>, String> {
+    let span = peek_span(context);
     let declaration_specifiers = match declaration_specifier(context)? {
-        Some(specifier) => specifier,
+        Some(specifier) => complete_type_specifiers(context, specifier.unwrap(), span),
         None => return Ok(None),
     };
 
+    let first = if peek_punctuator(context, PunctuatorKind::Semicolon) {
+        None
+    } else {
+        Some(expect_declarator(context)?)
+    };
+
+    let result = declaration_after_declarator(context, declaration_specifiers, first, span)?;
+    Ok(Some(result))
+}
+
+//continues a declaration whose specifiers and first declarator are already parsed, the
+//declaration beginning at begin
+pub(super) fn declaration_after_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    specifiers: NonEmptyVec<DeclarationSpecifier<'b>>,
+    first: Option<Declarator<'b>>,
+    begin: Span,
+) -> Result<Declaration<'b>, String> {
     let mut init_declarators = Vec::new();
-    loop {
-        let declarator = match init_declarator(context)? {
-            Some(d) => d,
-            None => return Err("Parse Error".to_string()),
-        };
-        init_declarators.push(declarator);
+    if let Some(mut declarator) = first {
+        loop {
+            init_declarators.push(init_declarator(context, &specifiers, declarator)?);
 
-        if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
-            break;
+            if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+                break;
+            }
+            declarator = expect_declarator(context)?;
         }
     }
     expect_punctuator(context, PunctuatorKind::Semicolon)?;
 
     let result = Declaration {
-        specifiers: declaration_specifiers,
+        specifiers,
         declarators: init_declarators,
+        span: span_since(context, begin),
     };
-    Ok(Some(result))
+    Ok(result)
+}
+
+//the declaration list of an old-style function definition `int f(a, b) int a; char *b; { }`,
+//which gives each identifier in the list its type. one left out is int.
+pub(super) fn parameter_declaration_list<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    declarator: &Declarator<'b>,
+    span: Span,
+) -> Result<Vec<Declaration<'b>>, String> {
+    let parameters: &[&str] = match declarator.function_parameters() {
+        Some(Either::Right(identifiers)) => identifiers,
+        _ => &[],
+    };
+
+    let mut declarations: Vec<Declaration> = Vec::new();
+    let mut declared = Vec::new();
+    while !peek_punctuator(context, PunctuatorKind::LeftCurlyBracket) {
+        let span = peek_span(context);
+        let declaration =
+            declaration(context)?.ok_or_else(|| expected(context, "parameter declaration"))?;
+        for init_declarator in &declaration.declarators {
+            let identifier = init_declarator.declarator.identifier();
+            let message = if !parameters.contains(&identifier) {
+                format!(
+                    "declaration for parameter '{}' but no such parameter.",
+                    identifier
+                )
+            } else if declared.contains(&identifier) {
+                format!("redefinition of parameter '{}'.", identifier)
+            } else {
+                declared.push(identifier);
+                if init_declarator.initializer.is_none() {
+                    continue;
+                }
+                format!("parameter '{}' is initialized.", identifier)
+            };
+            report(context, Diagnostic::error(message, span));
+        }
+        declarations.push(declaration);
+    }
+
+    for parameter in parameters {
+        if !declared.contains(parameter) {
+            let message = format!("type of parameter '{}' defaults to 'int'.", parameter);
+            report_implicit_int(context, message, span);
+        }
+    }
+
+    Ok(declarations)
+}
+
+//whether the declarator is followed by the declaration list of an old-style definition
+pub(super) fn is_parameter_declaration_list_head(
+    context: &ParseContext,
+    declarator: &Declarator,
+) -> bool {
+    matches!(declarator.function_parameters(), Some(Either::Right(identifiers)) if !identifiers.is_empty())
+        && is_declaration_specifier_head(context)
+}
+
+//C89 lets the type specifier be left out, and then the type is int
+pub(super) fn complete_type_specifiers<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    mut specifiers: Vec<DeclarationSpecifier<'b>>,
+    span: Span,
+) -> NonEmptyVec<DeclarationSpecifier<'b>> {
+    let has_type_specifier = specifiers
+        .iter()
+        .any(|specifier| matches!(specifier, DeclarationSpecifier::TypeSpecifier(_)));
+    if !has_type_specifier {
+        let message = "type specifier missing, defaults to 'int'.".to_string();
+        report_implicit_int(context, message, span);
+        specifiers.push(DeclarationSpecifier::TypeSpecifier(TypeSpecifier::Int));
+    }
+    NonEmptyVec::from(specifiers)
+}
+
+//implicit int is valid only in C89
+fn report_implicit_int(context: &mut ParseContext, message: String, span: Span) {
+    let diagnostic = if standard(context) == Standard::C89 {
+        Diagnostic::named_warning(Warning::ImplicitInt, message, span)
+    } else {
+        Diagnostic::error(message, span)
+    };
+    report(context, diagnostic);
+}
+
+pub(super) fn static_assert_declaration<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<StaticAssertDeclaration<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::StaticAssert).is_none() {
+        return Ok(None);
+    }
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let condition = constant_expression(context)?;
+    expect_punctuator(context, PunctuatorKind::Commma)?;
+    let message = match peek_token(context) {
+        TokenKind::Literal(Literal::String(message)) => {
+            consume_token(context);
+            *message
+        }
+        _ => return Err(expected(context, "string literal")),
+    };
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
+
+    Ok(Some(StaticAssertDeclaration {
+        condition,
+        message,
+        span,
+    }))
+}
+
+pub(super) fn is_declaration_specifier_head(context: &ParseContext) -> bool {
+    match peek_token(context) {
+        TokenKind::Keyword(kind) => {
+            matches!(
+                kind,
+                KeywordKind::Typedef
+                    | KeywordKind::Extern
+                    | KeywordKind::Static
+                    | KeywordKind::ThreadLocal
+                    | KeywordKind::Auto
+                    | KeywordKind::Register
+                    | KeywordKind::Inline
+                    | KeywordKind::Noreturn
+                    | KeywordKind::Alignas
+                    | KeywordKind::Attribute
+                    | KeywordKind::Extension
+            ) || is_type_name_head(context)
+        }
+        _ => is_type_name_head(context),
+    }
 }
 
-fn declaration_specifier<'a, 'b>(
+//whether the head starts a specifier-qualifier-list, which is what tells a cast
+//`(T)x` from a parenthesized expression `(x)`
+pub(super) fn is_type_name_head(context: &ParseContext) -> bool {
+    is_type_name_nth(context, 0)
+}
+
+pub(super) fn is_type_name_nth(context: &ParseContext, n: usize) -> bool {
+    match peek_nth(context, n) {
+        TokenKind::Keyword(kind) => matches!(
+            kind,
+            KeywordKind::Void
+                | KeywordKind::Char
+                | KeywordKind::Short
+                | KeywordKind::Int
+                | KeywordKind::Long
+                | KeywordKind::Float
+                | KeywordKind::Double
+                | KeywordKind::Signed
+                | KeywordKind::Unsigned
+                | KeywordKind::Bool
+                | KeywordKind::Complex
+                | KeywordKind::Struct
+                | KeywordKind::Union
+                | KeywordKind::Enum
+                | KeywordKind::Const
+                | KeywordKind::Restrict
+                | KeywordKind::Volatile
+                | KeywordKind::Atomic
+                | KeywordKind::Typeof
+        ),
+        TokenKind::Identifier(identifier) => is_typedef_name(context, identifier),
+        _ => false,
+    }
+}
+
+pub(super) fn declaration_specifier<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<NonEmptyVec<DeclarationSpecifier<'b>>>, String> {
     let mut declaration_specifiers = Vec::new();
+    let mut has_type_specifier = false;
     loop {
         let specifier = if let Some(specifier) = storage_class_specifier(context)? {
             DeclarationSpecifier::StorageSpecifier(specifier)
-        } else if let Some(specifier) = type_specifier(context)? {
+        } else if let Some(specifier) = type_specifier(context, has_type_specifier)? {
+            has_type_specifier = true;
             DeclarationSpecifier::TypeSpecifier(specifier)
         } else if let Some(qualifier) = type_qualifier(context)? {
             DeclarationSpecifier::TypeQualifier(qualifier)
         } else if let Some(specifier) = alignment_specifier(context)? {
-            specifier
+            DeclarationSpecifier::AlignmentSpecifier(specifier)
         } else if let Some(specifier) = function_specifier(context)? {
-            specifier
+            DeclarationSpecifier::FunctionSpecifier(specifier)
+        } else if peek_keyword(context, KeywordKind::Attribute) {
+            DeclarationSpecifier::Attributes(attribute_specifiers(context)?)
+        } else if peek_keyword(context, KeywordKind::Extension) {
+            //`__extension__` only silences pedantic warnings, which there are none of
+            require_gnu(context, "__extension__", peek_span(context));
+            consume_token(context);
+            continue;
         } else {
             break;
         };
@@ -217,6 +544,15 @@ fn declaration_specifier<'a, 'b>(
     Ok(vec_to_optional_non_empty_vec(declaration_specifiers))
 }
 
+fn is_typedef(specifiers: &[DeclarationSpecifier]) -> bool {
+    specifiers.iter().any(|specifier| {
+        matches!(
+            specifier,
+            DeclarationSpecifier::StorageSpecifier(StorageClassSpecifier::Typedef)
+        )
+    })
+}
+
 fn storage_class_specifier<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StorageClassSpecifier>, String> {
@@ -239,10 +575,15 @@ fn storage_class_specifier<'a, 'b>(
     Ok(specifier)
 }
 
+//a typedef name is only a type specifier when no other type specifier precedes it,
+//so that `T T;` in an inner scope declares an object named T
 fn type_specifier<'a, 'b>(
     context: &'a mut ParseContext<'b>,
+    has_type_specifier: bool,
 ) -> Result<Option<TypeSpecifier<'b>>, String> {
-    let specifier = if consume_keyword(context, KeywordKind::Bool).is_some() {
+    let specifier = if consume_keyword(context, KeywordKind::Void).is_some() {
+        Some(TypeSpecifier::Void)
+    } else if consume_keyword(context, KeywordKind::Bool).is_some() {
         Some(TypeSpecifier::Bool)
     } else if consume_keyword(context, KeywordKind::Char).is_some() {
         Some(TypeSpecifier::Char)
@@ -256,13 +597,266 @@ fn type_specifier<'a, 'b>(
         Some(TypeSpecifier::Float)
     } else if consume_keyword(context, KeywordKind::Double).is_some() {
         Some(TypeSpecifier::Double)
-    } else {
+    } else if consume_keyword(context, KeywordKind::Signed).is_some() {
+        Some(TypeSpecifier::Singned)
+    } else if consume_keyword(context, KeywordKind::Unsigned).is_some() {
+        Some(TypeSpecifier::Unsigned)
+    } else if consume_keyword(context, KeywordKind::Complex).is_some() {
+        Some(TypeSpecifier::Complex)
+    } else if let Some(specifier) = struct_or_union_specifier(context)? {
+        Some(TypeSpecifier::StructOrUnionSpecifier(specifier))
+    } else if let Some(specifier) = enum_specifier(context)? {
+        Some(TypeSpecifier::EnumSpecifier(specifier))
+    } else if let Some(specifier) = typeof_specifier(context)? {
+        Some(specifier)
+    } else if let Some(specifier) = atomic_type_specifier(context)? {
+        Some(specifier)
+    } else if has_type_specifier {
         None
+    } else {
+        consume_typedef_name(context).map(TypeSpecifier::TypedefName)
     };
 
     Ok(specifier)
 }
 
+fn typeof_specifier<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<TypeSpecifier<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Typeof).is_none() {
+        return Ok(None);
+    }
+    require_gnu(context, "typeof", span);
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let operand = if is_type_name_head(context) {
+        Either::Left(expect_type_name(context)?)
+    } else {
+        Either::Right(expression(context)?)
+    };
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    Ok(Some(TypeSpecifier::Typeof(operand)))
+}
+
+//`_Atomic` followed by a left parenthesis is a type specifier, otherwise a qualifier
+fn atomic_type_specifier<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<TypeSpecifier<'b>>, String> {
+    let is_specifier = matches!(peek_token(context), TokenKind::Keyword(KeywordKind::Atomic))
+        && matches!(
+            peek_nth(context, 1),
+            TokenKind::Punctuator(PunctuatorKind::LeftRoundBracket)
+        );
+    if !is_specifier {
+        return Ok(None);
+    }
+    consume_token(context);
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let type_name = expect_type_name(context)?;
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    Ok(Some(TypeSpecifier::AtomicTypeSpecifier(type_name)))
+}
+
+fn struct_or_union_specifier<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<StructOrUnionSpecifier<'b>>, String> {
+    let span = peek_span(context);
+    let kind = if consume_keyword(context, KeywordKind::Struct).is_some() {
+        StructOrUnion::Struct
+    } else if consume_keyword(context, KeywordKind::Union).is_some() {
+        StructOrUnion::Union
+    } else {
+        return Ok(None);
+    };
+
+    let mut attributes = attribute_specifiers(context)?;
+    let name = consume_identifier(context);
+    let members = if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_some() {
+        let mut members = Vec::new();
+        let mut recovered = false;
+        while consume_punctuator(context, PunctuatorKind::RightCurlyBracket).is_none() {
+            if peek_end(context) {
+                return Err(unexpected_token(context));
+            }
+
+            //a broken member is skipped up to its `;`, so the members after it still parse
+            let started_at = remaining(context);
+            match struct_declaration(context) {
+                Ok(member) => members.push(member),
+                Err(message) => {
+                    report_error(context, message, started_at);
+                    synchronize(context, started_at, |_| false);
+                    recovered = true;
+                }
+            }
+        }
+        attributes.extend(attribute_specifiers(context)?);
+        match vec_to_optional_non_empty_vec(members) {
+            Some(members) => Some(members),
+            //the members which were there are already reported
+            None if recovered => None,
+            None => return Err("struct or union must have at least one member.".to_string()),
+        }
+    } else {
+        None
+    };
+
+    let name_or_menbers =
+        options_to_either_or_both(name, members).ok_or_else(|| unexpected_token(context))?;
+
+    Ok(Some(StructOrUnionSpecifier {
+        kind,
+        attributes,
+        name_or_menbers,
+        span,
+    }))
+}
+
+fn struct_declaration<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<StructDeclaration<'b>, String> {
+    let span = peek_span(context);
+    let specifier_qualifier_list = specifier_qualifier_list(context)?
+        .ok_or_else(|| expected(context, "member declaration"))?;
+
+    let mut declarators = Vec::new();
+    if consume_punctuator(context, PunctuatorKind::Semicolon).is_none() {
+        loop {
+            declarators.push(struct_declarator(context)?);
+            if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+                break;
+            }
+        }
+        expect_punctuator(context, PunctuatorKind::Semicolon)?;
+    }
+
+    Ok(StructDeclaration {
+        specifier_qualifier_list,
+        declarators,
+        span: span_since(context, span),
+    })
+}
+
+fn struct_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<StructDeclarator<'b>, String> {
+    let mut declarator = declarator(context)?;
+    let width = if consume_punctuator(context, PunctuatorKind::Colon).is_some() {
+        Some(constant_expression(context)?)
+    } else {
+        None
+    };
+    //attributes may follow the width too, as in `int a : 4 __attribute__((packed))`
+    let attributes = attribute_specifiers(context)?;
+    if let Some(declarator) = &mut declarator {
+        declarator.attributes.extend(attributes);
+    }
+
+    options_to_either_or_both(declarator, width)
+        .ok_or_else(|| expected(context, "member declarator"))
+}
+
+pub(super) fn specifier_qualifier_list<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<NonEmptyVec<SpecifierOrQualifier<'b>>>, String> {
+    let mut result = Vec::new();
+    let mut has_type_specifier = false;
+    loop {
+        let span = peek_span(context);
+        let item = if let Some(specifier) = type_specifier(context, has_type_specifier)? {
+            has_type_specifier = true;
+            SpecifierOrQualifier::TypeSpecifier(specifier)
+        } else if let Some(qualifier) = type_qualifier(context)? {
+            SpecifierOrQualifier::TypeQualifier(qualifier)
+        } else if let Some(specifier) = alignment_specifier(context)? {
+            SpecifierOrQualifier::AlignmentSpecifier(specifier, span_since(context, span))
+        } else {
+            break;
+        };
+
+        result.push(item);
+    }
+
+    Ok(vec_to_optional_non_empty_vec(result))
+}
+
+pub(super) fn type_name<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<TypeName<'b>>, String> {
+    let span = peek_span(context);
+    let specifier_qualifier_list = match specifier_qualifier_list(context)? {
+        Some(list) => list,
+        None => return Ok(None),
+    };
+    let declarator = abstract_declarator(context)?;
+
+    Ok(Some(TypeName {
+        specifier_qualifier_list,
+        declarator,
+        span: span_since(context, span),
+    }))
+}
+
+pub(super) fn expect_type_name<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<TypeName<'b>, String> {
+    type_name(context)?.ok_or_else(|| expected(context, "type name"))
+}
+
+fn enum_specifier<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<EnumSpecifier<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Enum).is_none() {
+        return Ok(None);
+    }
+
+    let mut attributes = attribute_specifiers(context)?;
+    let name = consume_identifier(context);
+    let members = if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_some() {
+        let mut members = vec![enumerator(context)?];
+        while consume_punctuator(context, PunctuatorKind::RightCurlyBracket).is_none() {
+            expect_punctuator(context, PunctuatorKind::Commma)?;
+            if consume_punctuator(context, PunctuatorKind::RightCurlyBracket).is_some() {
+                break;
+            }
+            members.push(enumerator(context)?);
+        }
+        attributes.extend(attribute_specifiers(context)?);
+        Some(NonEmptyVec::from(members))
+    } else {
+        None
+    };
+
+    let name_or_menbers =
+        options_to_either_or_both(name, members).ok_or_else(|| unexpected_token(context))?;
+
+    Ok(Some(EnumSpecifier {
+        attributes,
+        name_or_menbers,
+        span,
+    }))
+}
+
+fn enumerator<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Enumerator<'b>, String> {
+    let span = peek_span(context);
+    let name = expect_identifier(context)?;
+    let value = if consume_punctuator(context, PunctuatorKind::Equal).is_some() {
+        Some(constant_expression(context)?)
+    } else {
+        None
+    };
+
+    //an enumeration constant is an ordinary identifier and hides outer typedef names
+    declare_identifier(context, name, OrdinaryIdentifierKind::EnumerationConstant);
+
+    Ok(Enumerator { name, value, span })
+}
+
 fn type_qualifier<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<TypeQualifier>, String> {
@@ -283,8 +877,72 @@ fn type_qualifier<'a, 'b>(
 
 fn alignment_specifier<'a, 'b>(
     context: &'a mut ParseContext<'b>,
-) -> Result<Option<DeclarationSpecifier<'b>>, String> {
-    Err("".to_string())
+) -> Result<Option<Either<TypeName<'b>, Expression<'b>>>, String> {
+    if consume_keyword(context, KeywordKind::Alignas).is_none() {
+        return Ok(None);
+    }
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let alignment = if is_type_name_head(context) {
+        Either::Left(expect_type_name(context)?)
+    } else {
+        Either::Right(constant_expression(context)?)
+    };
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    Ok(Some(alignment))
+}
+
+//any number of `__attribute__((...))`, each with a possibly empty list of attributes
+pub(super) fn attribute_specifiers<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Vec<Attribute<'b>>, String> {
+    let mut attributes = Vec::new();
+    loop {
+        let span = peek_span(context);
+        if consume_keyword(context, KeywordKind::Attribute).is_none() {
+            break;
+        }
+        require_gnu(context, "__attribute__", span);
+
+        expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+        expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+        while consume_punctuator(context, PunctuatorKind::RightRoundBracket).is_none() {
+            //empty attributes are allowed, as in `__attribute__((, unused))`
+            if consume_punctuator(context, PunctuatorKind::Commma).is_some() {
+                continue;
+            }
+            attributes.push(attribute(context)?);
+            if !peek_punctuator(context, PunctuatorKind::RightRoundBracket) {
+                expect_punctuator(context, PunctuatorKind::Commma)?;
+            }
+        }
+        expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+    }
+
+    Ok(attributes)
+}
+
+fn attribute<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Attribute<'b>, String> {
+    //keywords such as `const` are attribute names too
+    let name = match peek_token(context) {
+        TokenKind::Identifier(name) => *name,
+        TokenKind::Keyword(kind) => kind.spelling(),
+        _ => return Err(expected(context, "attribute name")),
+    };
+    consume_token(context);
+
+    let mut arguments = Vec::new();
+    if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
+        while consume_punctuator(context, PunctuatorKind::RightRoundBracket).is_none() {
+            if !arguments.is_empty() {
+                expect_punctuator(context, PunctuatorKind::Commma)?;
+            }
+            arguments.push(assign(context)?);
+        }
+    }
+
+    Ok(Attribute { name, arguments })
 }
 
 fn function_specifier<'a, 'b>(
@@ -303,10 +961,392 @@ fn function_specifier<'a, 'b>(
 
 fn init_declarator<'a, 'b>(
     context: &'a mut ParseContext<'b>,
-) -> Result<Option<InitDeclarator<'b>>, String> {
-    Err("".to_string())
+    specifiers: &[DeclarationSpecifier<'b>],
+    declarator: Declarator<'b>,
+) -> Result<InitDeclarator<'b>, String> {
+    let mut declarator = declarator;
+    let asm_label = asm_label(context)?;
+    declarator.attributes.extend(attribute_specifiers(context)?);
+
+    //the scope of an identifier begins just after its declarator, before the initializer
+    declare_declarator(context, specifiers, &declarator);
+
+    let initializer = if consume_punctuator(context, PunctuatorKind::Equal).is_some() {
+        Some(initializer(context)?)
+    } else {
+        None
+    };
+
+    Ok(InitDeclarator {
+        declarator,
+        asm_label,
+        initializer,
+    })
+}
+
+//`asm("name")` after a declarator gives the assembler name of the object or function
+fn asm_label<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Option<&'b str>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Asm).is_none() {
+        return Ok(None);
+    }
+    require_gnu(context, "asm label", span);
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let name = match peek_token(context) {
+        TokenKind::Literal(Literal::String(name)) => *name,
+        _ => return Err(expected(context, "string literal")),
+    };
+    consume_token(context);
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    Ok(Some(name))
+}
+
+fn initializer<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Initializer<'b>, String> {
+    let result = if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_some() {
+        Initializer::List(initializer_list(context)?)
+    } else {
+        Initializer::Expression(assign(context)?)
+    };
+
+    Ok(result)
+}
+
+//parses after `{`
+pub(super) fn initializer_list<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Vec<InitializerListItem<'b>>, String> {
+    let mut result = Vec::new();
+    while consume_punctuator(context, PunctuatorKind::RightCurlyBracket).is_none() {
+        let designators = designation(context)?;
+        let initializer = initializer(context)?;
+        result.push(InitializerListItem {
+            designators,
+            initializer,
+        });
+
+        if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+            expect_punctuator(context, PunctuatorKind::RightCurlyBracket)?;
+            break;
+        }
+    }
+
+    Ok(result)
 }
 
-fn declarator<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Option<Declarator<'b>>, String> {
-    Err("".to_string())
+fn designation<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<Designator<'b>>, String> {
+    let mut designators = Vec::new();
+    loop {
+        let designator = if consume_punctuator(context, PunctuatorKind::Dot).is_some() {
+            Designator::Member(expect_identifier(context)?)
+        } else if consume_punctuator(context, PunctuatorKind::LeftSquareBracket).is_some() {
+            let first = constant_expression(context)?;
+            let span = peek_span(context);
+            let designator = if consume_punctuator(context, PunctuatorKind::TripleDots).is_some() {
+                require_gnu(context, "designator range", span);
+                Designator::Range(first, constant_expression(context)?)
+            } else {
+                Designator::Index(first)
+            };
+            expect_punctuator(context, PunctuatorKind::RightSquareBracket)?;
+            designator
+        } else {
+            break;
+        };
+
+        designators.push(designator);
+    }
+
+    if !designators.is_empty() {
+        expect_punctuator(context, PunctuatorKind::Equal)?;
+    }
+
+    Ok(designators)
+}
+
+pub(super) fn declare_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    specifiers: &[DeclarationSpecifier<'b>],
+    declarator: &Declarator<'b>,
+) {
+    let kind = if is_typedef(specifiers) {
+        OrdinaryIdentifierKind::TypedefName
+    } else {
+        OrdinaryIdentifierKind::Other
+    };
+    declare_identifier(context, declarator.identifier(), kind);
+}
+
+//makes the parameters of a function definition visible in its body
+pub(super) fn declare_parameters<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    declarator: &Declarator<'b>,
+) {
+    match declarator.function_parameters() {
+        Some(Either::Left(parameters)) => {
+            for parameter in parameters.parameters() {
+                if let Some(identifier) = parameter.identifier() {
+                    declare_identifier(context, identifier, OrdinaryIdentifierKind::Other);
+                }
+            }
+        }
+        Some(Either::Right(identifiers)) => {
+            for identifier in identifiers {
+                declare_identifier(context, identifier, OrdinaryIdentifierKind::Other);
+            }
+        }
+        None => {}
+    }
+}
+
+pub(super) fn declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<Declarator<'b>>, String> {
+    match declarator_or_abstract_declarator(context)? {
+        Either::Left(declarator) => Ok(Some(declarator)),
+        Either::Right(AbstractDeclarator {
+            pointer,
+            declarator: None,
+        }) if pointer.is_empty() => Ok(None),
+        Either::Right(_) => Err("declarator must have an identifier.".to_string()),
+    }
+}
+
+fn expect_declarator<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Declarator<'b>, String> {
+    declarator(context)?.ok_or_else(|| expected(context, "declarator"))
+}
+
+pub(super) fn abstract_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<AbstractDeclarator<'b>>, String> {
+    match declarator_or_abstract_declarator(context)? {
+        Either::Left(_) => Err("abstract declarator must not have an identifier.".to_string()),
+        Either::Right(AbstractDeclarator {
+            pointer,
+            declarator: None,
+        }) if pointer.is_empty() => Ok(None),
+        Either::Right(declarator) => Ok(Some(declarator)),
+    }
+}
+
+//parameter declarations may have either a declarator or an abstract declarator,
+//and which one it is is only known once the identifier is (or is not) reached
+fn declarator_or_abstract_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Either<Declarator<'b>, AbstractDeclarator<'b>>, String> {
+    let pointer = pointer(context)?;
+    let result = match direct_declarator_or_abstract_declarator(context)? {
+        Either::Left(identifier) => Either::Left(Declarator {
+            pointer,
+            identifier,
+            attributes: attribute_specifiers(context)?,
+        }),
+        Either::Right(declarator) => Either::Right(AbstractDeclarator {
+            pointer,
+            declarator,
+        }),
+    };
+
+    Ok(result)
+}
+
+fn pointer<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Pointer<'b>, String> {
+    let mut result = Vec::new();
+    while consume_punctuator(context, PunctuatorKind::Star).is_some() {
+        let mut qualifiers = Vec::new();
+        let mut attributes = Vec::new();
+        loop {
+            if let Some(qualifier) = type_qualifier(context)? {
+                qualifiers.push(qualifier);
+            } else if peek_keyword(context, KeywordKind::Attribute) {
+                attributes.extend(attribute_specifiers(context)?);
+            } else {
+                break;
+            }
+        }
+        result.push(Star {
+            qualifiers,
+            attributes,
+        });
+    }
+
+    Ok(result)
+}
+
+fn direct_declarator_or_abstract_declarator<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Either<DirectDeclarator<'b>, Option<DirectAbstarctDeclarator<'b>>>, String> {
+    let span = peek_span(context);
+    let mut current = if let Some(identifier) = consume_identifier(context) {
+        Either::Left(DirectDeclarator::Identifier(identifier, span))
+    } else if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
+        //`(` followed by `)` or a type starts the parameter list of an abstract function
+        //declarator, e.g. `int (int)` or `int (T)` with T a typedef name
+        if peek_punctuator(context, PunctuatorKind::RightRoundBracket)
+            || is_declaration_specifier_head(context)
+        {
+            let parameters = abstract_function_parameters(context)?;
+            Either::Right(Some(DirectAbstarctDeclarator::Function(None, parameters)))
+        } else {
+            let inner = declarator_or_abstract_declarator(context)?;
+            expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+            match inner {
+                Either::Left(declarator) => {
+                    Either::Left(DirectDeclarator::Declarator(Box::new(declarator)))
+                }
+                Either::Right(AbstractDeclarator {
+                    pointer,
+                    declarator: None,
+                }) if pointer.is_empty() => return Err(expected(context, "declarator")),
+                Either::Right(declarator) => Either::Right(Some(
+                    DirectAbstarctDeclarator::Declarator(Box::new(declarator)),
+                )),
+            }
+        }
+    } else {
+        Either::Right(None)
+    };
+
+    loop {
+        if consume_punctuator(context, PunctuatorKind::LeftSquareBracket).is_some() {
+            let (qualifiers, size) = array_size(context)?;
+            current =
+                match current {
+                    Either::Left(declarator) => Either::Left(DirectDeclarator::Array(
+                        Box::new(declarator),
+                        qualifiers,
+                        size,
+                    )),
+                    Either::Right(declarator) => Either::Right(Some(
+                        DirectAbstarctDeclarator::Array(declarator.map(Box::new), qualifiers, size),
+                    )),
+                };
+        } else if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
+            current = match current {
+                Either::Left(declarator) => {
+                    let parameters = function_parameters(context)?;
+                    Either::Left(DirectDeclarator::Function(Box::new(declarator), parameters))
+                }
+                Either::Right(declarator) => {
+                    let parameters = abstract_function_parameters(context)?;
+                    Either::Right(Some(DirectAbstarctDeclarator::Function(
+                        declarator.map(Box::new),
+                        parameters,
+                    )))
+                }
+            };
+        } else {
+            break;
+        }
+    }
+
+    Ok(current)
+}
+
+//parses after `[`
+fn array_size<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<(Vec<TypeQualifier>, Option<Expression<'b>>), String> {
+    let mut qualifiers = Vec::new();
+    while let Some(qualifier) = type_qualifier(context)? {
+        qualifiers.push(qualifier);
+    }
+
+    let size = if consume_punctuator(context, PunctuatorKind::RightSquareBracket).is_some() {
+        None
+    } else {
+        let size = assign(context)?;
+        expect_punctuator(context, PunctuatorKind::RightSquareBracket)?;
+        Some(size)
+    };
+
+    Ok((qualifiers, size))
+}
+
+//parses after `(`
+fn function_parameters<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Either<ParameterTypeList<'b>, IdentifierList<'b>>, String> {
+    if consume_punctuator(context, PunctuatorKind::RightRoundBracket).is_some() {
+        return Ok(Either::Right(Vec::new()));
+    }
+
+    if !is_declaration_specifier_head(context) {
+        let mut identifiers = Vec::new();
+        loop {
+            identifiers.push(expect_identifier(context)?);
+            if consume_punctuator(context, PunctuatorKind::Commma).is_none() {
+                break;
+            }
+        }
+        expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+        return Ok(Either::Right(identifiers));
+    }
+
+    //function prototype scope
+    push_scope(context);
+    let parameters = parameter_type_list(context);
+    pop_scope(context);
+    let parameters = parameters?;
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    Ok(Either::Left(parameters))
+}
+
+//parses after `(`
+fn abstract_function_parameters<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<ParameterTypeList<'b>>, String> {
+    match function_parameters(context)? {
+        Either::Left(parameters) => Ok(Some(parameters)),
+        Either::Right(identifiers) if identifiers.is_empty() => Ok(None),
+        Either::Right(_) => Err("identifier list is not allowed here.".to_string()),
+    }
+}
+
+fn parameter_type_list<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<ParameterTypeList<'b>, String> {
+    let mut parameter_list = vec![parameter_declaration(context)?];
+    let mut variable_parameter = false;
+    while consume_punctuator(context, PunctuatorKind::Commma).is_some() {
+        if consume_punctuator(context, PunctuatorKind::TripleDots).is_some() {
+            variable_parameter = true;
+            break;
+        }
+        parameter_list.push(parameter_declaration(context)?);
+    }
+
+    Ok(ParameterTypeList {
+        parameter_list: NonEmptyVec::from(parameter_list),
+        variable_parameter,
+    })
+}
+
+fn parameter_declaration<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<ParameterDeclaration<'b>, String> {
+    let span = peek_span(context);
+    let specifiers = declaration_specifier(context)?
+        .ok_or_else(|| expected(context, "parameter declaration"))?;
+    let specifiers = complete_type_specifiers(context, specifiers.unwrap(), span);
+
+    let declarator = match declarator_or_abstract_declarator(context)? {
+        Either::Left(declarator) => {
+            declare_declarator(context, &specifiers, &declarator);
+            Some(Either::Left(declarator))
+        }
+        Either::Right(AbstractDeclarator {
+            pointer,
+            declarator: None,
+        }) if pointer.is_empty() => None,
+        Either::Right(declarator) => Some(Either::Right(declarator)),
+    };
+
+    Ok(ParameterDeclaration {
+        specifiers,
+        declarator,
+        span: span_since(context, span),
+    })
 }
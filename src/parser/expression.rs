@@ -1,10 +1,20 @@
+use crate::parser::declaration::*;
+use crate::parser::statement::*;
 use crate::parser::util::*;
 use crate::tokenizer::*;
 use std::sync::Arc;
 
+//the span of a parenthesized expression includes the parentheses
 #[derive(Debug)]
-pub enum Expression<'a> {
+pub struct Expression<'a> {
+    pub kind: ExpressionKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum ExpressionKind<'a> {
     TernaryOperator {
+        #[allow(dead_code)] // only shown in the dump of the tree, there is one kind
         kind: TernaryOperatorKind,
         first: Arc<Expression<'a>>,
         second: Arc<Expression<'a>>,
@@ -19,47 +29,98 @@ pub enum Expression<'a> {
         kind: UnaryOperatorKind<'a>,
         operand: Arc<Expression<'a>>,
     },
+    CompoundLiteral {
+        type_name: Box<TypeName<'a>>,
+        initializer: Vec<InitializerListItem<'a>>,
+    },
+    TypeOperator {
+        kind: TypeOperatorKind,
+        type_name: Box<TypeName<'a>>,
+    },
+    Statement(Box<StatementNode<'a>>), // ({ ... }), GNU extension
+    //__builtin_offsetof(type-name, member-designator), GNU extension. the first designator
+    //is always a member
+    Offsetof {
+        type_name: Box<TypeName<'a>>,
+        designators: Vec<Designator<'a>>,
+    },
+    GenericSelection {
+        controlling: Arc<Expression<'a>>,
+        associations: Vec<GenericAssociation<'a>>,
+    },
+    Identifier(&'a str),
     Immediate(Immediate<'a>),
 }
 
+#[derive(Debug)]
+pub struct GenericAssociation<'a> {
+    pub type_name: Option<TypeName<'a>>, // None for default
+    pub expression: Expression<'a>,
+}
+
 #[derive(Debug)]
 pub enum TernaryOperatorKind {
     Conditional,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinaryOperatorKind {
-    Add,           // +
-    Sub,           // -
-    Mul,           // *
-    Div,           // /
-    Mod,           // %
-    Dot,           // .
-    LeftShift,     // <<
-    RightShift,    // >>
-    Equal,         // ==
-    NotEqual,      // !=
-    LessThan,      // <
-    LessThanEqual, // <=
-    LogicalAnd,    // &&
-    LogicalOr,     // ||
-    BitwiseAnd,    // &
-    BitwiseXor,    // ^
-    BitwiseOr,     // |
-    Assign,        // =
-    Comma,         // ,
+    Add,              // +
+    Sub,              // -
+    Mul,              // *
+    Div,              // /
+    Mod,              // %
+    LeftShift,        // <<
+    RightShift,       // >>
+    Equal,            // ==
+    NotEqual,         // !=
+    LessThan,         // <
+    LessThanEqual,    // <=
+    LogicalAnd,       // &&
+    LogicalOr,        // ||
+    BitwiseAnd,       // &
+    BitwiseXor,       // ^
+    BitwiseOr,        // |
+    Assign,           // =
+    MulAssign,        // *=
+    DivAssign,        // /=
+    ModAssign,        // %=
+    AddAssign,        // +=
+    SubAssign,        // -=
+    LeftShiftAssign,  // <<=
+    RightShiftAssign, // >>=
+    BitwiseAndAssign, // &=
+    BitwiseXorAssign, // ^=
+    BitwiseOrAssign,  // |=
+    Comma,            // ,
+    Conditional,      // lhs ?: rhs, GNU extension
 }
 
 #[derive(Debug)]
 pub enum UnaryOperatorKind<'a> {
     LogicalNot,                        // !operand
     BitwiseNot,                        // ~operand
+    Plus,                              // +operand
+    Minus,                             // -operand
     Reference,                         // &operand
     Dereference,                       // *operand
-    Cast(Type),                        // (type-name)operand
+    PreIncrement,                      // ++operand
+    PreDecrement,                      // --operand
+    PostIncrement,                     // operand++
+    PostDecrement,                     // operand--
+    Member(&'a str),                   // operand.member
+    PointerMember(&'a str),            // operand->member
+    Cast(Box<TypeName<'a>>),           // (type-name)operand
+    Sizeof,                            // sizeof operand
     FunctionCall(Vec<Expression<'a>>), // operand(parameter-lisr)
 }
 
+#[derive(Debug)]
+pub enum TypeOperatorKind {
+    Sizeof,  // sizeof(type-name)
+    Alignof, // _Alignof(type-name)
+}
+
 #[derive(Debug)]
 pub enum Immediate<'a> {
     String(&'a str),
@@ -67,36 +128,132 @@ pub enum Immediate<'a> {
     Number(u32),
 }
 
-#[derive(Debug)]
-pub struct Type {}
+impl BinaryOperatorKind {
+    pub fn spelling(self) -> &'static str {
+        use BinaryOperatorKind::*;
+        match self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Mod => "%",
+            LeftShift => "<<",
+            RightShift => ">>",
+            Equal => "==",
+            NotEqual => "!=",
+            LessThan => "<",
+            LessThanEqual => "<=",
+            LogicalAnd => "&&",
+            LogicalOr => "||",
+            BitwiseAnd => "&",
+            BitwiseXor => "^",
+            BitwiseOr => "|",
+            Assign => "=",
+            MulAssign => "*=",
+            DivAssign => "/=",
+            ModAssign => "%=",
+            AddAssign => "+=",
+            SubAssign => "-=",
+            LeftShiftAssign => "<<=",
+            RightShiftAssign => ">>=",
+            BitwiseAndAssign => "&=",
+            BitwiseXorAssign => "^=",
+            BitwiseOrAssign => "|=",
+            Comma => ",",
+            Conditional => "?:",
+        }
+    }
+}
 
-//parser body
-pub fn expression<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
-    let mut current = assign(context)?;
-    loop {
-        if consume_punctuator(context, PunctuatorKind::Commma).is_some() {
-            current = Expression::BinaryOperator {
+impl<'a> Expression<'a> {
+    //the expression as it is written, for diagnostics. an operand which is an operation
+    //itself is put in parentheses, and what is too long to show is left out as `...`
+    pub fn spelling(&self) -> String {
+        match &self.kind {
+            ExpressionKind::Identifier(name) => name.to_string(),
+            ExpressionKind::Immediate(Immediate::Number(value)) => value.to_string(),
+            ExpressionKind::Immediate(Immediate::Char(value)) => format!("{:?}", value),
+            ExpressionKind::Immediate(Immediate::String(value)) => format!("{:?}", value),
+            ExpressionKind::BinaryOperator {
                 kind: BinaryOperatorKind::Comma,
-                lhs: Arc::new(current),
-                rhs: Arc::new(assign(context)?),
-            };
-        } else {
-            break;
+                lhs,
+                rhs,
+            } => format!("{}, {}", lhs.operand_spelling(), rhs.operand_spelling()),
+            ExpressionKind::BinaryOperator { kind, lhs, rhs } => format!(
+                "{} {} {}",
+                lhs.operand_spelling(),
+                kind.spelling(),
+                rhs.operand_spelling()
+            ),
+            ExpressionKind::TernaryOperator {
+                first,
+                second,
+                third,
+                ..
+            } => format!(
+                "{} ? {} : {}",
+                first.operand_spelling(),
+                second.operand_spelling(),
+                third.operand_spelling()
+            ),
+            ExpressionKind::UnaryOperator { kind, operand } => {
+                let operand = operand.operand_spelling();
+                match kind {
+                    UnaryOperatorKind::LogicalNot => format!("!{}", operand),
+                    UnaryOperatorKind::BitwiseNot => format!("~{}", operand),
+                    UnaryOperatorKind::Plus => format!("+{}", operand),
+                    UnaryOperatorKind::Minus => format!("-{}", operand),
+                    UnaryOperatorKind::Reference => format!("&{}", operand),
+                    UnaryOperatorKind::Dereference => format!("*{}", operand),
+                    UnaryOperatorKind::PreIncrement => format!("++{}", operand),
+                    UnaryOperatorKind::PreDecrement => format!("--{}", operand),
+                    UnaryOperatorKind::PostIncrement => format!("{}++", operand),
+                    UnaryOperatorKind::PostDecrement => format!("{}--", operand),
+                    UnaryOperatorKind::Member(member) => format!("{}.{}", operand, member),
+                    UnaryOperatorKind::PointerMember(member) => {
+                        format!("{}->{}", operand, member)
+                    }
+                    UnaryOperatorKind::Cast(_) => format!("(...){}", operand),
+                    UnaryOperatorKind::Sizeof => format!("sizeof {}", operand),
+                    UnaryOperatorKind::FunctionCall(arguments) => {
+                        let arguments: Vec<String> = arguments
+                            .iter()
+                            .map(|argument| argument.spelling())
+                            .collect();
+                        format!("{}({})", operand, arguments.join(", "))
+                    }
+                }
+            }
+            ExpressionKind::TypeOperator {
+                kind: TypeOperatorKind::Sizeof,
+                ..
+            } => "sizeof(...)".to_string(),
+            ExpressionKind::TypeOperator {
+                kind: TypeOperatorKind::Alignof,
+                ..
+            } => "_Alignof(...)".to_string(),
+            _ => "...".to_string(),
         }
     }
 
-    Ok(current)
+    fn operand_spelling(&self) -> String {
+        match self.kind {
+            ExpressionKind::BinaryOperator { .. } | ExpressionKind::TernaryOperator { .. } => {
+                format!("({})", self.spelling())
+            }
+            _ => self.spelling(),
+        }
+    }
 }
 
-fn assign<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
-    let mut current = conditional(context)?;
+//parser body
+pub(super) fn expression<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Expression<'b>, String> {
+    let mut current = assign(context)?;
     loop {
-        if consume_punctuator(context, PunctuatorKind::Equal).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Assign,
-                lhs: Arc::new(current),
-                rhs: Arc::new(conditional(context)?),
-            };
+        if consume_punctuator(context, PunctuatorKind::Commma).is_some() {
+            current = binary(BinaryOperatorKind::Comma, current, assign(context)?);
         } else {
             break;
         }
@@ -105,17 +262,76 @@ fn assign<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, S
     Ok(current)
 }
 
+//assignment is right-associative, so `a = b = c` is `a = (b = c)`
+pub(super) fn assign<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
+    let lhs = conditional(context)?;
+    let kind = match assign_operator(context) {
+        Some(kind) => kind,
+        None => return Ok(lhs),
+    };
+
+    Ok(binary(kind, lhs, assign(context)?))
+}
+
+fn assign_operator(context: &mut ParseContext) -> Option<BinaryOperatorKind> {
+    let kind = if consume_punctuator(context, PunctuatorKind::Equal).is_some() {
+        BinaryOperatorKind::Assign
+    } else if consume_punctuator(context, PunctuatorKind::StarEqual).is_some() {
+        BinaryOperatorKind::MulAssign
+    } else if consume_punctuator(context, PunctuatorKind::SlashEqual).is_some() {
+        BinaryOperatorKind::DivAssign
+    } else if consume_punctuator(context, PunctuatorKind::PercentEqual).is_some() {
+        BinaryOperatorKind::ModAssign
+    } else if consume_punctuator(context, PunctuatorKind::PlusEqual).is_some() {
+        BinaryOperatorKind::AddAssign
+    } else if consume_punctuator(context, PunctuatorKind::MinusEqual).is_some() {
+        BinaryOperatorKind::SubAssign
+    } else if consume_punctuator(context, PunctuatorKind::DoubleLessThansEqual).is_some() {
+        BinaryOperatorKind::LeftShiftAssign
+    } else if consume_punctuator(context, PunctuatorKind::DoubleGreaterThansEqual).is_some() {
+        BinaryOperatorKind::RightShiftAssign
+    } else if consume_punctuator(context, PunctuatorKind::AmpersandEqual).is_some() {
+        BinaryOperatorKind::BitwiseAndAssign
+    } else if consume_punctuator(context, PunctuatorKind::HatEqual).is_some() {
+        BinaryOperatorKind::BitwiseXorAssign
+    } else if consume_punctuator(context, PunctuatorKind::PipelineEqual).is_some() {
+        BinaryOperatorKind::BitwiseOrAssign
+    } else {
+        return None;
+    };
+
+    Some(kind)
+}
+
+pub(super) fn constant_expression<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Expression<'b>, String> {
+    conditional(context)
+}
+
 fn conditional<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
     let condition = logical_or(context)?;
     let result = if consume_punctuator(context, PunctuatorKind::Question).is_some() {
+        let span = peek_span(context);
+        if consume_punctuator(context, PunctuatorKind::Colon).is_some() {
+            require_gnu(context, "conditional with omitted operand", span);
+            return Ok(binary(
+                BinaryOperatorKind::Conditional,
+                condition,
+                conditional(context)?,
+            ));
+        }
         let second = expression(context)?;
         expect_punctuator(context, PunctuatorKind::Colon)?;
-        Expression::TernaryOperator {
+        let third = conditional(context)?;
+        let span = condition.span.to(third.span);
+        let kind = ExpressionKind::TernaryOperator {
             kind: TernaryOperatorKind::Conditional,
             first: Arc::new(condition),
             second: Arc::new(second),
-            third: Arc::new(conditional(context)?),
-        }
+            third: Arc::new(third),
+        };
+        Expression { kind, span }
     } else {
         condition
     };
@@ -127,11 +343,11 @@ fn logical_or<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b
     let mut current = logical_and(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::DoublePipelines).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LogicalOr,
-                lhs: Arc::new(current),
-                rhs: Arc::new(logical_and(context)?),
-            };
+            current = binary(
+                BinaryOperatorKind::LogicalOr,
+                current,
+                logical_and(context)?,
+            );
         } else {
             break current;
         }
@@ -144,11 +360,11 @@ fn logical_and<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'
     let mut current = bitwise_inclusive_or(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::DoubleAmpersands).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LogicalAnd,
-                lhs: Arc::new(current),
-                rhs: Arc::new(bitwise_inclusive_or(context)?),
-            };
+            current = binary(
+                BinaryOperatorKind::LogicalAnd,
+                current,
+                bitwise_inclusive_or(context)?,
+            );
         } else {
             break current;
         }
@@ -163,11 +379,11 @@ fn bitwise_inclusive_or<'a, 'b>(
     let mut current = bitwise_exclusive_or(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::Pipeline).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::BitwiseOr,
-                lhs: Arc::new(current),
-                rhs: Arc::new(bitwise_exclusive_or(context)?),
-            };
+            current = binary(
+                BinaryOperatorKind::BitwiseOr,
+                current,
+                bitwise_exclusive_or(context)?,
+            );
         } else {
             break current;
         }
@@ -182,11 +398,11 @@ fn bitwise_exclusive_or<'a, 'b>(
     let mut current = bitwise_and(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::Hat).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::BitwiseXor,
-                lhs: Arc::new(current),
-                rhs: Arc::new(bitwise_and(context)?),
-            };
+            current = binary(
+                BinaryOperatorKind::BitwiseXor,
+                current,
+                bitwise_and(context)?,
+            );
         } else {
             break current;
         }
@@ -199,11 +415,7 @@ fn bitwise_and<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'
     let mut current = equality(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::Ampersand).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::BitwiseAnd,
-                lhs: Arc::new(current),
-                rhs: Arc::new(equality(context)?),
-            };
+            current = binary(BinaryOperatorKind::BitwiseAnd, current, equality(context)?);
         } else {
             break current;
         }
@@ -216,17 +428,9 @@ fn equality<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
     let mut current = relational(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::DoubleEquals).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Equal,
-                lhs: Arc::new(current),
-                rhs: Arc::new(relational(context)?),
-            };
+            current = binary(BinaryOperatorKind::Equal, current, relational(context)?);
         } else if consume_punctuator(context, PunctuatorKind::ExclamationEqual).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::NotEqual,
-                lhs: Arc::new(current),
-                rhs: Arc::new(relational(context)?),
-            };
+            current = binary(BinaryOperatorKind::NotEqual, current, relational(context)?);
         } else {
             break current;
         }
@@ -239,29 +443,13 @@ fn relational<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b
     let mut current = shift(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::LessThan).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LessThan,
-                lhs: Arc::new(current),
-                rhs: Arc::new(shift(context)?),
-            };
+            current = binary(BinaryOperatorKind::LessThan, current, shift(context)?);
         } else if consume_punctuator(context, PunctuatorKind::GreaterThan).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LessThan,
-                lhs: Arc::new(shift(context)?),
-                rhs: Arc::new(current),
-            };
+            current = binary(BinaryOperatorKind::LessThan, shift(context)?, current);
         } else if consume_punctuator(context, PunctuatorKind::LessThanEqual).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LessThanEqual,
-                lhs: Arc::new(current),
-                rhs: Arc::new(shift(context)?),
-            };
+            current = binary(BinaryOperatorKind::LessThanEqual, current, shift(context)?);
         } else if consume_punctuator(context, PunctuatorKind::GreaterThanEqual).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LessThanEqual,
-                lhs: Arc::new(shift(context)?),
-                rhs: Arc::new(current),
-            };
+            current = binary(BinaryOperatorKind::LessThanEqual, shift(context)?, current);
         } else {
             break current;
         }
@@ -274,17 +462,9 @@ fn shift<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, St
     let mut current = add(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::DoubleLessThans).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::LeftShift,
-                lhs: Arc::new(current),
-                rhs: Arc::new(add(context)?),
-            };
+            current = binary(BinaryOperatorKind::LeftShift, current, add(context)?);
         } else if consume_punctuator(context, PunctuatorKind::DoubleGreaterThans).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::RightShift,
-                lhs: Arc::new(current),
-                rhs: Arc::new(add(context)?),
-            };
+            current = binary(BinaryOperatorKind::RightShift, current, add(context)?);
         } else {
             break current;
         }
@@ -297,17 +477,9 @@ fn add<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, Stri
     let mut current = multiply(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::Plus).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Add,
-                lhs: Arc::new(current),
-                rhs: Arc::new(multiply(context)?),
-            };
+            current = binary(BinaryOperatorKind::Add, current, multiply(context)?);
         } else if consume_punctuator(context, PunctuatorKind::Minus).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Sub,
-                lhs: Arc::new(current),
-                rhs: Arc::new(multiply(context)?),
-            };
+            current = binary(BinaryOperatorKind::Sub, current, multiply(context)?);
         } else {
             break current;
         }
@@ -320,23 +492,11 @@ fn multiply<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
     let mut current = unary(context)?;
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::Star).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Mul,
-                lhs: Arc::new(current),
-                rhs: Arc::new(unary(context)?),
-            };
+            current = binary(BinaryOperatorKind::Mul, current, unary(context)?);
         } else if consume_punctuator(context, PunctuatorKind::Slash).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Div,
-                lhs: Arc::new(current),
-                rhs: Arc::new(unary(context)?),
-            };
+            current = binary(BinaryOperatorKind::Div, current, unary(context)?);
         } else if consume_punctuator(context, PunctuatorKind::Percent).is_some() {
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Mod,
-                lhs: Arc::new(current),
-                rhs: Arc::new(unary(context)?),
-            };
+            current = binary(BinaryOperatorKind::Mod, current, unary(context)?);
         } else {
             break current;
         }
@@ -346,38 +506,113 @@ fn multiply<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
 }
 
 fn unary<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
-    let mut stack = Vec::<UnaryOperatorKind>::new();
-    loop {
-        if consume_punctuator(context, PunctuatorKind::Exclamation).is_some() {
-            stack.push(UnaryOperatorKind::LogicalNot);
+    //operators with the span of their token
+    let mut stack = Vec::<(UnaryOperatorKind, Span)>::new();
+    let mut current = loop {
+        let span = peek_span(context);
+        if peek_keyword(context, KeywordKind::Extension) {
+            require_gnu(context, "__extension__", peek_span(context));
+            consume_token(context);
+        } else if consume_punctuator(context, PunctuatorKind::Exclamation).is_some() {
+            stack.push((UnaryOperatorKind::LogicalNot, span));
         } else if consume_punctuator(context, PunctuatorKind::Tilde).is_some() {
-            stack.push(UnaryOperatorKind::BitwiseNot);
+            stack.push((UnaryOperatorKind::BitwiseNot, span));
+        } else if consume_punctuator(context, PunctuatorKind::Plus).is_some() {
+            stack.push((UnaryOperatorKind::Plus, span));
+        } else if consume_punctuator(context, PunctuatorKind::Minus).is_some() {
+            stack.push((UnaryOperatorKind::Minus, span));
+        } else if consume_punctuator(context, PunctuatorKind::DoublePluses).is_some() {
+            stack.push((UnaryOperatorKind::PreIncrement, span));
+        } else if consume_punctuator(context, PunctuatorKind::DoubleMinuses).is_some() {
+            stack.push((UnaryOperatorKind::PreDecrement, span));
         } else if consume_punctuator(context, PunctuatorKind::Ampersand).is_some() {
-            stack.push(UnaryOperatorKind::Reference);
+            stack.push((UnaryOperatorKind::Reference, span));
         } else if consume_punctuator(context, PunctuatorKind::Star).is_some() {
-            stack.push(UnaryOperatorKind::Dereference);
-        //} else if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
-        //    let typeName = Type {};
-        //    stack.push(UnaryOperatorKind::Cast(typeName));
+            stack.push((UnaryOperatorKind::Dereference, span));
+        } else if consume_keyword(context, KeywordKind::Sizeof).is_some() {
+            if !is_parenthesized_type_name_head(context) {
+                stack.push((UnaryOperatorKind::Sizeof, span));
+                continue;
+            }
+            let literal_span = peek_span(context);
+            let type_name = parenthesized_type_name(context)?;
+            if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_some() {
+                stack.push((UnaryOperatorKind::Sizeof, span));
+                break compound_literal(context, type_name, literal_span)?;
+            }
+            let kind = ExpressionKind::TypeOperator {
+                kind: TypeOperatorKind::Sizeof,
+                type_name: Box::new(type_name),
+            };
+            break node(context, kind, span);
+        } else if consume_keyword(context, KeywordKind::Alignof).is_some() {
+            let type_name = parenthesized_type_name(context)?;
+            let kind = ExpressionKind::TypeOperator {
+                kind: TypeOperatorKind::Alignof,
+                type_name: Box::new(type_name),
+            };
+            break node(context, kind, span);
+        } else if is_parenthesized_type_name_head(context) {
+            let type_name = parenthesized_type_name(context)?;
+            if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_some() {
+                break compound_literal(context, type_name, span)?;
+            }
+            stack.push((UnaryOperatorKind::Cast(Box::new(type_name)), span));
         } else {
-            break;
+            break postfix(context)?;
         }
-    }
+    };
 
-    let mut current = postfix(context)?;
-    while !stack.is_empty() {
-        let operator = stack.pop().unwrap();
-        current = Expression::UnaryOperator {
-            kind: operator,
-            operand: Arc::new(current),
-        };
+    while let Some((operator, span)) = stack.pop() {
+        current = unary_node(operator, current, span);
     }
 
     Ok(current)
 }
 
 fn postfix<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
-    let mut current = primary(context)?;
+    let primary = primary(context)?;
+    postfix_operators(context, primary)
+}
+
+//`(` followed by a type name starts a cast or a compound literal, anything else
+//after `(` is a parenthesized expression
+fn is_parenthesized_type_name_head(context: &ParseContext) -> bool {
+    matches!(
+        peek_nth(context, 0),
+        TokenKind::Punctuator(PunctuatorKind::LeftRoundBracket)
+    ) && is_type_name_nth(context, 1)
+}
+
+fn parenthesized_type_name<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<TypeName<'b>, String> {
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let type_name = expect_type_name(context)?;
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+    Ok(type_name)
+}
+
+//parses after `{`, a compound literal is a postfix expression like `(struct point){1, 2}.x`
+fn compound_literal<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    type_name: TypeName<'b>,
+    span: Span,
+) -> Result<Expression<'b>, String> {
+    let initializer = initializer_list(context)?;
+    let kind = ExpressionKind::CompoundLiteral {
+        type_name: Box::new(type_name),
+        initializer,
+    };
+    let literal = node(context, kind, span);
+    postfix_operators(context, literal)
+}
+
+fn postfix_operators<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    primary: Expression<'b>,
+) -> Result<Expression<'b>, String> {
+    let mut current = primary;
 
     let result = loop {
         if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
@@ -389,43 +624,29 @@ fn postfix<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
                         break;
                     }
                 }
+                expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
             }
-            current = Expression::UnaryOperator {
-                kind: UnaryOperatorKind::FunctionCall(parameters),
-                operand: Arc::new(current),
-            };
+            current = postfix_node(
+                context,
+                UnaryOperatorKind::FunctionCall(parameters),
+                current,
+            );
         } else if consume_punctuator(context, PunctuatorKind::LeftSquareBracket).is_some() {
+            //a[i] is *(a + i)
             let index = expression(context)?;
             expect_punctuator(context, PunctuatorKind::RightSquareBracket)?;
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Add,
-                lhs: Arc::new(current),
-                rhs: Arc::new(index),
-            };
+            let address = binary(BinaryOperatorKind::Add, current, index);
+            current = postfix_node(context, UnaryOperatorKind::Dereference, address);
+        } else if consume_punctuator(context, PunctuatorKind::Dot).is_some() {
+            let member = expect_identifier(context)?;
+            current = postfix_node(context, UnaryOperatorKind::Member(member), current);
+        } else if consume_punctuator(context, PunctuatorKind::Arrow).is_some() {
+            let member = expect_identifier(context)?;
+            current = postfix_node(context, UnaryOperatorKind::PointerMember(member), current);
         } else if consume_punctuator(context, PunctuatorKind::DoublePluses).is_some() {
-            let current_arc = Arc::new(current);
-            let add = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Add,
-                lhs: Arc::clone(&current_arc),
-                rhs: Arc::new(Expression::Immediate(Immediate::Number(1))),
-            };
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Assign,
-                lhs: Arc::clone(&current_arc),
-                rhs: Arc::new(add),
-            };
+            current = postfix_node(context, UnaryOperatorKind::PostIncrement, current);
         } else if consume_punctuator(context, PunctuatorKind::DoubleMinuses).is_some() {
-            let current_arc = Arc::new(current);
-            let sub = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Sub,
-                lhs: Arc::clone(&current_arc),
-                rhs: Arc::new(Expression::Immediate(Immediate::Number(1))),
-            };
-            current = Expression::BinaryOperator {
-                kind: BinaryOperatorKind::Assign,
-                lhs: Arc::clone(&current_arc),
-                rhs: Arc::new(sub),
-            };
+            current = postfix_node(context, UnaryOperatorKind::PostDecrement, current);
         } else {
             break current;
         }
@@ -435,17 +656,111 @@ fn postfix<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
 }
 
 fn primary<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
-    match consume_punctuator(context, PunctuatorKind::LeftRoundBracket) {
-        Some(_) => {
-            let result = expression(context);
+    let span = peek_span(context);
+    if consume_punctuator(context, PunctuatorKind::LeftRoundBracket).is_some() {
+        if peek_punctuator(context, PunctuatorKind::LeftCurlyBracket) {
+            require_gnu(context, "statement expression", peek_span(context));
+            let body = compound_statement(context)?.unwrap();
             expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
-            result
+            let kind = ExpressionKind::Statement(Box::new(body));
+            return Ok(node(context, kind, span));
         }
+        let mut result = expression(context)?;
+        expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+        result.span = span_since(context, span);
+        return Ok(result);
+    }
+
+    if let Some(selection) = generic_selection(context)? {
+        return Ok(selection);
+    }
+    if let Some(offsetof) = offsetof(context)? {
+        return Ok(offsetof);
+    }
+
+    match consume_identifier(context) {
+        Some(identifier) if is_typedef_name(context, identifier) => Err(format!(
+            "type name '{}' is not allowed in an expression.",
+            identifier
+        )),
+        Some(identifier) => Ok(node(context, ExpressionKind::Identifier(identifier), span)),
         None => literal(context),
     }
 }
 
+fn generic_selection<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<Expression<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Generic).is_none() {
+        return Ok(None);
+    }
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let controlling = assign(context)?;
+    let mut associations: Vec<GenericAssociation> = Vec::new();
+    while consume_punctuator(context, PunctuatorKind::RightRoundBracket).is_none() {
+        expect_punctuator(context, PunctuatorKind::Commma)?;
+        let type_name = if consume_keyword(context, KeywordKind::_Default).is_some() {
+            if associations
+                .iter()
+                .any(|association| association.type_name.is_none())
+            {
+                return Err("duplicate default generic association.".to_string());
+            }
+            None
+        } else {
+            Some(expect_type_name(context)?)
+        };
+        expect_punctuator(context, PunctuatorKind::Colon)?;
+        let expression = assign(context)?;
+        associations.push(GenericAssociation {
+            type_name,
+            expression,
+        });
+    }
+    if associations.is_empty() {
+        return Err("generic selection must have at least one association.".to_string());
+    }
+
+    let kind = ExpressionKind::GenericSelection {
+        controlling: Arc::new(controlling),
+        associations,
+    };
+    Ok(Some(node(context, kind, span)))
+}
+
+fn offsetof<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Option<Expression<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Offsetof).is_none() {
+        return Ok(None);
+    }
+
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let type_name = expect_type_name(context)?;
+    expect_punctuator(context, PunctuatorKind::Commma)?;
+    let mut designators = vec![Designator::Member(expect_identifier(context)?)];
+    loop {
+        if consume_punctuator(context, PunctuatorKind::Dot).is_some() {
+            designators.push(Designator::Member(expect_identifier(context)?));
+        } else if consume_punctuator(context, PunctuatorKind::LeftSquareBracket).is_some() {
+            designators.push(Designator::Index(expression(context)?));
+            expect_punctuator(context, PunctuatorKind::RightSquareBracket)?;
+        } else {
+            break;
+        }
+    }
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+
+    let kind = ExpressionKind::Offsetof {
+        type_name: Box::new(type_name),
+        designators,
+    };
+    Ok(Some(node(context, kind, span)))
+}
+
 fn literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>, String> {
+    let span = peek_span(context);
     let content = expect_literal(context)?;
 
     let result = match content {
@@ -454,5 +769,60 @@ fn literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Expression<'b>,
         Literal::Number(content) => Immediate::Number(*content),
     };
 
-    Ok(Expression::Immediate(result))
+    Ok(node(context, ExpressionKind::Immediate(result), span))
+}
+
+//an expression which began at begin and ends with the last consumed token
+fn node<'b>(context: &ParseContext<'b>, kind: ExpressionKind<'b>, begin: Span) -> Expression<'b> {
+    Expression {
+        kind,
+        span: span_since(context, begin),
+    }
+}
+
+fn binary<'b>(
+    kind: BinaryOperatorKind,
+    lhs: Expression<'b>,
+    rhs: Expression<'b>,
+) -> Expression<'b> {
+    let span = lhs.span.to(rhs.span);
+    Expression {
+        kind: ExpressionKind::BinaryOperator {
+            kind,
+            lhs: Arc::new(lhs),
+            rhs: Arc::new(rhs),
+        },
+        span,
+    }
+}
+
+//a prefix operator whose token is at span
+fn unary_node<'b>(
+    kind: UnaryOperatorKind<'b>,
+    operand: Expression<'b>,
+    span: Span,
+) -> Expression<'b> {
+    let span = span.to(operand.span);
+    Expression {
+        kind: ExpressionKind::UnaryOperator {
+            kind,
+            operand: Arc::new(operand),
+        },
+        span,
+    }
+}
+
+fn postfix_node<'b>(
+    context: &ParseContext<'b>,
+    kind: UnaryOperatorKind<'b>,
+    operand: Expression<'b>,
+) -> Expression<'b> {
+    let span = span_since(context, operand.span);
+    Expression {
+        kind: ExpressionKind::UnaryOperator {
+            kind,
+            operand: Arc::new(operand),
+        },
+        span,
+    }
 }
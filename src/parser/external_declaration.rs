@@ -0,0 +1,115 @@
+use crate::options::*;
+use crate::parser::declaration::*;
+use crate::parser::statement::*;
+use crate::parser::util::*;
+use crate::tokenizer::*;
+use crate::util::*;
+
+pub type TranslationUnit<'a> = Vec<ExternalDeclaration<'a>>;
+
+#[derive(Debug)]
+pub enum ExternalDeclaration<'a> {
+    FunctionDefinition(Box<FunctionDefinition<'a>>),
+    Declaration(Declaration<'a>),
+    StaticAssert(StaticAssertDeclaration<'a>),
+    Error, // a broken declaration skipped by error recovery
+}
+
+#[derive(Debug)]
+pub struct FunctionDefinition<'a> {
+    pub specifiers: NonEmptyVec<DeclarationSpecifier<'a>>,
+    pub declarator: Declarator<'a>,
+    pub declarations: Vec<Declaration<'a>>, // the declaration list of an old-style definition
+    pub body: StatementNode<'a>,
+    pub end: Span, // of the closing brace of the body
+}
+
+//parser body
+pub(super) fn translation_unit<'a, 'b>(context: &'a mut ParseContext<'b>) -> TranslationUnit<'b> {
+    let mut result = Vec::new();
+    while !peek_end(context) {
+        let started_at = remaining(context);
+        let declaration = match external_declaration(context) {
+            Ok(Some(declaration)) => declaration,
+            Ok(None) => {
+                report_error(context, expected(context, "declaration"), started_at);
+                synchronize(context, started_at, is_declaration_specifier_head);
+                ExternalDeclaration::Error
+            }
+            Err(message) => {
+                report_error(context, message, started_at);
+                synchronize(context, started_at, is_declaration_specifier_head);
+                ExternalDeclaration::Error
+            }
+        };
+        result.push(declaration);
+    }
+
+    result
+}
+
+fn external_declaration<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<ExternalDeclaration<'b>>, String> {
+    if let Some(assertion) = static_assert_declaration(context)? {
+        return Ok(Some(ExternalDeclaration::StaticAssert(assertion)));
+    }
+
+    let begin = peek_span(context);
+    let specifiers = match declaration_specifier(context)? {
+        Some(specifiers) => complete_type_specifiers(context, specifiers.unwrap(), begin),
+        //C89 lets a declaration at file scope start with its declarator, as in `main() { }`
+        None if standard(context) == Standard::C89
+            && matches!(peek_token(context), TokenKind::Identifier(_)) =>
+        {
+            complete_type_specifiers(context, Vec::new(), begin)
+        }
+        None => return Ok(None),
+    };
+
+    let span = peek_span(context);
+    let declarator = match declarator(context)? {
+        Some(declarator) => declarator,
+        None => {
+            let declaration = declaration_after_declarator(context, specifiers, None, begin)?;
+            return Ok(Some(ExternalDeclaration::Declaration(declaration)));
+        }
+    };
+
+    if !peek_punctuator(context, PunctuatorKind::LeftCurlyBracket)
+        && !is_parameter_declaration_list_head(context, &declarator)
+    {
+        let declaration =
+            declaration_after_declarator(context, specifiers, Some(declarator), begin)?;
+        return Ok(Some(ExternalDeclaration::Declaration(declaration)));
+    }
+
+    declare_declarator(context, &specifiers, &declarator);
+
+    push_scope(context);
+    declare_parameters(context, &declarator);
+    let result = function_body(context, &declarator, span);
+    pop_scope(context);
+    let (declarations, body) = result?;
+    let end = previous_span(context).unwrap();
+
+    Ok(Some(ExternalDeclaration::FunctionDefinition(Box::new(
+        FunctionDefinition {
+            specifiers,
+            declarator,
+            declarations,
+            body,
+            end,
+        },
+    ))))
+}
+
+fn function_body<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    declarator: &Declarator<'b>,
+    span: Span,
+) -> Result<(Vec<Declaration<'b>>, StatementNode<'b>), String> {
+    let declarations = parameter_declaration_list(context, declarator, span)?;
+    let body = compound_statement(context)?.unwrap();
+    Ok((declarations, body))
+}
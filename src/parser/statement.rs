@@ -1,6 +1,9 @@
+use crate::parser::asm::*;
+use crate::parser::declaration::*;
 use crate::parser::expression::*;
 use crate::parser::util::*;
 use crate::tokenizer::*;
+use crate::util::*;
 
 #[derive(Debug)]
 pub enum StatementNode<'a> {
@@ -9,12 +12,30 @@ pub enum StatementNode<'a> {
     If {
         condition: Box<Expression<'a>>,
         statement: Box<StatementNode<'a>>,
+        else_statement: Option<Box<StatementNode<'a>>>,
     },
     Switch {
         condition: Box<Expression<'a>>,
         statement: Box<StatementNode<'a>>,
     },
-    Labeled(&'a str),
+    Labeled {
+        label: &'a str,
+        span: Span, // of the label
+        statement: Box<StatementNode<'a>>,
+    },
+    Case {
+        value: Box<Expression<'a>>,
+        span: Span, // of the case keyword
+        statement: Box<StatementNode<'a>>,
+    },
+    //case first ... last:, GNU extension
+    CaseRange {
+        first: Box<Expression<'a>>,
+        last: Box<Expression<'a>>,
+        span: Span, // of the case keyword
+        statement: Box<StatementNode<'a>>,
+    },
+    Default(Box<StatementNode<'a>>, Span), // the span of the default keyword
     While {
         condition: Box<Expression<'a>>,
         statement: Box<StatementNode<'a>>,
@@ -23,21 +44,34 @@ pub enum StatementNode<'a> {
         condition: Box<Expression<'a>>,
         statement: Box<StatementNode<'a>>,
     },
+    //a declaration in the first clause is scoped to the for statement itself
     For {
-        initialization: Box<Expression<'a>>,
-        condition: Box<Expression<'a>>,
-        afterthought: Box<Expression<'a>>,
+        initialization: Option<Either<Declaration<'a>, Box<Expression<'a>>>>,
+        condition: Option<Box<Expression<'a>>>,
+        afterthought: Option<Box<Expression<'a>>>,
         statement: Box<StatementNode<'a>>,
     },
-    Compound(Vec<StatementNode<'a>>),
-    Return(Box<Expression<'a>>),
-    Break,
-    Continue,
-    Goto(&'a str),
+    Compound(Vec<BlockItem<'a>>),
+    Return(Option<Box<Expression<'a>>>, Span), // the span of the return keyword
+    Break(Span),                               // the span of the break keyword
+    Continue(Span),                            // the span of the continue keyword
+    Goto(&'a str, Span),                       // the span of the label
+    Asm(Box<AsmStatement<'a>>),
+    Attribute(Vec<Attribute<'a>>), // __attribute__((...));, GNU extension
+    Error,                         // a broken block item skipped by error recovery
+}
+
+#[derive(Debug)]
+pub enum BlockItem<'a> {
+    Declaration(Declaration<'a>),
+    StaticAssert(StaticAssertDeclaration<'a>),
+    Statement(StatementNode<'a>),
 }
 
 //parser body
-pub fn statement<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<StatementNode<'b>, String> {
+pub(super) fn statement<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<StatementNode<'b>, String> {
     if let Some(result) = null_statement(context)? {
         Ok(result)
     } else if let Some(result) = if_statement(context)? {
@@ -46,6 +80,10 @@ pub fn statement<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<StatementN
         Ok(result)
     } else if let Some(result) = labeled_statement(context)? {
         Ok(result)
+    } else if let Some(result) = case_statement(context)? {
+        Ok(result)
+    } else if let Some(result) = default_statement(context)? {
+        Ok(result)
     } else if let Some(result) = while_statement(context)? {
         Ok(result)
     } else if let Some(result) = do_while_statement(context)? {
@@ -62,10 +100,14 @@ pub fn statement<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<StatementN
         Ok(result)
     } else if let Some(result) = goto_statement(context)? {
         Ok(result)
+    } else if let Some(result) = attribute_statement(context)? {
+        Ok(result)
+    } else if let Some(result) = asm_statement(context)? {
+        Ok(StatementNode::Asm(Box::new(result)))
     } else if let Some(result) = expression_statement(context)? {
         Ok(result)
     } else {
-        Err("statement is expected but not found.".to_string())
+        Err(expected(context, "statement"))
     }
 }
 
@@ -82,6 +124,19 @@ fn null_statement<'a, 'b>(
     Ok(result)
 }
 
+//attributes that apply to the null statement they end, such as `fallthrough`
+fn attribute_statement<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<StatementNode<'b>>, String> {
+    if !peek_keyword(context, KeywordKind::Attribute) {
+        return Ok(None);
+    }
+    let attributes = attribute_specifiers(context)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
+
+    Ok(Some(StatementNode::Attribute(attributes)))
+}
+
 fn expression_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
@@ -104,9 +159,17 @@ fn if_statement<'a, 'b>(
 
     let body = statement(context)?;
 
+    //a dangling else belongs to the innermost if
+    let else_body = if consume_keyword(context, KeywordKind::Else).is_some() {
+        Some(Box::new(statement(context)?))
+    } else {
+        None
+    };
+
     Ok(Some(StatementNode::If {
         condition: Box::new(cond),
         statement: Box::new(body),
+        else_statement: else_body,
     }))
 }
 
@@ -132,14 +195,67 @@ fn switch_statement<'a, 'b>(
 fn labeled_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
-    let label = match consume_identifier(context) {
-        Some(result) => {
-            expect_punctuator(context, PunctuatorKind::Colon)?;
-            StatementNode::Labeled(result)
-        }
+    let span = peek_span(context);
+    let label = match consume_label(context) {
+        Some(label) => label,
         None => return Ok(None),
     };
-    Ok(Some(label))
+
+    let body = statement(context)?;
+
+    Ok(Some(StatementNode::Labeled {
+        label,
+        span,
+        statement: Box::new(body),
+    }))
+}
+
+fn case_statement<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<StatementNode<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::Case).is_none() {
+        return Ok(None);
+    }
+
+    let value = constant_expression(context)?;
+    let dots_span = peek_span(context);
+    if consume_punctuator(context, PunctuatorKind::TripleDots).is_some() {
+        require_gnu(context, "case range", dots_span);
+        let last = constant_expression(context)?;
+        expect_punctuator(context, PunctuatorKind::Colon)?;
+        let body = statement(context)?;
+        return Ok(Some(StatementNode::CaseRange {
+            first: Box::new(value),
+            last: Box::new(last),
+            span,
+            statement: Box::new(body),
+        }));
+    }
+    expect_punctuator(context, PunctuatorKind::Colon)?;
+
+    let body = statement(context)?;
+
+    Ok(Some(StatementNode::Case {
+        value: Box::new(value),
+        span,
+        statement: Box::new(body),
+    }))
+}
+
+fn default_statement<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<StatementNode<'b>>, String> {
+    let span = peek_span(context);
+    if consume_keyword(context, KeywordKind::_Default).is_none() {
+        return Ok(None);
+    }
+
+    expect_punctuator(context, PunctuatorKind::Colon)?;
+
+    let body = statement(context)?;
+
+    Ok(Some(StatementNode::Default(Box::new(body), span)))
 }
 
 fn while_statement<'a, 'b>(
@@ -167,13 +283,13 @@ fn do_while_statement<'a, 'b>(
         return Ok(None);
     }
 
-    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
-    let cond = expression(context)?;
-    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
-
     let body = statement(context)?;
 
     expect_keyword(context, KeywordKind::While)?;
+    expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
+    let cond = expression(context)?;
+    expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
 
     Ok(Some(StatementNode::DoWhile {
         condition: Box::new(cond),
@@ -188,58 +304,133 @@ fn for_statement<'a, 'b>(
         return Ok(None);
     }
 
+    push_scope(context);
+    let result = for_statement_body(context);
+    pop_scope(context);
+
+    Ok(Some(result?))
+}
+
+//parses after `for`
+fn for_statement_body<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<StatementNode<'b>, String> {
     expect_punctuator(context, PunctuatorKind::LeftRoundBracket)?;
-    let init = expression(context)?;
-    expect_punctuator(context, PunctuatorKind::Semicolon)?;
-    let cond = expression(context)?;
+    let init = if let Some(declaration) = declaration(context)? {
+        Some(Either::Left(declaration))
+    } else {
+        let init = optional_expression(context)?.map(Either::Right);
+        expect_punctuator(context, PunctuatorKind::Semicolon)?;
+        init
+    };
+    let cond = optional_expression(context)?;
     expect_punctuator(context, PunctuatorKind::Semicolon)?;
-    let after = expression(context)?;
+    let after = if peek_punctuator(context, PunctuatorKind::RightRoundBracket) {
+        None
+    } else {
+        Some(Box::new(expression(context)?))
+    };
     expect_punctuator(context, PunctuatorKind::RightRoundBracket)?;
 
     let body = statement(context)?;
 
-    Ok(Some(StatementNode::For {
-        initialization: Box::new(init),
-        condition: Box::new(cond),
-        afterthought: Box::new(after),
+    Ok(StatementNode::For {
+        initialization: init,
+        condition: cond,
+        afterthought: after,
         statement: Box::new(body),
-    }))
+    })
+}
+
+//an expression that may be omitted before `;`
+fn optional_expression<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+) -> Result<Option<Box<Expression<'b>>>, String> {
+    if peek_punctuator(context, PunctuatorKind::Semicolon) {
+        Ok(None)
+    } else {
+        Ok(Some(Box::new(expression(context)?)))
+    }
 }
 
-fn compound_statement<'a, 'b>(
+pub(super) fn compound_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
     if consume_punctuator(context, PunctuatorKind::LeftCurlyBracket).is_none() {
         return Ok(None);
     }
 
+    push_scope(context);
+    let result = block_items(context);
+    pop_scope(context);
+
+    Ok(Some(StatementNode::Compound(result?)))
+}
+
+fn block_items<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<Vec<BlockItem<'b>>, String> {
     let mut result = Vec::new();
     while consume_punctuator(context, PunctuatorKind::RightCurlyBracket).is_none() {
-        result.push(statement(context)?);
+        if peek_end(context) {
+            return Err(unexpected_token(context));
+        }
+
+        let started_at = remaining(context);
+        let item = match block_item(context) {
+            Ok(item) => item,
+            Err(message) => {
+                report_error(context, message, started_at);
+                synchronize(context, started_at, |_| false);
+                BlockItem::Statement(StatementNode::Error)
+            }
+        };
+        result.push(item);
     }
 
-    Ok(Some(StatementNode::Compound(result)))
+    Ok(result)
+}
+
+//a leading type specifier or typedef name decides for a declaration, so `T * x;` declares
+//a pointer when T names a type and multiplies otherwise
+fn block_item<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<BlockItem<'b>, String> {
+    let result = if let Some(assertion) = static_assert_declaration(context)? {
+        BlockItem::StaticAssert(assertion)
+    //attributes are declaration specifiers too, unless just a `;` follows them
+    } else if let Some(statement) = peek_keyword(context, KeywordKind::Attribute)
+        .then(|| speculate(context, attribute_statement))
+        .flatten()
+    {
+        BlockItem::Statement(statement)
+    } else if let Some(declaration) = declaration(context)? {
+        BlockItem::Declaration(declaration)
+    } else {
+        BlockItem::Statement(statement(context)?)
+    };
+
+    Ok(result)
 }
 
 fn return_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
+    let span = peek_span(context);
     if consume_keyword(context, KeywordKind::Return).is_none() {
         return Ok(None);
     }
 
-    let content = expression(context)?;
+    let content = optional_expression(context)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
 
-    Ok(Some(StatementNode::Return(Box::new(content))))
+    Ok(Some(StatementNode::Return(content, span)))
 }
 
 fn break_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
+    let span = peek_span(context);
     let result = match consume_keyword(context, KeywordKind::Break) {
         Some(_) => {
             expect_punctuator(context, PunctuatorKind::Semicolon)?;
-            Some(StatementNode::Break)
+            Some(StatementNode::Break(span))
         }
         None => None,
     };
@@ -249,10 +440,11 @@ fn break_statement<'a, 'b>(
 fn continue_statement<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<Option<StatementNode<'b>>, String> {
+    let span = peek_span(context);
     let result = match consume_keyword(context, KeywordKind::Continue) {
         Some(_) => {
             expect_punctuator(context, PunctuatorKind::Semicolon)?;
-            Some(StatementNode::Continue)
+            Some(StatementNode::Continue(span))
         }
         None => None,
     };
@@ -266,7 +458,220 @@ fn goto_statement<'a, 'b>(
         return Ok(None);
     }
 
+    let span = peek_span(context);
     let label = expect_identifier(context)?;
+    expect_punctuator(context, PunctuatorKind::Semicolon)?;
 
-    Ok(Some(StatementNode::Goto(label)))
+    Ok(Some(StatementNode::Goto(label, span)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::*;
+    use crate::options::*;
+    use crate::parser::external_declaration::*;
+    use crate::testing::*;
+
+    //the statements of the body of a function made of source, and the syntax errors
+    fn parsed(source: &str, check: impl FnOnce(&[&StatementNode], &[String])) {
+        let source = format!("void f(void) {{ {} }}", source);
+        let (ast, diagnostics) = parse_source(&source, Standard::Gnu11);
+        let errors: Vec<String> = diagnostics
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        let items = match ast.root.first() {
+            Some(ExternalDeclaration::FunctionDefinition(definition)) => match &definition.body {
+                StatementNode::Compound(items) => items,
+                _ => unreachable!(),
+            },
+            _ => panic!("no function definition: {:?}", errors),
+        };
+        let statements: Vec<&StatementNode> = items
+            .iter()
+            .filter_map(|item| match item {
+                BlockItem::Statement(statement) => Some(statement),
+                _ => None,
+            })
+            .collect();
+        check(&statements, &errors);
+    }
+
+    fn is_identifier(expression: &Expression, name: &str) -> bool {
+        matches!(expression.kind, ExpressionKind::Identifier(found) if found == name)
+    }
+
+    #[test]
+    fn null_and_expression_statements() {
+        parsed(";; x = 1; f();", |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            assert!(matches!(
+                statements[..2],
+                [StatementNode::Null, StatementNode::Null]
+            ));
+            assert!(matches!(statements[2], StatementNode::Expression(_)));
+            assert!(matches!(statements[3], StatementNode::Expression(_)));
+        });
+    }
+
+    #[test]
+    fn else_belongs_to_the_nearest_if() {
+        parsed("if (a) if (b) x; else y;", |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            match statements {
+                [StatementNode::If {
+                    statement,
+                    else_statement: None,
+                    ..
+                }] => assert!(matches!(
+                    **statement,
+                    StatementNode::If {
+                        else_statement: Some(_),
+                        ..
+                    }
+                )),
+                _ => panic!("{:?}", statements),
+            }
+        });
+    }
+
+    #[test]
+    fn switch_with_cases_and_default() {
+        let source = "switch (x) { case 1: case 2 ... 4: y; break; default: ; }";
+        parsed(source, |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            let items = match statements {
+                [StatementNode::Switch { statement, .. }] => match &**statement {
+                    StatementNode::Compound(items) => items,
+                    _ => panic!("{:?}", statement),
+                },
+                _ => panic!("{:?}", statements),
+            };
+            match &items[..] {
+                [BlockItem::Statement(StatementNode::Case { statement, .. }), BlockItem::Statement(StatementNode::Break(_)), BlockItem::Statement(StatementNode::Default(default, _))] =>
+                {
+                    assert!(matches!(**statement, StatementNode::CaseRange { .. }));
+                    assert!(matches!(**default, StatementNode::Null));
+                }
+                _ => panic!("{:?}", items),
+            }
+        });
+    }
+
+    #[test]
+    fn labels_are_told_from_expressions_starting_with_a_name() {
+        parsed("a: x = 1; a; b : ; goto a;", |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            match statements {
+                [StatementNode::Labeled {
+                    label: "a",
+                    statement,
+                    ..
+                }, StatementNode::Expression(expression), StatementNode::Labeled { label: "b", .. }, StatementNode::Goto("a", _)] =>
+                {
+                    assert!(matches!(**statement, StatementNode::Expression(_)));
+                    assert!(is_identifier(expression, "a"));
+                }
+                _ => panic!("{:?}", statements),
+            }
+        });
+    }
+
+    #[test]
+    fn loops() {
+        let source = "while (a) ; do x; while (b); for (int i = 0; i; ) continue; for (;;) break;";
+        parsed(source, |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            match statements {
+                [StatementNode::While { .. }, StatementNode::DoWhile {
+                    condition,
+                    statement,
+                }, StatementNode::For {
+                    initialization: Some(Either::Left(_)),
+                    condition: Some(_),
+                    afterthought: None,
+                    statement: first,
+                }, StatementNode::For {
+                    initialization: None,
+                    condition: None,
+                    afterthought: None,
+                    statement: second,
+                }] => {
+                    assert!(is_identifier(condition, "b"));
+                    assert!(matches!(**statement, StatementNode::Expression(_)));
+                    assert!(matches!(**first, StatementNode::Continue(_)));
+                    assert!(matches!(**second, StatementNode::Break(_)));
+                }
+                _ => panic!("{:?}", statements),
+            }
+        });
+        parsed("do x; while (b)", |_, errors| {
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+        });
+    }
+
+    #[test]
+    fn return_with_and_without_a_value() {
+        parsed("return; return x;", |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            assert!(matches!(
+                statements,
+                [
+                    StatementNode::Return(None, _),
+                    StatementNode::Return(Some(_), _)
+                ]
+            ));
+        });
+        parsed("return x", |_, errors| {
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+        });
+    }
+
+    #[test]
+    fn compound_attribute_and_asm_statements() {
+        let source = "{ int y; y; } __attribute__((fallthrough)); asm(\"nop\");";
+        parsed(source, |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            match statements {
+                [StatementNode::Compound(items), StatementNode::Attribute(_), StatementNode::Asm(asm)] =>
+                {
+                    assert!(matches!(
+                        items[..],
+                        [BlockItem::Declaration(_), BlockItem::Statement(_)]
+                    ));
+                    assert_eq!(asm.template, ["nop"]);
+                }
+                _ => panic!("{:?}", statements),
+            }
+        });
+    }
+
+    //the declarations are left out of the statements, so of `T * x;` only a multiplication
+    //is seen, and that only where T names an object
+    #[test]
+    fn typedef_names_start_declarations() {
+        let source = "typedef int T; T * x; { int T; T * y; } T * z; T (w);";
+        parsed(source, |statements, errors| {
+            assert!(errors.is_empty(), "{:?}", errors);
+            let items = match statements {
+                [StatementNode::Compound(items)] => items,
+                _ => panic!("{:?}", statements),
+            };
+            match &items[..] {
+                [BlockItem::Declaration(_), BlockItem::Statement(StatementNode::Expression(expression))] =>
+                {
+                    assert!(matches!(
+                        expression.kind,
+                        ExpressionKind::BinaryOperator {
+                            kind: BinaryOperatorKind::Mul,
+                            ..
+                        }
+                    ))
+                }
+                _ => panic!("{:?}", items),
+            }
+        });
+    }
 }
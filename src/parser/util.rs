@@ -1,67 +1,451 @@
+use crate::diagnostic::*;
+use crate::options::*;
 use crate::tokenizer::*;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrdinaryIdentifierKind {
+    TypedefName,
+    EnumerationConstant,
+    Other, // objects and functions
+}
+
+//a token the parser would have accepted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+    Punctuator(PunctuatorKind),
+    Keyword(KeywordKind),
+    Identifier,
+    Literal,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expected::Punctuator(kind) => write!(f, "'{}'", kind.spelling()),
+            Expected::Keyword(kind) => write!(f, "'{}'", kind.spelling()),
+            Expected::Identifier => write!(f, "identifier"),
+            Expected::Literal => write!(f, "literal"),
+        }
+    }
+}
 
 pub struct ParseContext<'a> {
+    standard: Standard,
     head: &'a [Token<'a>],
+    previous: Option<&'a Token<'a>>, // the last consumed token
+    scopes: Vec<HashMap<&'a str, OrdinaryIdentifierKind>>,
+    //the changes to scopes since the outermost checkpoint still pending, empty without one
+    undo: Vec<Undo<'a>>,
+    checkpoints: usize,
+    //every token tried and rejected at the token expected_at tokens before the end
+    expected: Vec<Expected>,
+    expected_at: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+//a change to the scopes, with what it takes to revert it
+enum Undo<'a> {
+    Declare(&'a str, Option<OrdinaryIdentifierKind>), // the kind the name had before
+    PushScope,
+    PopScope(HashMap<&'a str, OrdinaryIdentifierKind>),
+}
+
+//the parser state to go back to when a speculative parse does not work out. the scopes are
+//restored by undoing the changes logged since, so taking one does not copy them.
+pub struct Checkpoint<'a> {
+    head: &'a [Token<'a>],
+    previous: Option<&'a Token<'a>>,
+    scope_depth: usize,
+    undo_length: usize,
+    expected: Vec<Expected>,
+    expected_at: usize,
+    diagnostic_count: usize,
 }
 
 impl ParseContext<'_> {
-    pub fn new<'a>(tokens: &'a [Token]) -> ParseContext<'a> {
-        ParseContext::<'a> { head: tokens }
+    pub fn new<'a>(tokens: &'a [Token], standard: Standard) -> ParseContext<'a> {
+        ParseContext::<'a> {
+            standard,
+            head: tokens,
+            previous: None,
+            scopes: vec![HashMap::new()],
+            undo: Vec::new(),
+            checkpoints: 0,
+            expected: Vec::new(),
+            expected_at: tokens.len(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+pub fn standard(context: &ParseContext) -> Standard {
+    context.standard
+}
+
+//a checkpoint is either rewound to or committed
+pub fn checkpoint<'a, 'b>(context: &'a mut ParseContext<'b>) -> Checkpoint<'b> {
+    context.checkpoints += 1;
+    Checkpoint {
+        head: context.head,
+        previous: context.previous,
+        scope_depth: context.scopes.len(),
+        undo_length: context.undo.len(),
+        expected: context.expected.clone(),
+        expected_at: context.expected_at,
+        diagnostic_count: context.diagnostics.len(),
+    }
+}
+
+pub fn rewind<'a, 'b>(context: &'a mut ParseContext<'b>, checkpoint: Checkpoint<'b>) {
+    context.head = checkpoint.head;
+    context.previous = checkpoint.previous;
+    while context.undo.len() > checkpoint.undo_length {
+        match context.undo.pop().unwrap() {
+            Undo::Declare(identifier, Some(kind)) => {
+                context.scopes.last_mut().unwrap().insert(identifier, kind);
+            }
+            Undo::Declare(identifier, None) => {
+                context.scopes.last_mut().unwrap().remove(identifier);
+            }
+            Undo::PushScope => {
+                context.scopes.pop();
+            }
+            Undo::PopScope(scope) => context.scopes.push(scope),
+        }
+    }
+    assert_eq!(context.scopes.len(), checkpoint.scope_depth);
+    context.expected = checkpoint.expected;
+    context.expected_at = checkpoint.expected_at;
+    context.diagnostics.truncate(checkpoint.diagnostic_count);
+    release(context);
+}
+
+//keeps what was parsed since the checkpoint
+pub fn commit<'a, 'b>(context: &'a mut ParseContext<'b>, checkpoint: Checkpoint<'b>) {
+    drop(checkpoint);
+    release(context);
+}
+
+fn release(context: &mut ParseContext) {
+    context.checkpoints -= 1;
+    if context.checkpoints == 0 {
+        context.undo.clear();
+    }
+}
+
+//logs a change to the scopes if a checkpoint may have to undo it
+fn log<'a, 'b>(context: &'a mut ParseContext<'b>, undo: Undo<'b>) {
+    if context.checkpoints > 0 {
+        context.undo.push(undo);
+    }
+}
+
+//parse functions fail at the token they could not accept, so that is where the error is.
+//the construct which failed started with started_at tokens remaining.
+pub fn report_error(context: &mut ParseContext, message: String, started_at: usize) {
+    let mut diagnostic = Diagnostic::error(message, peek_span(context));
+    //a terminator which would have been accepted here was most likely forgotten after the
+    //last token, unless the construct is yet to begin or that token is the terminator
+    let terminator = [
+        PunctuatorKind::Semicolon,
+        PunctuatorKind::RightRoundBracket,
+        PunctuatorKind::RightSquareBracket,
+    ]
+    .iter()
+    .find(|kind| expected_here(context).contains(&Expected::Punctuator(**kind)));
+    if let (Some(previous), Some(terminator)) = (context.previous, terminator) {
+        let repeated = matches!(previous.kind, TokenKind::Punctuator(kind) if kind == *terminator);
+        if remaining(context) < started_at && !repeated {
+            diagnostic.fixits.push(FixIt {
+                span: Span {
+                    begin: previous.span.end,
+                    end: previous.span.end,
+                },
+                replacement: terminator.spelling().to_string(),
+            });
+        }
+    }
+    context.diagnostics.push(diagnostic);
+}
+
+pub fn report(context: &mut ParseContext, diagnostic: Diagnostic) {
+    context.diagnostics.push(diagnostic);
+}
+
+//GNU extensions are accepted only under -std=gnu11
+pub fn require_gnu(context: &mut ParseContext, extension: &str, span: Span) {
+    if context.standard != Standard::Gnu11 {
+        let message = format!("{} is a GNU extension, use -std=gnu11.", extension);
+        report(context, Diagnostic::error(message, span));
+    }
+}
+
+//records that the next token was tried as the given one, to be listed when it is rejected.
+//the keywords of GNU extensions are listed only under -std=gnu11.
+pub fn expect_here(context: &mut ParseContext, expected: Expected) {
+    if context.expected_at != remaining(context) {
+        context.expected.clear();
+        context.expected_at = remaining(context);
+    }
+    let is_gnu = matches!(expected, Expected::Keyword(kind) if kind.is_gnu_extension());
+    if is_gnu && context.standard != Standard::Gnu11 {
+        return;
+    }
+    if !context.expected.contains(&expected) {
+        context.expected.push(expected);
+    }
+}
+
+fn expected_here<'a, 'b>(context: &'a ParseContext<'b>) -> &'a [Expected] {
+    if context.expected_at == remaining(context) {
+        &context.expected
+    } else {
+        &[]
+    }
+}
+
+//"expected one of ';' or ',' but found 'int'." with the tokens tried at the next token
+pub fn unexpected_token(context: &ParseContext) -> String {
+    let expected: Vec<String> = expected_here(context)
+        .iter()
+        .map(|expected| expected.to_string())
+        .collect();
+    let found = peek_token(context);
+    match expected.as_slice() {
+        [] => format!("unexpected {}.", found),
+        [expected] => format!("expected {} but found {}.", expected, found),
+        [first, second] => format!("expected {} or {} but found {}.", first, second, found),
+        [init @ .., last] => format!(
+            "expected one of {} or {} but found {}.",
+            init.join(", "),
+            last,
+            found
+        ),
+    }
+}
+
+//"expected declarator but found '+'." for a construct which is not a single token
+pub fn expected(context: &ParseContext, what: &str) -> String {
+    format!("expected {} but found {}.", what, peek_token(context))
+}
+
+//the number of tokens left, which tells whether a parse made progress
+pub fn remaining(context: &ParseContext) -> usize {
+    context.head.len()
+}
+
+//panic-mode recovery: skips the rest of a broken construct, that is up to and including
+//`;` or a whole `{ ... }`, and stops before a `}` closing an enclosing block or before a
+//token for which stop_before holds. at least one token is skipped if the construct which
+//started with `started_at` tokens remaining has consumed nothing.
+pub fn synchronize(
+    context: &mut ParseContext,
+    started_at: usize,
+    stop_before: fn(&ParseContext) -> bool,
+) {
+    let mut depth = 0;
+    loop {
+        let progressed = remaining(context) < started_at;
+        match peek_token(context) {
+            TokenKind::End => return,
+            TokenKind::Punctuator(PunctuatorKind::Semicolon) if depth == 0 => {
+                consume_token(context);
+                return;
+            }
+            TokenKind::Punctuator(PunctuatorKind::LeftCurlyBracket) => {
+                depth += 1;
+                consume_token(context);
+            }
+            TokenKind::Punctuator(PunctuatorKind::RightCurlyBracket) if depth == 0 => {
+                if !progressed {
+                    consume_token(context);
+                }
+                return;
+            }
+            TokenKind::Punctuator(PunctuatorKind::RightCurlyBracket) => {
+                depth -= 1;
+                consume_token(context);
+                if depth == 0 {
+                    return;
+                }
+            }
+            _ if depth == 0 && progressed && stop_before(context) => return,
+            _ => consume_token(context),
+        }
+    }
+}
+
+//runs the parser and rewinds the context unless it succeeds with a result
+pub fn speculate<'a, 'b, T, F>(context: &'a mut ParseContext<'b>, parser: F) -> Option<T>
+where
+    F: FnOnce(&mut ParseContext<'b>) -> Result<Option<T>, String>,
+{
+    let checkpoint = checkpoint(context);
+    match parser(context) {
+        Ok(Some(result)) => {
+            commit(context, checkpoint);
+            Some(result)
+        }
+        _ => {
+            rewind(context, checkpoint);
+            None
+        }
+    }
+}
+
+pub fn push_scope(context: &mut ParseContext) {
+    context.scopes.push(HashMap::new());
+    log(context, Undo::PushScope);
+}
+
+pub fn pop_scope(context: &mut ParseContext) {
+    assert!(context.scopes.len() > 1);
+    let scope = context.scopes.pop().unwrap();
+    log(context, Undo::PopScope(scope));
+}
+
+pub fn declare_identifier<'a, 'b>(
+    context: &'a mut ParseContext<'b>,
+    identifier: &'b str,
+    kind: OrdinaryIdentifierKind,
+) {
+    let previous = context.scopes.last_mut().unwrap().insert(identifier, kind);
+    log(context, Undo::Declare(identifier, previous));
+}
+
+//the innermost declaration wins, so an ordinary identifier hides an outer typedef name
+pub fn is_typedef_name(context: &ParseContext, identifier: &str) -> bool {
+    context
+        .scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.get(identifier))
+        .is_some_and(|kind| *kind == OrdinaryIdentifierKind::TypedefName)
+}
+
+pub fn peek_token<'a, 'b>(context: &'a ParseContext<'b>) -> &'b TokenKind<'b> {
+    peek_nth(context, 0)
+}
+
+//looks n tokens ahead, and sees TokenKind::End past the end of the tokens
+pub fn peek_nth<'a, 'b>(context: &'a ParseContext<'b>, n: usize) -> &'b TokenKind<'b> {
+    assert!(!context.head.is_empty());
+    let head = context.head;
+    &head.get(n).unwrap_or(&head[head.len() - 1]).kind
+}
+
+pub fn peek_span(context: &ParseContext) -> Span {
+    assert!(!context.head.is_empty());
+    context.head[0].span
+}
+
+//the span of the last consumed token
+pub fn previous_span(context: &ParseContext) -> Option<Span> {
+    context.previous.map(|previous| previous.span)
+}
+
+//from the beginning of begin to the end of the last consumed token
+pub fn span_since(context: &ParseContext, begin: Span) -> Span {
+    match context.previous {
+        Some(previous) => begin.to(previous.span),
+        None => begin,
+    }
+}
+
+pub fn peek_punctuator(context: &mut ParseContext, target: PunctuatorKind) -> bool {
+    let found = matches!(peek_token(context), TokenKind::Punctuator(kind) if *kind == target);
+    if !found {
+        expect_here(context, Expected::Punctuator(target));
+    }
+    found
+}
+
+pub fn peek_keyword(context: &mut ParseContext, target: KeywordKind) -> bool {
+    let found = matches!(peek_token(context), TokenKind::Keyword(kind) if *kind == target);
+    if !found {
+        expect_here(context, Expected::Keyword(target));
     }
+    found
+}
+
+pub fn peek_end(context: &ParseContext) -> bool {
+    matches!(peek_token(context), TokenKind::End)
 }
 
 pub fn consume_token(context: &mut ParseContext) {
     assert!(!context.head.is_empty());
+    context.previous = Some(&context.head[0]);
     context.head = &context.head[1..];
 }
 
 pub fn consume_punctuator(context: &mut ParseContext, target: PunctuatorKind) -> Option<()> {
     assert!(!context.head.is_empty());
-    if let Token::Punctuator(kind) = &context.head[0] {
+    if let TokenKind::Punctuator(kind) = peek_token(context) {
         if target == *kind {
             consume_token(context);
             return Some(());
         }
     }
+    expect_here(context, Expected::Punctuator(target));
     None
 }
 
 pub fn expect_punctuator(context: &mut ParseContext, target: PunctuatorKind) -> Result<(), String> {
-    consume_punctuator(context, target)
-        .ok_or_else(|| format!("expected {:?} but not found.", target))
+    consume_punctuator(context, target).ok_or_else(|| unexpected_token(context))
 }
 
 pub fn consume_keyword(context: &mut ParseContext, target: KeywordKind) -> Option<()> {
     assert!(!context.head.is_empty());
-    if let Token::Keyword(kind) = &context.head[0] {
+    if let TokenKind::Keyword(kind) = peek_token(context) {
         if target == *kind {
             consume_token(context);
             return Some(());
         }
     }
+    expect_here(context, Expected::Keyword(target));
     None
 }
 
 pub fn expect_keyword(context: &mut ParseContext, target: KeywordKind) -> Result<(), String> {
-    consume_keyword(context, target).ok_or_else(|| format!("expected {:?} but not found.", target))
+    consume_keyword(context, target).ok_or_else(|| unexpected_token(context))
 }
 
 pub fn consume_identifier<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b str> {
     assert!(!context.head.is_empty());
-    if let Token::Identifier(content) = context.head[0] {
+    if let TokenKind::Identifier(content) = peek_token(context) {
+        consume_token(context);
         Some(content)
     } else {
+        expect_here(context, Expected::Identifier);
         None
     }
 }
 
 pub fn expect_identifier<'a, 'b>(context: &'a mut ParseContext<'b>) -> Result<&'b str, String> {
-    consume_identifier(context).ok_or_else(|| "expected identifier but not found.".to_string())
+    consume_identifier(context).ok_or_else(|| unexpected_token(context))
 }
 
-pub fn consume_literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b Literal<'b>> {
-    match &context.head[0] {
-        Token::Literal(content) => {
+//an identifier followed by `:` starts a labeled statement
+pub fn consume_label<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b str> {
+    match (peek_nth(context, 0), peek_nth(context, 1)) {
+        (TokenKind::Identifier(label), TokenKind::Punctuator(PunctuatorKind::Colon)) => {
+            consume_token(context);
+            consume_token(context);
+            Some(label)
+        }
+        _ => None,
+    }
+}
+
+pub fn consume_typedef_name<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b str> {
+    match peek_token(context) {
+        TokenKind::Identifier(content) if is_typedef_name(context, content) => {
             consume_token(context);
             Some(content)
         }
@@ -69,8 +453,65 @@ pub fn consume_literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b
     }
 }
 
+pub fn consume_literal<'a, 'b>(context: &'a mut ParseContext<'b>) -> Option<&'b Literal<'b>> {
+    match peek_token(context) {
+        TokenKind::Literal(content) => {
+            consume_token(context);
+            Some(content)
+        }
+        _ => {
+            expect_here(context, Expected::Literal);
+            None
+        }
+    }
+}
+
 pub fn expect_literal<'a, 'b>(
     context: &'a mut ParseContext<'b>,
 ) -> Result<&'b Literal<'b>, String> {
-    consume_literal(context).ok_or_else(|| "expected literal but not found.".to_string())
+    consume_literal(context).ok_or_else(|| unexpected_token(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewinding_undoes_the_changes_to_the_scopes() {
+        let (tokens, _) = tokenize("x", Standard::C11).unwrap();
+        let mut context = ParseContext::new(&tokens, Standard::C11);
+        declare_identifier(&mut context, "T", OrdinaryIdentifierKind::TypedefName);
+        let result: Option<()> = speculate(&mut context, |context| {
+            declare_identifier(context, "T", OrdinaryIdentifierKind::Other);
+            push_scope(context);
+            declare_identifier(context, "U", OrdinaryIdentifierKind::TypedefName);
+            push_scope(context);
+            pop_scope(context);
+            consume_token(context);
+            Ok(None)
+        });
+        assert!(result.is_none());
+        assert!(is_typedef_name(&context, "T"));
+        assert!(!is_typedef_name(&context, "U"));
+        assert_eq!(context.scopes.len(), 1);
+        assert_eq!(remaining(&context), tokens.len());
+        assert!(context.undo.is_empty());
+    }
+
+    #[test]
+    fn committing_keeps_the_changes() {
+        let (tokens, _) = tokenize("x", Standard::C11).unwrap();
+        let mut context = ParseContext::new(&tokens, Standard::C11);
+        let result = speculate(&mut context, |context| {
+            declare_identifier(context, "T", OrdinaryIdentifierKind::TypedefName);
+            let inner: Option<()> = speculate(context, |context| {
+                declare_identifier(context, "T", OrdinaryIdentifierKind::Other);
+                Ok(None)
+            });
+            Ok(Some(inner))
+        });
+        assert_eq!(result, Some(None));
+        assert!(is_typedef_name(&context, "T"));
+        assert!(context.undo.is_empty());
+    }
 }
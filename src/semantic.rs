@@ -0,0 +1,48 @@
+pub mod constant;
+pub mod control_flow;
+pub mod conversion;
+pub mod declaration;
+pub mod expression;
+pub mod external_declaration;
+pub mod initializer;
+pub mod layout;
+pub mod statement;
+pub mod symbol;
+pub mod types;
+mod util;
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::parser::AST;
+use crate::semantic::external_declaration::*;
+use crate::semantic::layout::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+
+//the syntax tree with every declaration resolved to a symbol and every expression
+//annotated with its type and value category
+#[derive(Debug)]
+pub struct TypedAST<'a> {
+    pub root: Vec<TypedExternalDeclaration<'a>>,
+    pub types: TypeArena,
+    pub symbols: SymbolTable,
+}
+
+//a broken declaration or statement is reported and dropped, and analysis goes on with
+//the rest
+pub fn analyze<'a>(
+    ast: &AST<'a>,
+    pragmas: &[Pragma],
+    options: &Options,
+) -> (TypedAST<'a>, Vec<Diagnostic>) {
+    let mut context = SemanticContext::new(options.standard);
+    pragma_pack(&mut context, pragmas);
+    let root = translation_unit(&mut context, &ast.root);
+    let typed = TypedAST {
+        root,
+        types: context.types,
+        symbols: context.symbols,
+    };
+    (typed, context.diagnostics)
+}
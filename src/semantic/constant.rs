@@ -0,0 +1,526 @@
+use crate::parser::declaration::*;
+use crate::parser::expression::*;
+use crate::semantic::expression::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use std::convert::TryFrom;
+
+//what folding looks at, which the analysis has and so does the typed tree after it
+pub trait ConstantContext {
+    fn types(&self) -> &TypeArena;
+    fn symbols(&self) -> &SymbolTable;
+}
+
+impl ConstantContext for SemanticContext {
+    fn types(&self) -> &TypeArena {
+        &self.types
+    }
+
+    fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+}
+
+//the value of a constant expression. an integer is held with the value it has in its type,
+//and a pointer converted from an integer is an integer too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constant<'a> {
+    Integer(i128),
+    Floating(f64),
+    Address(Address<'a>),
+}
+
+//an address constant, which static initializers may contain, is the address of an object
+//with static storage duration or of a function, plus an offset in bytes. the object may be
+//a string literal or a compound literal at file scope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Address<'a> {
+    pub base: AddressBase<'a>,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddressBase<'a> {
+    Symbol(SymbolId),
+    String(&'a str),
+    CompoundLiteral(CompoundLiteralId),
+}
+
+//folds a constant expression of the typed tree with the integer semantics of the target.
+//None if it is not a constant expression, and an error if it is one whose evaluation is
+//undefined, such as a division by zero or a signed overflow.
+pub fn evaluate<'a>(
+    context: &impl ConstantContext,
+    expression: &TypedExpression<'a>,
+) -> SemanticResult<Option<Constant<'a>>> {
+    let ty = expression.ty;
+    let span = expression.span;
+    let result = match &expression.kind {
+        TypedExpressionKind::Integer(value) => Constant::Integer(*value),
+        TypedExpressionKind::Identifier(id) => match context.symbols().get(*id).kind {
+            SymbolKind::EnumerationConstant(value) => Constant::Integer(i128::from(value)),
+            _ => return Ok(None),
+        },
+        TypedExpressionKind::Unary { kind, operand } => {
+            if *kind == UnaryKind::Reference {
+                return address(context, operand);
+            }
+            let operand = match evaluate(context, operand)? {
+                Some(operand) => operand,
+                None => return Ok(None),
+            };
+            match (kind, operand) {
+                (UnaryKind::LogicalNot, operand) => {
+                    Constant::Integer(i128::from(!is_true(operand)))
+                }
+                (UnaryKind::BitwiseNot, Constant::Integer(value)) => {
+                    Constant::Integer(wrap(context, !value, ty))
+                }
+                (UnaryKind::Plus, operand) => operand,
+                (UnaryKind::Minus, Constant::Integer(value)) => {
+                    integer(context, value.checked_neg(), ty, span)?
+                }
+                (UnaryKind::Minus, Constant::Floating(value)) => Constant::Floating(-value),
+                _ => return Ok(None),
+            }
+        }
+        TypedExpressionKind::Binary { kind, lhs, rhs } => {
+            return binary(context, *kind, lhs, rhs, ty, span);
+        }
+        TypedExpressionKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => {
+            let value = match evaluate(context, condition)? {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            match (is_true(value), then) {
+                (true, Some(then)) => return evaluate(context, then),
+                (true, None) => return convert(context, value, ty, span),
+                (false, _) => return evaluate(context, otherwise),
+            }
+        }
+        //an array or a function used as a value is converted to its address
+        TypedExpressionKind::Cast { operand, .. }
+            if context.types().is_array(operand.ty) || context.types().is_function(operand.ty) =>
+        {
+            return address(context, operand);
+        }
+        TypedExpressionKind::Cast { operand, .. } => {
+            let value = match evaluate(context, operand)? {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            return convert(context, value, ty, span);
+        }
+        TypedExpressionKind::Sizeof(ty) => match context.types().size_of(*ty) {
+            Some(size) => Constant::Integer(i128::from(size)),
+            None => return Ok(None),
+        },
+        TypedExpressionKind::Alignof(ty) => match context.types().align_of(*ty) {
+            Some(align) => Constant::Integer(i128::from(align)),
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(result))
+}
+
+//the value of an integer constant expression, which is an error if it is not one
+pub fn integer_value(
+    context: &impl ConstantContext,
+    expression: &TypedExpression,
+) -> SemanticResult<i128> {
+    if !context.types().is_integer(expression.ty) {
+        let message = format!(
+            "integer constant expression has type '{}'.",
+            context.types().display(expression.ty)
+        );
+        return error(message, expression.span);
+    }
+    match evaluate(context, expression)? {
+        Some(Constant::Integer(value)) => Ok(value),
+        _ => error(
+            "expression is not an integer constant expression.".to_string(),
+            expression.span,
+        ),
+    }
+}
+
+//the address of an lvalue or function designator, if it is an address constant
+fn address<'a>(
+    context: &impl ConstantContext,
+    expression: &TypedExpression<'a>,
+) -> SemanticResult<Option<Constant<'a>>> {
+    let base = match &expression.kind {
+        TypedExpressionKind::Identifier(id) if has_static_storage(context.symbols().get(*id)) => {
+            AddressBase::Symbol(*id)
+        }
+        TypedExpressionKind::String(content) => AddressBase::String(content),
+        TypedExpressionKind::CompoundLiteral { id: Some(id), .. } => {
+            AddressBase::CompoundLiteral(*id)
+        }
+        TypedExpressionKind::Unary {
+            kind: UnaryKind::Dereference,
+            operand,
+        } => return evaluate(context, operand),
+        //the address of a member of an object at a null pointer is the offset of the
+        //member, which is how offsetof used to be written
+        TypedExpressionKind::Member { operand, index } => {
+            let types = context.types();
+            let record = types.record(types.record_of(operand.ty).unwrap());
+            let member = &record.members.as_ref().unwrap()[*index];
+            if member.bit_width.is_some() {
+                return Ok(None);
+            }
+            let offset = member.offset;
+            return match address(context, operand)? {
+                Some(Constant::Address(address)) => Ok(Some(Constant::Address(Address {
+                    offset: address.offset + offset as i64,
+                    ..address
+                }))),
+                Some(Constant::Integer(value)) => {
+                    Ok(Some(Constant::Integer(value + i128::from(offset))))
+                }
+                _ => Ok(None),
+            };
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Constant::Address(Address { base, offset: 0 })))
+}
+
+//objects declared at file scope or with static, and functions
+pub fn has_static_storage(symbol: &Symbol) -> bool {
+    match symbol.kind {
+        SymbolKind::Function => true,
+        SymbolKind::Object => {
+            symbol.linkage != Linkage::None
+                || matches!(symbol.storage, Some(StorageClassSpecifier::Static))
+        }
+        _ => false,
+    }
+}
+
+pub fn is_true(value: Constant) -> bool {
+    match value {
+        Constant::Integer(value) => value != 0,
+        Constant::Floating(value) => value != 0.0,
+        Constant::Address(_) => true,
+    }
+}
+
+//the operands are already converted to the types the operator works on
+fn binary<'a>(
+    context: &impl ConstantContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression<'a>,
+    rhs: &TypedExpression<'a>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<Option<Constant<'a>>> {
+    use BinaryOperatorKind::*;
+    let lhs_value = match evaluate(context, lhs)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    //the right operand of && and || is not evaluated if the left one decides
+    match kind {
+        LogicalAnd if !is_true(lhs_value) => return Ok(Some(Constant::Integer(0))),
+        LogicalOr if is_true(lhs_value) => return Ok(Some(Constant::Integer(1))),
+        _ => {}
+    }
+    let rhs_value = match evaluate(context, rhs)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let boolean = |value: bool| Ok(Some(Constant::Integer(i128::from(value))));
+
+    let result = match (lhs_value, rhs_value) {
+        (_, rhs_value) if matches!(kind, LogicalAnd | LogicalOr) => {
+            return boolean(is_true(rhs_value));
+        }
+        (Constant::Integer(a), Constant::Integer(b)) => match kind {
+            Add => integer(context, a.checked_add(b), ty, span)?,
+            Sub => integer(context, a.checked_sub(b), ty, span)?,
+            Mul => integer(context, a.checked_mul(b), ty, span)?,
+            Div | Mod if b == 0 => {
+                return error("division by zero in constant expression.".to_string(), span);
+            }
+            Div => integer(context, a.checked_div(b), ty, span)?,
+            Mod => integer(context, a.checked_rem(b), ty, span)?,
+            LeftShift | RightShift => shift(context, kind, a, b, ty, span)?,
+            BitwiseAnd => Constant::Integer(a & b),
+            BitwiseXor => Constant::Integer(a ^ b),
+            BitwiseOr => Constant::Integer(a | b),
+            Equal => return boolean(a == b),
+            NotEqual => return boolean(a != b),
+            LessThan => return boolean(a < b),
+            LessThanEqual => return boolean(a <= b),
+            _ => return Ok(None),
+        },
+        (Constant::Floating(a), Constant::Floating(b)) => match kind {
+            Add => floating(context, a + b, ty),
+            Sub => floating(context, a - b, ty),
+            Mul => floating(context, a * b, ty),
+            Div => floating(context, a / b, ty),
+            Equal => return boolean(a == b),
+            NotEqual => return boolean(a != b),
+            LessThan => return boolean(a < b),
+            LessThanEqual => return boolean(a <= b),
+            _ => return Ok(None),
+        },
+        //pointer arithmetic moves an address by a multiple of the size of the pointed type
+        (Constant::Address(address), Constant::Integer(offset))
+        | (Constant::Integer(offset), Constant::Address(address))
+            if matches!(kind, Add | Sub) =>
+        {
+            if kind == Sub && matches!(lhs_value, Constant::Integer(_)) {
+                return Ok(None);
+            }
+            let size = match context.types().pointee_size(ty).map(i64::try_from) {
+                Some(Ok(size)) => size,
+                _ => return Ok(None),
+            };
+            let offset = i64::try_from(offset)
+                .ok()
+                .and_then(|offset| offset.checked_mul(size));
+            let offset = match (kind, offset) {
+                (Add, Some(offset)) => address.offset.checked_add(offset),
+                (_, Some(offset)) => address.offset.checked_sub(offset),
+                (_, None) => None,
+            };
+            match offset {
+                Some(offset) => Constant::Address(Address {
+                    base: address.base,
+                    offset,
+                }),
+                None => return overflow(context, ty, span),
+            }
+        }
+        (Constant::Address(a), Constant::Address(b)) if a.base == b.base => match kind {
+            Sub => match context.types().pointee_size(lhs.ty) {
+                Some(size) => Constant::Integer(i128::from(a.offset - b.offset) / i128::from(size)),
+                None => return Ok(None),
+            },
+            Equal => return boolean(a.offset == b.offset),
+            NotEqual => return boolean(a.offset != b.offset),
+            LessThan => return boolean(a.offset < b.offset),
+            LessThanEqual => return boolean(a.offset <= b.offset),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(result))
+}
+
+//a shift by a negative count or by the width of the type or more is undefined, and so is
+//a left shift of a negative value
+fn shift<'a>(
+    context: &impl ConstantContext,
+    kind: BinaryOperatorKind,
+    value: i128,
+    count: i128,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<Constant<'a>> {
+    let width = context.types().integer_kind(ty).unwrap().width();
+    if count < 0 {
+        return error(
+            "shift count is negative in constant expression.".to_string(),
+            span,
+        );
+    }
+    if count >= i128::from(width) {
+        let message = format!(
+            "shift count >= width of type '{}' in constant expression.",
+            context.types().display(ty)
+        );
+        return error(message, span);
+    }
+    if kind == BinaryOperatorKind::RightShift {
+        return Ok(Constant::Integer(value >> count));
+    }
+    if value < 0 {
+        return error(
+            "left shift of negative value in constant expression.".to_string(),
+            span,
+        );
+    }
+    integer(context, Some(value << count), ty, span)
+}
+
+//an unsigned result wraps around, a signed one which does not fit is an overflow
+fn integer<'a>(
+    context: &impl ConstantContext,
+    value: Option<i128>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<Constant<'a>> {
+    let kind = context.types().integer_kind(ty).unwrap();
+    match value {
+        Some(value) if !kind.is_signed() => Ok(Constant::Integer(wrap(context, value, ty))),
+        Some(value) if kind.min_value() <= value && value <= kind.max_value() => {
+            Ok(Constant::Integer(value))
+        }
+        _ => overflow(context, ty, span),
+    }
+}
+
+fn overflow<T>(context: &impl ConstantContext, ty: TypeId, span: Span) -> SemanticResult<T> {
+    let message = format!(
+        "overflow in constant expression of type '{}'.",
+        context.types().display(ty)
+    );
+    error(message, span)
+}
+
+//the value of the bits of value which fit in the integer type ty, as the conversion of an
+//integer to an integer type does. a pointer is held as an unsigned long.
+pub fn wrap(context: &impl ConstantContext, value: i128, ty: TypeId) -> i128 {
+    let kind = context
+        .types()
+        .integer_kind(ty)
+        .unwrap_or(IntegerKind::UnsignedLong);
+    if kind == IntegerKind::Bool {
+        return i128::from(value != 0);
+    }
+    let modulus = 1i128 << kind.width();
+    let value = value.rem_euclid(modulus);
+    if value > kind.max_value() {
+        value - modulus
+    } else {
+        value
+    }
+}
+
+fn floating<'a>(context: &impl ConstantContext, value: f64, ty: TypeId) -> Constant<'a> {
+    match context.types().kind(ty) {
+        TypeKind::Floating(FloatingKind::Float) => Constant::Floating(f64::from(value as f32)),
+        _ => Constant::Floating(value),
+    }
+}
+
+//the conversion of a constant to the type to
+fn convert<'a>(
+    context: &impl ConstantContext,
+    value: Constant<'a>,
+    to: TypeId,
+    span: Span,
+) -> SemanticResult<Option<Constant<'a>>> {
+    let types = context.types();
+    let result = match (value, types.kind(to)) {
+        (_, TypeKind::Void) => return Ok(None),
+        (value, TypeKind::Integer(IntegerKind::Bool)) => {
+            Constant::Integer(i128::from(is_true(value)))
+        }
+        (Constant::Integer(value), TypeKind::Integer(_) | TypeKind::Enum(_)) => {
+            Constant::Integer(wrap(context, value, to))
+        }
+        (Constant::Integer(value), TypeKind::Pointer(_)) => {
+            Constant::Integer(wrap(context, value, to))
+        }
+        (Constant::Integer(value), TypeKind::Floating(_)) => floating(context, value as f64, to),
+        //a floating value is truncated, and it must fit in the integer type
+        (Constant::Floating(value), TypeKind::Integer(_) | TypeKind::Enum(_)) => {
+            let kind = types.integer_kind(to).unwrap();
+            let value = value.trunc();
+            if !(kind.min_value() as f64 <= value && value <= kind.max_value() as f64) {
+                let message = format!(
+                    "floating value out of range of '{}' in constant expression.",
+                    context.types().display(to)
+                );
+                return error(message, span);
+            }
+            Constant::Integer(value as i128)
+        }
+        (Constant::Floating(value), TypeKind::Floating(_)) => floating(context, value, to),
+        //an address keeps its value only in an integer type as wide as a pointer
+        (Constant::Address(address), TypeKind::Pointer(_)) => Constant::Address(address),
+        (Constant::Address(address), TypeKind::Integer(kind)) if kind.size() == 8 => {
+            Constant::Address(address)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::*;
+    use crate::semantic::symbol::*;
+    use crate::testing::*;
+
+    //the value of the integer constant expression, found as that of an enumeration constant
+    fn value(expression: &str) -> i64 {
+        let source = format!("enum {{ __value = {} }};", expression);
+        let (typed, diagnostics) = analyze_source(&source, Standard::Gnu11);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let kind = typed.symbols.iter().next().map(|(_, symbol)| symbol.kind);
+        match kind {
+            Some(SymbolKind::EnumerationConstant(value)) => value,
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    fn messages(source: &str) -> Vec<String> {
+        let (_, diagnostics) = analyze_source(source, Standard::Gnu11);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn unsigned_arithmetic_and_conversions_wrap_around() {
+        assert_eq!(value("(unsigned)-1 / 2"), 2147483647);
+        assert_eq!(value("(unsigned char)300"), 44);
+        assert_eq!(value("-1 + (unsigned)0 > 0"), 1);
+        assert_eq!(value("(signed char)200 < 0"), 1);
+    }
+
+    #[test]
+    fn character_constants_have_the_value_of_their_char() {
+        assert_eq!(value("'a' + '\\xff'"), 96);
+        assert_eq!(value("'\\x80'"), -128);
+    }
+
+    #[test]
+    fn undefined_operations_are_errors() {
+        assert_eq!(
+            messages("int o = 2147483647 + 1;"),
+            ["overflow in constant expression of type 'int'."]
+        );
+        assert_eq!(
+            messages("int z = 1 / 0; int w = 1 % 0;"),
+            [
+                "division by zero in constant expression.",
+                "division by zero in constant expression."
+            ]
+        );
+        assert_eq!(
+            messages("int s = 1 << 40;"),
+            ["shift count >= width of type 'int' in constant expression."]
+        );
+    }
+
+    #[test]
+    fn address_constants() {
+        let source = "int x; int *p = &x + 1; char *s = \"abc\" + 1;
+            struct P { int a, b; }; struct P *gp = &(struct P){5, 6};
+            int *ip = (int[]){1, 2, 3};";
+        assert!(messages(source).is_empty());
+        assert_eq!(
+            messages("int x; int y; int *q = &x + y;"),
+            ["initializer element is not a compile-time constant."]
+        );
+    }
+}
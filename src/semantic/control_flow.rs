@@ -0,0 +1,223 @@
+use crate::semantic::constant::*;
+use crate::semantic::expression::*;
+use crate::semantic::statement::*;
+use crate::semantic::util::*;
+
+//whether control may reach the end of statement, judged from its structure alone: a loop
+//whose condition is a nonzero constant ends only by a break, and a call of a function
+//which does not return does not end. a labeled statement is reachable since a jump may go
+//there, and a broken statement is assumed to end nowhere so that it adds no warnings.
+pub fn can_complete(context: &SemanticContext, statement: &TypedStatement) -> bool {
+    match statement {
+        TypedStatement::Null | TypedStatement::Asm(_) => true,
+        TypedStatement::Expression(expression) => !is_noreturn_call(context, expression),
+        TypedStatement::If {
+            statement,
+            else_statement,
+            ..
+        } => match else_statement {
+            Some(else_statement) => {
+                can_complete(context, statement) || can_complete(context, else_statement)
+            }
+            None => true,
+        },
+        //without a default, control skips the body when no case matches
+        TypedStatement::Switch { statement, .. } => {
+            !has_default(statement) || can_complete(context, statement) || has_break(statement)
+        }
+        TypedStatement::Labeled { statement, .. }
+        | TypedStatement::Case { statement, .. }
+        | TypedStatement::CaseRange { statement, .. }
+        | TypedStatement::Default(statement) => can_complete(context, statement),
+        TypedStatement::While {
+            condition,
+            statement,
+        } => !is_always_true(context, Some(condition)) || has_break(statement),
+        TypedStatement::DoWhile {
+            condition,
+            statement,
+        } => {
+            let loops_back = can_complete(context, statement) || has_continue(statement);
+            (loops_back && !is_always_true(context, Some(condition))) || has_break(statement)
+        }
+        TypedStatement::For {
+            condition,
+            statement,
+            ..
+        } => !is_always_true(context, condition.as_deref()) || has_break(statement),
+        TypedStatement::Compound(items) => {
+            let mut reachable = true;
+            for item in items {
+                if let TypedBlockItem::Statement(statement) = item {
+                    reachable =
+                        (reachable || has_label(statement)) && can_complete(context, statement);
+                }
+            }
+            reachable
+        }
+        TypedStatement::Return(_)
+        | TypedStatement::Break
+        | TypedStatement::Continue
+        | TypedStatement::Goto(_)
+        | TypedStatement::Error => false,
+    }
+}
+
+//a missing condition of a for statement is true too
+fn is_always_true(context: &SemanticContext, condition: Option<&TypedExpression>) -> bool {
+    match condition {
+        Some(condition) => {
+            matches!(evaluate(context, condition), Ok(Some(value)) if is_true(value))
+        }
+        None => true,
+    }
+}
+
+fn is_noreturn_call(context: &SemanticContext, expression: &TypedExpression) -> bool {
+    match &expression.kind {
+        TypedExpressionKind::Cast { operand, .. } => is_noreturn_call(context, operand),
+        TypedExpressionKind::Call { callee, .. } => {
+            let mut callee: &TypedExpression = callee;
+            while let TypedExpressionKind::Cast { operand, .. } = &callee.kind {
+                callee = operand;
+            }
+            match callee.kind {
+                TypedExpressionKind::Identifier(id) => symbol(context, id).noreturn,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+//the statements directly nested in statement, without those in statement expressions
+pub fn children<'a, 'b>(statement: &'b TypedStatement<'a>) -> Vec<&'b TypedStatement<'a>> {
+    match statement {
+        TypedStatement::If {
+            statement,
+            else_statement,
+            ..
+        } => {
+            let mut result = vec![statement.as_ref()];
+            result.extend(else_statement.as_deref());
+            result
+        }
+        TypedStatement::Switch { statement, .. }
+        | TypedStatement::Labeled { statement, .. }
+        | TypedStatement::Case { statement, .. }
+        | TypedStatement::CaseRange { statement, .. }
+        | TypedStatement::Default(statement)
+        | TypedStatement::While { statement, .. }
+        | TypedStatement::DoWhile { statement, .. }
+        | TypedStatement::For { statement, .. } => vec![statement.as_ref()],
+        TypedStatement::Compound(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                TypedBlockItem::Statement(statement) => Some(statement),
+                TypedBlockItem::Declaration(_) => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_loop(statement: &TypedStatement) -> bool {
+    matches!(
+        statement,
+        TypedStatement::While { .. } | TypedStatement::DoWhile { .. } | TypedStatement::For { .. }
+    )
+}
+
+//a break which leaves statement itself, not a loop or switch nested in it
+fn has_break(statement: &TypedStatement) -> bool {
+    match statement {
+        TypedStatement::Break => true,
+        TypedStatement::Switch { .. } => false,
+        statement if is_loop(statement) => false,
+        statement => children(statement).into_iter().any(has_break),
+    }
+}
+
+fn has_continue(statement: &TypedStatement) -> bool {
+    match statement {
+        TypedStatement::Continue => true,
+        statement if is_loop(statement) => false,
+        statement => children(statement).into_iter().any(has_continue),
+    }
+}
+
+//a default label of the switch whose body is statement
+fn has_default(statement: &TypedStatement) -> bool {
+    match statement {
+        TypedStatement::Default(_) => true,
+        TypedStatement::Switch { .. } => false,
+        statement => children(statement).into_iter().any(has_default),
+    }
+}
+
+pub fn has_label(statement: &TypedStatement) -> bool {
+    match statement {
+        TypedStatement::Labeled { .. }
+        | TypedStatement::Case { .. }
+        | TypedStatement::CaseRange { .. }
+        | TypedStatement::Default(_) => true,
+        statement => children(statement).into_iter().any(has_label),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::*;
+    use crate::testing::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        let (_, diagnostics) = analyze_source(source, Standard::C11);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn jumps_need_an_enclosing_statement_or_label() {
+        let source = "void f(int x) { break; continue; case 1: ; default: ; goto nowhere; }";
+        assert_eq!(
+            messages(source),
+            [
+                "'break' statement not in loop or switch statement.",
+                "'continue' statement not in loop statement.",
+                "'case' statement not in switch statement.",
+                "'default' statement not in switch statement.",
+                "use of undeclared label 'nowhere'."
+            ]
+        );
+        let source =
+            "void f(int x) { switch (x) { case 1: case 1: ; default: default: ; } l: l: ; }";
+        assert_eq!(
+            messages(source),
+            [
+                "duplicate case value '1'.",
+                "previous case defined here.",
+                "multiple default labels in one switch.",
+                "previous case defined here.",
+                "redefinition of label 'l'.",
+                "previous definition is here."
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_match_the_return_type() {
+        assert_eq!(
+            messages("void f(void) { return 1; }"),
+            ["void function should not return a value."]
+        );
+        assert_eq!(
+            messages("int g(int x) { if (x) return 1; }"),
+            ["control reaches end of non-void function."]
+        );
+        let source = "int h(int x) { while (1) { if (x) return 1; } }
+            void k(void); int m(void) { for (;;) ; } int n(int x) { if (x) return 1; else return 2; }";
+        assert!(messages(source).is_empty(), "{:?}", messages(source));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::diagnostic::*;
+use crate::semantic::constant::*;
+use crate::semantic::expression::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+
+//the places where a value is converted as if by assignment, which differ only in how the
+//diagnostics describe them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignmentKind {
+    Assignment,
+    Initialization,
+    Argument,
+    Return,
+}
+
+//converts the value of expression to ty with an implicit cast, unless it already has
+//that type
+pub fn convert<'a>(
+    context: &mut SemanticContext,
+    expression: TypedExpression<'a>,
+    ty: TypeId,
+) -> TypedExpression<'a> {
+    let ty = context.types.unqualified(ty);
+    if context.types.unqualified(expression.ty) == ty {
+        return expression;
+    }
+
+    let span = expression.span;
+    TypedExpression {
+        kind: TypedExpressionKind::Cast {
+            kind: CastKind::Implicit,
+            operand: Box::new(expression),
+        },
+        ty,
+        category: ValueCategory::RValue,
+        span,
+    }
+}
+
+//an array or function designator whose value is used is converted to a pointer
+pub fn decayed<'a>(
+    context: &mut SemanticContext,
+    expression: TypedExpression<'a>,
+) -> TypedExpression<'a> {
+    let ty = value_type(context, &expression);
+    convert(context, expression, ty)
+}
+
+pub fn promoted<'a>(
+    context: &mut SemanticContext,
+    expression: TypedExpression<'a>,
+) -> TypedExpression<'a> {
+    let ty = value_type(context, &expression);
+    let ty = context.types.promoted(ty);
+    convert(context, expression, ty)
+}
+
+//an argument without a parameter type is promoted, and a float becomes a double
+pub fn default_argument_promoted<'a>(
+    context: &mut SemanticContext,
+    expression: TypedExpression<'a>,
+) -> SemanticResult<TypedExpression<'a>> {
+    let ty = value_type(context, &expression);
+    let ty = match context.types.kind(ty) {
+        TypeKind::Void => {
+            return error(
+                "argument may not have 'void' type.".to_string(),
+                expression.span,
+            );
+        }
+        TypeKind::Floating(FloatingKind::Float) => context.types.floating(FloatingKind::Double),
+        _ => context.types.promoted(ty),
+    };
+    Ok(convert(context, expression, ty))
+}
+
+//an integer constant expression with the value 0, or one cast to void *
+pub fn is_null_pointer_constant(context: &SemanticContext, expression: &TypedExpression) -> bool {
+    let types = &context.types;
+    if types.is_integer(expression.ty) {
+        return matches!(
+            evaluate(context, expression),
+            Ok(Some(Constant::Integer(0)))
+        );
+    }
+    match &expression.kind {
+        TypedExpressionKind::Cast { operand, .. } => {
+            types.pointee(expression.ty).is_some_and(|pointee| {
+                types.is_void(pointee) && types.qualifiers(pointee) == Qualifiers::default()
+            }) && is_null_pointer_constant(context, operand)
+        }
+        _ => false,
+    }
+}
+
+//converts the value of expression to ty as if by assignment. the conversions between
+//pointers to different types and between pointers and integers are accepted with a
+//warning, like the other compilers do.
+pub fn assign<'a>(
+    context: &mut SemanticContext,
+    expression: TypedExpression<'a>,
+    ty: TypeId,
+    kind: AssignmentKind,
+) -> SemanticResult<TypedExpression<'a>> {
+    let target = context.types.unqualified(ty);
+    let expression = decayed(context, expression);
+    let source = value_type(context, &expression);
+    let span = expression.span;
+    let is_null = is_null_pointer_constant(context, &expression);
+    let types = &mut context.types;
+
+    let warning = if (types.is_arithmetic(target) && types.is_arithmetic(source))
+        || (types.is_record(target) && types.is_compatible(target, source))
+    {
+        None
+    } else if let (Some(target_pointee), Some(source_pointee)) =
+        (types.pointee(target), types.pointee(source))
+    {
+        let target_qualifiers = types.qualifiers(target_pointee);
+        let source_qualifiers = types.qualifiers(source_pointee);
+        let target_pointee = types.unqualified(target_pointee);
+        let source_pointee = types.unqualified(source_pointee);
+        //void * converts to and from a pointer to any object type
+        let is_void_conversion = (types.is_void(target_pointee)
+            && !types.is_function(source_pointee))
+            || (types.is_void(source_pointee) && !types.is_function(target_pointee));
+        if !is_void_conversion && !types.is_compatible(target_pointee, source_pointee) {
+            Some(format!(
+                "incompatible pointer types {}.",
+                describe(context, kind, target, source)
+            ))
+        } else if !source_qualifiers.is_subset_of(target_qualifiers) {
+            Some(format!(
+                "{} discards qualifiers.",
+                describe(context, kind, target, source)
+            ))
+        } else {
+            None
+        }
+    } else if types.is_pointer(target) && is_null {
+        None
+    } else if types.is_pointer(target) && types.is_integer(source) {
+        Some(format!(
+            "incompatible integer to pointer conversion {}.",
+            describe(context, kind, target, source)
+        ))
+    } else if types.integer_kind(target) == Some(IntegerKind::Bool) && types.is_pointer(source) {
+        None
+    } else if types.is_integer(target) && types.is_pointer(source) {
+        Some(format!(
+            "incompatible pointer to integer conversion {}.",
+            describe(context, kind, target, source)
+        ))
+    } else {
+        let message = format!(
+            "incompatible types {}.",
+            describe(context, kind, target, source)
+        );
+        return error(message, span);
+    };
+    if let Some(message) = warning {
+        report(context, Diagnostic::warning(message, span));
+    }
+
+    Ok(convert(context, expression, target))
+}
+
+fn describe(
+    context: &SemanticContext,
+    kind: AssignmentKind,
+    target: TypeId,
+    source: TypeId,
+) -> String {
+    let target = display_type(context, target);
+    let source = display_type(context, source);
+    match kind {
+        AssignmentKind::Assignment => format!("assigning to '{}' from '{}'", target, source),
+        AssignmentKind::Initialization => {
+            format!(
+                "initializing '{}' with an expression of type '{}'",
+                target, source
+            )
+        }
+        AssignmentKind::Argument => {
+            format!("passing '{}' to parameter of type '{}'", source, target)
+        }
+        AssignmentKind::Return => format!(
+            "returning '{}' from a function with result type '{}'",
+            source, target
+        ),
+    }
+}
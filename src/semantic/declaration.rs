@@ -0,0 +1,1581 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::parser::declaration::*;
+use crate::parser::expression::*;
+use crate::semantic::constant::*;
+use crate::semantic::conversion::*;
+use crate::semantic::expression::*;
+use crate::semantic::initializer::*;
+use crate::semantic::layout::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use crate::util::*;
+use crate::warning::*;
+
+//a declared object or function, typedefs leave nothing in the typed tree
+#[derive(Debug)]
+pub struct TypedDeclaration<'a> {
+    pub symbol: SymbolId,
+    pub initializer: Option<TypedInitializer<'a>>,
+    pub lengths: Vec<(LengthId, TypedExpression<'a>)>, // evaluated before the initializer
+}
+
+//analyzer body
+pub fn declaration<'a>(
+    context: &mut SemanticContext,
+    declaration: &Declaration<'a>,
+) -> Vec<TypedDeclaration<'a>> {
+    if declaration.declarators.is_empty() {
+        forward_declaration(context, &declaration.specifiers);
+    }
+    let (base, storage, alignment) =
+        match declaration_specifiers(context, &declaration.specifiers, declaration.span) {
+            Ok(result) => result,
+            Err(diagnostic) => {
+                report(context, diagnostic);
+                return Vec::new();
+            }
+        };
+
+    let attributed =
+        match specifier_attribute_alignment(context, &declaration.specifiers, declaration.span) {
+            Ok(alignment) => alignment,
+            Err(diagnostic) => {
+                report(context, diagnostic);
+                return Vec::new();
+            }
+        };
+
+    let mut result = Vec::new();
+    for init_declarator in &declaration.declarators {
+        let noreturn = is_noreturn(&declaration.specifiers, &init_declarator.declarator);
+        let specifiers = (base, storage, alignment);
+        match self::init_declarator(context, init_declarator, specifiers, attributed, noreturn) {
+            Ok(Some(declaration)) => result.push(declaration),
+            Ok(None) => {}
+            Err(diagnostic) => report(context, diagnostic),
+        }
+    }
+
+    result
+}
+
+fn init_declarator<'a>(
+    context: &mut SemanticContext,
+    init_declarator: &InitDeclarator<'a>,
+    (base, storage, alignment): (TypeId, Option<StorageClassSpecifier>, RequestedAlignment),
+    attributed: Option<u64>,
+    noreturn: bool,
+) -> SemanticResult<Option<TypedDeclaration<'a>>> {
+    let declarator = &init_declarator.declarator;
+    let name = declarator.identifier();
+    let span = declarator.span();
+    let first = context.types.length_count();
+    let mut ty = declarator_type(context, declarator, base)?;
+    let lengths = variable_lengths(context, &declarator.identifier, first)?;
+
+    let kind = if matches!(storage, Some(StorageClassSpecifier::Typedef)) {
+        SymbolKind::Typedef
+    } else if context.types.is_function(ty) {
+        SymbolKind::Function
+    } else {
+        SymbolKind::Object
+    };
+    if init_declarator.initializer.is_some() && kind != SymbolKind::Object {
+        return error(format!("'{}' cannot be initialized.", name), span);
+    }
+    if let Some((alignment, alignment_span)) = alignment {
+        let what = match kind {
+            SymbolKind::Typedef => Some("typedef"),
+            SymbolKind::Function => Some("function"),
+            _ if matches!(storage, Some(StorageClassSpecifier::Register)) => {
+                Some("'register' object")
+            }
+            _ => None,
+        };
+        if let Some(what) = what {
+            let message = format!("alignment specified for {} '{}'.", what, name);
+            return error(message, alignment_span);
+        }
+        requested_alignment(context, alignment, ty, None, name, alignment_span)?;
+    }
+    //the aligned attribute gives a typedef its alignment, and raises that of an object
+    let placement = attribute_placement(context, &declarator.attributes, span)?;
+    let attributed = attributed.max(placement.alignment);
+    if let (Some(alignment), SymbolKind::Typedef) = (attributed, kind) {
+        ty = context.types.aligned(ty, alignment);
+    }
+
+    //an array may still be completed by its initializer, and an object at file scope by
+    //a later declaration
+    let is_array = context.types.is_array(ty);
+    let may_be_completed = (is_array && init_declarator.initializer.is_some())
+        || is_file_scope(context)
+        || matches!(storage, Some(StorageClassSpecifier::Extern));
+    if kind == SymbolKind::Object && !context.types.is_complete(ty) && !may_be_completed {
+        let message = format!(
+            "variable '{}' has incomplete type '{}'.",
+            name,
+            context.types.display(ty)
+        );
+        return error(message, span);
+    }
+
+    let is_extern = matches!(storage, Some(StorageClassSpecifier::Extern));
+    let has_initializer = init_declarator.initializer.is_some();
+    if kind == SymbolKind::Function
+        && matches!(storage, Some(StorageClassSpecifier::Static))
+        && !is_file_scope(context)
+    {
+        let message = format!(
+            "function '{}' declared in block scope cannot have 'static' storage class.",
+            name
+        );
+        return error(message, span);
+    }
+    if is_extern && has_initializer && !is_file_scope(context) {
+        let message = format!(
+            "declaration of block scope identifier '{}' with linkage cannot have an initializer.",
+            name
+        );
+        return error(message, span);
+    }
+    let is_static = matches!(storage, Some(StorageClassSpecifier::Static));
+    if kind == SymbolKind::Object && !lengths.is_empty() && (is_static || is_extern) {
+        let message = if is_static {
+            "variable length array declaration cannot have 'static' storage duration."
+        } else {
+            "variable length array declaration cannot have 'extern' linkage."
+        };
+        return error(message.to_string(), span);
+    }
+    let definition = match kind {
+        SymbolKind::Object if has_initializer => Definition::Definition,
+        SymbolKind::Object if is_extern => Definition::Declaration,
+        SymbolKind::Object if is_file_scope(context) => Definition::Tentative,
+        SymbolKind::Function => Definition::Declaration,
+        _ => Definition::Definition,
+    };
+
+    //the scope of an identifier begins right after its declarator, so the initializer
+    //can refer to it
+    let symbol = declare(context, name, ty, kind, storage, definition, span);
+    if kind == SymbolKind::Function && noreturn {
+        symbol_mut(context, symbol).noreturn = true;
+    }
+    if let Some((alignment, _)) = alignment {
+        let declared = symbol_mut(context, symbol);
+        declared.alignment = declared.alignment.max(alignment);
+    }
+    if let (Some(alignment), SymbolKind::Object) = (attributed, kind) {
+        let declared = symbol_mut(context, symbol);
+        declared.alignment = declared.alignment.max(alignment);
+    }
+    if let Some(label) = init_declarator.asm_label {
+        symbol_mut(context, symbol).asm_label = Some(label.to_string());
+    }
+    //the lengths of a variably modified typedef are evaluated at its declaration too
+    if kind == SymbolKind::Typedef && lengths.is_empty() {
+        return Ok(None);
+    }
+
+    let initializer = match &init_declarator.initializer {
+        Some(initializer) => {
+            let (initializer, length) = self::initializer(context, initializer, ty, span)?;
+            if let TypeKind::Array(element, ArraySize::Incomplete) = *context.types.kind(ty) {
+                let ty = context.types.array(element, ArraySize::Constant(length));
+                symbol_mut(context, symbol).ty = ty;
+            }
+            if has_static_storage(self::symbol(context, symbol)) {
+                static_initializer(context, &initializer)?;
+            }
+            Some(initializer)
+        }
+        None => None,
+    };
+
+    Ok(Some(TypedDeclaration {
+        symbol,
+        initializer,
+        lengths,
+    }))
+}
+
+//the size expressions of the variable length arrays made by the array declarators of
+//declarator, which are those made since the count first. they were analyzed when the
+//type was, which reported their diagnostics already.
+fn variable_lengths<'a>(
+    context: &mut SemanticContext,
+    declarator: &DirectDeclarator<'a>,
+    first: usize,
+) -> SemanticResult<Vec<(LengthId, TypedExpression<'a>)>> {
+    let mut sizes = Vec::new();
+    let mut current = declarator;
+    loop {
+        match current {
+            DirectDeclarator::Identifier(_, _) => break,
+            DirectDeclarator::Declarator(inner) => current = &inner.identifier,
+            DirectDeclarator::Array(inner, _, size) => {
+                sizes.extend(size);
+                current = inner;
+            }
+            //the parameters of a function are adjusted, so their sizes are never used
+            DirectDeclarator::Function(inner, _) => current = inner,
+        }
+    }
+    typed_lengths(context, sizes, first)
+}
+
+//the lengths of the variable length arrays a type name makes, like variable_lengths
+pub fn type_name_lengths<'a>(
+    context: &mut SemanticContext,
+    type_name: &TypeName<'a>,
+    first: usize,
+) -> SemanticResult<Vec<(LengthId, TypedExpression<'a>)>> {
+    let mut sizes = Vec::new();
+    let mut current = type_name
+        .declarator
+        .as_ref()
+        .and_then(|declarator| declarator.declarator.as_ref());
+    while let Some(declarator) = current {
+        current = match declarator {
+            DirectAbstarctDeclarator::Declarator(inner) => inner.declarator.as_ref(),
+            DirectAbstarctDeclarator::Array(inner, _, size) => {
+                sizes.extend(size);
+                inner.as_deref()
+            }
+            DirectAbstarctDeclarator::Function(inner, _) => inner.as_deref(),
+        };
+    }
+    typed_lengths(context, sizes, first)
+}
+
+fn typed_lengths<'a>(
+    context: &mut SemanticContext,
+    sizes: Vec<&Expression<'a>>,
+    first: usize,
+) -> SemanticResult<Vec<(LengthId, TypedExpression<'a>)>> {
+    let mut lengths = Vec::new();
+    let reported = context.diagnostics.len();
+    for size in sizes.into_iter().rev() {
+        let length = context
+            .types
+            .lengths_from(first)
+            .find(|length| context.types.length(*length).span == size.span);
+        if let Some(length) = length {
+            let size_type = context.types.size_type();
+            let size = expression(context, size)?;
+            let size = decayed(context, size);
+            lengths.push((length, convert(context, size, size_type)));
+        }
+    }
+    context.diagnostics.truncate(reported);
+
+    Ok(lengths)
+}
+
+//an object with static storage duration is initialized before the program starts, so its
+//initializer is made of constant expressions. a string literal initializing an array is
+//one too.
+fn static_initializer(
+    context: &SemanticContext,
+    initializer: &TypedInitializer,
+) -> SemanticResult<()> {
+    let expressions: Vec<_> = match initializer {
+        TypedInitializer::Expression(expression) => vec![expression],
+        TypedInitializer::List(items) => items.iter().map(|item| &item.expression).collect(),
+    };
+    for expression in expressions {
+        if let TypedExpressionKind::String(_) = expression.kind {
+            continue;
+        }
+        if evaluate(context, expression)?.is_none() {
+            let message = "initializer element is not a compile-time constant.".to_string();
+            return error(message, expression.span);
+        }
+    }
+
+    Ok(())
+}
+
+//the condition of a static assertion is an integer constant expression
+pub fn static_assert<'a>(context: &mut SemanticContext, assertion: &StaticAssertDeclaration<'a>) {
+    match integer_constant(context, &assertion.condition) {
+        Ok(0) => {
+            let message = format!("static assertion failed: \"{}\"", assertion.message);
+            report(context, Diagnostic::error(message, assertion.span));
+        }
+        Ok(_) => {}
+        Err(diagnostic) => report(context, diagnostic),
+    }
+}
+
+//declarations of an identifier with linkage refer to the same entity, which keeps the
+//symbol of its first declaration, so a block scope extern declaration binds the symbol
+//declared at file scope
+pub fn declare(
+    context: &mut SemanticContext,
+    name: &str,
+    ty: TypeId,
+    kind: SymbolKind,
+    storage: Option<StorageClassSpecifier>,
+    definition: Definition,
+    span: Span,
+) -> SymbolId {
+    let linkage = linkage(context, name, kind, storage);
+    let previous = match lookup_in_current_scope(context, name) {
+        Some(id) => Some(id),
+        None if linkage != Linkage::None => lookup(context, name)
+            .filter(|id| symbol(context, *id).linkage != Linkage::None)
+            .or_else(|| context.external.get(name).copied()),
+        None => None,
+    };
+    if let Some(id) =
+        previous.and_then(|id| redeclare(context, id, ty, kind, linkage, definition, span))
+    {
+        bind_symbol(context, id);
+        return id;
+    }
+    if linkage == Linkage::None && lookup_in_current_scope(context, name).is_none() {
+        shadow(context, name, span);
+    }
+
+    add_symbol(
+        context,
+        Symbol {
+            name: name.to_string(),
+            ty,
+            kind,
+            storage,
+            linkage,
+            definition,
+            noreturn: false,
+            parameter: false,
+            used: false,
+            alignment: 0,
+            asm_label: None,
+            span,
+        },
+    )
+}
+
+//a declaration in a block which hides one of an enclosing scope
+fn shadow(context: &mut SemanticContext, name: &str, span: Span) {
+    if matches!(scope_kind(context), ScopeKind::File | ScopeKind::Prototype) {
+        return;
+    }
+    let (kind, previous) = match context
+        .scopes
+        .iter()
+        .rev()
+        .find_map(|scope| Some((scope.kind, *scope.ordinary.get(name)?)))
+    {
+        Some(previous) => previous,
+        None => return,
+    };
+    let previous = symbol(context, previous);
+    let what = if kind == ScopeKind::File {
+        "a global declaration"
+    } else if previous.parameter {
+        "a parameter"
+    } else {
+        "a previous local"
+    };
+    let message = format!("declaration of '{}' shadows {}.", name, what);
+    let previous = previous.span;
+    let warning = Warning::Shadow;
+    report(context, Diagnostic::named_warning(warning, message, span));
+    let note = "shadowed declaration is here.".to_string();
+    report(context, Diagnostic::note(note, previous));
+}
+
+//an identifier declared extern, or a function declared without a storage class, has the
+//linkage of a visible declaration if there is one
+fn linkage(
+    context: &SemanticContext,
+    name: &str,
+    kind: SymbolKind,
+    storage: Option<StorageClassSpecifier>,
+) -> Linkage {
+    match (kind, storage) {
+        (SymbolKind::Typedef | SymbolKind::EnumerationConstant(_), _) => Linkage::None,
+        (_, Some(StorageClassSpecifier::Static)) if is_file_scope(context) => Linkage::Internal,
+        (_, Some(StorageClassSpecifier::Extern)) | (SymbolKind::Function, None) => {
+            lookup(context, name)
+                .map(|id| symbol(context, id).linkage)
+                .filter(|linkage| *linkage != Linkage::None)
+                .unwrap_or(Linkage::External)
+        }
+        _ if is_file_scope(context) => Linkage::External,
+        _ => Linkage::None,
+    }
+}
+
+//checks a redeclaration against the previous declaration of the identifier and merges
+//them, or gives None if it declares a new entity hiding or replacing the previous one
+fn redeclare(
+    context: &mut SemanticContext,
+    id: SymbolId,
+    ty: TypeId,
+    kind: SymbolKind,
+    linkage: Linkage,
+    definition: Definition,
+    span: Span,
+) -> Option<SymbolId> {
+    let previous = symbol(context, id).clone();
+    let name = &previous.name;
+    let same_kind = match (previous.kind, kind) {
+        (SymbolKind::EnumerationConstant(_), SymbolKind::EnumerationConstant(_)) => true,
+        (previous, kind) => previous == kind,
+    };
+    if !same_kind {
+        let message = format!("redefinition of '{}' as different kind of symbol.", name);
+        report_conflict(
+            context,
+            message,
+            span,
+            "previous definition is here.",
+            previous.span,
+        );
+        return None;
+    }
+
+    match kind {
+        //C11 allows a typedef to be repeated with the same type
+        SymbolKind::Typedef => {
+            let message = if previous.ty != ty {
+                format!(
+                    "typedef redefinition with different types ('{}' vs '{}').",
+                    display_type(context, ty),
+                    display_type(context, previous.ty)
+                )
+            } else if context.standard < Standard::C11 {
+                format!("redefinition of typedef '{}'.", name)
+            } else {
+                return Some(id);
+            };
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous definition is here.",
+                previous.span,
+            );
+            return Some(id);
+        }
+        SymbolKind::EnumerationConstant(_) => {
+            let message = format!("redefinition of enumerator '{}'.", name);
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous definition is here.",
+                previous.span,
+            );
+            return None;
+        }
+        _ => {}
+    }
+
+    if linkage == Linkage::None || previous.linkage == Linkage::None {
+        let message = format!("redefinition of '{}'.", name);
+        report_conflict(
+            context,
+            message,
+            span,
+            "previous definition is here.",
+            previous.span,
+        );
+        return None;
+    }
+    if linkage != previous.linkage {
+        let message = if linkage == Linkage::Internal {
+            format!(
+                "static declaration of '{}' follows non-static declaration.",
+                name
+            )
+        } else {
+            format!(
+                "non-static declaration of '{}' follows static declaration.",
+                name
+            )
+        };
+        report_conflict(
+            context,
+            message,
+            span,
+            "previous declaration is here.",
+            previous.span,
+        );
+        return Some(id);
+    }
+    if !context.types.is_compatible(previous.ty, ty) {
+        let message = format!("conflicting types for '{}'.", name);
+        report_conflict(
+            context,
+            message,
+            span,
+            "previous declaration is here.",
+            previous.span,
+        );
+        return Some(id);
+    }
+    if previous.definition == Definition::Definition && definition == Definition::Definition {
+        let message = format!("redefinition of '{}'.", name);
+        report_conflict(
+            context,
+            message,
+            span,
+            "previous definition is here.",
+            previous.span,
+        );
+        return Some(id);
+    }
+
+    let ty = composite_type(context, previous.ty, ty);
+    let symbol = symbol_mut(context, id);
+    symbol.ty = ty;
+    if definition > symbol.definition {
+        symbol.definition = definition;
+        symbol.span = span;
+    }
+    Some(id)
+}
+
+//of two compatible types, the one which tells more
+fn composite_type(context: &SemanticContext, old: TypeId, new: TypeId) -> TypeId {
+    let types = &context.types;
+    match (types.kind(old), types.kind(new)) {
+        (TypeKind::Array(_, ArraySize::Incomplete), _) => new,
+        (TypeKind::Function(old_function), TypeKind::Function(_)) if !old_function.prototype => new,
+        _ => old,
+    }
+}
+
+//the alignment requested by _Alignas and where it is
+pub type RequestedAlignment = Option<(u64, Span)>;
+
+//the type given by the type specifiers and qualifiers with the storage class and the
+//requested alignment
+pub fn declaration_specifiers<'a>(
+    context: &mut SemanticContext,
+    specifiers: &[DeclarationSpecifier<'a>],
+    span: Span,
+) -> SemanticResult<(TypeId, Option<StorageClassSpecifier>, RequestedAlignment)> {
+    let mut type_specifiers = Vec::new();
+    let mut qualifiers = Vec::new();
+    let mut storage = None;
+    let mut alignment: RequestedAlignment = None;
+    for specifier in specifiers {
+        match specifier {
+            DeclarationSpecifier::StorageSpecifier(specifier) => storage = Some(*specifier),
+            DeclarationSpecifier::TypeSpecifier(specifier) => type_specifiers.push(specifier),
+            DeclarationSpecifier::TypeQualifier(qualifier) => qualifiers.push(*qualifier),
+            //the strictest of several alignment specifiers applies
+            DeclarationSpecifier::AlignmentSpecifier(specifier) => {
+                let value = alignment_specifier(context, specifier, span)?;
+                if alignment.is_none_or(|(previous, _)| previous < value) {
+                    alignment = Some((value, span));
+                }
+            }
+            DeclarationSpecifier::FunctionSpecifier(_) | DeclarationSpecifier::Attributes(_) => {}
+        }
+    }
+
+    let ty = base_type(context, &type_specifiers, span)?;
+    Ok((qualified(context, ty, &qualifiers), storage, alignment))
+}
+
+//the alignment requested by _Alignas, where 0 requests none
+fn alignment_specifier<'a>(
+    context: &mut SemanticContext,
+    specifier: &Either<TypeName<'a>, Expression<'a>>,
+    span: Span,
+) -> SemanticResult<u64> {
+    match specifier {
+        Either::Left(type_name) => {
+            let ty = self::type_name(context, type_name)?;
+            match context.types.align_of(ty) {
+                Some(alignment) => Ok(alignment),
+                None => {
+                    let message = format!(
+                        "invalid application of '_Alignas' to an incomplete type '{}'.",
+                        display_type(context, ty)
+                    );
+                    error(message, span)
+                }
+            }
+        }
+        Either::Right(expression) => {
+            let alignment = integer_constant(context, expression)?;
+            if alignment < 0 || alignment & (alignment - 1) != 0 {
+                let message = "requested alignment is not a power of two.".to_string();
+                return error(message, expression.span);
+            }
+            if alignment > 1 << 28 {
+                let message = "requested alignment must be 268435456 bytes or smaller.";
+                return error(message.to_string(), expression.span);
+            }
+            Ok(alignment as u64)
+        }
+    }
+}
+
+//of a type name, where an alignment cannot be given
+pub fn specifier_qualifier_list<'a>(
+    context: &mut SemanticContext,
+    list: &[SpecifierOrQualifier<'a>],
+    span: Span,
+) -> SemanticResult<TypeId> {
+    match member_specifiers(context, list, span)? {
+        (_, Some((_, span))) => error("alignment specified for type name.".to_string(), span),
+        (ty, None) => Ok(ty),
+    }
+}
+
+//the type of the members declared with list, with the alignment requested by _Alignas
+//and where it is
+fn member_specifiers<'a>(
+    context: &mut SemanticContext,
+    list: &[SpecifierOrQualifier<'a>],
+    span: Span,
+) -> SemanticResult<(TypeId, RequestedAlignment)> {
+    let mut type_specifiers = Vec::new();
+    let mut qualifiers = Vec::new();
+    let mut alignment: RequestedAlignment = None;
+    for item in list {
+        match item {
+            SpecifierOrQualifier::TypeSpecifier(specifier) => type_specifiers.push(specifier),
+            SpecifierOrQualifier::TypeQualifier(qualifier) => qualifiers.push(*qualifier),
+            //the strictest of several alignment specifiers applies
+            SpecifierOrQualifier::AlignmentSpecifier(specifier, span) => {
+                let value = alignment_specifier(context, specifier, *span)?;
+                if alignment.is_none_or(|(previous, _)| previous < value) {
+                    alignment = Some((value, *span));
+                }
+            }
+        }
+    }
+
+    let ty = base_type(context, &type_specifiers, span)?;
+    Ok((qualified(context, ty, &qualifiers), alignment))
+}
+
+//GNU attributes may be spelled with underscores around, as in `__packed__`
+pub fn attribute_name<'a>(attribute: &Attribute<'a>) -> &'a str {
+    attribute
+        .name
+        .strip_prefix("__")
+        .and_then(|name| name.strip_suffix("__"))
+        .unwrap_or(attribute.name)
+}
+
+//whether a function declared so does not return, by _Noreturn or the GNU noreturn attribute
+pub fn is_noreturn(specifiers: &[DeclarationSpecifier], declarator: &Declarator) -> bool {
+    let is_noreturn_attribute = |attribute: &Attribute| attribute_name(attribute) == "noreturn";
+    declarator.attributes.iter().any(is_noreturn_attribute)
+        || specifiers.iter().any(|specifier| match specifier {
+            DeclarationSpecifier::FunctionSpecifier(FunctionSpecifier::Noreturn) => true,
+            DeclarationSpecifier::Attributes(attributes) => {
+                attributes.iter().any(is_noreturn_attribute)
+            }
+            _ => false,
+        })
+}
+
+//the alignment asked for by the aligned attributes among the declaration specifiers, which
+//apply to every declarator
+fn specifier_attribute_alignment<'a>(
+    context: &mut SemanticContext,
+    specifiers: &[DeclarationSpecifier<'a>],
+    span: Span,
+) -> SemanticResult<Option<u64>> {
+    let mut alignment = None;
+    for specifier in specifiers {
+        if let DeclarationSpecifier::Attributes(attributes) = specifier {
+            let placement = attribute_placement(context, attributes, span)?;
+            alignment = alignment.max(placement.alignment);
+        }
+    }
+
+    Ok(alignment)
+}
+
+//the placement asked for by the GNU aligned and packed attributes, others are ignored
+fn attribute_placement<'a>(
+    context: &mut SemanticContext,
+    attributes: &[Attribute<'a>],
+    span: Span,
+) -> SemanticResult<Placement> {
+    let mut placement = Placement::default();
+    for attribute in attributes {
+        let name = attribute_name(attribute);
+        match (name, &attribute.arguments[..]) {
+            ("packed", []) => placement.packed = true,
+            //the largest alignment of any type on x86-64
+            ("aligned", []) => placement.alignment = Some(16),
+            ("aligned", [alignment]) => {
+                let value = integer_constant(context, alignment)?;
+                if value <= 0 || value & (value - 1) != 0 {
+                    let message = "requested alignment is not a power of two.".to_string();
+                    return error(message, alignment.span);
+                }
+                let value = value as u64;
+                placement.alignment = Some(placement.alignment.map_or(value, |a| a.max(value)));
+            }
+            ("packed" | "aligned", _) => {
+                let message = format!("wrong number of arguments to attribute '{}'.", name);
+                return error(message, span);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(placement)
+}
+
+fn qualified(context: &mut SemanticContext, ty: TypeId, list: &[TypeQualifier]) -> TypeId {
+    let mut qualifiers = Qualifiers::default();
+    for qualifier in list {
+        match qualifier {
+            TypeQualifier::Const => qualifiers.is_const = true,
+            TypeQualifier::Restrict => qualifiers.is_restrict = true,
+            TypeQualifier::Volatile => qualifiers.is_volatile = true,
+            TypeQualifier::Atomic => qualifiers.is_atomic = true,
+        }
+    }
+    context.types.qualified(ty, qualifiers)
+}
+
+//the arithmetic type specifiers may come in any order, so they are sorted before they
+//are matched
+fn base_type<'a>(
+    context: &mut SemanticContext,
+    specifiers: &[&TypeSpecifier<'a>],
+    span: Span,
+) -> SemanticResult<TypeId> {
+    let mut words = Vec::new();
+    let mut other = None;
+    for specifier in specifiers {
+        let word = match specifier {
+            TypeSpecifier::Void => "void",
+            TypeSpecifier::Char => "char",
+            TypeSpecifier::Short => "short",
+            TypeSpecifier::Int => "int",
+            TypeSpecifier::Long => "long",
+            TypeSpecifier::Float => "float",
+            TypeSpecifier::Double => "double",
+            TypeSpecifier::Singned => "signed",
+            TypeSpecifier::Unsigned => "unsigned",
+            TypeSpecifier::Bool => "_Bool",
+            TypeSpecifier::Complex => "_Complex",
+            TypeSpecifier::AtomicTypeSpecifier(type_name) => {
+                other = Some(atomic_type(context, type_name)?);
+                continue;
+            }
+            TypeSpecifier::StructOrUnionSpecifier(specifier) => {
+                other = Some(struct_or_union_specifier(context, specifier)?);
+                continue;
+            }
+            TypeSpecifier::EnumSpecifier(specifier) => {
+                other = Some(enum_specifier(context, specifier)?);
+                continue;
+            }
+            TypeSpecifier::TypedefName(name) => {
+                other = Some(typedef_name(context, name, span)?);
+                continue;
+            }
+            TypeSpecifier::Typeof(Either::Left(type_name)) => {
+                other = Some(self::type_name(context, type_name)?);
+                continue;
+            }
+            TypeSpecifier::Typeof(Either::Right(operand)) => {
+                other = Some(expression(context, operand)?.ty);
+                continue;
+            }
+        };
+        words.push(word);
+    }
+    words.sort_unstable();
+
+    use FloatingKind::*;
+    use IntegerKind::*;
+    let types = &mut context.types;
+    let ty = match (words.as_slice(), other) {
+        ([], Some(ty)) => ty,
+        (_, Some(_)) => {
+            return error("invalid combination of type specifiers.".to_string(), span);
+        }
+        (["void"], None) => types.void(),
+        (["_Bool"], None) => types.integer(Bool),
+        (["char"], None) => types.integer(Char),
+        (["char", "signed"], None) => types.integer(SignedChar),
+        (["char", "unsigned"], None) => types.integer(UnsignedChar),
+        (["short"], None)
+        | (["int", "short"], None)
+        | (["short", "signed"], None)
+        | (["int", "short", "signed"], None) => types.integer(Short),
+        (["short", "unsigned"], None) | (["int", "short", "unsigned"], None) => {
+            types.integer(UnsignedShort)
+        }
+        //implicit int leaves no specifier at all
+        ([], None) | (["int"], None) | (["signed"], None) | (["int", "signed"], None) => {
+            types.integer(Int)
+        }
+        (["unsigned"], None) | (["int", "unsigned"], None) => types.integer(UnsignedInt),
+        (["long"], None)
+        | (["int", "long"], None)
+        | (["long", "signed"], None)
+        | (["int", "long", "signed"], None) => types.integer(Long),
+        (["long", "unsigned"], None) | (["int", "long", "unsigned"], None) => {
+            types.integer(UnsignedLong)
+        }
+        (["long", "long"], None)
+        | (["int", "long", "long"], None)
+        | (["long", "long", "signed"], None)
+        | (["int", "long", "long", "signed"], None) => types.integer(LongLong),
+        (["long", "long", "unsigned"], None) | (["int", "long", "long", "unsigned"], None) => {
+            types.integer(UnsignedLongLong)
+        }
+        (["float"], None) => types.floating(Float),
+        (["double"], None) => types.floating(Double),
+        (["double", "long"], None) => types.floating(LongDouble),
+        (["_Complex", "float"], None) => types.complex(Float),
+        (["_Complex"], None) | (["_Complex", "double"], None) => types.complex(Double),
+        (["_Complex", "double", "long"], None) => types.complex(LongDouble),
+        _ => return error("invalid combination of type specifiers.".to_string(), span),
+    };
+
+    Ok(ty)
+}
+
+//the type named by _Atomic ( type-name ), which may not be an array, a function, an atomic
+//or a qualified type
+fn atomic_type<'a>(
+    context: &mut SemanticContext,
+    type_name: &TypeName<'a>,
+) -> SemanticResult<TypeId> {
+    let ty = self::type_name(context, type_name)?;
+    let types = &context.types;
+    let kind = if types.is_array(ty) {
+        Some("array")
+    } else if types.is_function(ty) {
+        Some("function")
+    } else if types.qualifiers(ty).is_atomic {
+        Some("atomic")
+    } else if types.qualifiers(ty) != Qualifiers::default() {
+        Some("qualified")
+    } else {
+        None
+    };
+    if let Some(kind) = kind {
+        let message = format!(
+            "_Atomic cannot be applied to {} type '{}'.",
+            kind,
+            types.display(ty)
+        );
+        return error(message, type_name.span);
+    }
+
+    let qualifiers = Qualifiers {
+        is_atomic: true,
+        ..Qualifiers::default()
+    };
+    Ok(context.types.qualified(ty, qualifiers))
+}
+
+fn typedef_name(context: &SemanticContext, name: &str, span: Span) -> SemanticResult<TypeId> {
+    match lookup(context, name).map(|id| symbol(context, id)) {
+        Some(symbol) if symbol.kind == SymbolKind::Typedef => Ok(symbol.ty),
+        _ => error(format!("unknown type name '{}'.", name), span),
+    }
+}
+
+fn struct_or_union_specifier<'a>(
+    context: &mut SemanticContext,
+    specifier: &StructOrUnionSpecifier<'a>,
+) -> SemanticResult<TypeId> {
+    let kind = match specifier.kind {
+        StructOrUnion::Struct => RecordKind::Struct,
+        StructOrUnion::Union => RecordKind::Union,
+    };
+    let (name, members) = match &specifier.name_or_menbers {
+        EitherOrBoth::Both(name, members) => (Some(*name), Some(members)),
+        EitherOrBoth::Left(name) => (Some(*name), None),
+        EitherOrBoth::Right(members) => (None, Some(members)),
+    };
+
+    let is_kind = |types: &TypeArena, ty: TypeId| {
+        types
+            .record_of(ty)
+            .is_some_and(|record| types.record(record).kind == kind)
+    };
+    let new = |types: &mut TypeArena| types.new_record(kind, name.map(str::to_string));
+    let ty = tag_type(
+        context,
+        name,
+        members.is_some(),
+        is_kind,
+        new,
+        specifier.span,
+    );
+
+    if let Some(members) = members {
+        let (mut members, placements): (Vec<_>, Vec<_>) =
+            struct_members(context, members)?.into_iter().unzip();
+        let placement = attribute_placement(context, &specifier.attributes, specifier.span)?;
+        let pack = pack_at(context, specifier.span.begin);
+        let (size, align) = layout(
+            &context.types,
+            kind,
+            &mut members,
+            &placements,
+            placement,
+            pack,
+        );
+        let record = context.types.record_of(ty).unwrap();
+        let record = context.types.record_mut(record);
+        record.members = Some(members);
+        record.size = size;
+        record.align = align;
+    }
+
+    Ok(ty)
+}
+
+//a definition declares a new type in the current scope unless it completes one declared
+//there, a reference finds the type in any enclosing scope and declares it if there is none
+fn tag_type(
+    context: &mut SemanticContext,
+    name: Option<&str>,
+    is_definition: bool,
+    is_kind: impl Fn(&TypeArena, TypeId) -> bool,
+    new: impl FnOnce(&mut TypeArena) -> TypeId,
+    span: Span,
+) -> TypeId {
+    let name = match name {
+        Some(name) => name,
+        None => return new(&mut context.types),
+    };
+    let previous = if is_definition {
+        lookup_tag_in_current_scope(context, name)
+    } else {
+        lookup_tag(context, name)
+    };
+
+    if let Some(previous) = previous {
+        if !is_kind(&context.types, previous.ty) {
+            let message = format!("'{}' defined as a wrong kind of tag.", name);
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous use is here.",
+                previous.span,
+            );
+        } else if is_definition && context.types.is_complete(previous.ty) {
+            let message = format!("redefinition of '{}'.", display_type(context, previous.ty));
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous definition is here.",
+                previous.span,
+            );
+        } else {
+            return previous.ty;
+        }
+    }
+
+    //the conflicting declaration is replaced so that later uses refer to this one
+    let ty = new(&mut context.types);
+    if scope_kind(context) == ScopeKind::Prototype {
+        let message = format!(
+            "declaration of '{}' will not be visible outside of this function.",
+            display_type(context, ty)
+        );
+        report(context, Diagnostic::warning(message, span));
+    }
+    declare_tag(context, name, ty, span);
+    ty
+}
+
+//a struct or union declared by a declaration without declarators, as in struct s;, is a
+//new type in the current scope even if the tag is visible from an enclosing one
+fn forward_declaration(context: &mut SemanticContext, specifiers: &[DeclarationSpecifier]) {
+    for specifier in specifiers {
+        if let DeclarationSpecifier::TypeSpecifier(TypeSpecifier::StructOrUnionSpecifier(
+            specifier,
+        )) = specifier
+        {
+            if let EitherOrBoth::Left(name) = specifier.name_or_menbers {
+                if lookup_tag_in_current_scope(context, name).is_none() {
+                    let kind = match specifier.kind {
+                        StructOrUnion::Struct => RecordKind::Struct,
+                        StructOrUnion::Union => RecordKind::Union,
+                    };
+                    let ty = context.types.new_record(kind, Some(name.to_string()));
+                    declare_tag(context, name, ty, specifier.span);
+                }
+            }
+        }
+    }
+}
+
+//the members with where they ask to be placed
+fn struct_members<'a>(
+    context: &mut SemanticContext,
+    declarations: &[StructDeclaration<'a>],
+) -> SemanticResult<Vec<(Member, Placement)>> {
+    let mut result: Vec<(Member, Placement)> = Vec::new();
+    for (index, declaration) in declarations.iter().enumerate() {
+        let span = declaration.span;
+        let (base, alignment) =
+            member_specifiers(context, &declaration.specifier_qualifier_list, span)?;
+
+        //a struct or union without a name or declarator is an anonymous member, whose
+        //members are found as if they were members of the enclosing one
+        if declaration.declarators.is_empty() {
+            let anonymous = context
+                .types
+                .record_of(base)
+                .is_some_and(|record| context.types.record(record).name.is_none());
+            if !anonymous {
+                let message = "declaration does not declare anything.".to_string();
+                report(context, Diagnostic::warning(message, span));
+                continue;
+            }
+            let placement = Placement {
+                alignment: alignment.map(|(alignment, _)| alignment),
+                packed: false,
+            };
+            let member = Member {
+                name: None,
+                ty: base,
+                bit_width: None,
+                offset: 0,
+                bit_offset: 0,
+            };
+            result.push((member, placement));
+            continue;
+        }
+
+        let is_last = index + 1 == declarations.len();
+        for (position, declarator) in declaration.declarators.iter().enumerate() {
+            let (declarator, width) = match declarator {
+                EitherOrBoth::Both(declarator, width) => (Some(declarator), Some(width)),
+                EitherOrBoth::Left(declarator) => (Some(declarator), None),
+                EitherOrBoth::Right(width) => (None, Some(width)),
+            };
+            let first = context.types.length_count();
+            let ty = match declarator {
+                Some(declarator) => declarator_type(context, declarator, base)?,
+                None => base,
+            };
+            let name = declarator.map(|declarator| declarator.identifier());
+            let span = declarator.map_or(span, |declarator| declarator.span());
+            let display_name = name.unwrap_or("<anonymous>");
+            if context.types.lengths_from(first).next().is_some() {
+                let message = format!(
+                    "member '{}' of a struct or union cannot have a variably modified type.",
+                    display_name
+                );
+                return error(message, span);
+            }
+
+            if let Some(name) = name {
+                if result
+                    .iter()
+                    .any(|(member, _)| member.name.as_deref() == Some(name))
+                {
+                    return error(format!("duplicate member '{}'.", name), span);
+                }
+            }
+            let is_flexible = matches!(
+                context.types.kind(ty),
+                TypeKind::Array(_, ArraySize::Incomplete)
+            );
+            if is_flexible && !(is_last && position + 1 == declaration.declarators.len()) {
+                let message = format!(
+                    "flexible array member '{}' is not at the end of the struct.",
+                    display_name
+                );
+                return error(message, span);
+            }
+            if !is_flexible && !context.types.is_complete(ty) {
+                let message = format!(
+                    "field '{}' has incomplete type '{}'.",
+                    display_name,
+                    context.types.display(ty)
+                );
+                return error(message, span);
+            }
+
+            let bit_width = match width {
+                Some(width) => Some(bit_field_width(context, width, ty, name, span)?),
+                None => None,
+            };
+            let mut placement = match declarator {
+                Some(declarator) => attribute_placement(context, &declarator.attributes, span)?,
+                None => Placement::default(),
+            };
+            if let Some((alignment, alignment_span)) = alignment {
+                requested_alignment(
+                    context,
+                    alignment,
+                    ty,
+                    bit_width,
+                    display_name,
+                    alignment_span,
+                )?;
+                if alignment > 0 {
+                    placement.alignment = placement.alignment.max(Some(alignment));
+                }
+            }
+            let member = Member {
+                name: name.map(str::to_string),
+                ty,
+                bit_width,
+                offset: 0,
+                bit_offset: 0,
+            };
+            result.push((member, placement));
+        }
+    }
+
+    Ok(result)
+}
+
+//_Alignas cannot make an object or a member less aligned than its type, nor align a
+//bit-field
+fn requested_alignment(
+    context: &SemanticContext,
+    alignment: u64,
+    ty: TypeId,
+    bit_width: Option<u64>,
+    name: &str,
+    span: Span,
+) -> SemanticResult<()> {
+    if bit_width.is_some() {
+        return error(
+            format!("alignment specified for bit-field '{}'.", name),
+            span,
+        );
+    }
+    match context.types.align_of(ty) {
+        Some(minimum) if alignment != 0 && alignment < minimum => {
+            let message = format!(
+                "requested alignment is less than minimum alignment of {} for type '{}'.",
+                minimum,
+                display_type(context, ty)
+            );
+            error(message, span)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn bit_field_width<'a>(
+    context: &mut SemanticContext,
+    width: &Expression<'a>,
+    ty: TypeId,
+    name: Option<&str>,
+    span: Span,
+) -> SemanticResult<u64> {
+    let display_name = name.unwrap_or("<anonymous>");
+    let kind = match context.types.integer_kind(ty) {
+        Some(kind) => kind,
+        None => {
+            let message = format!(
+                "bit-field '{}' has non-integer type '{}'.",
+                display_name,
+                context.types.display(ty)
+            );
+            return error(message, span);
+        }
+    };
+    let width = integer_constant(context, width)?;
+    if width < 0 {
+        let message = format!("bit-field '{}' has negative width.", display_name);
+        return error(message, span);
+    }
+    if width == 0 && name.is_some() {
+        let message = format!("named bit-field '{}' has zero width.", display_name);
+        return error(message, span);
+    }
+    if width as u64 > u64::from(kind.width()) {
+        let message = format!(
+            "width of bit-field '{}' ({} bits) exceeds the width of its type ({} bits).",
+            display_name,
+            width,
+            kind.width()
+        );
+        return error(message, span);
+    }
+
+    Ok(width as u64)
+}
+
+fn enum_specifier<'a>(
+    context: &mut SemanticContext,
+    specifier: &EnumSpecifier<'a>,
+) -> SemanticResult<TypeId> {
+    let (name, enumerators) = match &specifier.name_or_menbers {
+        EitherOrBoth::Both(name, enumerators) => (Some(*name), Some(enumerators)),
+        EitherOrBoth::Left(name) => (Some(*name), None),
+        EitherOrBoth::Right(enumerators) => (None, Some(enumerators)),
+    };
+
+    let is_kind = |types: &TypeArena, ty: TypeId| matches!(types.kind(ty), TypeKind::Enum(_));
+    let new = |types: &mut TypeArena| types.new_enumeration(name.map(str::to_string));
+    let ty = tag_type(
+        context,
+        name,
+        enumerators.is_some(),
+        is_kind,
+        new,
+        specifier.span,
+    );
+
+    if let Some(enumerators) = enumerators {
+        let int = context.types.int();
+        let mut value = 0;
+        for enumerator in enumerators.iter() {
+            if let Some(expression) = &enumerator.value {
+                value = integer_constant(context, expression)?;
+            }
+            if value < i128::from(i32::MIN) || value > i128::from(i32::MAX) {
+                let message = format!(
+                    "value of enumerator '{}' is not representable as 'int'.",
+                    enumerator.name
+                );
+                return error(message, enumerator.span);
+            }
+            let kind = SymbolKind::EnumerationConstant(value as i64);
+            declare(
+                context,
+                enumerator.name,
+                int,
+                kind,
+                None,
+                Definition::Definition,
+                enumerator.span,
+            );
+            value += 1;
+        }
+        if let TypeKind::Enum(id) = *context.types.kind(ty) {
+            context.types.enumeration_mut(id).complete = true;
+        }
+    }
+
+    Ok(ty)
+}
+
+//the value of an integer constant expression, which is an error if it cannot be folded
+pub fn integer_constant<'a>(
+    context: &mut SemanticContext,
+    expression: &Expression<'a>,
+) -> SemanticResult<i128> {
+    let typed = self::expression(context, expression)?;
+    integer_value(context, &typed)
+}
+
+pub fn type_name<'a>(
+    context: &mut SemanticContext,
+    type_name: &TypeName<'a>,
+) -> SemanticResult<TypeId> {
+    let span = type_name.span;
+    let base = specifier_qualifier_list(context, &type_name.specifier_qualifier_list, span)?;
+    match &type_name.declarator {
+        Some(declarator) => abstract_declarator_type(context, declarator, base, span),
+        None => Ok(base),
+    }
+}
+
+//declarators are applied from the outside in, so the pointers of the outermost declarator
+//are applied to the base type first
+pub fn declarator_type<'a>(
+    context: &mut SemanticContext,
+    declarator: &Declarator<'a>,
+    base: TypeId,
+) -> SemanticResult<TypeId> {
+    let ty = pointer_type(context, &declarator.pointer, base, declarator.span())?;
+    direct_declarator_type(context, &declarator.identifier, ty, declarator.span())
+}
+
+fn direct_declarator_type<'a>(
+    context: &mut SemanticContext,
+    declarator: &DirectDeclarator<'a>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    match declarator {
+        DirectDeclarator::Identifier(_, _) => Ok(ty),
+        DirectDeclarator::Declarator(declarator) => declarator_type(context, declarator, ty),
+        DirectDeclarator::Array(declarator, _, size) => {
+            let ty = array_type(context, ty, size.as_ref(), span)?;
+            direct_declarator_type(context, declarator, ty, span)
+        }
+        DirectDeclarator::Function(declarator, parameters) => {
+            let parameters = match parameters {
+                Either::Left(parameters) => Some(parameters),
+                Either::Right(_) => None,
+            };
+            let ty = function_type(context, ty, parameters, span)?;
+            direct_declarator_type(context, declarator, ty, span)
+        }
+    }
+}
+
+fn abstract_declarator_type<'a>(
+    context: &mut SemanticContext,
+    declarator: &AbstractDeclarator<'a>,
+    base: TypeId,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    let ty = pointer_type(context, &declarator.pointer, base, span)?;
+    match &declarator.declarator {
+        Some(declarator) => direct_abstract_declarator_type(context, declarator, ty, span),
+        None => Ok(ty),
+    }
+}
+
+fn direct_abstract_declarator_type<'a>(
+    context: &mut SemanticContext,
+    declarator: &DirectAbstarctDeclarator<'a>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    let (inner, ty) = match declarator {
+        DirectAbstarctDeclarator::Declarator(declarator) => {
+            return abstract_declarator_type(context, declarator, ty, span);
+        }
+        DirectAbstarctDeclarator::Array(inner, _, size) => {
+            (inner, array_type(context, ty, size.as_ref(), span)?)
+        }
+        DirectAbstarctDeclarator::Function(inner, parameters) => (
+            inner,
+            function_type(context, ty, parameters.as_ref(), span)?,
+        ),
+    };
+    match inner {
+        Some(inner) => direct_abstract_declarator_type(context, inner, ty, span),
+        None => Ok(ty),
+    }
+}
+
+//the aligned attribute after a `*` can only raise the alignment of the pointer type
+fn pointer_type(
+    context: &mut SemanticContext,
+    pointer: &Pointer,
+    base: TypeId,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    let mut ty = base;
+    for star in pointer {
+        ty = context.types.pointer(ty);
+        ty = qualified(context, ty, &star.qualifiers);
+        let placement = attribute_placement(context, &star.attributes, span)?;
+        let minimum = context.types.align_of(ty);
+        if let Some(alignment) = placement.alignment.filter(|a| Some(*a) > minimum) {
+            ty = context.types.aligned(ty, alignment);
+        }
+    }
+
+    Ok(ty)
+}
+
+fn array_type<'a>(
+    context: &mut SemanticContext,
+    element: TypeId,
+    size: Option<&Expression<'a>>,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    if context.types.is_function(element) {
+        return error("array of functions is not allowed.".to_string(), span);
+    }
+    if !context.types.is_complete(element) {
+        let message = format!(
+            "array has incomplete element type '{}'.",
+            context.types.display(element)
+        );
+        return error(message, span);
+    }
+
+    let size = match size {
+        Some(written) => {
+            let size = expression(context, written)?;
+            if !context.types.is_integer(size.ty) {
+                let message = format!(
+                    "size of array has non-integer type '{}'.",
+                    context.types.display(size.ty)
+                );
+                return error(message, size.span);
+            }
+            match evaluate(context, &size)? {
+                Some(Constant::Integer(value)) if value < 0 => {
+                    return error("array has negative size.".to_string(), size.span);
+                }
+                Some(Constant::Integer(value)) => ArraySize::Constant(value as u64),
+                _ if is_file_scope(context) => {
+                    let message = "variable length array declaration not allowed at file scope.";
+                    return error(message.to_string(), size.span);
+                }
+                _ => {
+                    let length = context.types.new_length(written.spelling(), written.span);
+                    ArraySize::Variable(length)
+                }
+            }
+        }
+        None => ArraySize::Incomplete,
+    };
+
+    Ok(context.types.array(element, size))
+}
+
+//parameters is None for `()`, which declares a function without a prototype
+fn function_type<'a>(
+    context: &mut SemanticContext,
+    return_type: TypeId,
+    parameters: Option<&ParameterTypeList<'a>>,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    if context.types.is_array(return_type) || context.types.is_function(return_type) {
+        let message = format!(
+            "function cannot return type '{}'.",
+            context.types.display(return_type)
+        );
+        return error(message, span);
+    }
+
+    let parameters = match parameters {
+        Some(parameters) => parameters,
+        None => {
+            return Ok(context.types.function(FunctionType {
+                return_type,
+                parameters: Vec::new(),
+                variadic: false,
+                prototype: false,
+            }))
+        }
+    };
+
+    //the parameters are in scope in the rest of the parameter list
+    push_scope(context, ScopeKind::Prototype);
+    let result = parameter_types(context, parameters);
+    pop_scope(context);
+    let parameter_types = result?;
+
+    Ok(context.types.function(FunctionType {
+        return_type,
+        parameters: parameter_types,
+        variadic: parameters.variable_parameter,
+        prototype: true,
+    }))
+}
+
+fn parameter_types<'a>(
+    context: &mut SemanticContext,
+    parameters: &ParameterTypeList<'a>,
+) -> SemanticResult<Vec<TypeId>> {
+    let mut result = Vec::new();
+    for parameter in parameters.parameters() {
+        let ty = parameter_type(context, parameter)?;
+        //`(void)` is an empty parameter list
+        if context.types.is_void(ty) && parameter.declarator.is_none() {
+            if parameters.parameters().len() > 1 || parameters.variable_parameter {
+                let message = "'void' must be the first and only parameter.".to_string();
+                return error(message, parameter.span);
+            }
+            break;
+        }
+        if let Some(Either::Left(declarator)) = &parameter.declarator {
+            declare(
+                context,
+                declarator.identifier(),
+                ty,
+                SymbolKind::Object,
+                None,
+                Definition::Definition,
+                declarator.span(),
+            );
+        }
+        result.push(ty);
+    }
+
+    Ok(result)
+}
+
+//a parameter of array or function type is adjusted to a pointer, which for an array gets
+//the qualifiers written in its brackets, as in `int a[const]`
+pub fn parameter_type<'a>(
+    context: &mut SemanticContext,
+    parameter: &ParameterDeclaration<'a>,
+) -> SemanticResult<TypeId> {
+    let (base, _, alignment) =
+        declaration_specifiers(context, &parameter.specifiers, parameter.span)?;
+    if let Some((_, span)) = alignment {
+        return error("alignment specified for parameter.".to_string(), span);
+    }
+    let ty = match &parameter.declarator {
+        Some(Either::Left(declarator)) => declarator_type(context, declarator, base)?,
+        Some(Either::Right(declarator)) => {
+            abstract_declarator_type(context, declarator, base, parameter.span)?
+        }
+        None => base,
+    };
+
+    let ty = match *context.types.kind(ty) {
+        TypeKind::Array(element, _) => {
+            let pointer = context.types.pointer(element);
+            let qualifiers = match &parameter.declarator {
+                Some(Either::Left(declarator)) => array_qualifiers(&declarator.identifier),
+                Some(Either::Right(declarator)) => declarator
+                    .declarator
+                    .as_ref()
+                    .and_then(abstract_array_qualifiers),
+                None => None,
+            };
+            match qualifiers {
+                Some(qualifiers) => qualified(context, pointer, qualifiers),
+                None => pointer,
+            }
+        }
+        TypeKind::Function(_) => context.types.pointer(ty),
+        _ => ty,
+    };
+    Ok(ty)
+}
+
+//the derivation nearest the identifier is the outermost one of the type, so for an array
+//type the brackets that made it are the innermost ones
+fn array_qualifiers<'b>(declarator: &'b DirectDeclarator) -> Option<&'b [TypeQualifier]> {
+    match declarator {
+        DirectDeclarator::Identifier(_, _) => None,
+        DirectDeclarator::Declarator(declarator) => array_qualifiers(&declarator.identifier),
+        DirectDeclarator::Array(inner, qualifiers, _) => {
+            array_qualifiers(inner).or(Some(qualifiers))
+        }
+        DirectDeclarator::Function(inner, _) => array_qualifiers(inner),
+    }
+}
+
+fn abstract_array_qualifiers<'b>(
+    declarator: &'b DirectAbstarctDeclarator,
+) -> Option<&'b [TypeQualifier]> {
+    match declarator {
+        DirectAbstarctDeclarator::Declarator(declarator) => declarator
+            .declarator
+            .as_ref()
+            .and_then(abstract_array_qualifiers),
+        DirectAbstarctDeclarator::Array(inner, qualifiers, _) => inner
+            .as_deref()
+            .and_then(abstract_array_qualifiers)
+            .or(Some(qualifiers)),
+        DirectAbstarctDeclarator::Function(inner, _) => {
+            inner.as_deref().and_then(abstract_array_qualifiers)
+        }
+    }
+}
@@ -0,0 +1,1289 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::parser::declaration::*;
+use crate::parser::expression::*;
+use crate::parser::statement::*;
+use crate::semantic::constant::*;
+use crate::semantic::conversion::*;
+use crate::semantic::declaration::*;
+use crate::semantic::initializer::*;
+use crate::semantic::statement::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use crate::warning::*;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+//ty is the type of the expression itself, before an array or function designator is
+//converted to a pointer
+#[derive(Debug)]
+pub struct TypedExpression<'a> {
+    pub kind: TypedExpressionKind<'a>,
+    pub ty: TypeId,
+    pub category: ValueCategory,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueCategory {
+    LValue,
+    RValue,
+    FunctionDesignator,
+}
+
+//a compound literal outside the body of a function has static storage duration, and is
+//numbered among those of the translation unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompoundLiteralId(usize);
+
+#[derive(Debug)]
+pub enum TypedExpressionKind<'a> {
+    Integer(i128), // a value of the type of the expression
+    String(&'a str),
+    Identifier(SymbolId),
+    Unary {
+        kind: UnaryKind,
+        operand: Box<TypedExpression<'a>>,
+    },
+    //assignments and the comma operator are binary operators too
+    Binary {
+        kind: BinaryOperatorKind,
+        lhs: Box<TypedExpression<'a>>,
+        rhs: Box<TypedExpression<'a>>,
+    },
+    //`lhs op= rhs`. the value of lhs is converted to computation, operator is applied, and
+    //the result is converted back to the type of lhs.
+    CompoundAssignment {
+        operator: BinaryOperatorKind,
+        lhs: Box<TypedExpression<'a>>,
+        rhs: Box<TypedExpression<'a>>,
+        computation: TypeId,
+    },
+    //then is None for `condition ?: otherwise`, GNU extension
+    Conditional {
+        condition: Box<TypedExpression<'a>>,
+        then: Option<Box<TypedExpression<'a>>>,
+        otherwise: Box<TypedExpression<'a>>,
+    },
+    //to the type of the expression
+    Cast {
+        #[allow(dead_code)] // only shown in the printed tree
+        kind: CastKind,
+        operand: Box<TypedExpression<'a>>,
+    },
+    Call {
+        callee: Box<TypedExpression<'a>>,
+        arguments: Vec<TypedExpression<'a>>,
+    },
+    //`a->m` is `(*a).m`, and a member of an anonymous member is a member of a member
+    Member {
+        operand: Box<TypedExpression<'a>>,
+        index: usize,
+    },
+    Sizeof(TypeId),
+    Alignof(TypeId),
+    CompoundLiteral {
+        initializer: Box<TypedInitializer<'a>>,
+        id: Option<CompoundLiteralId>, // of one with static storage duration
+    },
+    Statement(Box<TypedStatement<'a>>),
+    //the lengths of the variable length arrays of a type name are evaluated before the
+    //expression which uses it
+    VariableLengths {
+        lengths: Vec<(LengthId, TypedExpression<'a>)>,
+        operand: Box<TypedExpression<'a>>,
+    },
+}
+
+//the conversions the language applies without a cast operator are implicit casts, so an
+//operand always has the type its operator works on. the left operand of a compound
+//assignment is the exception, it is converted to the type of the right one, or to its
+//promoted type for a shift, before the operation and back after it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CastKind {
+    Explicit,
+    Implicit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryKind {
+    LogicalNot,
+    BitwiseNot,
+    Plus,
+    Minus,
+    Reference,
+    Dereference,
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
+}
+
+//the type of the value of the expression, with arrays and functions converted to pointers
+//and the qualifiers dropped
+pub fn value_type(context: &mut SemanticContext, expression: &TypedExpression) -> TypeId {
+    context.types.decay(expression.ty)
+}
+
+//analyzer body
+pub fn expression<'a>(
+    context: &mut SemanticContext,
+    expression: &Expression<'a>,
+) -> SemanticResult<TypedExpression<'a>> {
+    let span = expression.span;
+    match &expression.kind {
+        ExpressionKind::Immediate(immediate) => Ok(immediate_expression(context, immediate, span)),
+        ExpressionKind::Identifier(name) => identifier(context, name, span),
+        ExpressionKind::UnaryOperator { kind, operand } => unary(context, kind, operand, span),
+        ExpressionKind::BinaryOperator { kind, lhs, rhs } => binary(context, *kind, lhs, rhs, span),
+        ExpressionKind::TernaryOperator {
+            first,
+            second,
+            third,
+            ..
+        } => {
+            let condition = self::expression(context, first)?;
+            let then = self::expression(context, second)?;
+            let otherwise = self::expression(context, third)?;
+            conditional(context, condition, Some(then), otherwise, span)
+        }
+        ExpressionKind::CompoundLiteral {
+            type_name,
+            initializer,
+        } => {
+            let mut ty = self::type_name(context, type_name)?;
+            let (items, length) = initializer_list(context, initializer, ty, span)?;
+            let initializer = TypedInitializer::List(items);
+            if let TypeKind::Array(element, ArraySize::Incomplete) = *context.types.kind(ty) {
+                ty = context.types.array(element, ArraySize::Constant(length));
+            }
+            let id = match is_file_scope(context) {
+                true => {
+                    context.compound_literals += 1;
+                    Some(CompoundLiteralId(context.compound_literals - 1))
+                }
+                false => None,
+            };
+            Ok(TypedExpression {
+                kind: TypedExpressionKind::CompoundLiteral {
+                    initializer: Box::new(initializer),
+                    id,
+                },
+                ty,
+                category: ValueCategory::LValue,
+                span,
+            })
+        }
+        ExpressionKind::TypeOperator { kind, type_name } => {
+            let first = context.types.length_count();
+            let ty = self::type_name(context, type_name)?;
+            match kind {
+                TypeOperatorKind::Sizeof => {
+                    let lengths = type_name_lengths(context, type_name, first)?;
+                    let size = size_of(context, ty, span)?;
+                    Ok(with_lengths(lengths, size))
+                }
+                TypeOperatorKind::Alignof => {
+                    let kind = TypedExpressionKind::Alignof(ty);
+                    let ty = context.types.size_type();
+                    Ok(rvalue(kind, ty, span))
+                }
+            }
+        }
+        ExpressionKind::Offsetof {
+            type_name,
+            designators,
+        } => offsetof(context, type_name, designators, span),
+        ExpressionKind::Statement(body) => statement_expression(context, body, span),
+        ExpressionKind::GenericSelection {
+            controlling,
+            associations,
+        } => generic_selection(context, controlling, associations, span),
+    }
+}
+
+fn with_lengths<'a>(
+    lengths: Vec<(LengthId, TypedExpression<'a>)>,
+    expression: TypedExpression<'a>,
+) -> TypedExpression<'a> {
+    if lengths.is_empty() {
+        return expression;
+    }
+    let (ty, span) = (expression.ty, expression.span);
+    let kind = TypedExpressionKind::VariableLengths {
+        lengths,
+        operand: Box::new(expression),
+    };
+    rvalue(kind, ty, span)
+}
+
+fn rvalue<'a>(kind: TypedExpressionKind<'a>, ty: TypeId, span: Span) -> TypedExpression<'a> {
+    TypedExpression {
+        kind,
+        ty,
+        category: ValueCategory::RValue,
+        span,
+    }
+}
+
+//a decimal constant has the first of int and long which can represent it, and a character
+//constant has type int with the value of its char, which is signed on x86-64
+fn immediate_expression<'a>(
+    context: &mut SemanticContext,
+    immediate: &Immediate<'a>,
+    span: Span,
+) -> TypedExpression<'a> {
+    match immediate {
+        Immediate::Number(value) => {
+            let kind = if i32::try_from(*value).is_ok() {
+                IntegerKind::Int
+            } else {
+                IntegerKind::Long
+            };
+            let ty = context.types.integer(kind);
+            rvalue(TypedExpressionKind::Integer(i128::from(*value)), ty, span)
+        }
+        Immediate::Char(value) => {
+            let ty = context.types.int();
+            let value = i128::from(*value as u8 as i8);
+            rvalue(TypedExpressionKind::Integer(value), ty, span)
+        }
+        //a string literal is an array object holding its characters and a null character
+        Immediate::String(content) => {
+            let char = context.types.integer(IntegerKind::Char);
+            let size = ArraySize::Constant(string_bytes(content).len() as u64 + 1);
+            TypedExpression {
+                kind: TypedExpressionKind::String(content),
+                ty: context.types.array(char, size),
+                category: ValueCategory::LValue,
+                span,
+            }
+        }
+    }
+}
+
+fn identifier<'a>(
+    context: &mut SemanticContext,
+    name: &str,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let id = match lookup(context, name) {
+        Some(id) => id,
+        None => return error(format!("use of undeclared identifier '{}'.", name), span),
+    };
+    symbol_mut(context, id).used = true;
+    let symbol = symbol(context, id);
+    let category = match symbol.kind {
+        SymbolKind::Object => ValueCategory::LValue,
+        SymbolKind::Function => ValueCategory::FunctionDesignator,
+        SymbolKind::EnumerationConstant(_) => ValueCategory::RValue,
+        SymbolKind::Typedef => {
+            let message = format!("type name '{}' is not allowed in an expression.", name);
+            return error(message, span);
+        }
+    };
+
+    Ok(TypedExpression {
+        kind: TypedExpressionKind::Identifier(id),
+        ty: symbol.ty,
+        category,
+        span,
+    })
+}
+
+fn unary<'a>(
+    context: &mut SemanticContext,
+    kind: &UnaryOperatorKind<'a>,
+    operand: &Expression<'a>,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    //a call of an undeclared identifier declares it in C89
+    if let (UnaryOperatorKind::FunctionCall(arguments), ExpressionKind::Identifier(name)) =
+        (kind, &operand.kind)
+    {
+        if context.standard == Standard::C89 && lookup(context, name).is_none() {
+            declare_implicit_function(context, name, operand.span);
+        }
+        let callee = expression(context, operand)?;
+        return call(context, callee, arguments, span);
+    }
+
+    let operand = expression(context, operand)?;
+    let kind = match kind {
+        UnaryOperatorKind::LogicalNot => UnaryKind::LogicalNot,
+        UnaryOperatorKind::BitwiseNot => UnaryKind::BitwiseNot,
+        UnaryOperatorKind::Plus => UnaryKind::Plus,
+        UnaryOperatorKind::Minus => UnaryKind::Minus,
+        UnaryOperatorKind::Reference => UnaryKind::Reference,
+        UnaryOperatorKind::Dereference => UnaryKind::Dereference,
+        UnaryOperatorKind::PreIncrement => UnaryKind::PreIncrement,
+        UnaryOperatorKind::PreDecrement => UnaryKind::PreDecrement,
+        UnaryOperatorKind::PostIncrement => UnaryKind::PostIncrement,
+        UnaryOperatorKind::PostDecrement => UnaryKind::PostDecrement,
+        UnaryOperatorKind::Member(name) => return member(context, operand, name, span),
+        UnaryOperatorKind::PointerMember(name) => {
+            let operand = dereference(context, operand, span)?;
+            return member(context, operand, name, span);
+        }
+        UnaryOperatorKind::Cast(type_name) => {
+            let first = context.types.length_count();
+            let ty = self::type_name(context, type_name)?;
+            let lengths = type_name_lengths(context, type_name, first)?;
+            let cast = cast(context, operand, ty, span)?;
+            return Ok(with_lengths(lengths, cast));
+        }
+        UnaryOperatorKind::Sizeof => {
+            if is_bit_field(context, &operand) {
+                let message = "invalid application of 'sizeof' to bit-field.".to_string();
+                return error(message, span);
+            }
+            return size_of(context, operand.ty, span);
+        }
+        UnaryOperatorKind::FunctionCall(arguments) => {
+            return call(context, operand, arguments, span)
+        }
+    };
+    match kind {
+        UnaryKind::Dereference => return dereference(context, operand, span),
+        UnaryKind::PreIncrement
+        | UnaryKind::PreDecrement
+        | UnaryKind::PostIncrement
+        | UnaryKind::PostDecrement => modifiable_lvalue(context, &operand)?,
+        UnaryKind::Reference => addressable(context, &operand, span)?,
+        _ => {}
+    }
+
+    let operand_type = value_type(context, &operand);
+    let types = &mut context.types;
+    let valid = match kind {
+        UnaryKind::LogicalNot => types.is_scalar(operand_type),
+        UnaryKind::BitwiseNot => types.is_integer(operand_type),
+        UnaryKind::Plus | UnaryKind::Minus => types.is_arithmetic(operand_type),
+        UnaryKind::Reference => operand.category != ValueCategory::RValue,
+        _ => types.is_scalar(operand_type),
+    };
+    if !valid {
+        let message = if kind == UnaryKind::Reference {
+            format!(
+                "cannot take the address of an rvalue of type '{}'.",
+                types.display(operand.ty)
+            )
+        } else {
+            format!(
+                "invalid argument type '{}' to unary expression.",
+                types.display(operand_type)
+            )
+        };
+        return error(message, span);
+    }
+
+    //the operand of an increment or decrement is an object, which is not converted
+    let (operand, ty) = match kind {
+        UnaryKind::LogicalNot => (decayed(context, operand), context.types.int()),
+        UnaryKind::BitwiseNot | UnaryKind::Plus | UnaryKind::Minus => {
+            let operand = promoted(context, operand);
+            let ty = operand.ty;
+            (operand, ty)
+        }
+        UnaryKind::Reference => {
+            let ty = context.types.pointer(operand.ty);
+            (operand, ty)
+        }
+        _ => {
+            if context.types.is_pointer(operand_type) {
+                pointer_arithmetic(context, operand_type, span)?;
+            }
+            (operand, operand_type)
+        }
+    };
+    let kind = TypedExpressionKind::Unary {
+        kind,
+        operand: Box::new(operand),
+    };
+    Ok(rvalue(kind, ty, span))
+}
+
+//a cast converts a scalar to another scalar type, or any value to void
+fn cast<'a>(
+    context: &mut SemanticContext,
+    operand: TypedExpression<'a>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let ty = context.types.unqualified(ty);
+    let operand = decayed(context, operand);
+    let operand_type = value_type(context, &operand);
+    let types = &context.types;
+    if !types.is_void(ty) {
+        if !types.is_scalar(ty) {
+            let message = format!(
+                "used type '{}' where arithmetic or pointer type is required.",
+                types.display(ty)
+            );
+            return error(message, span);
+        }
+        if !types.is_scalar(operand_type) {
+            let message = format!(
+                "operand of type '{}' where arithmetic or pointer type is required.",
+                types.display(operand_type)
+            );
+            return error(message, span);
+        }
+        if types.is_pointer(operand_type) && types.is_floating(ty) {
+            let message = format!("pointer cannot be cast to type '{}'.", types.display(ty));
+            return error(message, span);
+        }
+        if types.is_floating(operand_type) && types.is_pointer(ty) {
+            let message = format!(
+                "operand of type '{}' cannot be cast to a pointer type.",
+                types.display(operand_type)
+            );
+            return error(message, span);
+        }
+    }
+
+    let kind = TypedExpressionKind::Cast {
+        kind: CastKind::Explicit,
+        operand: Box::new(operand),
+    };
+    Ok(rvalue(kind, ty, span))
+}
+
+//pointer arithmetic scales the integer operand by the size of the pointed type, which
+//must be a complete object type. GNU C takes the size of void and functions as 1.
+fn pointer_arithmetic(context: &mut SemanticContext, ty: TypeId, span: Span) -> SemanticResult<()> {
+    let pointee = context.types.pointee(ty).unwrap();
+    if context.types.scaled_pointee(ty).is_none() {
+        if context.standard != Standard::Gnu11 {
+            let message = format!(
+                "arithmetic on a pointer to '{}' is a GNU extension, use -std=gnu11.",
+                display_type(context, pointee)
+            );
+            return error(message, span);
+        }
+    } else if !context.types.is_complete(pointee) {
+        let message = format!(
+            "arithmetic on a pointer to an incomplete type '{}'.",
+            display_type(context, pointee)
+        );
+        return error(message, span);
+    }
+
+    Ok(())
+}
+
+//the operand of an assignment, an increment or a decrement must designate an object which
+//may be modified
+pub fn modifiable_lvalue(
+    context: &SemanticContext,
+    expression: &TypedExpression,
+) -> SemanticResult<()> {
+    let types = &context.types;
+    let ty = expression.ty;
+    let message = match expression.category {
+        ValueCategory::RValue => "expression is not assignable.".to_string(),
+        ValueCategory::FunctionDesignator => {
+            format!("non-object type '{}' is not assignable.", types.display(ty))
+        }
+        ValueCategory::LValue if types.is_array(ty) => {
+            format!("array type '{}' is not assignable.", types.display(ty))
+        }
+        ValueCategory::LValue if !types.is_complete(ty) => {
+            format!("incomplete type '{}' is not assignable.", types.display(ty))
+        }
+        ValueCategory::LValue if types.qualifiers(ty).is_const => match expression.kind {
+            TypedExpressionKind::Identifier(id) => format!(
+                "cannot assign to variable '{}' with const-qualified type '{}'.",
+                symbol(context, id).name,
+                types.display(ty)
+            ),
+            _ => format!(
+                "read-only location of type '{}' is not assignable.",
+                types.display(ty)
+            ),
+        },
+        ValueCategory::LValue if types.has_const_member(ty) => format!(
+            "cannot assign to an object of type '{}' with a const-qualified member.",
+            types.display(ty)
+        ),
+        ValueCategory::LValue => return Ok(()),
+    };
+
+    error(message, expression.span)
+}
+
+//a bit-field and an object declared register have no address, which also holds for their
+//members
+fn addressable(
+    context: &SemanticContext,
+    expression: &TypedExpression,
+    span: Span,
+) -> SemanticResult<()> {
+    if is_bit_field(context, expression) {
+        return error("address of bit-field requested.".to_string(), span);
+    }
+    let mut current = expression;
+    while let TypedExpressionKind::Member { operand, .. } = &current.kind {
+        current = operand;
+    }
+    if let TypedExpressionKind::Identifier(id) = current.kind {
+        let symbol = symbol(context, id);
+        if matches!(symbol.storage, Some(StorageClassSpecifier::Register)) {
+            let message = format!("address of register variable '{}' requested.", symbol.name);
+            return error(message, span);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_bit_field(context: &SemanticContext, expression: &TypedExpression) -> bool {
+    match &expression.kind {
+        TypedExpressionKind::Member { operand, index } => {
+            let types = &context.types;
+            types.record_of(operand.ty).is_some_and(|record| {
+                types.record(record).members.as_ref().unwrap()[*index]
+                    .bit_width
+                    .is_some()
+            })
+        }
+        _ => false,
+    }
+}
+
+fn declare_implicit_function(context: &mut SemanticContext, name: &str, span: Span) {
+    let message = format!("implicit declaration of function '{}'.", name);
+    let warning = Warning::ImplicitFunctionDeclaration;
+    report(context, Diagnostic::named_warning(warning, message, span));
+
+    //the declaration is `extern int name();` at file scope, unless a block scope
+    //declaration which is no longer visible already declared the function
+    if let Some(id) = context.external.get(name).copied() {
+        context.scopes[0].ordinary.insert(name.to_string(), id);
+        return;
+    }
+    let int = context.types.int();
+    let ty = context.types.function(FunctionType {
+        return_type: int,
+        parameters: Vec::new(),
+        variadic: false,
+        prototype: false,
+    });
+    let id = context.symbols.add(Symbol {
+        name: name.to_string(),
+        ty,
+        kind: SymbolKind::Function,
+        storage: None,
+        linkage: Linkage::External,
+        definition: Definition::Declaration,
+        noreturn: false,
+        parameter: false,
+        used: false,
+        alignment: 0,
+        asm_label: None,
+        span,
+    });
+    context.scopes[0].ordinary.insert(name.to_string(), id);
+    context.external.insert(name.to_string(), id);
+}
+
+fn dereference<'a>(
+    context: &mut SemanticContext,
+    operand: TypedExpression<'a>,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let operand = decayed(context, operand);
+    let operand_type = value_type(context, &operand);
+    let ty = match context.types.pointee(operand_type) {
+        Some(ty) => ty,
+        None => {
+            let message = format!(
+                "indirection requires pointer operand ('{}' invalid).",
+                context.types.display(operand_type)
+            );
+            return error(message, span);
+        }
+    };
+    let category = if context.types.is_function(ty) {
+        ValueCategory::FunctionDesignator
+    } else {
+        ValueCategory::LValue
+    };
+
+    Ok(TypedExpression {
+        kind: TypedExpressionKind::Unary {
+            kind: UnaryKind::Dereference,
+            operand: Box::new(operand),
+        },
+        ty,
+        category,
+        span,
+    })
+}
+
+//a member of a qualified struct has the qualifiers of the struct too
+fn member<'a>(
+    context: &mut SemanticContext,
+    operand: TypedExpression<'a>,
+    name: &str,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let types = &mut context.types;
+    if !types.is_record(operand.ty) {
+        let message = format!(
+            "member reference base type '{}' is not a structure or union.",
+            types.display(operand.ty)
+        );
+        return error(message, span);
+    }
+    let path = match types.find_member(operand.ty, name) {
+        Some(path) => path,
+        None => {
+            let message = format!(
+                "no member named '{}' in '{}'.",
+                name,
+                types.display(operand.ty)
+            );
+            return error(message, span);
+        }
+    };
+
+    let qualifiers = types.qualifiers(operand.ty);
+    let category = operand.category;
+    let mut result = operand;
+    for (index, ty) in path {
+        result = TypedExpression {
+            kind: TypedExpressionKind::Member {
+                operand: Box::new(result),
+                index,
+            },
+            ty: types.qualified(ty, qualifiers),
+            category,
+            span,
+        };
+    }
+
+    Ok(result)
+}
+
+//the arguments are converted to the types of the parameters if the function has a
+//prototype, and promoted if not
+fn call<'a>(
+    context: &mut SemanticContext,
+    callee: TypedExpression<'a>,
+    arguments: &[Expression<'a>],
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let callee = decayed(context, callee);
+    let callee_type = value_type(context, &callee);
+    let function = context
+        .types
+        .pointee(callee_type)
+        .and_then(|pointee| context.types.function_type(pointee));
+    let function = match function {
+        Some(function) => function.clone(),
+        None => {
+            let message = format!(
+                "called object type '{}' is not a function or function pointer.",
+                context.types.display(callee_type)
+            );
+            return error(message, span);
+        }
+    };
+
+    let expected = function.parameters.len();
+    if function.prototype
+        && (arguments.len() < expected || (arguments.len() > expected && !function.variadic))
+    {
+        let message = format!(
+            "too {} arguments to function call, expected {}, have {}.",
+            if arguments.len() < expected {
+                "few"
+            } else {
+                "many"
+            },
+            expected,
+            arguments.len()
+        );
+        return error(message, span);
+    }
+
+    let mut typed_arguments = Vec::new();
+    for (index, argument) in arguments.iter().enumerate() {
+        let argument = expression(context, argument)?;
+        let argument = match function.parameters.get(index) {
+            Some(parameter) if function.prototype => {
+                assign(context, argument, *parameter, AssignmentKind::Argument)?
+            }
+            _ => default_argument_promoted(context, argument)?,
+        };
+        typed_arguments.push(argument);
+    }
+
+    let ty = context.types.unqualified(function.return_type);
+    let kind = TypedExpressionKind::Call {
+        callee: Box::new(callee),
+        arguments: typed_arguments,
+    };
+    Ok(rvalue(kind, ty, span))
+}
+
+fn size_of<'a>(
+    context: &mut SemanticContext,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    if context.types.is_function(ty) || !context.types.is_complete(ty) {
+        let message = format!(
+            "invalid application of 'sizeof' to an incomplete type '{}'.",
+            context.types.display(ty)
+        );
+        return error(message, span);
+    }
+
+    let size_type = context.types.size_type();
+    Ok(rvalue(TypedExpressionKind::Sizeof(ty), size_type, span))
+}
+
+//the offset in bytes of the designated member or element, with the indices as integer
+//constant expressions. it wraps around like size_t for a negative index.
+fn offsetof<'a>(
+    context: &mut SemanticContext,
+    type_name: &TypeName<'a>,
+    designators: &[Designator<'a>],
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let mut ty = self::type_name(context, type_name)?;
+    if !context.types.is_record(ty) {
+        let message = format!(
+            "offsetof requires struct or union type, '{}' invalid.",
+            display_type(context, ty)
+        );
+        return error(message, type_name.span);
+    }
+
+    let mut offset: i128 = 0;
+    for designator in designators {
+        match designator {
+            Designator::Member(name) => {
+                let types = &context.types;
+                let path = match types.find_member(ty, name) {
+                    Some(path) => path,
+                    None => {
+                        let message =
+                            format!("no member named '{}' in '{}'.", name, types.display(ty));
+                        return error(message, span);
+                    }
+                };
+                for (index, member_type) in path {
+                    let record = types.record(types.record_of(ty).unwrap());
+                    let member = &record.members.as_ref().unwrap()[index];
+                    if member.bit_width.is_some() {
+                        let message = format!("cannot compute offset of bit-field '{}'.", name);
+                        return error(message, span);
+                    }
+                    offset += i128::from(member.offset);
+                    ty = member_type;
+                }
+            }
+            Designator::Index(index) | Designator::Range(index, _) => {
+                let element = match context.types.element(ty) {
+                    Some(element) => element,
+                    None => {
+                        let message = format!(
+                            "subscripted value of type '{}' is not an array.",
+                            display_type(context, ty)
+                        );
+                        return error(message, index.span);
+                    }
+                };
+                let index = integer_constant(context, index)?;
+                offset += index * i128::from(context.types.size_of(element).unwrap_or(0));
+                ty = element;
+            }
+        }
+    }
+
+    let size_type = context.types.size_type();
+    Ok(rvalue(
+        TypedExpressionKind::Integer(offset),
+        size_type,
+        span,
+    ))
+}
+
+fn binary<'a>(
+    context: &mut SemanticContext,
+    kind: BinaryOperatorKind,
+    lhs: &Expression<'a>,
+    rhs: &Expression<'a>,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    use BinaryOperatorKind::*;
+    let lhs = expression(context, lhs)?;
+    let rhs = expression(context, rhs)?;
+    let (lhs, rhs, ty) = match kind {
+        Conditional => return conditional(context, lhs, None, rhs, span),
+        Assign => {
+            modifiable_lvalue(context, &lhs)?;
+            let ty = context.types.unqualified(lhs.ty);
+            let rhs = assign(context, rhs, ty, AssignmentKind::Assignment)?;
+            (lhs, rhs, ty)
+        }
+        MulAssign | DivAssign | ModAssign | AddAssign | SubAssign | LeftShiftAssign
+        | RightShiftAssign | BitwiseAndAssign | BitwiseXorAssign | BitwiseOrAssign => {
+            let operator = match kind {
+                MulAssign => Mul,
+                DivAssign => Div,
+                ModAssign => Mod,
+                AddAssign => Add,
+                SubAssign => Sub,
+                LeftShiftAssign => LeftShift,
+                RightShiftAssign => RightShift,
+                BitwiseAndAssign => BitwiseAnd,
+                BitwiseXorAssign => BitwiseXor,
+                _ => BitwiseOr,
+            };
+            modifiable_lvalue(context, &lhs)?;
+            //only the left operand of += and -= may be a pointer
+            let rhs_type = value_type(context, &rhs);
+            if context.types.is_pointer(rhs_type) {
+                let lhs_type = value_type(context, &lhs);
+                return invalid_operands(context, lhs_type, rhs_type, span);
+            }
+            let (computation, rhs_type, _) = binary_type(context, operator, &lhs, &rhs, span)?;
+            let ty = context.types.unqualified(lhs.ty);
+            let kind = TypedExpressionKind::CompoundAssignment {
+                operator,
+                lhs: Box::new(lhs),
+                rhs: Box::new(convert(context, rhs, rhs_type)),
+                computation,
+            };
+            return Ok(rvalue(kind, ty, span));
+        }
+        _ => {
+            let (lhs_type, rhs_type, ty) = binary_type(context, kind, &lhs, &rhs, span)?;
+            let lhs = match kind {
+                Comma => lhs,
+                _ => convert(context, lhs, lhs_type),
+            };
+            (lhs, convert(context, rhs, rhs_type), ty)
+        }
+    };
+
+    let kind = TypedExpressionKind::Binary {
+        kind,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    };
+    Ok(rvalue(kind, ty, span))
+}
+
+fn invalid_operands<T>(
+    context: &SemanticContext,
+    lhs: TypeId,
+    rhs: TypeId,
+    span: Span,
+) -> SemanticResult<T> {
+    let message = format!(
+        "invalid operands to binary expression ('{}' and '{}').",
+        display_type(context, lhs),
+        display_type(context, rhs)
+    );
+    error(message, span)
+}
+
+//the types the operands are converted to and the type of the result
+fn binary_type(
+    context: &mut SemanticContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    span: Span,
+) -> SemanticResult<(TypeId, TypeId, TypeId)> {
+    use BinaryOperatorKind::*;
+    let lhs_type = value_type(context, lhs);
+    let rhs_type = value_type(context, rhs);
+    let types = &mut context.types;
+    let arithmetic = types.is_arithmetic(lhs_type) && types.is_arithmetic(rhs_type);
+    let integer = types.is_integer(lhs_type) && types.is_integer(rhs_type);
+    let scalar = types.is_scalar(lhs_type) && types.is_scalar(rhs_type);
+    let pointers = types.is_pointer(lhs_type) || types.is_pointer(rhs_type);
+
+    //the integer operand of pointer arithmetic is converted to the type of a difference
+    let difference = types.pointer_difference_type();
+    let result = match kind {
+        Comma => (lhs_type, rhs_type, rhs_type),
+        Add | Sub if types.is_pointer(lhs_type) && types.is_integer(rhs_type) => {
+            pointer_arithmetic(context, lhs_type, span)?;
+            (lhs_type, difference, lhs_type)
+        }
+        Add if types.is_integer(lhs_type) && types.is_pointer(rhs_type) => {
+            pointer_arithmetic(context, rhs_type, span)?;
+            (difference, rhs_type, rhs_type)
+        }
+        Sub if types.is_pointer(lhs_type) && types.is_pointer(rhs_type) => {
+            let lhs_pointee = types.pointee(lhs_type).unwrap();
+            let rhs_pointee = types.pointee(rhs_type).unwrap();
+            let lhs_pointee = types.unqualified(lhs_pointee);
+            let rhs_pointee = types.unqualified(rhs_pointee);
+            if !types.is_compatible(lhs_pointee, rhs_pointee) {
+                let message = format!(
+                    "'{}' and '{}' are not pointers to compatible types.",
+                    types.display(lhs_type),
+                    types.display(rhs_type)
+                );
+                return error(message, span);
+            }
+            pointer_arithmetic(context, lhs_type, span)?;
+            (lhs_type, rhs_type, difference)
+        }
+        Add | Sub | Mul | Div if arithmetic => {
+            let ty = types.common_type(lhs_type, rhs_type);
+            (ty, ty, ty)
+        }
+        Mod | BitwiseAnd | BitwiseXor | BitwiseOr if integer => {
+            let ty = types.common_type(lhs_type, rhs_type);
+            (ty, ty, ty)
+        }
+        //the operands of a shift are promoted separately
+        LeftShift | RightShift if integer => {
+            let ty = types.promoted(lhs_type);
+            (ty, types.promoted(rhs_type), ty)
+        }
+        LogicalAnd | LogicalOr if scalar => (lhs_type, rhs_type, types.int()),
+        Equal | NotEqual | LessThan | LessThanEqual if arithmetic => {
+            let ty = types.common_type(lhs_type, rhs_type);
+            sign_compare(context, kind, lhs, rhs, ty, span);
+            (ty, ty, context.types.int())
+        }
+        Equal | NotEqual | LessThan | LessThanEqual if scalar && pointers => {
+            let ty = comparison_type(context, kind, lhs, rhs, span);
+            (ty, ty, context.types.int())
+        }
+        _ => return invalid_operands(context, lhs_type, rhs_type, span),
+    };
+
+    Ok(result)
+}
+
+//a comparison which converts a signed operand to the unsigned type of the other gives a
+//surprising result for negative values. it does not if the signed operand is a constant
+//which is not negative, or if the unsigned one is a constant which the signed type of the
+//comparison holds and the comparison is for equality.
+fn sign_compare(
+    context: &mut SemanticContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    ty: TypeId,
+    span: Span,
+) {
+    let lhs_type = value_type(context, lhs);
+    let rhs_type = value_type(context, rhs);
+    let types = &context.types;
+    let signedness = |ty| types.integer_kind(ty).map(IntegerKind::is_signed);
+    let (signed, unsigned) = match (signedness(lhs_type), signedness(rhs_type), signedness(ty)) {
+        (Some(true), Some(false), Some(false)) => (lhs, rhs),
+        (Some(false), Some(true), Some(false)) => (rhs, lhs),
+        _ => return,
+    };
+    if matches!(evaluate(context, signed), Ok(Some(Constant::Integer(value))) if value >= 0) {
+        return;
+    }
+    let is_equality = matches!(
+        kind,
+        BinaryOperatorKind::Equal | BinaryOperatorKind::NotEqual
+    );
+    let signed_max = (1i128 << (types.size_of(ty).unwrap() * 8 - 1)) - 1;
+    if is_equality
+        && matches!(evaluate(context, unsigned), Ok(Some(Constant::Integer(value))) if value <= signed_max)
+    {
+        return;
+    }
+
+    let message = format!(
+        "comparison of integer expressions of different signedness: '{}' and '{}'.",
+        display_type(context, lhs_type),
+        display_type(context, rhs_type)
+    );
+    let warning = Warning::SignCompare;
+    report(context, Diagnostic::named_warning(warning, message, span));
+}
+
+//the pointer type both operands of a comparison are converted to. comparing a pointer with
+//a pointer to another type or with an integer other than 0 is accepted with a warning.
+fn comparison_type(
+    context: &mut SemanticContext,
+    kind: BinaryOperatorKind,
+    lhs: &TypedExpression,
+    rhs: &TypedExpression,
+    span: Span,
+) -> TypeId {
+    let lhs_type = value_type(context, lhs);
+    let rhs_type = value_type(context, rhs);
+    let is_equality = matches!(
+        kind,
+        BinaryOperatorKind::Equal | BinaryOperatorKind::NotEqual
+    );
+    let types = &mut context.types;
+    let (ty, message) = match (types.pointee(lhs_type), types.pointee(rhs_type)) {
+        (Some(lhs_pointee), Some(rhs_pointee)) => {
+            let lhs_pointee = types.unqualified(lhs_pointee);
+            let rhs_pointee = types.unqualified(rhs_pointee);
+            if types.is_compatible(lhs_pointee, rhs_pointee)
+                || (is_equality && types.is_void(lhs_pointee))
+            {
+                (lhs_type, None)
+            } else if is_equality && types.is_void(rhs_pointee) {
+                (rhs_type, None)
+            } else {
+                (lhs_type, Some("comparison of distinct pointer types"))
+            }
+        }
+        (Some(_), None) if is_null_pointer_constant(context, rhs) => (lhs_type, None),
+        (None, Some(_)) if is_null_pointer_constant(context, lhs) => (rhs_type, None),
+        (Some(_), None) => (lhs_type, Some("comparison between pointer and integer")),
+        (None, _) => (rhs_type, Some("comparison between pointer and integer")),
+    };
+    if let Some(message) = message {
+        let message = format!(
+            "{} ('{}' and '{}').",
+            message,
+            display_type(context, lhs_type),
+            display_type(context, rhs_type)
+        );
+        report(context, Diagnostic::warning(message, span));
+    }
+
+    ty
+}
+
+//then is None for the GNU conditional with omitted operand, whose condition is the value
+//when it is not zero. that value is converted to the type of the result too.
+fn conditional<'a>(
+    context: &mut SemanticContext,
+    condition: TypedExpression<'a>,
+    then: Option<TypedExpression<'a>>,
+    otherwise: TypedExpression<'a>,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let condition = decayed(context, condition);
+    let condition_type = value_type(context, &condition);
+    if !context.types.is_scalar(condition_type) {
+        let message = format!(
+            "used type '{}' where arithmetic or pointer type is required.",
+            context.types.display(condition_type)
+        );
+        return error(message, condition.span);
+    }
+
+    let ty = conditional_type(
+        context,
+        then.as_ref().unwrap_or(&condition),
+        &otherwise,
+        span,
+    )?;
+    let (then, otherwise) = if context.types.is_void(ty) {
+        (then, otherwise)
+    } else {
+        let then = then.map(|then| convert(context, then, ty));
+        (then, convert(context, otherwise, ty))
+    };
+
+    let kind = TypedExpressionKind::Conditional {
+        condition: Box::new(condition),
+        then: then.map(Box::new),
+        otherwise: Box::new(otherwise),
+    };
+    Ok(rvalue(kind, ty, span))
+}
+
+fn conditional_type(
+    context: &mut SemanticContext,
+    then: &TypedExpression,
+    otherwise: &TypedExpression,
+    span: Span,
+) -> SemanticResult<TypeId> {
+    let then_type = value_type(context, then);
+    let otherwise_type = value_type(context, otherwise);
+    let then_is_null = is_null_pointer_constant(context, then);
+    let otherwise_is_null = is_null_pointer_constant(context, otherwise);
+    let types = &mut context.types;
+    if types.is_arithmetic(then_type) && types.is_arithmetic(otherwise_type) {
+        return Ok(types.common_type(then_type, otherwise_type));
+    }
+    if types.is_void(then_type) && types.is_void(otherwise_type) {
+        return Ok(then_type);
+    }
+    if types.is_record(then_type) && types.is_compatible(then_type, otherwise_type) {
+        return Ok(then_type);
+    }
+
+    let (ty, message) = match (types.pointee(then_type), types.pointee(otherwise_type)) {
+        //the result points to a type with the qualifiers of both
+        (Some(then_pointee), Some(otherwise_pointee)) => {
+            let qualifiers = types
+                .qualifiers(then_pointee)
+                .union(types.qualifiers(otherwise_pointee));
+            let then_pointee = types.unqualified(then_pointee);
+            let otherwise_pointee = types.unqualified(otherwise_pointee);
+            let (pointee, message) = if types.is_compatible(then_pointee, otherwise_pointee)
+                || types.is_void(then_pointee)
+                || otherwise_is_null
+            {
+                (then_pointee, None)
+            } else if types.is_void(otherwise_pointee) || then_is_null {
+                (otherwise_pointee, None)
+            } else {
+                (types.void(), Some("pointer type mismatch"))
+            };
+            let pointee = types.qualified(pointee, qualifiers);
+            (types.pointer(pointee), message)
+        }
+        (Some(_), None) if otherwise_is_null => (then_type, None),
+        (None, Some(_)) if then_is_null => (otherwise_type, None),
+        (Some(_), None) if types.is_integer(otherwise_type) => (
+            then_type,
+            Some("pointer/integer type mismatch in conditional expression"),
+        ),
+        (None, Some(_)) if types.is_integer(then_type) => (
+            otherwise_type,
+            Some("pointer/integer type mismatch in conditional expression"),
+        ),
+        _ => {
+            let message = format!(
+                "incompatible operand types ('{}' and '{}').",
+                types.display(then_type),
+                types.display(otherwise_type)
+            );
+            return error(message, span);
+        }
+    };
+    if let Some(message) = message {
+        let message = format!(
+            "{} ('{}' and '{}').",
+            message,
+            display_type(context, then_type),
+            display_type(context, otherwise_type)
+        );
+        report(context, Diagnostic::warning(message, span));
+    }
+
+    Ok(ty)
+}
+
+//the value of `({ ...; expression; })` is that of the last expression statement
+fn statement_expression<'a>(
+    context: &mut SemanticContext,
+    body: &StatementNode<'a>,
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let body = statement(context, body);
+    let ty = match &body {
+        TypedStatement::Compound(items) => match items.last() {
+            Some(TypedBlockItem::Statement(TypedStatement::Expression(last))) => {
+                value_type(context, last)
+            }
+            _ => context.types.void(),
+        },
+        _ => context.types.void(),
+    };
+
+    Ok(rvalue(
+        TypedExpressionKind::Statement(Box::new(body)),
+        ty,
+        span,
+    ))
+}
+
+//the association whose type is compatible with the type of the controlling expression is
+//the result, the other associations are not evaluated
+fn generic_selection<'a>(
+    context: &mut SemanticContext,
+    controlling: &Arc<Expression<'a>>,
+    associations: &[GenericAssociation<'a>],
+    span: Span,
+) -> SemanticResult<TypedExpression<'a>> {
+    let controlling = expression(context, controlling)?;
+    let controlling_type = value_type(context, &controlling);
+
+    let mut types: Vec<TypeId> = Vec::new();
+    let mut selected = None;
+    let mut default = None;
+    for association in associations {
+        let typed = expression(context, &association.expression)?;
+        let type_name = match &association.type_name {
+            Some(type_name) => type_name,
+            None => {
+                default = Some(typed);
+                continue;
+            }
+        };
+        let ty = self::type_name(context, type_name)?;
+        if let Some(previous) = types
+            .iter()
+            .find(|previous| context.types.is_compatible(**previous, ty))
+        {
+            let message = format!(
+                "type '{}' in generic association compatible with previously specified type '{}'.",
+                context.types.display(ty),
+                context.types.display(*previous)
+            );
+            return error(message, type_name.span);
+        }
+        types.push(ty);
+        if context.types.is_compatible(controlling_type, ty) {
+            selected = Some(typed);
+        }
+    }
+
+    match selected.or(default) {
+        Some(mut result) => {
+            result.span = span;
+            Ok(result)
+        }
+        None => {
+            let message = format!(
+                "controlling expression type '{}' not compatible with any generic association type.",
+                context.types.display(controlling_type)
+            );
+            error(message, controlling.span)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::*;
+    use crate::testing::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        let (_, diagnostics) = analyze_source(source, Standard::C11);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn compound_assignments_take_the_operands_of_their_operator() {
+        let source = "int *p; double d; int i;
+            void f(void) { p += i; p -= 1; i += d; d *= i; i <<= 1; }";
+        assert!(messages(source).is_empty(), "{:?}", messages(source));
+        let source = "int *p, *q; double d; int i;
+            void f(void) { p += q; i <<= d; i %= d; p *= 2; d += p; }";
+        assert_eq!(
+            messages(source),
+            [
+                "invalid operands to binary expression ('int *' and 'int *').",
+                "invalid operands to binary expression ('int' and 'double').",
+                "invalid operands to binary expression ('int' and 'double').",
+                "invalid operands to binary expression ('int *' and 'int').",
+                "invalid operands to binary expression ('double' and 'int *')."
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::diagnostic::*;
+use crate::parser::external_declaration::*;
+use crate::parser::statement::*;
+use crate::semantic::control_flow::*;
+use crate::semantic::declaration::*;
+use crate::semantic::statement::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use crate::util::*;
+use crate::warning::*;
+
+#[derive(Debug)]
+pub enum TypedExternalDeclaration<'a> {
+    FunctionDefinition(TypedFunctionDefinition<'a>),
+    Declaration(Vec<TypedDeclaration<'a>>),
+}
+
+//the parameters in the order they are declared, None for one without a name
+#[derive(Debug)]
+pub struct TypedFunctionDefinition<'a> {
+    pub symbol: SymbolId,
+    pub parameters: Vec<Option<SymbolId>>,
+    pub body: TypedStatement<'a>,
+}
+
+//analyzer body
+pub fn translation_unit<'a>(
+    context: &mut SemanticContext,
+    unit: &TranslationUnit<'a>,
+) -> Vec<TypedExternalDeclaration<'a>> {
+    let mut result = Vec::new();
+    for declaration in unit {
+        match declaration {
+            ExternalDeclaration::FunctionDefinition(definition) => {
+                match function_definition(context, definition) {
+                    Ok(definition) => {
+                        result.push(TypedExternalDeclaration::FunctionDefinition(definition))
+                    }
+                    Err(diagnostic) => report(context, diagnostic),
+                }
+            }
+            ExternalDeclaration::Declaration(declaration) => result.push(
+                TypedExternalDeclaration::Declaration(self::declaration(context, declaration)),
+            ),
+            ExternalDeclaration::StaticAssert(assertion) => static_assert(context, assertion),
+            ExternalDeclaration::Error => {}
+        }
+    }
+    complete_tentative_definitions(context);
+
+    result
+}
+
+//a tentative definition which is not followed by a definition becomes one with a zero
+//initializer at the end of the translation unit, so its type must be complete by then
+fn complete_tentative_definitions(context: &mut SemanticContext) {
+    let tentative: Vec<SymbolId> = context
+        .symbols
+        .iter()
+        .filter(|(_, symbol)| symbol.definition == Definition::Tentative)
+        .map(|(id, _)| id)
+        .collect();
+    for id in tentative {
+        let Symbol { ty, span, .. } = *symbol(context, id);
+        if let TypeKind::Array(element, ArraySize::Incomplete) = *context.types.kind(ty) {
+            let message = format!(
+                "array '{}' assumed to have one element.",
+                symbol(context, id).name
+            );
+            report(context, Diagnostic::warning(message, span));
+            symbol_mut(context, id).ty = context.types.array(element, ArraySize::Constant(1));
+        } else if !context.types.is_complete(ty) {
+            let message = format!(
+                "tentative definition has type '{}' that is never completed.",
+                display_type(context, ty)
+            );
+            report(context, Diagnostic::error(message, span));
+        }
+        symbol_mut(context, id).definition = Definition::Definition;
+    }
+}
+
+//a goto may jump to any label of its function, so they are checked after the body
+fn check_labels(context: &mut SemanticContext) {
+    let labels = std::mem::take(&mut context.labels);
+    for (label, span) in labels.used {
+        if !labels.defined.contains_key(&label) {
+            let message = format!("use of undeclared label '{}'.", label);
+            report(context, Diagnostic::error(message, span));
+        }
+    }
+}
+
+fn function_definition<'a>(
+    context: &mut SemanticContext,
+    definition: &FunctionDefinition<'a>,
+) -> SemanticResult<TypedFunctionDefinition<'a>> {
+    let declarator = &definition.declarator;
+    let name = declarator.identifier();
+    let span = declarator.span();
+    let (base, storage, alignment) = declaration_specifiers(context, &definition.specifiers, span)?;
+    if let Some((_, span)) = alignment {
+        return error(
+            format!("alignment specified for function '{}'.", name),
+            span,
+        );
+    }
+    let mut ty = declarator_type(context, declarator, base)?;
+    let function = match context.types.function_type(ty) {
+        Some(function) => function.clone(),
+        None => return error(format!("'{}' is not a function.", name), span),
+    };
+
+    //an old-style definition takes the types of its parameters from its declaration list,
+    //but it still does not give the function a prototype
+    let parameters: Vec<(Option<&str>, Span, TypeId)> = match declarator.function_parameters() {
+        Some(Either::Left(list)) => list
+            .parameters()
+            .iter()
+            .zip(&function.parameters)
+            .map(|(parameter, ty)| (parameter.identifier(), parameter.span, *ty))
+            .collect(),
+        Some(Either::Right(identifiers)) => {
+            let mut parameters = Vec::new();
+            for identifier in identifiers {
+                let (ty, span) = old_style_parameter(context, definition, identifier, span)?;
+                parameters.push((Some(*identifier), span, ty));
+            }
+            let parameter_types = parameters.iter().map(|(_, _, ty)| *ty).collect();
+            ty = context.types.function(FunctionType {
+                parameters: parameter_types,
+                ..function.clone()
+            });
+            parameters
+        }
+        None => Vec::new(),
+    };
+
+    let kind = SymbolKind::Function;
+    let symbol = declare(
+        context,
+        name,
+        ty,
+        kind,
+        storage,
+        Definition::Definition,
+        span,
+    );
+    if is_noreturn(&definition.specifiers, declarator) {
+        symbol_mut(context, symbol).noreturn = true;
+    }
+
+    push_scope(context, ScopeKind::Function);
+    context.return_type = Some(function.return_type);
+    context.labels = Labels::default();
+    let mut parameter_symbols = Vec::new();
+    for (name, span, ty) in parameters {
+        let symbol = name.map(|name| {
+            let kind = SymbolKind::Object;
+            let symbol = declare(context, name, ty, kind, None, Definition::Definition, span);
+            symbol_mut(context, symbol).parameter = true;
+            symbol
+        });
+        parameter_symbols.push(symbol);
+    }
+    let body = match &definition.body {
+        StatementNode::Compound(items) => {
+            TypedStatement::Compound(block_items(context, items, true))
+        }
+        body => statement(context, body),
+    };
+    context.return_type = None;
+    pop_scope(context);
+    check_labels(context);
+
+    //reaching the end of main returns 0
+    if !context.types.is_void(function.return_type)
+        && name != "main"
+        && can_complete(context, &body)
+    {
+        let message = "control reaches end of non-void function.".to_string();
+        let warning = Warning::ReturnType;
+        report(
+            context,
+            Diagnostic::named_warning(warning, message, definition.end),
+        );
+    }
+
+    Ok(TypedFunctionDefinition {
+        symbol,
+        parameters: parameter_symbols,
+        body,
+    })
+}
+
+//the type of a parameter of an old-style definition, which is int if it is not declared
+fn old_style_parameter<'a>(
+    context: &mut SemanticContext,
+    definition: &FunctionDefinition<'a>,
+    identifier: &str,
+    span: Span,
+) -> SemanticResult<(TypeId, Span)> {
+    for declaration in &definition.declarations {
+        for init_declarator in &declaration.declarators {
+            let declarator = &init_declarator.declarator;
+            if declarator.identifier() != identifier {
+                continue;
+            }
+            let (base, _, alignment) =
+                declaration_specifiers(context, &declaration.specifiers, declaration.span)?;
+            if let Some((_, span)) = alignment {
+                return error("alignment specified for parameter.".to_string(), span);
+            }
+            let ty = declarator_type(context, declarator, base)?;
+            let ty = match *context.types.kind(ty) {
+                TypeKind::Array(element, _) => context.types.pointer(element),
+                TypeKind::Function(_) => context.types.pointer(ty),
+                _ => ty,
+            };
+            return Ok((ty, declarator.span()));
+        }
+    }
+
+    Ok((context.types.int(), span))
+}
@@ -0,0 +1,621 @@
+use crate::diagnostic::*;
+use crate::parser::declaration::*;
+use crate::parser::expression::*;
+use crate::semantic::conversion::*;
+use crate::semantic::declaration::*;
+use crate::semantic::expression::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+
+#[derive(Debug)]
+pub enum TypedInitializer<'a> {
+    Expression(TypedExpression<'a>),
+    List(Vec<TypedInitializerItem<'a>>),
+}
+
+impl<'a> TypedInitializer<'a> {
+    //each expression with the subobjects of an object of type ty it initializes
+    pub fn stores(&self, ty: TypeId) -> Vec<(Vec<Subobject>, &TypedExpression<'a>)> {
+        match self {
+            TypedInitializer::Expression(expression) => {
+                vec![(vec![Subobject::object(ty)], expression)]
+            }
+            TypedInitializer::List(items) => items
+                .iter()
+                .map(|item| (item.subobjects.clone(), &item.expression))
+                .collect(),
+        }
+    }
+}
+
+//an expression of a braced initializer with the subobjects it initializes, which a range
+//designator makes more than one of. the items are in the order they are stored, so that a
+//later one overrides an earlier one, and the subobjects they leave out are zero.
+#[derive(Debug)]
+pub struct TypedInitializerItem<'a> {
+    pub subobjects: Vec<Subobject>,
+    pub expression: TypedExpression<'a>,
+}
+
+//a part of the object being initialized, offset bytes into it. a string literal initializes
+//a character array and an expression of struct or union type a whole struct or union, any
+//other expression a scalar.
+#[derive(Debug, Clone, Copy)]
+pub struct Subobject {
+    pub offset: u64,
+    pub bit_field: Option<(u64, u64)>, // the offset of the first bit and the width
+    pub ty: TypeId,
+}
+
+impl Subobject {
+    pub fn object(ty: TypeId) -> Subobject {
+        Subobject {
+            offset: 0,
+            bit_field: None,
+            ty,
+        }
+    }
+
+    pub fn member(self, member: &Member) -> Subobject {
+        Subobject {
+            offset: self.offset + member.offset,
+            bit_field: member
+                .bit_width
+                .map(|bit_width| (member.bit_offset, bit_width)),
+            ty: member.ty,
+        }
+    }
+
+    fn before(self, bytes: u64) -> Subobject {
+        Subobject {
+            offset: self.offset - bytes,
+            ..self
+        }
+    }
+}
+
+//a subobject whose members or elements are initialized one after another, with the index of
+//the one an initializer without designators is for
+struct Level {
+    object: Subobject,
+    next: u64,
+}
+
+//returns the initializer with the number of elements it gives an array
+pub fn initializer<'a>(
+    context: &mut SemanticContext,
+    initializer: &Initializer<'a>,
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<(TypedInitializer<'a>, u64)> {
+    if let TypeKind::Array(_, ArraySize::Variable(_)) = context.types.kind(ty) {
+        return error(
+            "variable-sized object may not be initialized.".to_string(),
+            span,
+        );
+    }
+    match initializer {
+        Initializer::Expression(expression) => {
+            let expression = self::expression(context, expression)?;
+            if !context.types.is_array(ty) {
+                let expression = assign(context, expression, ty, AssignmentKind::Initialization)?;
+                return Ok((TypedInitializer::Expression(expression), 1));
+            }
+            //a string literal initializes a character array by its characters
+            match string_length(context, &expression) {
+                Some(length) => Ok((TypedInitializer::Expression(expression), length)),
+                None => {
+                    let message =
+                        "array initializer must be an initializer list or string literal."
+                            .to_string();
+                    error(message, expression.span)
+                }
+            }
+        }
+        Initializer::List(items) => {
+            let (items, length) = initializer_list(context, items, ty, span)?;
+            Ok((TypedInitializer::List(items), length))
+        }
+    }
+}
+
+//flattens a braced initializer into the subobjects of an object of type ty its expressions
+//initialize, following the current object of C11 6.7.9p17-20
+pub fn initializer_list<'a>(
+    context: &mut SemanticContext,
+    items: &[InitializerListItem<'a>],
+    ty: TypeId,
+    span: Span,
+) -> SemanticResult<(Vec<TypedInitializerItem<'a>>, u64)> {
+    let mut result = Vec::new();
+    let length = list(context, items, Subobject::object(ty), span, &mut result)?;
+    Ok((result, length))
+}
+
+fn list<'a>(
+    context: &mut SemanticContext,
+    items: &[InitializerListItem<'a>],
+    object: Subobject,
+    span: Span,
+    result: &mut Vec<TypedInitializerItem<'a>>,
+) -> SemanticResult<u64> {
+    let types = &context.types;
+    if !types.is_array(object.ty) && !types.is_record(object.ty) {
+        return scalar_list(context, items, object, span, result);
+    }
+
+    let mut stack = vec![Level { object, next: 0 }];
+    let mut length = 0;
+    for item in items {
+        //how many bytes before the designated subobject each copy a range designator makes of
+        //it is
+        let copies = if item.designators.is_empty() {
+            vec![0]
+        } else {
+            stack.truncate(1);
+            designate(
+                context,
+                &mut stack,
+                &item.designators,
+                item_span(item, span),
+            )?
+        };
+
+        let expression = match &item.initializer {
+            Initializer::Expression(expression) => Some(self::expression(context, expression)?),
+            Initializer::List(_) => None,
+        };
+        //a string literal in braces initializes a character array by itself
+        if let (Some(length), [_]) = (
+            expression
+                .as_ref()
+                .and_then(|expression| string_length(context, expression)),
+            items,
+        ) {
+            if item.designators.is_empty() {
+                let mut object = object;
+                if let TypeKind::Array(element, ArraySize::Incomplete) =
+                    *context.types.kind(object.ty)
+                {
+                    object.ty = context.types.array(element, ArraySize::Constant(length));
+                }
+                result.push(TypedInitializerItem {
+                    subobjects: vec![object],
+                    expression: expression.unwrap(),
+                });
+                return Ok(length);
+            }
+        }
+
+        let target = match next_subobject(context, &mut stack) {
+            Some(target) => target,
+            None => {
+                let message = format!(
+                    "excess elements in {} initializer.",
+                    aggregate_name(context, object.ty)
+                );
+                report(context, Diagnostic::warning(message, item_span(item, span)));
+                break;
+            }
+        };
+        length = length.max(stack[0].next + 1);
+        if let TypeKind::Array(_, ArraySize::Incomplete) = context.types.kind(target.ty) {
+            let message = "initialization of flexible array member is not allowed.".to_string();
+            return error(message, item_span(item, span));
+        }
+
+        match (expression, &item.initializer) {
+            (Some(expression), _) => {
+                let subobject = elide(context, &mut stack, target, &expression);
+                let is_string = matches!(expression.kind, TypedExpressionKind::String(_));
+                let expression = if is_string && context.types.is_array(subobject.ty) {
+                    expression
+                } else {
+                    assign(
+                        context,
+                        expression,
+                        subobject.ty,
+                        AssignmentKind::Initialization,
+                    )?
+                };
+                result.push(TypedInitializerItem {
+                    subobjects: copies
+                        .iter()
+                        .map(|bytes| subobject.before(*bytes))
+                        .collect(),
+                    expression,
+                });
+            }
+            (None, Initializer::List(items)) => {
+                let start = result.len();
+                list(context, items, target, span, result)?;
+                for item in &mut result[start..] {
+                    let subobjects = item.subobjects.clone();
+                    item.subobjects = copies
+                        .iter()
+                        .flat_map(|bytes| subobjects.iter().map(move |s| s.before(*bytes)))
+                        .collect();
+                }
+            }
+            (None, Initializer::Expression(_)) => unreachable!(),
+        }
+        let level = stack.last_mut().unwrap();
+        advance(&context.types, level);
+    }
+
+    Ok(length)
+}
+
+//the braces around the initializer of a scalar may be given, with just one expression in
+//them
+fn scalar_list<'a>(
+    context: &mut SemanticContext,
+    items: &[InitializerListItem<'a>],
+    object: Subobject,
+    span: Span,
+    result: &mut Vec<TypedInitializerItem<'a>>,
+) -> SemanticResult<u64> {
+    let (item, rest) = match items.split_first() {
+        Some(split) => split,
+        None => return Ok(1),
+    };
+    if !item.designators.is_empty() {
+        let message = format!(
+            "designator in initializer for scalar type '{}'.",
+            context.types.display(object.ty)
+        );
+        return error(message, item_span(item, span));
+    }
+    match &item.initializer {
+        Initializer::Expression(expression) => {
+            let expression = self::expression(context, expression)?;
+            let expression = assign(
+                context,
+                expression,
+                object.ty,
+                AssignmentKind::Initialization,
+            )?;
+            result.push(TypedInitializerItem {
+                subobjects: vec![object],
+                expression,
+            });
+        }
+        Initializer::List(items) => {
+            scalar_list(context, items, object, span, result)?;
+        }
+    }
+    if let Some(item) = rest.first() {
+        let message = "excess elements in scalar initializer.".to_string();
+        report(context, Diagnostic::warning(message, item_span(item, span)));
+    }
+    Ok(1)
+}
+
+//makes the designated subobject the next one of the innermost level of stack, whose first
+//level is the current object
+fn designate<'a>(
+    context: &mut SemanticContext,
+    stack: &mut Vec<Level>,
+    designators: &[Designator<'a>],
+    span: Span,
+) -> SemanticResult<Vec<u64>> {
+    let mut copies = vec![0];
+    for (nth, designator) in designators.iter().enumerate() {
+        if nth > 0 {
+            descend(&context.types, stack);
+        }
+        let current = stack.last().unwrap().object.ty;
+        match designator {
+            Designator::Member(name) => {
+                let path = match context.types.find_member(current, name) {
+                    Some(path) => path,
+                    None => {
+                        let message = format!(
+                            "field designator '{}' does not refer to any field in type '{}'.",
+                            name,
+                            context.types.display(current)
+                        );
+                        return error(message, span);
+                    }
+                };
+                //a member of an anonymous struct or union is designated through it
+                for (nth, (index, _)) in path.iter().enumerate() {
+                    if nth > 0 {
+                        descend(&context.types, stack);
+                    }
+                    stack.last_mut().unwrap().next = *index as u64;
+                }
+            }
+            Designator::Index(index) => {
+                array_element(context, current, index.span)?;
+                stack.last_mut().unwrap().next = array_index(context, index, current)?;
+            }
+            Designator::Range(first, last) => {
+                let element = array_element(context, current, first.span)?;
+                let span = first.span;
+                let first = array_index(context, first, current)?;
+                let last = array_index(context, last, current)?;
+                if last < first {
+                    let message = format!("array designator range [{}, {}] is empty.", first, last);
+                    return error(message, span);
+                }
+                stack.last_mut().unwrap().next = last;
+                //the designated subobject is the one at last
+                let size = context.types.size_of(element).unwrap_or(0);
+                copies = copies
+                    .iter()
+                    .flat_map(|bytes| {
+                        (first..=last).map(move |index| bytes + (last - index) * size)
+                    })
+                    .collect();
+            }
+        }
+    }
+    Ok(copies)
+}
+
+//makes the next subobject of the innermost level a level of its own
+fn descend(types: &TypeArena, stack: &mut Vec<Level>) {
+    let level = stack.last().unwrap();
+    let object = subobject(types, level.object, level.next).unwrap();
+    stack.push(Level { object, next: 0 });
+}
+
+//the subobject an initializer without designators is for, after those levels of stack which
+//are complete. None when the current object is complete.
+fn next_subobject(context: &SemanticContext, stack: &mut Vec<Level>) -> Option<Subobject> {
+    let types = &context.types;
+    loop {
+        let level = stack.last_mut().unwrap();
+        skip_unnamed_bit_fields(types, level);
+        if let Some(subobject) = subobject(types, level.object, level.next) {
+            return Some(subobject);
+        }
+        if stack.len() == 1 {
+            return None;
+        }
+        stack.pop();
+        advance(types, stack.last_mut().unwrap());
+    }
+}
+
+//braces may be left out around the initializer of a nested aggregate, which then starts
+//with the expression. returns the subobject expression initializes, whose enclosing ones
+//are pushed onto stack.
+fn elide(
+    context: &SemanticContext,
+    stack: &mut Vec<Level>,
+    mut target: Subobject,
+    expression: &TypedExpression,
+) -> Subobject {
+    let types = &context.types;
+    loop {
+        let is_string = matches!(expression.kind, TypedExpressionKind::String(_));
+        let initializes = if types.is_array(target.ty) {
+            is_string
+        } else if types.is_record(target.ty) {
+            types.record_of(target.ty) == types.record_of(expression.ty)
+        } else {
+            true
+        };
+        if initializes {
+            return target;
+        }
+
+        let mut level = Level {
+            object: target,
+            next: 0,
+        };
+        skip_unnamed_bit_fields(types, &mut level);
+        match subobject(types, target, level.next) {
+            Some(subobject) => {
+                stack.push(level);
+                target = subobject;
+            }
+            //an empty struct or array, which the expression cannot initialize
+            None => return target,
+        }
+    }
+}
+
+//a union is complete after one of its members is initialized
+fn advance(types: &TypeArena, level: &mut Level) {
+    level.next = match types.record_of(level.object.ty).map(|id| types.record(id)) {
+        Some(record) if record.kind == RecordKind::Union => record
+            .members
+            .as_ref()
+            .map_or(0, |members| members.len() as u64),
+        _ => level.next + 1,
+    };
+}
+
+//unnamed bit-fields are not initialized
+fn skip_unnamed_bit_fields(types: &TypeArena, level: &mut Level) {
+    let record = match types.record_of(level.object.ty) {
+        Some(record) => types.record(record),
+        None => return,
+    };
+    let members = match &record.members {
+        Some(members) => members,
+        None => return,
+    };
+    while let Some(member) = members.get(level.next as usize) {
+        if member.name.is_some() || member.bit_width.is_none() {
+            break;
+        }
+        level.next += 1;
+    }
+}
+
+//the member or element at index, or None past the end of object
+fn subobject(types: &TypeArena, object: Subobject, index: u64) -> Option<Subobject> {
+    if let Some(element) = types.element(object.ty) {
+        match types.kind(object.ty) {
+            TypeKind::Array(_, ArraySize::Constant(length)) if index >= *length => return None,
+            TypeKind::Array(_, ArraySize::Variable(_)) => return None,
+            _ => {}
+        }
+        return Some(Subobject {
+            offset: object.offset + index * types.size_of(element)?,
+            bit_field: None,
+            ty: element,
+        });
+    }
+    let record = types.record(types.record_of(object.ty)?);
+    let member = record.members.as_ref()?.get(index as usize)?;
+    Some(object.member(member))
+}
+
+//the length of the array a string literal initializes
+fn string_length(context: &SemanticContext, expression: &TypedExpression) -> Option<u64> {
+    match (&expression.kind, context.types.kind(expression.ty)) {
+        (TypedExpressionKind::String(_), TypeKind::Array(_, ArraySize::Constant(length))) => {
+            Some(*length)
+        }
+        _ => None,
+    }
+}
+
+fn aggregate_name(context: &SemanticContext, ty: TypeId) -> &'static str {
+    let types = &context.types;
+    match types.record_of(ty).map(|id| types.record(id).kind) {
+        Some(RecordKind::Struct) => "struct",
+        Some(RecordKind::Union) => "union",
+        None => "array",
+    }
+}
+
+//the span of the first expression in the item, or span if it has none
+fn item_span(item: &InitializerListItem, span: Span) -> Span {
+    match &item.initializer {
+        Initializer::Expression(expression) => expression.span,
+        Initializer::List(items) => items.first().map_or(span, |item| item_span(item, span)),
+    }
+}
+
+//an index within the bounds of the array of type ty
+fn array_index<'a>(
+    context: &mut SemanticContext,
+    index: &Expression<'a>,
+    ty: TypeId,
+) -> SemanticResult<u64> {
+    let value = integer_constant(context, index)?;
+    if value < 0 {
+        let message = format!("array designator value '{}' is negative.", value);
+        return error(message, index.span);
+    }
+    if let TypeKind::Array(_, ArraySize::Constant(length)) = context.types.kind(ty) {
+        if value as u64 >= *length {
+            let message = "array index in initializer exceeds array bounds.".to_string();
+            return error(message, index.span);
+        }
+    }
+    Ok(value as u64)
+}
+
+fn array_element(context: &SemanticContext, ty: TypeId, span: Span) -> SemanticResult<TypeId> {
+    match context.types.element(ty) {
+        Some(element) => Ok(element),
+        None => {
+            let message = format!(
+                "array designator on non-array type '{}'.",
+                context.types.display(ty)
+            );
+            error(message, span)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::*;
+    use crate::semantic::external_declaration::*;
+    use crate::semantic::initializer::*;
+    use crate::testing::*;
+
+    //the offsets the initializer of the last declaration stores at, and the diagnostics
+    fn flatten(source: &str) -> (Vec<Vec<u64>>, Vec<String>) {
+        let (typed, diagnostics) = analyze_source(source, Standard::Gnu11);
+        let messages = diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        let declaration = match typed.root.last() {
+            Some(TypedExternalDeclaration::Declaration(declarations)) => declarations.first(),
+            _ => None,
+        };
+        let (symbol, initializer) = match declaration {
+            Some(TypedDeclaration {
+                symbol,
+                initializer: Some(initializer),
+                ..
+            }) => (*symbol, initializer),
+            _ => return (Vec::new(), messages),
+        };
+        let stores = initializer.stores(typed.symbols.get(symbol).ty);
+        let offsets = stores
+            .into_iter()
+            .map(|(subobjects, _)| subobjects.iter().map(|s| s.offset).collect())
+            .collect();
+        (offsets, messages)
+    }
+
+    #[test]
+    fn braces_may_be_left_out() {
+        let (offsets, _) = flatten("int a[2][2] = {1, 2, 3, 4};");
+        assert_eq!(offsets, [[0], [4], [8], [12]]);
+        let source = "struct O { struct In { int z, w; } in; int after; } o = {1, 2, 3};";
+        assert_eq!(flatten(source).0, [[0], [4], [8]]);
+        let source = "struct O { struct In { int z, w; } in; int after; } o = {{1}, 3};";
+        assert_eq!(flatten(source).0, [[0], [8]]);
+    }
+
+    #[test]
+    fn designators_continue_in_the_designated_subobject() {
+        let source = "struct O { struct In { int z, w; } in; int after; } o = {.in.z = 1, 2};";
+        assert_eq!(flatten(source).0, [[0], [4]]);
+        let (offsets, _) = flatten("int a[4] = {[2] = 1, 2, [0] = 3};");
+        assert_eq!(offsets, [[8], [12], [0]]);
+    }
+
+    #[test]
+    fn ranges_initialize_each_element_with_one_expression() {
+        let (offsets, _) = flatten("int r[6] = {[1 ... 3] = 7, 9};");
+        assert_eq!(offsets, [vec![4, 8, 12], vec![16]]);
+    }
+
+    #[test]
+    fn unions_and_unnamed_bit_fields() {
+        let (offsets, _) = flatten("union U { char c; int i; } u[2] = {1, 2};");
+        assert_eq!(offsets, [[0], [4]]);
+        let (offsets, _) = flatten("struct { int x : 3; int : 4; int y; } s = {1, 2};");
+        assert_eq!(offsets, [[0], [4]]);
+    }
+
+    #[test]
+    fn excess_elements_are_warned_about() {
+        let (offsets, messages) = flatten("int b[2] = {1, 2, 3};");
+        assert_eq!(offsets, [[0], [4]]);
+        assert_eq!(messages, ["excess elements in array initializer."]);
+        let (_, messages) = flatten("int x = {1, 2};");
+        assert_eq!(messages, ["excess elements in scalar initializer."]);
+        let (_, messages) = flatten("int a[2] = {[2] = 1};");
+        assert_eq!(
+            messages,
+            ["array index in initializer exceeds array bounds."]
+        );
+    }
+
+    #[test]
+    fn arrays_of_unknown_size_take_their_length_from_the_initializer() {
+        let source = "char s[] = {\"hey\"}; int a[] = {[4] = 1}; int n = sizeof s + sizeof a;";
+        let (typed, _) = analyze_source(source, Standard::C11);
+        let sizes: Vec<_> = typed
+            .symbols
+            .iter()
+            .map(|(_, symbol)| typed.types.size_of(symbol.ty))
+            .collect();
+        assert_eq!(sizes, [Some(4), Some(20), Some(4)]);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::semantic::types::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use crate::warning::*;
+
+//what a declaration asks of the placement of a member or a whole record besides its type,
+//by _Alignas and the aligned and packed attributes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Placement {
+    pub alignment: Option<u64>, // at least this, in bytes
+    pub packed: bool,           // byte aligned, and a bit-field may straddle its storage unit
+}
+
+//the members of the arguments of #pragma pack
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PackArgument<'a> {
+    Identifier(&'a str),
+    Number(u32),
+}
+
+//the alignments set by #pragma pack are recorded with where they take effect, so that a
+//struct or union is laid out with the one in effect where it is defined
+pub fn pragma_pack(context: &mut SemanticContext, pragmas: &[Pragma]) {
+    let mut current = None;
+    //the pushed alignments with the identifiers they were pushed with
+    let mut stack: Vec<(Option<&str>, Option<u64>)> = Vec::new();
+    for pragma in pragmas {
+        let arguments = match pack_arguments(pragma.text) {
+            Some(Ok(arguments)) => arguments,
+            Some(Err(())) => {
+                let message = "malformed '#pragma pack', ignored.".to_string();
+                report(
+                    context,
+                    Diagnostic::named_warning(Warning::Pragmas, message, pragma.span),
+                );
+                continue;
+            }
+            None => continue,
+        };
+
+        let (action, rest) = match arguments.split_first() {
+            Some((PackArgument::Identifier(action), rest)) => (Some(*action), rest),
+            _ => (None, &arguments[..]),
+        };
+        let (identifier, alignment) = match rest {
+            [] => (None, None),
+            [PackArgument::Number(n)] => (None, Some(*n)),
+            [PackArgument::Identifier(name)] if action.is_some() => (Some(*name), None),
+            [PackArgument::Identifier(name), PackArgument::Number(n)] if action.is_some() => {
+                (Some(*name), Some(*n))
+            }
+            _ => {
+                let message = "malformed '#pragma pack', ignored.".to_string();
+                report(
+                    context,
+                    Diagnostic::named_warning(Warning::Pragmas, message, pragma.span),
+                );
+                continue;
+            }
+        };
+        let alignment = match alignment {
+            Some(n) if [1, 2, 4, 8, 16].contains(&n) => Some(u64::from(n)),
+            Some(_) => {
+                let message = "expected #pragma pack parameter to be '1', '2', '4', '8', or '16'."
+                    .to_string();
+                report(
+                    context,
+                    Diagnostic::named_warning(Warning::Pragmas, message, pragma.span),
+                );
+                continue;
+            }
+            None => None,
+        };
+
+        match action {
+            None => current = alignment,
+            Some("push") => {
+                stack.push((identifier, current));
+                if alignment.is_some() {
+                    current = alignment;
+                }
+            }
+            //popping to an identifier pops everything pushed after it too
+            Some("pop") => {
+                let position = match identifier {
+                    Some(identifier) => stack
+                        .iter()
+                        .rposition(|(name, _)| *name == Some(identifier)),
+                    None => stack.len().checked_sub(1),
+                };
+                match position {
+                    Some(position) => {
+                        current = stack[position].1;
+                        stack.truncate(position);
+                    }
+                    None => {
+                        let message = "#pragma pack(pop, ...) failed: stack empty.".to_string();
+                        report(
+                            context,
+                            Diagnostic::named_warning(Warning::Pragmas, message, pragma.span),
+                        );
+                    }
+                }
+                if alignment.is_some() {
+                    current = alignment;
+                }
+            }
+            Some(action) => {
+                let message = format!("unknown action '{}' for '#pragma pack', ignored.", action);
+                report(
+                    context,
+                    Diagnostic::named_warning(Warning::Pragmas, message, pragma.span),
+                );
+                continue;
+            }
+        }
+        context.packing.push((pragma.span.begin, current));
+    }
+}
+
+//None if the pragma is not #pragma pack, and an error if its arguments are not a list
+//of identifiers and numbers in parentheses
+fn pack_arguments(text: &str) -> Option<Result<Vec<PackArgument<'_>>, ()>> {
+    let tokens = match tokenize(text, Standard::C11) {
+        Ok((tokens, _)) => tokens,
+        Err(_) => return None,
+    };
+    let mut tokens = tokens.iter().map(|token| &token.kind);
+    match tokens.next() {
+        Some(TokenKind::Identifier("pack")) => {}
+        _ => return None,
+    }
+    if !matches!(
+        tokens.next(),
+        Some(TokenKind::Punctuator(PunctuatorKind::LeftRoundBracket))
+    ) {
+        return Some(Err(()));
+    }
+
+    let mut arguments = Vec::new();
+    loop {
+        let argument = match tokens.next() {
+            Some(TokenKind::Punctuator(PunctuatorKind::RightRoundBracket))
+                if arguments.is_empty() =>
+            {
+                break;
+            }
+            Some(TokenKind::Identifier(identifier)) => PackArgument::Identifier(identifier),
+            Some(TokenKind::Literal(Literal::Number(n))) => PackArgument::Number(*n),
+            _ => return Some(Err(())),
+        };
+        arguments.push(argument);
+        match tokens.next() {
+            Some(TokenKind::Punctuator(PunctuatorKind::Commma)) => {}
+            Some(TokenKind::Punctuator(PunctuatorKind::RightRoundBracket)) => break,
+            _ => return Some(Err(())),
+        }
+    }
+    match tokens.next() {
+        Some(TokenKind::End) => Some(Ok(arguments)),
+        _ => Some(Err(())),
+    }
+}
+
+//the maximum alignment of members set by #pragma pack at location, None for none
+pub fn pack_at(context: &SemanticContext, location: Location) -> Option<u64> {
+    context
+        .packing
+        .iter()
+        .rev()
+        .find(|(begin, _)| *begin <= location)
+        .and_then(|(_, alignment)| *alignment)
+}
+
+fn round_up(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+//lays out the members as the System V x86-64 ABI does with the extensions of gcc, and
+//gives the size and alignment of the record. the members are complete except for a
+//flexible array member, which takes no space.
+//
+//a member is aligned to its type unless it is packed, an alignment asked for raises that,
+//and #pragma pack lowers both. a bit-field goes right after the previous member, unless it
+//would straddle a storage unit of its type while neither packed nor under #pragma pack.
+//an unnamed bit-field does not affect the alignment of the record, and one of width 0
+//aligns the next member to its type.
+pub fn layout(
+    types: &TypeArena,
+    kind: RecordKind,
+    members: &mut [Member],
+    placements: &[Placement],
+    record: Placement,
+    pack: Option<u64>,
+) -> (u64, u64) {
+    let mut end = 0; // in bits, of the last member of a struct or the largest of a union
+    let mut align = 1;
+    for (member, placement) in members.iter_mut().zip(placements) {
+        let packed = placement.packed || record.packed;
+        let type_align = types.align_of(member.ty).unwrap_or(1);
+        let type_size = types.size_of(member.ty).unwrap_or(0);
+        let mut field_align = if packed { 1 } else { type_align };
+        if let Some(alignment) = placement.alignment {
+            field_align = field_align.max(alignment);
+        }
+        if let Some(pack) = pack {
+            field_align = field_align.min(pack);
+        }
+
+        let next = match kind {
+            RecordKind::Struct => end,
+            RecordKind::Union => 0,
+        };
+        let (begin, width) = match member.bit_width {
+            Some(0) => {
+                if kind == RecordKind::Struct {
+                    end = round_up(end, type_align * 8);
+                }
+                continue;
+            }
+            Some(width) => {
+                let straddles = next % (type_align * 8) + width > type_size * 8;
+                let begin = if placement.alignment.is_some() {
+                    round_up(next, field_align * 8)
+                } else if straddles && !packed && pack.is_none() {
+                    round_up(next, type_align * 8)
+                } else {
+                    next
+                };
+                (begin, width)
+            }
+            None => (round_up(next, field_align * 8), type_size * 8),
+        };
+        if member.name.is_some() || member.bit_width.is_none() {
+            align = align.max(field_align);
+        }
+
+        member.offset = begin / 8;
+        member.bit_offset = begin % 8;
+        end = end.max(begin + width);
+    }
+
+    if let Some(alignment) = record.alignment {
+        align = align.max(alignment);
+    }
+    (round_up(end.div_ceil(8), align), align)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::*;
+    use crate::testing::*;
+
+    //the value of each of the expressions after the definitions, found as the size of a
+    //char array of that length
+    fn values(definitions: &str, expressions: &[&str]) -> Vec<u64> {
+        let probe = |index: usize| format!("__probe{}", index);
+        let probes: String = expressions
+            .iter()
+            .enumerate()
+            .map(|(index, expression)| format!("char {}[{}];\n", probe(index), expression))
+            .collect();
+        let source = format!("{}\n{}", definitions, probes);
+        let (typed, diagnostics) = analyze_source(&source, Standard::Gnu11);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        (0..expressions.len())
+            .map(|index| {
+                let (_, symbol) = typed
+                    .symbols
+                    .iter()
+                    .find(|(_, symbol)| symbol.name == probe(index))
+                    .unwrap();
+                typed.types.size_of(symbol.ty).unwrap()
+            })
+            .collect()
+    }
+
+    //the expected values are what gcc 12 gives on x86-64
+    #[test]
+    fn members_are_aligned_to_their_types() {
+        let definitions = "struct A { char c; double d; short s; };
+            union H { char c[5]; int i; };
+            struct K { char c; struct { short s; char t; }; long double ld; };";
+        let expressions = [
+            "sizeof(struct A)",
+            "_Alignof(struct A)",
+            "__builtin_offsetof(struct A, d)",
+            "__builtin_offsetof(struct A, s)",
+            "sizeof(union H)",
+            "_Alignof(union H)",
+            "sizeof(struct K)",
+            "_Alignof(struct K)",
+            "__builtin_offsetof(struct K, t)",
+            "__builtin_offsetof(struct K, ld)",
+        ];
+        assert_eq!(
+            values(definitions, &expressions),
+            [24, 8, 8, 16, 8, 4, 32, 16, 4, 16]
+        );
+    }
+
+    #[test]
+    fn bit_fields_share_storage_units() {
+        let definitions = "struct B { char c : 3; int i : 20; char d; };
+            struct C { char c; int : 0; char d; };
+            struct I { char c; long long x : 40; char d; };";
+        let expressions = [
+            "sizeof(struct B)",
+            "_Alignof(struct B)",
+            "__builtin_offsetof(struct B, d)",
+            "sizeof(struct C)",
+            "__builtin_offsetof(struct C, d)",
+            "sizeof(struct I)",
+            "_Alignof(struct I)",
+            "__builtin_offsetof(struct I, d)",
+        ];
+        assert_eq!(values(definitions, &expressions), [4, 4, 3, 5, 4, 8, 8, 6]);
+    }
+
+    #[test]
+    fn flexible_array_members_take_no_space() {
+        let definitions = "struct D { int n; char tail[]; };";
+        let expressions = ["sizeof(struct D)", "__builtin_offsetof(struct D, tail)"];
+        assert_eq!(values(definitions, &expressions), [4, 4]);
+    }
+
+    #[test]
+    fn requested_alignment_packing_and_pragma_pack() {
+        let definitions = "struct E { char c; _Alignas(16) int i; };
+            struct __attribute__((packed)) F { char c; int i; };
+            struct G { char c; int i __attribute__((aligned(8))); };
+            #pragma pack(push, 2)
+            struct J { char c; int i; double d; };
+            #pragma pack(pop)";
+        let expressions = [
+            "sizeof(struct E)",
+            "_Alignof(struct E)",
+            "__builtin_offsetof(struct E, i)",
+            "sizeof(struct F)",
+            "_Alignof(struct F)",
+            "__builtin_offsetof(struct F, i)",
+            "sizeof(struct G)",
+            "_Alignof(struct G)",
+            "__builtin_offsetof(struct G, i)",
+            "sizeof(struct J)",
+            "_Alignof(struct J)",
+            "__builtin_offsetof(struct J, i)",
+            "__builtin_offsetof(struct J, d)",
+        ];
+        assert_eq!(
+            values(definitions, &expressions),
+            [32, 16, 16, 5, 1, 1, 16, 8, 8, 14, 2, 2, 6]
+        );
+    }
+
+    #[test]
+    fn aligned_typedefs() {
+        let definitions = "typedef int wide __attribute__((aligned(16)));
+            typedef struct { char c; } small __attribute__((aligned(8)));
+            struct L { char c; wide w; };";
+        let expressions = [
+            "sizeof(wide)",
+            "_Alignof(wide)",
+            "sizeof(small)",
+            "_Alignof(small)",
+            "sizeof(struct L)",
+            "__builtin_offsetof(struct L, w)",
+        ];
+        assert_eq!(values(definitions, &expressions), [4, 16, 1, 8, 32, 16]);
+    }
+
+    #[test]
+    fn aligned_pointers() {
+        let definitions = "void f(int *__attribute__((unused)) r);
+            int *const __attribute__((aligned(16))) volatile s;";
+        let expressions = [
+            "_Alignof(int *__attribute__((aligned(16))))",
+            "_Alignof(char *__attribute__((aligned(2))))",
+            "sizeof(int *__attribute__((aligned(16))))",
+        ];
+        assert_eq!(values(definitions, &expressions), [16, 8, 8]);
+    }
+}
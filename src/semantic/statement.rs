@@ -0,0 +1,710 @@
+use crate::diagnostic::*;
+use crate::parser::asm::*;
+use crate::parser::declaration::Declaration;
+use crate::parser::expression::*;
+use crate::parser::statement::*;
+use crate::semantic::constant::*;
+use crate::semantic::control_flow::*;
+use crate::semantic::conversion::*;
+use crate::semantic::declaration::*;
+use crate::semantic::expression::*;
+use crate::semantic::util::*;
+use crate::tokenizer::*;
+use crate::util::*;
+use crate::warning::*;
+
+#[derive(Debug)]
+pub enum TypedStatement<'a> {
+    Null,
+    Expression(TypedExpression<'a>),
+    If {
+        condition: Box<TypedExpression<'a>>,
+        statement: Box<TypedStatement<'a>>,
+        else_statement: Option<Box<TypedStatement<'a>>>,
+    },
+    Switch {
+        condition: Box<TypedExpression<'a>>,
+        statement: Box<TypedStatement<'a>>,
+    },
+    Labeled {
+        label: &'a str,
+        statement: Box<TypedStatement<'a>>,
+    },
+    //the values of the constant expressions
+    Case {
+        value: i128,
+        statement: Box<TypedStatement<'a>>,
+    },
+    CaseRange {
+        first: i128,
+        last: i128,
+        statement: Box<TypedStatement<'a>>,
+    },
+    Default(Box<TypedStatement<'a>>),
+    While {
+        condition: Box<TypedExpression<'a>>,
+        statement: Box<TypedStatement<'a>>,
+    },
+    DoWhile {
+        condition: Box<TypedExpression<'a>>,
+        statement: Box<TypedStatement<'a>>,
+    },
+    For {
+        initialization: Option<Either<Vec<TypedDeclaration<'a>>, Box<TypedExpression<'a>>>>,
+        condition: Option<Box<TypedExpression<'a>>>,
+        afterthought: Option<Box<TypedExpression<'a>>>,
+        statement: Box<TypedStatement<'a>>,
+    },
+    Compound(Vec<TypedBlockItem<'a>>),
+    Return(Option<Box<TypedExpression<'a>>>),
+    Break,
+    Continue,
+    Goto(&'a str),
+    Asm(Box<TypedAsmStatement<'a>>),
+    Error, // a statement whose analysis failed, or which failed to parse
+}
+
+//static assertions are checked by the analysis and leave nothing behind
+#[derive(Debug)]
+pub enum TypedBlockItem<'a> {
+    Declaration(Vec<TypedDeclaration<'a>>),
+    Statement(TypedStatement<'a>),
+}
+
+#[derive(Debug)]
+pub struct TypedAsmStatement<'a> {
+    pub qualifiers: Vec<AsmQualifier>,
+    pub template: Vec<&'a str>,
+    pub outputs: Vec<TypedAsmOperand<'a>>,
+    pub inputs: Vec<TypedAsmOperand<'a>>,
+    pub clobbers: Vec<&'a str>,
+    pub labels: Vec<&'a str>,
+}
+
+#[derive(Debug)]
+pub struct TypedAsmOperand<'a> {
+    pub name: Option<&'a str>,
+    pub constraint: &'a str,
+    pub expression: TypedExpression<'a>,
+}
+
+//analyzer body
+//a broken expression is reported and makes its statement an error, but the statements
+//nested in it are still analyzed
+pub fn statement<'a>(
+    context: &mut SemanticContext,
+    statement: &StatementNode<'a>,
+) -> TypedStatement<'a> {
+    match statement_body(context, statement) {
+        Ok(result) => result,
+        Err(diagnostic) => {
+            report(context, diagnostic);
+            TypedStatement::Error
+        }
+    }
+}
+
+fn statement_body<'a>(
+    context: &mut SemanticContext,
+    statement: &StatementNode<'a>,
+) -> SemanticResult<TypedStatement<'a>> {
+    let result = match statement {
+        StatementNode::Null | StatementNode::Attribute(_) => TypedStatement::Null,
+        StatementNode::Expression(expression) => {
+            TypedStatement::Expression(self::expression(context, expression)?)
+        }
+        StatementNode::If {
+            condition,
+            statement,
+            else_statement,
+        } => {
+            let condition = self::condition(context, condition);
+            let statement = self::statement(context, statement);
+            let else_statement = else_statement
+                .as_ref()
+                .map(|else_statement| Box::new(self::statement(context, else_statement)));
+            TypedStatement::If {
+                condition: Box::new(condition?),
+                statement: Box::new(statement),
+                else_statement,
+            }
+        }
+        StatementNode::Switch {
+            condition,
+            statement,
+        } => {
+            let condition = switch_condition(context, condition);
+            context.enclosing.push(Enclosing::Switch(Switch {
+                ty: condition.as_ref().ok().map(|condition| condition.ty),
+                cases: Vec::new(),
+                default: None,
+            }));
+            //control enters the body only at its labels
+            let statement = match statement.as_ref() {
+                StatementNode::Compound(items) => compound_statement(context, items, false),
+                statement => self::statement(context, statement),
+            };
+            context.enclosing.pop();
+            TypedStatement::Switch {
+                condition: Box::new(condition?),
+                statement: Box::new(statement),
+            }
+        }
+        StatementNode::Labeled {
+            label,
+            span,
+            statement,
+        } => {
+            if let Some(previous) = context.labels.defined.get(*label).copied() {
+                let message = format!("redefinition of label '{}'.", label);
+                report_conflict(
+                    context,
+                    message,
+                    *span,
+                    "previous definition is here.",
+                    previous,
+                );
+            } else {
+                context.labels.defined.insert(label.to_string(), *span);
+            }
+            TypedStatement::Labeled {
+                label,
+                statement: Box::new(self::statement(context, statement)),
+            }
+        }
+        StatementNode::Case {
+            value,
+            span,
+            statement,
+        } => {
+            let value = integer_constant(context, value)
+                .and_then(|value| case_label(context, value, value, *span));
+            let statement = self::statement(context, statement);
+            TypedStatement::Case {
+                value: value?.0,
+                statement: Box::new(statement),
+            }
+        }
+        StatementNode::CaseRange {
+            first,
+            last,
+            span,
+            statement,
+        } => {
+            let range_span = first.span;
+            let first = integer_constant(context, first);
+            let last = integer_constant(context, last);
+            let range = match (first, last) {
+                (Ok(first), Ok(last)) if last < first => {
+                    let message = "empty case range specified.".to_string();
+                    report(context, Diagnostic::warning(message, range_span));
+                    Ok((first, last))
+                }
+                (Ok(first), Ok(last)) => case_label(context, first, last, *span),
+                (Err(diagnostic), _) | (_, Err(diagnostic)) => Err(diagnostic),
+            };
+            let statement = self::statement(context, statement);
+            let (first, last) = range?;
+            TypedStatement::CaseRange {
+                first,
+                last,
+                statement: Box::new(statement),
+            }
+        }
+        StatementNode::Default(statement, span) => {
+            let label = default_label(context, *span);
+            let statement = self::statement(context, statement);
+            label?;
+            TypedStatement::Default(Box::new(statement))
+        }
+        StatementNode::While {
+            condition,
+            statement,
+        } => {
+            let condition = self::condition(context, condition);
+            let statement = loop_body(context, statement);
+            TypedStatement::While {
+                condition: Box::new(condition?),
+                statement: Box::new(statement),
+            }
+        }
+        StatementNode::DoWhile {
+            condition,
+            statement,
+        } => {
+            let statement = loop_body(context, statement);
+            TypedStatement::DoWhile {
+                condition: Box::new(self::condition(context, condition)?),
+                statement: Box::new(statement),
+            }
+        }
+        StatementNode::For {
+            initialization,
+            condition,
+            afterthought,
+            statement,
+        } => {
+            push_scope(context, ScopeKind::Block);
+            let result = for_statement(
+                context,
+                initialization,
+                condition.as_deref(),
+                afterthought.as_deref(),
+                statement,
+            );
+            pop_scope(context);
+            result?
+        }
+        StatementNode::Compound(items) => compound_statement(context, items, true),
+        StatementNode::Return(expression, span) => {
+            return_statement(context, expression.as_deref(), *span)?
+        }
+        StatementNode::Break(span) => {
+            if context.enclosing.is_empty() {
+                let message = "'break' statement not in loop or switch statement.".to_string();
+                return error(message, *span);
+            }
+            TypedStatement::Break
+        }
+        StatementNode::Continue(span) => {
+            if !context
+                .enclosing
+                .iter()
+                .any(|enclosing| matches!(enclosing, Enclosing::Loop))
+            {
+                let message = "'continue' statement not in loop statement.".to_string();
+                return error(message, *span);
+            }
+            TypedStatement::Continue
+        }
+        StatementNode::Goto(label, span) => {
+            context.labels.used.push((label.to_string(), *span));
+            TypedStatement::Goto(label)
+        }
+        StatementNode::Asm(statement) => {
+            TypedStatement::Asm(Box::new(asm_statement(context, statement)?))
+        }
+        StatementNode::Error => TypedStatement::Error,
+    };
+
+    Ok(result)
+}
+
+//the controlling expression of a selection or iteration statement is compared with 0
+fn condition<'a>(
+    context: &mut SemanticContext,
+    condition: &Expression<'a>,
+) -> SemanticResult<TypedExpression<'a>> {
+    assignment_condition(context, condition);
+    let condition = expression(context, condition)?;
+    let condition = decayed(context, condition);
+    let ty = value_type(context, &condition);
+    if !context.types.is_scalar(ty) {
+        let message = format!(
+            "statement requires expression of scalar type ('{}' invalid).",
+            display_type(context, ty)
+        );
+        return error(message, condition.span);
+    }
+
+    Ok(condition)
+}
+
+//an assignment as a condition is more often a mistyped == than not, extra parentheses
+//around it say it is meant. those make its span begin before its left operand.
+fn assignment_condition(context: &mut SemanticContext, condition: &Expression) {
+    let lhs = match &condition.kind {
+        ExpressionKind::BinaryOperator {
+            kind: BinaryOperatorKind::Assign,
+            lhs,
+            ..
+        } => lhs,
+        _ => return,
+    };
+    if condition.span.begin != lhs.span.begin {
+        return;
+    }
+
+    let message = "suggest parentheses around assignment used as truth value.".to_string();
+    let mut diagnostic = Diagnostic::named_warning(Warning::Parentheses, message, condition.span);
+    for (location, replacement) in [(condition.span.begin, "("), (condition.span.end, ")")] {
+        diagnostic.fixits.push(FixIt {
+            span: Span {
+                begin: location,
+                end: location,
+            },
+            replacement: replacement.to_string(),
+        });
+    }
+    report(context, diagnostic);
+}
+
+//the controlling expression of a switch statement is promoted, and so are the values of
+//its case labels
+fn switch_condition<'a>(
+    context: &mut SemanticContext,
+    condition: &Expression<'a>,
+) -> SemanticResult<TypedExpression<'a>> {
+    let condition = expression(context, condition)?;
+    let ty = value_type(context, &condition);
+    if !context.types.is_integer(ty) {
+        let message = format!(
+            "statement requires expression of integer type ('{}' invalid).",
+            display_type(context, ty)
+        );
+        return error(message, condition.span);
+    }
+
+    Ok(promoted(context, condition))
+}
+
+//the innermost switch statement, whose case labels may be nested in loops in its body
+fn innermost_switch(context: &mut SemanticContext) -> Option<&mut Switch> {
+    context
+        .enclosing
+        .iter_mut()
+        .rev()
+        .find_map(|enclosing| match enclosing {
+            Enclosing::Switch(switch) => Some(switch),
+            Enclosing::Loop => None,
+        })
+}
+
+//the range of values of a case label converted to the type of the condition, which must
+//not overlap another case label of the switch
+fn case_label(
+    context: &mut SemanticContext,
+    first: i128,
+    last: i128,
+    span: Span,
+) -> SemanticResult<(i128, i128)> {
+    let ty = match innermost_switch(context) {
+        Some(switch) => switch.ty,
+        None => {
+            let message = "'case' statement not in switch statement.".to_string();
+            return error(message, span);
+        }
+    };
+    let (first, last) = match ty {
+        Some(ty) => (wrap(context, first, ty), wrap(context, last, ty)),
+        None => (first, last),
+    };
+
+    let switch = innermost_switch(context).unwrap();
+    let previous = switch
+        .cases
+        .iter()
+        .find(|(previous_first, previous_last, _)| {
+            first <= *previous_last && *previous_first <= last
+        })
+        .copied();
+    match previous {
+        Some((previous_first, _, previous)) => {
+            let message = format!("duplicate case value '{}'.", first.max(previous_first));
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous case defined here.",
+                previous,
+            );
+        }
+        None => switch.cases.push((first, last, span)),
+    }
+
+    Ok((first, last))
+}
+
+fn default_label(context: &mut SemanticContext, span: Span) -> SemanticResult<()> {
+    let switch = match innermost_switch(context) {
+        Some(switch) => switch,
+        None => {
+            let message = "'default' statement not in switch statement.".to_string();
+            return error(message, span);
+        }
+    };
+    match switch.default {
+        Some(previous) => {
+            let message = "multiple default labels in one switch.".to_string();
+            report_conflict(
+                context,
+                message,
+                span,
+                "previous case defined here.",
+                previous,
+            );
+        }
+        None => switch.default = Some(span),
+    }
+
+    Ok(())
+}
+
+//the body of a loop, which break and continue may leave
+fn loop_body<'a>(
+    context: &mut SemanticContext,
+    statement: &StatementNode<'a>,
+) -> TypedStatement<'a> {
+    context.enclosing.push(Enclosing::Loop);
+    let result = self::statement(context, statement);
+    context.enclosing.pop();
+    result
+}
+
+//the value is converted to the return type of the function as if by assignment
+fn return_statement<'a>(
+    context: &mut SemanticContext,
+    expression: Option<&Expression<'a>>,
+    span: Span,
+) -> SemanticResult<TypedStatement<'a>> {
+    let return_type = context.return_type.unwrap();
+    let is_void = context.types.is_void(return_type);
+    let expression = match expression {
+        Some(expression) => self::expression(context, expression)?,
+        None => {
+            if !is_void {
+                let message = "non-void function should return a value.".to_string();
+                let warning = Warning::ReturnType;
+                report(context, Diagnostic::named_warning(warning, message, span));
+            }
+            return Ok(TypedStatement::Return(None));
+        }
+    };
+    let expression = if is_void {
+        //GNU C allows to return an expression of type void
+        if !context.types.is_void(expression.ty) {
+            let message = "void function should not return a value.".to_string();
+            return error(message, expression.span);
+        }
+        expression
+    } else {
+        assign(context, expression, return_type, AssignmentKind::Return)?
+    };
+
+    Ok(TypedStatement::Return(Some(Box::new(expression))))
+}
+
+fn for_statement<'a>(
+    context: &mut SemanticContext,
+    initialization: &Option<Either<Declaration<'a>, Box<Expression<'a>>>>,
+    condition: Option<&Expression<'a>>,
+    afterthought: Option<&Expression<'a>>,
+    statement: &StatementNode<'a>,
+) -> SemanticResult<TypedStatement<'a>> {
+    let initialization = match initialization {
+        Some(Either::Left(declaration)) => {
+            Some(Ok(Either::Left(self::declaration(context, declaration))))
+        }
+        Some(Either::Right(expression)) => Some(
+            self::expression(context, expression)
+                .map(|expression| Either::Right(Box::new(expression))),
+        ),
+        None => None,
+    };
+    let condition = condition.map(|condition| self::condition(context, condition).map(Box::new));
+    let afterthought =
+        afterthought.map(|afterthought| expression(context, afterthought).map(Box::new));
+    let statement = loop_body(context, statement);
+
+    Ok(TypedStatement::For {
+        initialization: initialization.transpose()?,
+        condition: condition.transpose()?,
+        afterthought: afterthought.transpose()?,
+        statement: Box::new(statement),
+    })
+}
+
+//the items of a block whose scope is already pushed
+fn compound_statement<'a>(
+    context: &mut SemanticContext,
+    items: &[BlockItem<'a>],
+    reachable: bool,
+) -> TypedStatement<'a> {
+    push_scope(context, ScopeKind::Block);
+    let items = block_items(context, items, reachable);
+    pop_scope(context);
+    TypedStatement::Compound(items)
+}
+
+//reachable tells whether control reaches the beginning of the block, which it does not for
+//the body of a switch statement. the first statement of a run which control does not reach
+//is reported, and so is a case label control falls through to from the statement before.
+pub fn block_items<'a>(
+    context: &mut SemanticContext,
+    items: &[BlockItem<'a>],
+    mut reachable: bool,
+) -> Vec<TypedBlockItem<'a>> {
+    let mut result = Vec::new();
+    //set when unreachable code was reported, or when a broken statement made it unknown
+    let mut reported = false;
+    let mut previous = None;
+    for item in items {
+        match item {
+            BlockItem::Declaration(declaration) => {
+                result.push(TypedBlockItem::Declaration(self::declaration(
+                    context,
+                    declaration,
+                )));
+            }
+            BlockItem::StaticAssert(assertion) => static_assert(context, assertion),
+            BlockItem::Statement(statement) => {
+                let typed = self::statement(context, statement);
+                if reachable && previous.is_some_and(|previous| falls_through(previous, statement))
+                {
+                    let span = statement_span(statement).unwrap();
+                    let message = "unannotated fall-through between switch labels.".to_string();
+                    let warning = Warning::ImplicitFallthrough;
+                    report(context, Diagnostic::named_warning(warning, message, span));
+                }
+
+                let labeled = has_label(&typed);
+                if !reachable && !labeled && !reported {
+                    if let Some(span) = unreachable_span(statement) {
+                        let message = "code will never be executed.".to_string();
+                        let warning = Warning::UnreachableCode;
+                        report(context, Diagnostic::named_warning(warning, message, span));
+                        reported = true;
+                    }
+                }
+                if matches!(typed, TypedStatement::Error) {
+                    reported = true;
+                } else if labeled {
+                    reported = false;
+                }
+                reachable = (reachable || labeled) && can_complete(context, &typed);
+                previous = Some(statement);
+                result.push(TypedBlockItem::Statement(typed));
+            }
+        }
+    }
+
+    result
+}
+
+//whether a case label of the switch follows the statement before without a break, and
+//without __attribute__((fallthrough)); saying that it is meant to
+fn falls_through(previous: &StatementNode, statement: &StatementNode) -> bool {
+    let is_case = matches!(
+        statement,
+        StatementNode::Case { .. } | StatementNode::CaseRange { .. } | StatementNode::Default(..)
+    );
+    let mut previous = previous;
+    while let StatementNode::Labeled { statement, .. }
+    | StatementNode::Case { statement, .. }
+    | StatementNode::CaseRange { statement, .. }
+    | StatementNode::Default(statement, _) = previous
+    {
+        previous = statement;
+    }
+    let annotated = match previous {
+        StatementNode::Null => true,
+        StatementNode::Attribute(attributes) => attributes
+            .iter()
+            .any(|attribute| attribute_name(attribute) == "fallthrough"),
+        _ => false,
+    };
+    is_case && !annotated
+}
+
+//where a statement control does not reach is reported. a break after a statement which
+//does not complete, and an empty statement, are not worth it.
+fn unreachable_span(statement: &StatementNode) -> Option<Span> {
+    match statement {
+        StatementNode::Null
+        | StatementNode::Attribute(_)
+        | StatementNode::Break(_)
+        | StatementNode::Error => None,
+        statement => statement_span(statement),
+    }
+}
+
+//the span of the statement itself, or of its first part which has one
+fn statement_span(statement: &StatementNode) -> Option<Span> {
+    match statement {
+        StatementNode::Expression(expression) => Some(expression.span),
+        StatementNode::If { condition, .. }
+        | StatementNode::Switch { condition, .. }
+        | StatementNode::While { condition, .. } => Some(condition.span),
+        StatementNode::DoWhile {
+            condition,
+            statement,
+        } => statement_span(statement).or(Some(condition.span)),
+        StatementNode::For {
+            initialization,
+            condition,
+            afterthought,
+            statement,
+        } => match initialization {
+            Some(Either::Right(expression)) => Some(expression.span),
+            _ => condition
+                .as_ref()
+                .or(afterthought.as_ref())
+                .map(|expression| expression.span)
+                .or_else(|| statement_span(statement)),
+        },
+        StatementNode::Labeled { span, .. }
+        | StatementNode::Case { span, .. }
+        | StatementNode::CaseRange { span, .. }
+        | StatementNode::Default(_, span)
+        | StatementNode::Return(_, span)
+        | StatementNode::Break(span)
+        | StatementNode::Continue(span)
+        | StatementNode::Goto(_, span) => Some(*span),
+        StatementNode::Compound(items) => items.iter().find_map(|item| match item {
+            BlockItem::Statement(statement) => statement_span(statement),
+            _ => None,
+        }),
+        StatementNode::Null
+        | StatementNode::Asm(_)
+        | StatementNode::Attribute(_)
+        | StatementNode::Error => None,
+    }
+}
+
+fn asm_statement<'a>(
+    context: &mut SemanticContext,
+    statement: &AsmStatement<'a>,
+) -> SemanticResult<TypedAsmStatement<'a>> {
+    Ok(TypedAsmStatement {
+        qualifiers: statement.qualifiers.clone(),
+        template: statement.template.clone(),
+        outputs: asm_operands(context, &statement.outputs, None)?,
+        inputs: asm_operands(context, &statement.inputs, Some(statement.outputs.len()))?,
+        clobbers: statement.clobbers.clone(),
+        labels: statement.labels.clone(),
+    })
+}
+
+//an output is stored to, so it is a modifiable lvalue, and an input with a memory
+//constraint is an object. the other inputs are values. outputs is None for the outputs,
+//and how many there are for the inputs, which are numbered after them.
+fn asm_operands<'a>(
+    context: &mut SemanticContext,
+    operands: &[AsmOperand<'a>],
+    outputs: Option<usize>,
+) -> SemanticResult<Vec<TypedAsmOperand<'a>>> {
+    let mut result = Vec::new();
+    for (index, operand) in operands.iter().enumerate() {
+        let expression = expression(context, &operand.expression)?;
+        let expression = if outputs.is_none() {
+            modifiable_lvalue(context, &expression)?;
+            expression
+        } else if is_memory_constraint(operand.constraint) {
+            if expression.category != ValueCategory::LValue {
+                let number = outputs.unwrap_or(0) + index;
+                let message = format!("memory input {} is not directly addressable.", number);
+                return error(message, expression.span);
+            }
+            expression
+        } else {
+            decayed(context, expression)
+        };
+        result.push(TypedAsmOperand {
+            name: operand.name,
+            constraint: operand.constraint,
+            expression,
+        });
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,150 @@
+use crate::parser::declaration::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolId(usize);
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub ty: TypeId,
+    pub kind: SymbolKind,
+    pub storage: Option<StorageClassSpecifier>,
+    pub linkage: Linkage,
+    pub definition: Definition,
+    pub noreturn: bool, // a function declared _Noreturn or noreturn in any declaration
+    pub parameter: bool, // of the function being defined
+    pub used: bool,     // referred to by an expression
+    pub alignment: u64, // the strictest requested by _Alignas in any declaration, or 0
+    pub asm_label: Option<String>, // the assembler name given by `asm("name")`, GNU extension
+    pub span: Span,     // of the identifier in the first declaration, or in the definition
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Object,
+    Function,
+    EnumerationConstant(i64),
+    Typedef,
+}
+
+//declarations of an identifier with linkage in different scopes denote the same entity,
+//within a translation unit for internal linkage and across them for external linkage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Linkage {
+    None,
+    Internal,
+    External,
+}
+
+//a file scope object declared without an initializer and without extern is a tentative
+//definition, which becomes a definition initialized with zero at the end of the translation
+//unit unless a definition with an initializer comes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Definition {
+    Declaration,
+    Tentative,
+    Definition,
+}
+
+//a struct, union or enum tag
+#[derive(Debug, Clone, Copy)]
+pub struct Tag {
+    pub ty: TypeId,
+    pub span: Span, // of the declaration which declared the tag
+}
+
+//every declared entity, which the typed tree refers to by id
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    pub fn add(&mut self, symbol: Symbol) -> SymbolId {
+        self.symbols.push(symbol);
+        SymbolId(self.symbols.len() - 1)
+    }
+
+    pub fn get(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id.0]
+    }
+
+    pub fn get_mut(&mut self, id: SymbolId) -> &mut Symbol {
+        &mut self.symbols[id.0]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (SymbolId(index), symbol))
+    }
+}
+
+//the alignment of the object of symbol, which _Alignas may make stricter than that of its
+//type
+pub fn object_align(types: &TypeArena, symbol: &Symbol) -> u64 {
+    types.align_of(symbol.ty).unwrap_or(1).max(symbol.alignment)
+}
+
+//the name the object or function of symbol has for the assembler and the linker
+pub fn assembler_name(symbol: &Symbol) -> &str {
+    symbol.asm_label.as_deref().unwrap_or(&symbol.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::*;
+    use crate::options::*;
+    use crate::testing::*;
+
+    fn errors(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    fn messages(source: &str) -> Vec<String> {
+        errors(analyze_source(source, Standard::C11).1)
+    }
+
+    #[test]
+    fn inner_declarations_hide_outer_ones() {
+        let source = "int x; struct S { int a; };
+            void f(void) { int x; { char x; x; } struct S { char c; } s; s.c; }";
+        assert!(messages(source).is_empty(), "{:?}", messages(source));
+        assert_eq!(
+            messages("void f(void) { int x; int x; }"),
+            ["redefinition of 'x'."]
+        );
+        assert_eq!(
+            messages("void f(void) { { int y; } y; }"),
+            ["use of undeclared identifier 'y'."]
+        );
+    }
+
+    #[test]
+    fn declarations_with_linkage_denote_one_entity() {
+        let source = "int x; void f(void) { int x; { extern int x; } } extern int x;";
+        let (typed, diagnostics) = analyze_source(source, Standard::C11);
+        assert!(errors(diagnostics).is_empty());
+        let count = typed
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| symbol.name == "x")
+            .count();
+        assert_eq!(count, 2);
+        assert_eq!(
+            messages("int g; void f(void) { extern char g; }"),
+            ["conflicting types for 'g'."]
+        );
+    }
+}
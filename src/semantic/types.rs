@@ -0,0 +1,850 @@
+use crate::tokenizer::*;
+use std::collections::HashMap;
+
+//an index into the type arena. equal types are interned once, so two types are the same
+//exactly when their ids are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecordId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnumId(usize);
+
+//the length of a variable length array, which is evaluated when its declaration is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LengthId(usize);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub qualifiers: Qualifiers,
+    pub align: Option<u64>, // set by the aligned attribute of a typedef, in bytes
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+    Void,
+    Integer(IntegerKind),
+    Floating(FloatingKind),
+    Complex(FloatingKind),
+    Pointer(TypeId),
+    Array(TypeId, ArraySize),
+    Function(FunctionType),
+    Struct(RecordId),
+    Union(RecordId),
+    Enum(EnumId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+    pub is_atomic: bool,
+}
+
+//in the order of their conversion ranks, each signed kind before its unsigned one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IntegerKind {
+    Bool,
+    Char, // signed on x86-64, but a type of its own
+    SignedChar,
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FloatingKind {
+    Float,
+    Double,
+    LongDouble,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArraySize {
+    Incomplete,         // int a[]
+    Constant(u64),      // int a[4]
+    Variable(LengthId), // int a[n], a variable length array
+}
+
+//a function declared without a prototype, as in `int f();`, has prototype false and its
+//parameter types are not checked against the arguments
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FunctionType {
+    pub return_type: TypeId,
+    pub parameters: Vec<TypeId>,
+    pub variadic: bool,
+    pub prototype: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Struct,
+    Union,
+}
+
+//a struct or union, which is incomplete until its member list is seen. size and align are
+//given by the layout of the members once it is complete.
+#[derive(Debug)]
+pub struct Record {
+    pub kind: RecordKind,
+    pub name: Option<String>,
+    pub members: Option<Vec<Member>>,
+    pub size: u64,
+    pub align: u64,
+}
+
+//a bit-field starts bit_offset bits into the byte at offset, the other members start at
+//offset with bit_offset 0
+#[derive(Debug)]
+pub struct Member {
+    pub name: Option<String>, // None for an unnamed bit-field or an anonymous struct or union
+    pub ty: TypeId,
+    pub bit_width: Option<u64>,
+    pub offset: u64,
+    pub bit_offset: u64,
+}
+
+#[derive(Debug)]
+pub struct Enumeration {
+    pub name: Option<String>,
+    pub complete: bool,
+}
+
+//the size expression of a variable length array as it is written, for diagnostics, and
+//where it is
+#[derive(Debug)]
+pub struct VariableLength {
+    pub spelling: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Default)]
+pub struct TypeArena {
+    types: Vec<Type>,
+    ids: HashMap<Type, TypeId>,
+    records: Vec<Record>,
+    enumerations: Vec<Enumeration>,
+    lengths: Vec<VariableLength>,
+}
+
+impl IntegerKind {
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntegerKind::Char
+                | IntegerKind::SignedChar
+                | IntegerKind::Short
+                | IntegerKind::Int
+                | IntegerKind::Long
+                | IntegerKind::LongLong
+        )
+    }
+
+    //the conversion rank, which is the same for the signed and unsigned kinds of a size
+    pub fn rank(self) -> u8 {
+        match self {
+            IntegerKind::Bool => 0,
+            IntegerKind::Char | IntegerKind::SignedChar | IntegerKind::UnsignedChar => 1,
+            IntegerKind::Short | IntegerKind::UnsignedShort => 2,
+            IntegerKind::Int | IntegerKind::UnsignedInt => 3,
+            IntegerKind::Long | IntegerKind::UnsignedLong => 4,
+            IntegerKind::LongLong | IntegerKind::UnsignedLongLong => 5,
+        }
+    }
+
+    //in bytes on x86-64
+    pub fn size(self) -> u64 {
+        match self.rank() {
+            0 | 1 => 1,
+            2 => 2,
+            3 => 4,
+            _ => 8,
+        }
+    }
+
+    pub fn width(self) -> u32 {
+        if self == IntegerKind::Bool {
+            1
+        } else {
+            self.size() as u32 * 8
+        }
+    }
+
+    pub fn to_unsigned(self) -> IntegerKind {
+        match self {
+            IntegerKind::Char | IntegerKind::SignedChar => IntegerKind::UnsignedChar,
+            IntegerKind::Short => IntegerKind::UnsignedShort,
+            IntegerKind::Int => IntegerKind::UnsignedInt,
+            IntegerKind::Long => IntegerKind::UnsignedLong,
+            IntegerKind::LongLong => IntegerKind::UnsignedLongLong,
+            kind => kind,
+        }
+    }
+
+    pub fn min_value(self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.width() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max_value(self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.width() - 1)) - 1
+        } else {
+            (1 << self.width()) - 1
+        }
+    }
+
+    pub fn spelling(self) -> &'static str {
+        match self {
+            IntegerKind::Bool => "_Bool",
+            IntegerKind::Char => "char",
+            IntegerKind::SignedChar => "signed char",
+            IntegerKind::UnsignedChar => "unsigned char",
+            IntegerKind::Short => "short",
+            IntegerKind::UnsignedShort => "unsigned short",
+            IntegerKind::Int => "int",
+            IntegerKind::UnsignedInt => "unsigned int",
+            IntegerKind::Long => "long",
+            IntegerKind::UnsignedLong => "unsigned long",
+            IntegerKind::LongLong => "long long",
+            IntegerKind::UnsignedLongLong => "unsigned long long",
+        }
+    }
+}
+
+impl FloatingKind {
+    //in bytes on x86-64, where long double is the 80-bit x87 format padded to 16 bytes
+    pub fn size(self) -> u64 {
+        match self {
+            FloatingKind::Float => 4,
+            FloatingKind::Double => 8,
+            FloatingKind::LongDouble => 16,
+        }
+    }
+
+    pub fn spelling(self) -> &'static str {
+        match self {
+            FloatingKind::Float => "float",
+            FloatingKind::Double => "double",
+            FloatingKind::LongDouble => "long double",
+        }
+    }
+}
+
+impl Qualifiers {
+    pub fn union(self, other: Qualifiers) -> Qualifiers {
+        Qualifiers {
+            is_const: self.is_const || other.is_const,
+            is_volatile: self.is_volatile || other.is_volatile,
+            is_restrict: self.is_restrict || other.is_restrict,
+            is_atomic: self.is_atomic || other.is_atomic,
+        }
+    }
+
+    //whether every qualifier of self is in other too
+    pub fn is_subset_of(self, other: Qualifiers) -> bool {
+        self.union(other) == other
+    }
+
+    fn spelling(self) -> String {
+        let mut result = String::new();
+        for (present, spelling) in [
+            (self.is_const, "const "),
+            (self.is_volatile, "volatile "),
+            (self.is_restrict, "restrict "),
+            (self.is_atomic, "_Atomic "),
+        ] {
+            if present {
+                result.push_str(spelling);
+            }
+        }
+        result
+    }
+}
+
+impl TypeArena {
+    pub fn new() -> TypeArena {
+        TypeArena::default()
+    }
+
+    pub fn intern(&mut self, ty: Type) -> TypeId {
+        if let Some(id) = self.ids.get(&ty) {
+            return *id;
+        }
+        let id = TypeId(self.types.len());
+        self.types.push(ty.clone());
+        self.ids.insert(ty, id);
+        id
+    }
+
+    pub fn get(&self, id: TypeId) -> &Type {
+        &self.types[id.0]
+    }
+
+    pub fn kind(&self, id: TypeId) -> &TypeKind {
+        &self.get(id).kind
+    }
+
+    pub fn qualifiers(&self, id: TypeId) -> Qualifiers {
+        self.get(id).qualifiers
+    }
+
+    fn unqualified_type(&mut self, kind: TypeKind) -> TypeId {
+        self.intern(Type {
+            kind,
+            qualifiers: Qualifiers::default(),
+            align: None,
+        })
+    }
+
+    pub fn void(&mut self) -> TypeId {
+        self.unqualified_type(TypeKind::Void)
+    }
+
+    pub fn integer(&mut self, kind: IntegerKind) -> TypeId {
+        self.unqualified_type(TypeKind::Integer(kind))
+    }
+
+    pub fn int(&mut self) -> TypeId {
+        self.integer(IntegerKind::Int)
+    }
+
+    //size_t on x86-64
+    pub fn size_type(&mut self) -> TypeId {
+        self.integer(IntegerKind::UnsignedLong)
+    }
+
+    //ptrdiff_t on x86-64
+    pub fn pointer_difference_type(&mut self) -> TypeId {
+        self.integer(IntegerKind::Long)
+    }
+
+    pub fn floating(&mut self, kind: FloatingKind) -> TypeId {
+        self.unqualified_type(TypeKind::Floating(kind))
+    }
+
+    pub fn complex(&mut self, kind: FloatingKind) -> TypeId {
+        self.unqualified_type(TypeKind::Complex(kind))
+    }
+
+    pub fn pointer(&mut self, pointee: TypeId) -> TypeId {
+        self.unqualified_type(TypeKind::Pointer(pointee))
+    }
+
+    pub fn array(&mut self, element: TypeId, size: ArraySize) -> TypeId {
+        self.unqualified_type(TypeKind::Array(element, size))
+    }
+
+    pub fn function(&mut self, function: FunctionType) -> TypeId {
+        self.unqualified_type(TypeKind::Function(function))
+    }
+
+    //qualifiers of an array type apply to its elements
+    pub fn qualified(&mut self, id: TypeId, qualifiers: Qualifiers) -> TypeId {
+        if let TypeKind::Array(element, size) = *self.kind(id) {
+            let element = self.qualified(element, qualifiers);
+            return self.array(element, size);
+        }
+        let ty = self.get(id);
+        let ty = Type {
+            kind: ty.kind.clone(),
+            qualifiers: ty.qualifiers.union(qualifiers),
+            align: ty.align,
+        };
+        self.intern(ty)
+    }
+
+    //the type with the alignment given to a typedef by the aligned attribute, which may
+    //also lower it. the size stays the same.
+    pub fn aligned(&mut self, id: TypeId, align: u64) -> TypeId {
+        let ty = self.get(id);
+        let ty = Type {
+            kind: ty.kind.clone(),
+            qualifiers: ty.qualifiers,
+            align: Some(align),
+        };
+        self.intern(ty)
+    }
+
+    pub fn unqualified(&mut self, id: TypeId) -> TypeId {
+        let kind = self.kind(id).clone();
+        self.unqualified_type(kind)
+    }
+
+    pub fn new_record(&mut self, kind: RecordKind, name: Option<String>) -> TypeId {
+        let id = RecordId(self.records.len());
+        self.records.push(Record {
+            kind,
+            name,
+            members: None,
+            size: 0,
+            align: 1,
+        });
+        match kind {
+            RecordKind::Struct => self.unqualified_type(TypeKind::Struct(id)),
+            RecordKind::Union => self.unqualified_type(TypeKind::Union(id)),
+        }
+    }
+
+    pub fn record(&self, id: RecordId) -> &Record {
+        &self.records[id.0]
+    }
+
+    pub fn record_mut(&mut self, id: RecordId) -> &mut Record {
+        &mut self.records[id.0]
+    }
+
+    pub fn new_enumeration(&mut self, name: Option<String>) -> TypeId {
+        let id = EnumId(self.enumerations.len());
+        self.enumerations.push(Enumeration {
+            name,
+            complete: false,
+        });
+        self.unqualified_type(TypeKind::Enum(id))
+    }
+
+    pub fn enumeration(&self, id: EnumId) -> &Enumeration {
+        &self.enumerations[id.0]
+    }
+
+    pub fn enumeration_mut(&mut self, id: EnumId) -> &mut Enumeration {
+        &mut self.enumerations[id.0]
+    }
+
+    pub fn new_length(&mut self, spelling: String, span: Span) -> LengthId {
+        self.lengths.push(VariableLength { spelling, span });
+        LengthId(self.lengths.len() - 1)
+    }
+
+    pub fn length(&self, id: LengthId) -> &VariableLength {
+        &self.lengths[id.0]
+    }
+
+    //the lengths made from the first one numbered first on, so the ones of a declarator
+    //are those made since the count before it
+    pub fn lengths_from(&self, first: usize) -> impl Iterator<Item = LengthId> {
+        (first..self.lengths.len()).map(LengthId)
+    }
+
+    pub fn length_count(&self) -> usize {
+        self.lengths.len()
+    }
+
+    //the record of a struct or union type
+    pub fn record_of(&self, id: TypeId) -> Option<RecordId> {
+        match self.kind(id) {
+            TypeKind::Struct(record) | TypeKind::Union(record) => Some(*record),
+            _ => None,
+        }
+    }
+
+    //enumerated types behave as their compatible integer type, which is int here
+    pub fn integer_kind(&self, id: TypeId) -> Option<IntegerKind> {
+        match self.kind(id) {
+            TypeKind::Integer(kind) => Some(*kind),
+            TypeKind::Enum(_) => Some(IntegerKind::Int),
+            _ => None,
+        }
+    }
+
+    //the indices of the members leading to the one named name, which is more than one when
+    //it is a member of an anonymous struct or union, with the type of each
+    pub fn find_member(&self, id: TypeId, name: &str) -> Option<Vec<(usize, TypeId)>> {
+        let record = self.record(self.record_of(id)?);
+        for (index, member) in record.members.as_ref()?.iter().enumerate() {
+            match &member.name {
+                Some(member_name) if member_name == name => return Some(vec![(index, member.ty)]),
+                Some(_) => {}
+                None => {
+                    if let Some(path) = self.find_member(member.ty, name) {
+                        let mut result = vec![(index, member.ty)];
+                        result.extend(path);
+                        return Some(result);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    //a struct or union with a const member, directly or in a member, cannot be assigned
+    pub fn has_const_member(&self, id: TypeId) -> bool {
+        let members = match self.record_of(id) {
+            Some(record) => self.record(record).members.as_ref(),
+            None => None,
+        };
+        members.is_some_and(|members| {
+            members.iter().any(|member| {
+                let mut ty = member.ty;
+                while let Some(element) = self.element(ty) {
+                    ty = element;
+                }
+                self.qualifiers(ty).is_const || self.has_const_member(ty)
+            })
+        })
+    }
+
+    pub fn is_void(&self, id: TypeId) -> bool {
+        matches!(self.kind(id), TypeKind::Void)
+    }
+
+    pub fn is_integer(&self, id: TypeId) -> bool {
+        self.integer_kind(id).is_some()
+    }
+
+    pub fn is_floating(&self, id: TypeId) -> bool {
+        matches!(self.kind(id), TypeKind::Floating(_) | TypeKind::Complex(_))
+    }
+
+    pub fn is_arithmetic(&self, id: TypeId) -> bool {
+        self.is_integer(id) || self.is_floating(id)
+    }
+
+    pub fn is_pointer(&self, id: TypeId) -> bool {
+        matches!(self.kind(id), TypeKind::Pointer(_))
+    }
+
+    pub fn is_scalar(&self, id: TypeId) -> bool {
+        self.is_arithmetic(id) || self.is_pointer(id)
+    }
+
+    pub fn is_array(&self, id: TypeId) -> bool {
+        matches!(self.kind(id), TypeKind::Array(_, _))
+    }
+
+    pub fn is_function(&self, id: TypeId) -> bool {
+        matches!(self.kind(id), TypeKind::Function(_))
+    }
+
+    pub fn is_record(&self, id: TypeId) -> bool {
+        self.record_of(id).is_some()
+    }
+
+    pub fn pointee(&self, id: TypeId) -> Option<TypeId> {
+        match self.kind(id) {
+            TypeKind::Pointer(pointee) => Some(*pointee),
+            _ => None,
+        }
+    }
+
+    //the type pointer arithmetic on a pointer of type id scales by the size of. GNU C
+    //scales by 1 for void and functions, which have no size, and for those this is None.
+    pub fn scaled_pointee(&self, id: TypeId) -> Option<TypeId> {
+        let pointee = self.pointee(id)?;
+        if self.is_void(pointee) || self.is_function(pointee) {
+            return None;
+        }
+        Some(pointee)
+    }
+
+    //None for a pointer to a variable length array, which is scaled by its size at run time
+    pub fn pointee_size(&self, id: TypeId) -> Option<u64> {
+        self.pointee(id)?;
+        match self.scaled_pointee(id) {
+            Some(pointee) => self.size_of(pointee),
+            None => Some(1),
+        }
+    }
+
+    pub fn element(&self, id: TypeId) -> Option<TypeId> {
+        match self.kind(id) {
+            TypeKind::Array(element, _) => Some(*element),
+            _ => None,
+        }
+    }
+
+    pub fn function_type(&self, id: TypeId) -> Option<&FunctionType> {
+        match self.kind(id) {
+            TypeKind::Function(function) => Some(function),
+            _ => None,
+        }
+    }
+
+    //an object type whose size is known
+    pub fn is_complete(&self, id: TypeId) -> bool {
+        match self.kind(id) {
+            TypeKind::Void | TypeKind::Function(_) => false,
+            TypeKind::Array(_, ArraySize::Incomplete) => false,
+            TypeKind::Array(element, _) => self.is_complete(*element),
+            TypeKind::Struct(record) | TypeKind::Union(record) => {
+                self.record(*record).members.is_some()
+            }
+            TypeKind::Enum(enumeration) => self.enumeration(*enumeration).complete,
+            _ => true,
+        }
+    }
+
+    //an array whose size is known only when its declaration is reached, which is a
+    //variable length array or an array of them
+    pub fn is_variably_sized(&self, id: TypeId) -> bool {
+        match self.kind(id) {
+            TypeKind::Array(_, ArraySize::Variable(_)) => true,
+            TypeKind::Array(element, _) => self.is_variably_sized(*element),
+            _ => false,
+        }
+    }
+
+    //arrays and functions used as values become pointers to their first element and to
+    //themselves, and the qualifiers of an rvalue are dropped
+    pub fn decay(&mut self, id: TypeId) -> TypeId {
+        match *self.kind(id) {
+            TypeKind::Array(element, _) => self.pointer(element),
+            TypeKind::Function(_) => self.pointer(id),
+            _ => self.unqualified(id),
+        }
+    }
+
+    //the size in bytes of a complete type on x86-64, None for the others
+    pub fn size_of(&self, id: TypeId) -> Option<u64> {
+        match self.kind(id) {
+            TypeKind::Integer(kind) => Some(kind.size()),
+            TypeKind::Floating(kind) => Some(kind.size()),
+            TypeKind::Complex(kind) => Some(kind.size() * 2),
+            TypeKind::Pointer(_) => Some(8),
+            TypeKind::Array(element, ArraySize::Constant(size)) => {
+                self.size_of(*element)?.checked_mul(*size)
+            }
+            TypeKind::Enum(enumeration) if self.enumeration(*enumeration).complete => Some(4),
+            TypeKind::Struct(record) | TypeKind::Union(record) if self.is_complete(id) => {
+                Some(self.record(*record).size)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn align_of(&self, id: TypeId) -> Option<u64> {
+        if let Some(align) = self.get(id).align {
+            return Some(align);
+        }
+        match self.kind(id) {
+            TypeKind::Array(element, _) => self.align_of(*element),
+            TypeKind::Complex(kind) => Some(kind.size()),
+            TypeKind::Struct(record) | TypeKind::Union(record) if self.is_complete(id) => {
+                Some(self.record(*record).align)
+            }
+            _ => self.size_of(id),
+        }
+    }
+
+    //the integer promotions: types of lower rank than int become int, which can represent
+    //all their values
+    pub fn promoted(&mut self, id: TypeId) -> TypeId {
+        match self.integer_kind(id) {
+            Some(kind) if kind.rank() < IntegerKind::Int.rank() => self.int(),
+            Some(kind) => self.integer(kind),
+            None => self.unqualified(id),
+        }
+    }
+
+    //the usual arithmetic conversions, which give the common type of the operands of most
+    //binary operators
+    pub fn common_type(&mut self, a: TypeId, b: TypeId) -> TypeId {
+        match (self.kind(a).clone(), self.kind(b).clone()) {
+            (TypeKind::Complex(a), TypeKind::Complex(b)) => {
+                self.unqualified_type(TypeKind::Complex(a.max(b)))
+            }
+            (TypeKind::Complex(a), TypeKind::Floating(b))
+            | (TypeKind::Floating(b), TypeKind::Complex(a)) => {
+                self.unqualified_type(TypeKind::Complex(a.max(b)))
+            }
+            (TypeKind::Complex(_), _) => self.unqualified(a),
+            (_, TypeKind::Complex(_)) => self.unqualified(b),
+            (TypeKind::Floating(a), TypeKind::Floating(b)) => self.floating(a.max(b)),
+            (TypeKind::Floating(_), _) => self.unqualified(a),
+            (_, TypeKind::Floating(_)) => self.unqualified(b),
+            _ => {
+                let a = self.promoted(a);
+                let b = self.promoted(b);
+                let (a, b) = match (self.integer_kind(a), self.integer_kind(b)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return a,
+                };
+                let kind = if a == b {
+                    a
+                } else if a.is_signed() == b.is_signed() {
+                    a.max(b)
+                } else {
+                    let (signed, unsigned) = if a.is_signed() { (a, b) } else { (b, a) };
+                    if unsigned.rank() >= signed.rank() {
+                        unsigned
+                    } else if signed.size() > unsigned.size() {
+                        signed
+                    } else {
+                        signed.to_unsigned()
+                    }
+                };
+                self.integer(kind)
+            }
+        }
+    }
+
+    //two types are compatible when they could be the same type, which is weaker than
+    //equality for arrays of unknown size and functions without a prototype
+    pub fn is_compatible(&self, a: TypeId, b: TypeId) -> bool {
+        if a == b {
+            return true;
+        }
+        if self.qualifiers(a) != self.qualifiers(b) {
+            return false;
+        }
+        //an aligned typedef names the same type as the one it aligns
+        if self.kind(a) == self.kind(b) {
+            return true;
+        }
+        match (self.kind(a), self.kind(b)) {
+            (TypeKind::Pointer(a), TypeKind::Pointer(b)) => self.is_compatible(*a, *b),
+            (TypeKind::Array(a, a_size), TypeKind::Array(b, b_size)) => {
+                let sizes = match (a_size, b_size) {
+                    (ArraySize::Constant(a), ArraySize::Constant(b)) => a == b,
+                    _ => true,
+                };
+                sizes && self.is_compatible(*a, *b)
+            }
+            (TypeKind::Function(a), TypeKind::Function(b)) => {
+                if !self.is_compatible(a.return_type, b.return_type) {
+                    return false;
+                }
+                if !a.prototype || !b.prototype {
+                    return true;
+                }
+                a.variadic == b.variadic
+                    && a.parameters.len() == b.parameters.len()
+                    && a.parameters.iter().zip(&b.parameters).all(|(a, b)| {
+                        let (a, b) = (self.get(*a), self.get(*b));
+                        //top-level qualifiers of parameters do not matter
+                        a.kind == b.kind
+                    })
+            }
+            (TypeKind::Enum(_), TypeKind::Integer(IntegerKind::Int))
+            | (TypeKind::Integer(IntegerKind::Int), TypeKind::Enum(_)) => true,
+            _ => false,
+        }
+    }
+
+    //the type as it is spelled in C, for diagnostics
+    pub fn display(&self, id: TypeId) -> String {
+        self.display_with(id, String::new())
+    }
+
+    //declarators are written inside out, so the part already written goes in the middle
+    fn display_with(&self, id: TypeId, inner: String) -> String {
+        let ty = self.get(id);
+        let qualifiers = ty.qualifiers.spelling();
+        let base = match &ty.kind {
+            TypeKind::Pointer(pointee) => {
+                let inner = format!("*{}{}", qualifiers, inner);
+                let inner = match self.kind(*pointee) {
+                    TypeKind::Array(_, _) | TypeKind::Function(_) => format!("({})", inner),
+                    _ => inner,
+                };
+                return self.display_with(*pointee, inner);
+            }
+            TypeKind::Array(element, size) => {
+                let size = match size {
+                    ArraySize::Incomplete => String::new(),
+                    ArraySize::Constant(size) => size.to_string(),
+                    ArraySize::Variable(length) => self.length(*length).spelling.clone(),
+                };
+                return self.display_with(*element, format!("{}[{}]", inner, size));
+            }
+            TypeKind::Function(function) => {
+                let mut parameters: Vec<String> = function
+                    .parameters
+                    .iter()
+                    .map(|parameter| self.display(*parameter))
+                    .collect();
+                if function.variadic {
+                    parameters.push("...".to_string());
+                }
+                if parameters.is_empty() && function.prototype {
+                    parameters.push("void".to_string());
+                }
+                let inner = format!("{}({})", inner, parameters.join(", "));
+                return self.display_with(function.return_type, inner);
+            }
+            TypeKind::Void => "void".to_string(),
+            TypeKind::Integer(kind) => kind.spelling().to_string(),
+            TypeKind::Floating(kind) => kind.spelling().to_string(),
+            TypeKind::Complex(kind) => format!("_Complex {}", kind.spelling()),
+            TypeKind::Struct(record) | TypeKind::Union(record) => {
+                let record = self.record(*record);
+                let keyword = match record.kind {
+                    RecordKind::Struct => "struct",
+                    RecordKind::Union => "union",
+                };
+                match &record.name {
+                    Some(name) => format!("{} {}", keyword, name),
+                    None => format!("{} <anonymous>", keyword),
+                }
+            }
+            TypeKind::Enum(enumeration) => match &self.enumeration(*enumeration).name {
+                Some(name) => format!("enum {}", name),
+                None => "enum <anonymous>".to_string(),
+            },
+        };
+
+        if inner.is_empty() {
+            format!("{}{}", qualifiers, base)
+        } else {
+            format!("{}{} {}", qualifiers, base, inner)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use IntegerKind::*;
+
+    fn common(types: &mut TypeArena, a: IntegerKind, b: IntegerKind) -> TypeId {
+        let (a, b) = (types.integer(a), types.integer(b));
+        types.common_type(a, b)
+    }
+
+    #[test]
+    fn integers_narrower_than_int_are_promoted() {
+        let mut types = TypeArena::new();
+        let int = types.int();
+        for kind in [Bool, Char, SignedChar, UnsignedChar, Short, UnsignedShort] {
+            let ty = types.integer(kind);
+            assert_eq!(types.promoted(ty), int, "{:?}", kind);
+        }
+        let unsigned = types.integer(UnsignedInt);
+        assert_eq!(types.promoted(unsigned), unsigned);
+    }
+
+    #[test]
+    fn usual_arithmetic_conversions() {
+        let mut types = TypeArena::new();
+        let expected = [
+            (Char, Short, Int),
+            (Int, UnsignedInt, UnsignedInt),
+            (UnsignedInt, Long, Long),
+            (UnsignedLong, LongLong, UnsignedLongLong),
+            (UnsignedShort, Int, Int),
+        ];
+        for (a, b, result) in expected {
+            let result = types.integer(result);
+            assert_eq!(common(&mut types, a, b), result, "{:?} {:?}", a, b);
+            assert_eq!(common(&mut types, b, a), result, "{:?} {:?}", b, a);
+        }
+        let long = types.integer(Long);
+        let float = types.floating(FloatingKind::Float);
+        let double = types.floating(FloatingKind::Double);
+        assert_eq!(types.common_type(long, float), float);
+        assert_eq!(types.common_type(float, double), double);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::semantic::symbol::*;
+use crate::semantic::types::*;
+use crate::tokenizer::*;
+use crate::warning::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    File,
+    Prototype, // the parameter list of a function declarator
+    Function,  // the outermost block of a function definition, with the parameters
+    Block,
+}
+
+//identifiers of objects, functions, typedefs and enumeration constants share the ordinary
+//name space, struct, union and enum tags have their own
+#[derive(Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub ordinary: HashMap<String, SymbolId>,
+    pub tags: HashMap<String, Tag>,
+}
+
+//labels are the only identifiers with function scope, so a goto may come before its label
+#[derive(Debug, Default)]
+pub struct Labels {
+    pub defined: HashMap<String, Span>,
+    pub used: Vec<(String, Span)>,
+}
+
+//the statements a break may leave, the innermost last. a switch collects its labels to
+//find the duplicates
+#[derive(Debug)]
+pub enum Enclosing {
+    Loop,
+    Switch(Switch),
+}
+
+#[derive(Debug)]
+pub struct Switch {
+    pub ty: Option<TypeId>, // of the promoted condition, None if it is broken
+    pub cases: Vec<(i128, i128, Span)>, // the ranges of values of the case labels
+    pub default: Option<Span>,
+}
+
+pub struct SemanticContext {
+    pub standard: Standard,
+    pub types: TypeArena,
+    pub symbols: SymbolTable,
+    pub scopes: Vec<Scope>,                    // the file scope first
+    pub external: HashMap<String, SymbolId>,   // identifiers with external linkage
+    pub labels: Labels,                        // of the function being analyzed
+    pub enclosing: Vec<Enclosing>,             // of the statement being analyzed
+    pub return_type: Option<TypeId>,           // of the function being analyzed
+    pub packing: Vec<(Location, Option<u64>)>, // set by #pragma pack from each location on
+    pub compound_literals: usize,              // with static storage duration so far
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl SemanticContext {
+    pub fn new(standard: Standard) -> SemanticContext {
+        SemanticContext {
+            standard,
+            types: TypeArena::new(),
+            symbols: SymbolTable::new(),
+            scopes: vec![Scope::new(ScopeKind::File)],
+            external: HashMap::new(),
+            labels: Labels::default(),
+            enclosing: Vec::new(),
+            return_type: None,
+            packing: Vec::new(),
+            compound_literals: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl Scope {
+    pub fn new(kind: ScopeKind) -> Scope {
+        Scope {
+            kind,
+            ordinary: HashMap::new(),
+            tags: HashMap::new(),
+        }
+    }
+}
+
+//the result of analyzing a construct which is dropped from the typed tree if it is broken.
+//the diagnostic is reported where the construct is dropped.
+pub type SemanticResult<T> = Result<T, Diagnostic>;
+
+pub fn report(context: &mut SemanticContext, diagnostic: Diagnostic) {
+    context.diagnostics.push(diagnostic);
+}
+
+//reports an error about a declaration with a note pointing at the one it conflicts with
+pub fn report_conflict(
+    context: &mut SemanticContext,
+    message: String,
+    span: Span,
+    note: &str,
+    previous: Span,
+) {
+    report(context, Diagnostic::error(message, span));
+    report(context, Diagnostic::note(note.to_string(), previous));
+}
+
+pub fn error<T>(message: String, span: Span) -> SemanticResult<T> {
+    Err(Diagnostic::error(message, span))
+}
+
+pub fn push_scope(context: &mut SemanticContext, kind: ScopeKind) {
+    context.scopes.push(Scope::new(kind));
+}
+
+//the objects declared in a block which no expression referred to are reported when it ends
+pub fn pop_scope(context: &mut SemanticContext) {
+    let scope = context.scopes.pop().unwrap();
+    if !matches!(scope.kind, ScopeKind::Block | ScopeKind::Function) {
+        return;
+    }
+    let mut unused: Vec<(String, Span)> = scope
+        .ordinary
+        .values()
+        .map(|id| symbol(context, *id))
+        .filter(|symbol| {
+            symbol.kind == SymbolKind::Object
+                && symbol.linkage == Linkage::None
+                && !symbol.parameter
+                && !symbol.used
+        })
+        .map(|symbol| (symbol.name.clone(), symbol.span))
+        .collect();
+    unused.sort_by_key(|(_, span)| span.begin);
+    for (name, span) in unused {
+        let message = format!("unused variable '{}'.", name);
+        let warning = Warning::UnusedVariable;
+        report(context, Diagnostic::named_warning(warning, message, span));
+    }
+}
+
+pub fn scope_kind(context: &SemanticContext) -> ScopeKind {
+    context.scopes.last().unwrap().kind
+}
+
+pub fn is_file_scope(context: &SemanticContext) -> bool {
+    scope_kind(context) == ScopeKind::File
+}
+
+pub fn symbol(context: &SemanticContext, id: SymbolId) -> &Symbol {
+    context.symbols.get(id)
+}
+
+pub fn symbol_mut(context: &mut SemanticContext, id: SymbolId) -> &mut Symbol {
+    context.symbols.get_mut(id)
+}
+
+//makes the symbol visible in the current scope, where it may already be declared in
+//another one
+pub fn bind_symbol(context: &mut SemanticContext, id: SymbolId) {
+    let name = symbol(context, id).name.clone();
+    if symbol(context, id).linkage == Linkage::External {
+        context.external.insert(name.clone(), id);
+    }
+    context.scopes.last_mut().unwrap().ordinary.insert(name, id);
+}
+
+//declares symbol in the current scope
+pub fn add_symbol(context: &mut SemanticContext, symbol: Symbol) -> SymbolId {
+    let id = context.symbols.add(symbol);
+    bind_symbol(context, id);
+    id
+}
+
+//the innermost declaration of name which is visible
+pub fn lookup(context: &SemanticContext, name: &str) -> Option<SymbolId> {
+    context
+        .scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.ordinary.get(name).copied())
+}
+
+pub fn lookup_in_current_scope(context: &SemanticContext, name: &str) -> Option<SymbolId> {
+    context.scopes.last().unwrap().ordinary.get(name).copied()
+}
+
+pub fn declare_tag(context: &mut SemanticContext, name: &str, ty: TypeId, span: Span) {
+    context
+        .scopes
+        .last_mut()
+        .unwrap()
+        .tags
+        .insert(name.to_string(), Tag { ty, span });
+}
+
+pub fn lookup_tag(context: &SemanticContext, name: &str) -> Option<Tag> {
+    context
+        .scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.tags.get(name).copied())
+}
+
+pub fn lookup_tag_in_current_scope(context: &SemanticContext, name: &str) -> Option<Tag> {
+    context.scopes.last().unwrap().tags.get(name).copied()
+}
+
+pub fn display_type(context: &SemanticContext, ty: TypeId) -> String {
+    context.types.display(ty)
+}
@@ -0,0 +1,38 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::parser::*;
+use crate::semantic::*;
+use crate::tokenizer::*;
+
+//the syntax tree of source and the diagnostics of parsing it
+pub fn parse_source(source: &str, standard: Standard) -> (AST<'static>, Vec<Diagnostic>) {
+    let (tokens, _) = leaked_tokens(source, standard);
+    parse(tokens, &options(standard))
+}
+
+//the typed tree of source and the diagnostics of analyzing it, which must parse
+pub fn analyze_source(source: &str, standard: Standard) -> (TypedAST<'static>, Vec<Diagnostic>) {
+    let (tokens, pragmas) = leaked_tokens(source, standard);
+    let options = options(standard);
+    let (ast, diagnostics) = parse(tokens, &options);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    analyze(&ast, &pragmas, &options)
+}
+
+//the trees borrow the source and its tokens, which are leaked so that the trees can be
+//returned. a test can afford that.
+fn leaked_tokens(
+    source: &str,
+    standard: Standard,
+) -> (&'static [Token<'static>], Vec<Pragma<'static>>) {
+    let source: &'static str = Box::leak(source.to_string().into_boxed_str());
+    let (tokens, pragmas) = tokenize(source, standard).unwrap();
+    (Box::leak(tokens.into_boxed_slice()), pragmas)
+}
+
+fn options(standard: Standard) -> Options {
+    Options {
+        standard,
+        ..Options::default()
+    }
+}
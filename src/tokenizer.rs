@@ -1,3 +1,4 @@
+use crate::options::*;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -104,6 +105,172 @@ pub enum KeywordKind {
     Noreturn,
     StaticAssert,
     ThreadLocal,
+    Asm,       // GNU extension
+    Attribute, // GNU extension
+    Extension, // GNU extension
+    Typeof,    // GNU extension
+    Offsetof,  // GNU extension
+}
+
+//longer punctuators come first so that the longest match wins
+const PUNCTUATORS: [(&str, PunctuatorKind); 54] = [
+    ("%:%:", PunctuatorKind::DoublePercentColons),
+    ("...", PunctuatorKind::TripleDots),
+    ("<<=", PunctuatorKind::DoubleLessThansEqual),
+    (">>=", PunctuatorKind::DoubleGreaterThansEqual),
+    ("->", PunctuatorKind::Arrow),
+    ("++", PunctuatorKind::DoublePluses),
+    ("--", PunctuatorKind::DoubleMinuses),
+    ("<<", PunctuatorKind::DoubleLessThans),
+    (">>", PunctuatorKind::DoubleGreaterThans),
+    ("<=", PunctuatorKind::LessThanEqual),
+    (">=", PunctuatorKind::GreaterThanEqual),
+    ("==", PunctuatorKind::DoubleEquals),
+    ("!=", PunctuatorKind::ExclamationEqual),
+    ("&&", PunctuatorKind::DoubleAmpersands),
+    ("||", PunctuatorKind::DoublePipelines),
+    ("*=", PunctuatorKind::StarEqual),
+    ("/=", PunctuatorKind::SlashEqual),
+    ("%=", PunctuatorKind::PercentEqual),
+    ("+=", PunctuatorKind::PlusEqual),
+    ("-=", PunctuatorKind::MinusEqual),
+    ("&=", PunctuatorKind::AmpersandEqual),
+    ("^=", PunctuatorKind::HatEqual),
+    ("|=", PunctuatorKind::PipelineEqual),
+    ("##", PunctuatorKind::DoubleSharps),
+    ("<:", PunctuatorKind::LessThanColon),
+    (":>", PunctuatorKind::ColonGreaterThan),
+    ("<%", PunctuatorKind::LessThanPercent),
+    ("%>", PunctuatorKind::PercentGreaterThan),
+    ("%:", PunctuatorKind::PercentColon),
+    ("[", PunctuatorKind::LeftSquareBracket),
+    ("]", PunctuatorKind::RightSquareBracket),
+    ("(", PunctuatorKind::LeftRoundBracket),
+    (")", PunctuatorKind::RightRoundBracket),
+    ("{", PunctuatorKind::LeftCurlyBracket),
+    ("}", PunctuatorKind::RightCurlyBracket),
+    (".", PunctuatorKind::Dot),
+    ("&", PunctuatorKind::Ampersand),
+    ("*", PunctuatorKind::Star),
+    ("+", PunctuatorKind::Plus),
+    ("-", PunctuatorKind::Minus),
+    ("~", PunctuatorKind::Tilde),
+    ("!", PunctuatorKind::Exclamation),
+    ("/", PunctuatorKind::Slash),
+    ("%", PunctuatorKind::Percent),
+    ("<", PunctuatorKind::LessThan),
+    (">", PunctuatorKind::GreaterThan),
+    ("^", PunctuatorKind::Hat),
+    ("|", PunctuatorKind::Pipeline),
+    ("?", PunctuatorKind::Question),
+    (":", PunctuatorKind::Colon),
+    (";", PunctuatorKind::Semicolon),
+    ("=", PunctuatorKind::Equal),
+    (",", PunctuatorKind::Commma),
+    ("#", PunctuatorKind::Sharp),
+];
+
+//the first spelling of a kind is the one used in diagnostics
+const KEYWORDS: [(&str, KeywordKind); 63] = [
+    ("auto", KeywordKind::Auto),
+    ("break", KeywordKind::Break),
+    ("case", KeywordKind::Case),
+    ("char", KeywordKind::Char),
+    ("const", KeywordKind::Const),
+    ("continue", KeywordKind::Continue),
+    ("default", KeywordKind::_Default),
+    ("do", KeywordKind::Do),
+    ("double", KeywordKind::Double),
+    ("else", KeywordKind::Else),
+    ("enum", KeywordKind::Enum),
+    ("extern", KeywordKind::Extern),
+    ("float", KeywordKind::Float),
+    ("for", KeywordKind::For),
+    ("goto", KeywordKind::Goto),
+    ("if", KeywordKind::If),
+    ("inline", KeywordKind::Inline),
+    ("int", KeywordKind::Int),
+    ("long", KeywordKind::Long),
+    ("register", KeywordKind::Register),
+    ("restrict", KeywordKind::Restrict),
+    ("return", KeywordKind::Return),
+    ("short", KeywordKind::Short),
+    ("signed", KeywordKind::Signed),
+    ("sizeof", KeywordKind::Sizeof),
+    ("static", KeywordKind::Static),
+    ("struct", KeywordKind::Struct),
+    ("switch", KeywordKind::Switch),
+    ("typedef", KeywordKind::Typedef),
+    ("union", KeywordKind::Union),
+    ("unsigned", KeywordKind::Unsigned),
+    ("void", KeywordKind::Void),
+    ("volatile", KeywordKind::Volatile),
+    ("while", KeywordKind::While),
+    ("_Alignas", KeywordKind::Alignas),
+    ("_Alignof", KeywordKind::Alignof),
+    ("_Atomic", KeywordKind::Atomic),
+    ("_Bool", KeywordKind::Bool),
+    ("_Complex", KeywordKind::Complex),
+    ("_Generic", KeywordKind::Generic),
+    ("_Imaginary", KeywordKind::Imaginary),
+    ("_Noreturn", KeywordKind::Noreturn),
+    ("_Static_assert", KeywordKind::StaticAssert),
+    ("_Thread_local", KeywordKind::ThreadLocal),
+    //reserved spellings of GNU extensions, which are keywords in every mode
+    ("__asm__", KeywordKind::Asm),
+    ("__asm", KeywordKind::Asm),
+    ("__attribute__", KeywordKind::Attribute),
+    ("__attribute", KeywordKind::Attribute),
+    ("__extension__", KeywordKind::Extension),
+    ("__typeof__", KeywordKind::Typeof),
+    ("__typeof", KeywordKind::Typeof),
+    ("__alignof__", KeywordKind::Alignof),
+    ("__builtin_offsetof", KeywordKind::Offsetof),
+    ("__const__", KeywordKind::Const),
+    ("__const", KeywordKind::Const),
+    ("__inline__", KeywordKind::Inline),
+    ("__inline", KeywordKind::Inline),
+    ("__restrict__", KeywordKind::Restrict),
+    ("__restrict", KeywordKind::Restrict),
+    ("__signed__", KeywordKind::Signed),
+    ("__signed", KeywordKind::Signed),
+    ("__volatile__", KeywordKind::Volatile),
+    ("__volatile", KeywordKind::Volatile),
+];
+
+//ordinary identifiers in ISO C, keywords only with GNU extensions
+const GNU_KEYWORDS: [(&str, KeywordKind); 2] =
+    [("asm", KeywordKind::Asm), ("typeof", KeywordKind::Typeof)];
+
+impl PunctuatorKind {
+    pub fn spelling(self) -> &'static str {
+        PUNCTUATORS
+            .iter()
+            .find(|(_, kind)| *kind == self)
+            .map(|(spelling, _)| *spelling)
+            .unwrap()
+    }
+}
+
+impl KeywordKind {
+    pub fn spelling(self) -> &'static str {
+        KEYWORDS
+            .iter()
+            .find(|(_, kind)| *kind == self)
+            .map(|(spelling, _)| *spelling)
+            .unwrap()
+    }
+
+    pub fn is_gnu_extension(self) -> bool {
+        matches!(
+            self,
+            KeywordKind::Asm
+                | KeywordKind::Attribute
+                | KeywordKind::Extension
+                | KeywordKind::Typeof
+                | KeywordKind::Offsetof
+        )
+    }
 }
 
 pub enum Literal<'a> {
@@ -122,7 +289,7 @@ impl fmt::Debug for Literal<'_> {
     }
 }
 
-pub enum Token<'a> {
+pub enum TokenKind<'a> {
     Punctuator(PunctuatorKind),
     Keyword(KeywordKind),
     Identifier(&'a str),
@@ -130,30 +297,104 @@ pub enum Token<'a> {
     End,
 }
 
-impl fmt::Debug for Token<'_> {
+impl fmt::Debug for TokenKind<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Token::Punctuator(kind) => write!(f, "Punctuator:{:?}", kind),
-            Token::Keyword(kind) => write!(f, "Keyword:{:?}", kind),
-            Token::Identifier(s) => write!(f, "Identifier:{}", s),
-            Token::Literal(s) => write!(f, "Literal:{:?}", s),
-            Token::End => write!(f, "End"),
+            TokenKind::Punctuator(kind) => write!(f, "Punctuator:{:?}", kind),
+            TokenKind::Keyword(kind) => write!(f, "Keyword:{:?}", kind),
+            TokenKind::Identifier(s) => write!(f, "Identifier:{}", s),
+            TokenKind::Literal(s) => write!(f, "Literal:{:?}", s),
+            TokenKind::End => write!(f, "End"),
         }
     }
 }
 
+//the token as it is spelled in the source, for diagnostics
+impl fmt::Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Punctuator(kind) => write!(f, "'{}'", kind.spelling()),
+            TokenKind::Keyword(kind) => write!(f, "'{}'", kind.spelling()),
+            TokenKind::Identifier(s) => write!(f, "'{}'", s),
+            TokenKind::Literal(Literal::String(s)) => write!(f, "\"{}\"", s),
+            TokenKind::Literal(Literal::Char(c)) => write!(f, "'{}'", c.escape_default()),
+            TokenKind::Literal(Literal::Number(n)) => write!(f, "'{}'", n),
+            TokenKind::End => write!(f, "end of file"),
+        }
+    }
+}
+
+//1-origin line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+//from begin up to but excluding end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub begin: Location,
+    pub end: Location,
+}
+
+impl Span {
+    //the smallest span covering both
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            begin: self.begin.min(other.begin),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+pub struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub span: Span,
+}
+
+//a `#pragma` line, which is kept apart from the tokens since it may appear between any
+//two of them. text is what follows `pragma`.
+#[derive(Debug)]
+pub struct Pragma<'a> {
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl fmt::Debug for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:?}", self.span.begin, self.kind)
+    }
+}
+
 struct TokenizationContext<'a> {
     head: &'a str,
+    location: Location,
 }
 
 //context.head must be greater than or equal count
 fn consume_str(context: &mut TokenizationContext, count: usize) {
     assert!(context.head.len() >= count);
+    for c in context.head[..count].chars() {
+        if c == '\n' {
+            context.location.line += 1;
+            context.location.column = 1;
+        } else {
+            context.location.column += 1;
+        }
+    }
     context.head = &context.head[count..];
 }
 
 fn skip_whitespace(context: &mut TokenizationContext) {
-    context.head = context.head.trim_start();
+    let count = context.head.len() - context.head.trim_start().len();
+    consume_str(context, count);
 }
 
 fn consume_reserved(context: &mut TokenizationContext, q: &str) -> bool {
@@ -165,6 +406,13 @@ fn consume_reserved(context: &mut TokenizationContext, q: &str) -> bool {
     }
 }
 
+fn consume_punctuator(context: &mut TokenizationContext) -> Option<PunctuatorKind> {
+    PUNCTUATORS
+        .iter()
+        .find(|(spelling, _)| consume_reserved(context, spelling))
+        .map(|(_, kind)| *kind)
+}
+
 fn is_identifier_head(p: char) -> bool {
     p == '_' || p.is_ascii_alphabetic()
 }
@@ -203,33 +451,119 @@ fn consume_identifier<'a, 'b>(context: &'a mut TokenizationContext<'b>) -> Optio
     }
 }
 
-fn consume_literal<'a, 'b>(context: &'a mut TokenizationContext<'b>) -> Option<Literal<'b>> {
+fn keyword(identifier: &str, standard: Standard) -> Option<KeywordKind> {
+    let gnu_keywords: &[(&str, KeywordKind)] = if standard == Standard::Gnu11 {
+        &GNU_KEYWORDS
+    } else {
+        &[]
+    };
+    KEYWORDS
+        .iter()
+        .chain(gnu_keywords)
+        .find(|(spelling, _)| *spelling == identifier)
+        .map(|(_, kind)| *kind)
+}
+
+//the value of the escape sequence after a backslash at the start of rest, and how many bytes
+//long it is. an octal or hexadecimal one must fit in a byte.
+fn escape(rest: &str) -> Result<(u8, usize), &'static str> {
+    let simple = match rest.chars().next() {
+        Some('n') => b'\n',
+        Some('t') => b'\t',
+        Some('r') => b'\r',
+        Some('a') => 0x07,
+        Some('b') => 0x08,
+        Some('f') => 0x0c,
+        Some('v') => 0x0b,
+        Some(c @ ('\\' | '\'' | '"' | '?')) => c as u8,
+        Some('x') => {
+            let digits = rest[1..]
+                .chars()
+                .take_while(char::is_ascii_hexdigit)
+                .count();
+            if digits == 0 {
+                return Err("\\x used with no following hex digits");
+            }
+            return match u8::from_str_radix(&rest[1..1 + digits], 16) {
+                Ok(value) => Ok((value, 1 + digits)),
+                Err(_) => Err("hex escape sequence out of range"),
+            };
+        }
+        Some('0'..='7') => {
+            let digits = rest.chars().take(3).take_while(|c| c.is_digit(8)).count();
+            return match u8::from_str_radix(&rest[..digits], 8) {
+                Ok(value) => Ok((value, digits)),
+                Err(_) => Err("octal escape sequence out of range"),
+            };
+        }
+        _ => return Err("unknown escape sequence"),
+    };
+    Ok((simple, 1))
+}
+
+//the bytes the spelling of a string literal stands for, its escape sequences replaced by
+//the characters they denote. the spelling was checked when it was tokenized.
+pub fn string_bytes(spelling: &str) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut rest = spelling;
+    while let Some(position) = rest.find('\\') {
+        result.extend_from_slice(&rest.as_bytes()[..position]);
+        let (value, length) = escape(&rest[position + 1..]).unwrap();
+        result.push(value);
+        rest = &rest[position + 1 + length..];
+    }
+    result.extend_from_slice(rest.as_bytes());
+    result
+}
+
+//the length of the body of a string or character literal up to the closing quote, which
+//must be on the same line
+fn literal_body(body: &str, quote: char) -> Result<usize, &'static str> {
+    let mut length = 0;
+    loop {
+        match body[length..].chars().next() {
+            Some(c) if c == quote => return Ok(length),
+            Some('\\') => length += 1 + escape(&body[length + 1..])?.1,
+            Some('\n') | None if quote == '"' => return Err("missing terminating '\"' character"),
+            Some('\n') | None => return Err("missing terminating ' character"),
+            Some(c) => length += c.len_utf8(),
+        }
+    }
+}
+
+fn consume_literal<'a, 'b>(
+    context: &'a mut TokenizationContext<'b>,
+) -> Result<Option<Literal<'b>>, &'static str> {
     let mut chars = context.head.chars();
     match chars.next() {
-        Some(c) if c == '\"' => {
-            let mut count = 0;
-            for c in chars {
-                match c {
-                    c if c == '\"' => {
-                        break;
-                    }
-                    _ => {}
+        Some('"') => {
+            let length = literal_body(&context.head[1..], '"')?;
+            let result = &context.head[1..1 + length];
+            consume_str(context, length + 2);
+            Ok(Some(Literal::String(result)))
+        }
+        Some('\'') => {
+            let length = literal_body(&context.head[1..], '\'')?;
+            let body = &context.head[1..1 + length];
+            let (value, used) = match body.chars().next() {
+                Some('\\') => {
+                    let (value, length) = escape(&body[1..])?;
+                    (value, 1 + length)
                 }
-                count += 1;
+                Some(c) if c.is_ascii() => (c as u8, 1),
+                Some(_) => return Err("non-ASCII character constant"),
+                None => return Err("empty character constant"),
+            };
+            if used != length {
+                return Err("multi-character character constant");
             }
-            let result = &context.head[1..count - 1];
-            consume_str(context, count);
-            Some(Literal::String(result))
-        }
-        Some(c) if c == '\'' => {
-            let result = chars.next().unwrap();
-            consume_str(context, 3);
-            Some(Literal::Char(result))
+            consume_str(context, length + 2);
+            Ok(Some(Literal::Char(char::from(value))))
         }
-        Some(c) if c.is_digit(10) => {
+        Some(c) if c.is_ascii_digit() => {
             let mut count = 1;
             for c in chars {
-                if !c.is_digit(10) {
+                if !c.is_ascii_digit() {
                     break;
                 }
                 count += 1;
@@ -238,232 +572,158 @@ fn consume_literal<'a, 'b>(context: &'a mut TokenizationContext<'b>) -> Option<L
             let result = result.parse::<u32>();
             consume_str(context, count);
             match result {
-                Ok(n) => Some(Literal::Number(n)),
-                Err(_) => None,
+                Ok(n) => Ok(Some(Literal::Number(n))),
+                Err(_) => Ok(None),
             }
         }
-        _ => None,
+        _ => Ok(None),
     }
 }
 
-pub fn tokenize(source: &str) -> Result<Vec<Token>, &str> {
+//a directive is a line which starts with #
+fn is_line_head(tokens: &[Token], location: Location) -> bool {
+    tokens
+        .last()
+        .is_none_or(|token| token.span.end.line < location.line)
+}
+
+//there is no preprocessor, so #pragma is the only directive understood
+fn consume_directive<'a, 'b>(
+    context: &'a mut TokenizationContext<'b>,
+) -> Result<Pragma<'b>, &'static str> {
+    let begin = context.location;
+    let length = context.head.find('\n').unwrap_or(context.head.len());
+    let line = &context.head[1..length];
+    consume_str(context, length);
+    let span = Span {
+        begin,
+        end: context.location,
+    };
+
+    match line.trim_start().strip_prefix("pragma") {
+        Some(text) if text.is_empty() || text.starts_with(char::is_whitespace) => Ok(Pragma {
+            text: text.trim(),
+            span,
+        }),
+        _ => Err("unsupported preprocessing directive"),
+    }
+}
+
+pub fn tokenize(
+    source: &str,
+    standard: Standard,
+) -> Result<(Vec<Token<'_>>, Vec<Pragma<'_>>), &str> {
     let mut result = Vec::<Token>::new();
-    let context = &mut TokenizationContext { head: source };
+    let mut pragmas = Vec::new();
+    let context = &mut TokenizationContext {
+        head: source,
+        location: Location { line: 1, column: 1 },
+    };
     loop {
+        skip_whitespace(context);
         if context.head.is_empty() {
             break;
         }
 
-        skip_whitespace(context);
+        let begin = context.location;
+        if context.head.starts_with('#') && is_line_head(&result, begin) {
+            pragmas.push(consume_directive(context)?);
+            continue;
+        }
 
-        let item: Token = if consume_reserved(context, "%:%:") {
-            Token::Punctuator(PunctuatorKind::DoublePercentColons)
-        } else if consume_reserved(context, "...") {
-            Token::Punctuator(PunctuatorKind::TripleDots)
-        } else if consume_reserved(context, "<<=") {
-            Token::Punctuator(PunctuatorKind::DoubleLessThansEqual)
-        } else if consume_reserved(context, ">>=") {
-            Token::Punctuator(PunctuatorKind::DoubleGreaterThansEqual)
-        } else if consume_reserved(context, "->") {
-            Token::Punctuator(PunctuatorKind::Arrow)
-        } else if consume_reserved(context, "++") {
-            Token::Punctuator(PunctuatorKind::DoublePluses)
-        } else if consume_reserved(context, "--") {
-            Token::Punctuator(PunctuatorKind::DoubleMinuses)
-        } else if consume_reserved(context, "<<") {
-            Token::Punctuator(PunctuatorKind::DoubleLessThans)
-        } else if consume_reserved(context, ">>") {
-            Token::Punctuator(PunctuatorKind::DoubleGreaterThans)
-        } else if consume_reserved(context, "<=") {
-            Token::Punctuator(PunctuatorKind::LessThanEqual)
-        } else if consume_reserved(context, ">=") {
-            Token::Punctuator(PunctuatorKind::GreaterThanEqual)
-        } else if consume_reserved(context, "==") {
-            Token::Punctuator(PunctuatorKind::DoubleEquals)
-        } else if consume_reserved(context, "!=") {
-            Token::Punctuator(PunctuatorKind::ExclamationEqual)
-        } else if consume_reserved(context, "&&") {
-            Token::Punctuator(PunctuatorKind::DoubleAmpersands)
-        } else if consume_reserved(context, "||") {
-            Token::Punctuator(PunctuatorKind::DoublePipelines)
-        } else if consume_reserved(context, "*=") {
-            Token::Punctuator(PunctuatorKind::StarEqual)
-        } else if consume_reserved(context, "/=") {
-            Token::Punctuator(PunctuatorKind::SlashEqual)
-        } else if consume_reserved(context, "%=") {
-            Token::Punctuator(PunctuatorKind::PercentEqual)
-        } else if consume_reserved(context, "+=") {
-            Token::Punctuator(PunctuatorKind::PlusEqual)
-        } else if consume_reserved(context, "-=") {
-            Token::Punctuator(PunctuatorKind::MinusEqual)
-        } else if consume_reserved(context, "&=") {
-            Token::Punctuator(PunctuatorKind::AmpersandEqual)
-        } else if consume_reserved(context, "^=") {
-            Token::Punctuator(PunctuatorKind::HatEqual)
-        } else if consume_reserved(context, "|=") {
-            Token::Punctuator(PunctuatorKind::PipelineEqual)
-        } else if consume_reserved(context, "##") {
-            Token::Punctuator(PunctuatorKind::DoubleSharps)
-        } else if consume_reserved(context, "<:") {
-            Token::Punctuator(PunctuatorKind::LessThanColon)
-        } else if consume_reserved(context, ":>") {
-            Token::Punctuator(PunctuatorKind::ColonGreaterThan)
-        } else if consume_reserved(context, "<%") {
-            Token::Punctuator(PunctuatorKind::LessThanPercent)
-        } else if consume_reserved(context, "%>") {
-            Token::Punctuator(PunctuatorKind::PercentGreaterThan)
-        } else if consume_reserved(context, "%:") {
-            Token::Punctuator(PunctuatorKind::PercentColon)
-        } else if consume_reserved(context, "[") {
-            Token::Punctuator(PunctuatorKind::LeftSquareBracket)
-        } else if consume_reserved(context, "]") {
-            Token::Punctuator(PunctuatorKind::RightSquareBracket)
-        } else if consume_reserved(context, "(") {
-            Token::Punctuator(PunctuatorKind::LeftRoundBracket)
-        } else if consume_reserved(context, ")") {
-            Token::Punctuator(PunctuatorKind::RightRoundBracket)
-        } else if consume_reserved(context, "{") {
-            Token::Punctuator(PunctuatorKind::LeftCurlyBracket)
-        } else if consume_reserved(context, "}") {
-            Token::Punctuator(PunctuatorKind::RightCurlyBracket)
-        } else if consume_reserved(context, ".") {
-            Token::Punctuator(PunctuatorKind::Dot)
-        } else if consume_reserved(context, "&") {
-            Token::Punctuator(PunctuatorKind::Ampersand)
-        } else if consume_reserved(context, "*") {
-            Token::Punctuator(PunctuatorKind::Star)
-        } else if consume_reserved(context, "+") {
-            Token::Punctuator(PunctuatorKind::Plus)
-        } else if consume_reserved(context, "-") {
-            Token::Punctuator(PunctuatorKind::Minus)
-        } else if consume_reserved(context, "~") {
-            Token::Punctuator(PunctuatorKind::Tilde)
-        } else if consume_reserved(context, "!") {
-            Token::Punctuator(PunctuatorKind::Exclamation)
-        } else if consume_reserved(context, "/") {
-            Token::Punctuator(PunctuatorKind::Slash)
-        } else if consume_reserved(context, "%") {
-            Token::Punctuator(PunctuatorKind::Percent)
-        } else if consume_reserved(context, "<") {
-            Token::Punctuator(PunctuatorKind::LessThan)
-        } else if consume_reserved(context, ">") {
-            Token::Punctuator(PunctuatorKind::GreaterThan)
-        } else if consume_reserved(context, "^") {
-            Token::Punctuator(PunctuatorKind::Hat)
-        } else if consume_reserved(context, "|") {
-            Token::Punctuator(PunctuatorKind::Pipeline)
-        } else if consume_reserved(context, "?") {
-            Token::Punctuator(PunctuatorKind::Question)
-        } else if consume_reserved(context, ":") {
-            Token::Punctuator(PunctuatorKind::Colon)
-        } else if consume_reserved(context, ";") {
-            Token::Punctuator(PunctuatorKind::Semicolon)
-        } else if consume_reserved(context, "=") {
-            Token::Punctuator(PunctuatorKind::Equal)
-        } else if consume_reserved(context, ",") {
-            Token::Punctuator(PunctuatorKind::Commma)
-        } else if consume_reserved(context, "#") {
-            Token::Punctuator(PunctuatorKind::Sharp)
-        } else if consume_reserved(context, "auto") {
-            Token::Keyword(KeywordKind::Auto)
-        } else if consume_reserved(context, "break") {
-            Token::Keyword(KeywordKind::Break)
-        } else if consume_reserved(context, "case") {
-            Token::Keyword(KeywordKind::Case)
-        } else if consume_reserved(context, "char") {
-            Token::Keyword(KeywordKind::Char)
-        } else if consume_reserved(context, "const") {
-            Token::Keyword(KeywordKind::Const)
-        } else if consume_reserved(context, "continue") {
-            Token::Keyword(KeywordKind::Continue)
-        } else if consume_reserved(context, "default") {
-            Token::Keyword(KeywordKind::_Default)
-        } else if consume_reserved(context, "do") {
-            Token::Keyword(KeywordKind::Do)
-        } else if consume_reserved(context, "double") {
-            Token::Keyword(KeywordKind::Double)
-        } else if consume_reserved(context, "else") {
-            Token::Keyword(KeywordKind::Else)
-        } else if consume_reserved(context, "enum") {
-            Token::Keyword(KeywordKind::Enum)
-        } else if consume_reserved(context, "extern") {
-            Token::Keyword(KeywordKind::Extern)
-        } else if consume_reserved(context, "float") {
-            Token::Keyword(KeywordKind::Float)
-        } else if consume_reserved(context, "for") {
-            Token::Keyword(KeywordKind::For)
-        } else if consume_reserved(context, "goto") {
-            Token::Keyword(KeywordKind::Goto)
-        } else if consume_reserved(context, "if") {
-            Token::Keyword(KeywordKind::If)
-        } else if consume_reserved(context, "inline") {
-            Token::Keyword(KeywordKind::Inline)
-        } else if consume_reserved(context, "int") {
-            Token::Keyword(KeywordKind::Int)
-        } else if consume_reserved(context, "long") {
-            Token::Keyword(KeywordKind::Long)
-        } else if consume_reserved(context, "register") {
-            Token::Keyword(KeywordKind::Register)
-        } else if consume_reserved(context, "restrict") {
-            Token::Keyword(KeywordKind::Restrict)
-        } else if consume_reserved(context, "return") {
-            Token::Keyword(KeywordKind::Return)
-        } else if consume_reserved(context, "short") {
-            Token::Keyword(KeywordKind::Short)
-        } else if consume_reserved(context, "signed") {
-            Token::Keyword(KeywordKind::Signed)
-        } else if consume_reserved(context, "sizeof") {
-            Token::Keyword(KeywordKind::Sizeof)
-        } else if consume_reserved(context, "static") {
-            Token::Keyword(KeywordKind::Static)
-        } else if consume_reserved(context, "struct") {
-            Token::Keyword(KeywordKind::Struct)
-        } else if consume_reserved(context, "switch") {
-            Token::Keyword(KeywordKind::Switch)
-        } else if consume_reserved(context, "typedef") {
-            Token::Keyword(KeywordKind::Typedef)
-        } else if consume_reserved(context, "union") {
-            Token::Keyword(KeywordKind::Union)
-        } else if consume_reserved(context, "unsigned") {
-            Token::Keyword(KeywordKind::Unsigned)
-        } else if consume_reserved(context, "void") {
-            Token::Keyword(KeywordKind::Void)
-        } else if consume_reserved(context, "volatile") {
-            Token::Keyword(KeywordKind::Volatile)
-        } else if consume_reserved(context, "while") {
-            Token::Keyword(KeywordKind::While)
-        } else if consume_reserved(context, "_Alignas") {
-            Token::Keyword(KeywordKind::Alignas)
-        } else if consume_reserved(context, "_Alignof") {
-            Token::Keyword(KeywordKind::Alignof)
-        } else if consume_reserved(context, "_Atomic") {
-            Token::Keyword(KeywordKind::Atomic)
-        } else if consume_reserved(context, "_Bool") {
-            Token::Keyword(KeywordKind::Bool)
-        } else if consume_reserved(context, "_Complex") {
-            Token::Keyword(KeywordKind::Complex)
-        } else if consume_reserved(context, "_Generic") {
-            Token::Keyword(KeywordKind::Generic)
-        } else if consume_reserved(context, "_Imaginary") {
-            Token::Keyword(KeywordKind::Imaginary)
-        } else if consume_reserved(context, "_Noreturn") {
-            Token::Keyword(KeywordKind::Noreturn)
-        } else if consume_reserved(context, "_Static_assert") {
-            Token::Keyword(KeywordKind::StaticAssert)
-        } else if consume_reserved(context, "_Thread_local") {
-            Token::Keyword(KeywordKind::ThreadLocal)
+        let kind: TokenKind = if let Some(kind) = consume_punctuator(context) {
+            TokenKind::Punctuator(kind)
         } else if let Some(identifier) = consume_identifier(context) {
-            Token::Identifier(identifier)
-        } else if let Some(literal) = consume_literal(context) {
-            Token::Literal(literal)
+            match keyword(identifier, standard) {
+                Some(kind) => TokenKind::Keyword(kind),
+                None => TokenKind::Identifier(identifier),
+            }
+        } else if let Some(literal) = consume_literal(context)? {
+            TokenKind::Literal(literal)
         } else {
             println!("{}", context.head);
             return Err("invalid token");
         };
 
-        result.push(item);
+        result.push(Token {
+            kind,
+            span: Span {
+                begin,
+                end: context.location,
+            },
+        });
+    }
+
+    result.push(Token {
+        kind: TokenKind::End,
+        span: Span {
+            begin: context.location,
+            end: context.location,
+        },
+    });
+    Ok((result, pragmas))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(source: &str) -> Result<Vec<String>, &str> {
+        let (tokens, _) = tokenize(source, Standard::C11)?;
+        let result = tokens
+            .iter()
+            .filter(|token| matches!(token.kind, TokenKind::Literal(_)))
+            .map(|token| token.kind.to_string())
+            .collect();
+        Ok(result)
     }
 
-    result.push(Token::End);
-    Ok(result)
+    #[test]
+    fn string_with_escaped_quote() {
+        assert_eq!(
+            literals(r#"f("a\"b", "\\");"#),
+            Ok(vec![r#""a\"b""#.to_string(), r#""\\""#.to_string()])
+        );
+    }
+
+    #[test]
+    fn character_escapes() {
+        let (tokens, _) = tokenize(r"'\n' '\x41' '\0' '\'' 'z'", Standard::C11).unwrap();
+        let values: Vec<char> = tokens
+            .iter()
+            .filter_map(|token| match token.kind {
+                TokenKind::Literal(Literal::Char(c)) => Some(c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values, vec!['\n', 'A', '\0', '\'', 'z']);
+    }
+
+    #[test]
+    fn string_escapes_are_decoded() {
+        assert_eq!(
+            string_bytes(r"a\tb\x41\101\\\n\0"),
+            b"a\tbAA\\\n\0".to_vec()
+        );
+        assert_eq!(string_bytes(r"\xff\1234"), vec![0xff, 0o123, b'4']);
+    }
+
+    #[test]
+    fn malformed_literals_are_errors() {
+        for source in [
+            r#""abc"#,
+            "\"ab\ncd\"",
+            "'\u{e9}'",
+            r"'\q'",
+            "'ab'",
+            "''",
+            r"'\x'",
+            r"'\777'",
+        ]
+        .iter()
+        .copied()
+        {
+            assert!(tokenize(source, Standard::C11).is_err(), "{}", source);
+        }
+    }
 }
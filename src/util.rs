@@ -7,12 +7,6 @@ pub struct NonEmptyVec<T> {
 }
 
 impl<T> NonEmptyVec<T> {
-    pub fn new(neccesary: T) -> NonEmptyVec<T> {
-        NonEmptyVec {
-            body: [neccesary].into(),
-        }
-    }
-
     pub fn unwrap(self) -> Vec<T> {
         self.body
     }
@@ -33,10 +27,15 @@ pub fn vec_to_optional_non_empty_vec<T>(from: Vec<T>) -> Option<NonEmptyVec<T>>
     }
 }
 
-pub fn optional_non_empty_vec_to_vec<T>(from: Option<NonEmptyVec<T>>) -> Vec<T> {
-    match from {
-        Some(vec) => vec.body,
-        None => Vec::new(),
+pub fn options_to_either_or_both<L, R>(
+    left: Option<L>,
+    right: Option<R>,
+) -> Option<EitherOrBoth<L, R>> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(EitherOrBoth::Both(left, right)),
+        (Some(left), None) => Some(EitherOrBoth::Left(left)),
+        (None, Some(right)) => Some(EitherOrBoth::Right(right)),
+        (None, None) => None,
     }
 }
 
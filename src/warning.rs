@@ -0,0 +1,410 @@
+use crate::diagnostic::*;
+use crate::options::*;
+use crate::tokenizer::*;
+use std::collections::HashMap;
+
+//the warnings which are controlled by name, the others are always given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    UnusedVariable,
+    Shadow,
+    SignCompare,
+    ImplicitFallthrough,
+    Parentheses,
+    UnreachableCode,
+    ReturnType,
+    ImplicitFunctionDeclaration,
+    ImplicitInt,
+    Pragmas,
+}
+
+const WARNINGS: [(Warning, &str); 10] = [
+    (Warning::UnusedVariable, "unused-variable"),
+    (Warning::Shadow, "shadow"),
+    (Warning::SignCompare, "sign-compare"),
+    (Warning::ImplicitFallthrough, "implicit-fallthrough"),
+    (Warning::Parentheses, "parentheses"),
+    (Warning::UnreachableCode, "unreachable-code"),
+    (Warning::ReturnType, "return-type"),
+    (
+        Warning::ImplicitFunctionDeclaration,
+        "implicit-function-declaration",
+    ),
+    (Warning::ImplicitInt, "implicit-int"),
+    (Warning::Pragmas, "pragmas"),
+];
+
+//the warnings given without any option
+const DEFAULT: [Warning; 4] = [
+    Warning::ReturnType,
+    Warning::ImplicitFunctionDeclaration,
+    Warning::ImplicitInt,
+    Warning::Pragmas,
+];
+
+//the groups enabled by -Wall and -Wextra, which as in gcc does not include -Wall
+const ALL: [Warning; 5] = [
+    Warning::UnusedVariable,
+    Warning::Parentheses,
+    Warning::ReturnType,
+    Warning::ImplicitFunctionDeclaration,
+    Warning::ImplicitInt,
+];
+const EXTRA: [Warning; 2] = [Warning::SignCompare, Warning::ImplicitFallthrough];
+
+impl Warning {
+    //the name in -W options, without the -W
+    pub fn name(self) -> &'static str {
+        WARNINGS
+            .iter()
+            .find(|(warning, _)| *warning == self)
+            .unwrap()
+            .1
+    }
+}
+
+//the warnings a name in a -W option stands for
+fn named(name: &str) -> Option<Vec<Warning>> {
+    match name {
+        "all" => Some(ALL.to_vec()),
+        "extra" => Some(EXTRA.to_vec()),
+        name => WARNINGS
+            .iter()
+            .find(|(_, warning_name)| *warning_name == name)
+            .map(|(warning, _)| vec![*warning]),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarningState {
+    Ignored,
+    Warning,
+    Error,
+}
+
+//error is None for a warning which is an error only if -Werror is given. explicit is set
+//by an option naming the warning itself, which -Wall and -Wextra then leave alone
+#[derive(Debug, Clone, Copy)]
+struct Setting {
+    enabled: bool,
+    error: Option<bool>,
+    explicit: bool,
+}
+
+//what the -W options, and #pragma GCC diagnostic after them, ask of each warning
+#[derive(Debug, Clone)]
+pub struct WarningOptions {
+    settings: HashMap<Warning, Setting>,
+    errors: bool, // -Werror, which makes errors of all warnings, the unnamed ones too
+}
+
+impl Default for WarningOptions {
+    fn default() -> WarningOptions {
+        let settings = WARNINGS
+            .iter()
+            .map(|(warning, _)| {
+                let enabled = DEFAULT.contains(warning);
+                (
+                    *warning,
+                    Setting {
+                        enabled,
+                        error: None,
+                        explicit: false,
+                    },
+                )
+            })
+            .collect();
+        WarningOptions {
+            settings,
+            errors: false,
+        }
+    }
+}
+
+impl WarningOptions {
+    //an option without the -W: error, no-error, error=name, no-error=name, name or no-name,
+    //where name may be all or extra too. an error message for an unknown name.
+    pub fn apply(&mut self, option: &str) -> Result<(), String> {
+        let unknown = || format!("unknown warning option '-W{}'.", option);
+        match option {
+            "error" => self.errors = true,
+            "no-error" => self.errors = false,
+            _ => {
+                if let Some(name) = option.strip_prefix("error=") {
+                    let state = WarningState::Error;
+                    self.set(named(name).ok_or_else(unknown)?, state);
+                } else if let Some(name) = option.strip_prefix("no-error=") {
+                    for warning in named(name).ok_or_else(unknown)? {
+                        self.settings.get_mut(&warning).unwrap().error = Some(false);
+                    }
+                } else {
+                    let (enabled, name) = match option.strip_prefix("no-") {
+                        Some(name) => (false, name),
+                        None => (true, option),
+                    };
+                    let group = name == "all" || name == "extra";
+                    for warning in named(name).ok_or_else(unknown)? {
+                        let setting = self.settings.get_mut(&warning).unwrap();
+                        if !group {
+                            setting.explicit = true;
+                        } else if setting.explicit {
+                            continue;
+                        }
+                        setting.enabled = enabled;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //as #pragma GCC diagnostic does, where warning gives a warning even under -Werror
+    fn set(&mut self, warnings: Vec<Warning>, state: WarningState) {
+        for warning in warnings {
+            let setting = self.settings.get_mut(&warning).unwrap();
+            match state {
+                WarningState::Ignored => setting.enabled = false,
+                WarningState::Warning => {
+                    setting.enabled = true;
+                    setting.error = Some(false);
+                }
+                WarningState::Error => {
+                    setting.enabled = true;
+                    setting.error = Some(true);
+                }
+            }
+        }
+    }
+
+    //of a warning controlled by warning, or of an unnamed one for None
+    pub fn state(&self, warning: Option<Warning>) -> WarningState {
+        let setting = match warning {
+            Some(warning) => self.settings[&warning],
+            None => Setting {
+                enabled: true,
+                error: None,
+                explicit: false,
+            },
+        };
+        if !setting.enabled {
+            WarningState::Ignored
+        } else if setting.error.unwrap_or(self.errors) {
+            WarningState::Error
+        } else {
+            WarningState::Warning
+        }
+    }
+}
+
+//the warning options in effect from each location on, those of the command line before the
+//first #pragma GCC diagnostic
+#[derive(Debug)]
+pub struct DiagnosticControl {
+    options: WarningOptions,
+    changes: Vec<(Location, WarningOptions)>,
+}
+
+pub fn diagnostic_control(
+    options: &WarningOptions,
+    pragmas: &[Pragma],
+) -> (DiagnosticControl, Vec<Diagnostic>) {
+    let mut control = DiagnosticControl {
+        options: options.clone(),
+        changes: Vec::new(),
+    };
+    let mut diagnostics = Vec::new();
+    let mut current = options.clone();
+    let mut stack = Vec::new();
+    for pragma in pragmas {
+        let tokens = match tokenize(pragma.text, Standard::C11) {
+            Ok((tokens, _)) => tokens,
+            Err(_) => continue,
+        };
+        let mut tokens = tokens.iter().map(|token| &token.kind);
+        match (tokens.next(), tokens.next()) {
+            (Some(TokenKind::Identifier("GCC")), Some(TokenKind::Identifier("diagnostic"))) => {}
+            _ => continue,
+        }
+
+        let state = match tokens.next() {
+            Some(TokenKind::Identifier("push")) => {
+                stack.push(current.clone());
+                continue;
+            }
+            Some(TokenKind::Identifier("pop")) => {
+                match stack.pop() {
+                    Some(options) => current = options,
+                    None => {
+                        let message = "pragma diagnostic pop could not pop, no matching push.";
+                        diagnostics.push(pragma_warning(message, pragma.span));
+                        continue;
+                    }
+                }
+                control.changes.push((pragma.span.begin, current.clone()));
+                continue;
+            }
+            Some(TokenKind::Identifier("ignored")) => WarningState::Ignored,
+            Some(TokenKind::Identifier("warning")) => WarningState::Warning,
+            Some(TokenKind::Identifier("error")) => WarningState::Error,
+            Some(TokenKind::End) => {
+                let message =
+                    "missing [error|warning|ignored|push|pop] after '#pragma GCC diagnostic'.";
+                diagnostics.push(pragma_warning(message, pragma.span));
+                continue;
+            }
+            _ => {
+                let message =
+                    "expected [error|warning|ignored|push|pop] after '#pragma GCC diagnostic'.";
+                diagnostics.push(pragma_warning(message, pragma.span));
+                continue;
+            }
+        };
+        let option = match (tokens.next(), tokens.next()) {
+            (Some(TokenKind::Literal(Literal::String(option))), Some(TokenKind::End)) => option,
+            _ => {
+                let message = "missing option after '#pragma GCC diagnostic' kind.";
+                diagnostics.push(pragma_warning(message, pragma.span));
+                continue;
+            }
+        };
+        match option.strip_prefix("-W").and_then(named) {
+            Some(warnings) => current.set(warnings, state),
+            None => {
+                let message = "unknown option after '#pragma GCC diagnostic' kind.";
+                diagnostics.push(pragma_warning(message, pragma.span));
+                continue;
+            }
+        }
+        control.changes.push((pragma.span.begin, current.clone()));
+    }
+
+    (control, diagnostics)
+}
+
+fn pragma_warning(message: &str, span: Span) -> Diagnostic {
+    Diagnostic::named_warning(Warning::Pragmas, message.to_string(), span)
+}
+
+//the warning options in effect at location
+fn options_at(control: &DiagnosticControl, location: Location) -> &WarningOptions {
+    control
+        .changes
+        .iter()
+        .rev()
+        .find(|(begin, _)| *begin <= location)
+        .map_or(&control.options, |(_, options)| options)
+}
+
+//drops the disabled warnings with the notes which follow them, and makes errors of those
+//asked to be
+pub fn filter_diagnostics(
+    control: &DiagnosticControl,
+    diagnostics: Vec<Diagnostic>,
+) -> Vec<Diagnostic> {
+    let mut result = Vec::new();
+    let mut dropped = false;
+    for mut diagnostic in diagnostics {
+        match diagnostic.severity {
+            Severity::Error => dropped = false,
+            Severity::Warning => {
+                let options = options_at(control, diagnostic.span.begin);
+                match options.state(diagnostic.warning) {
+                    WarningState::Ignored => dropped = true,
+                    WarningState::Warning => dropped = false,
+                    WarningState::Error => {
+                        diagnostic.severity = Severity::Error;
+                        dropped = false;
+                    }
+                }
+            }
+            Severity::Note => {}
+        }
+        if !dropped {
+            result.push(diagnostic);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(arguments: &[&str]) -> WarningOptions {
+        let mut options = WarningOptions::default();
+        for argument in arguments {
+            options.apply(argument).unwrap();
+        }
+        options
+    }
+
+    fn state(arguments: &[&str], warning: Warning) -> WarningState {
+        options(arguments).state(Some(warning))
+    }
+
+    #[test]
+    fn defaults_and_groups() {
+        assert_eq!(state(&[], Warning::UnusedVariable), WarningState::Ignored);
+        assert_eq!(state(&[], Warning::ReturnType), WarningState::Warning);
+        assert_eq!(
+            state(&["all"], Warning::UnusedVariable),
+            WarningState::Warning
+        );
+        assert_eq!(state(&["all"], Warning::SignCompare), WarningState::Ignored);
+        assert_eq!(
+            state(&["extra"], Warning::SignCompare),
+            WarningState::Warning
+        );
+        assert_eq!(
+            state(&["all", "no-all"], Warning::Parentheses),
+            WarningState::Ignored
+        );
+    }
+
+    #[test]
+    fn explicit_options_override_groups() {
+        let off = &["no-unused-variable", "all"];
+        assert_eq!(state(off, Warning::UnusedVariable), WarningState::Ignored);
+        assert_eq!(state(off, Warning::Parentheses), WarningState::Warning);
+        let on = &["unused-variable", "no-all"];
+        assert_eq!(state(on, Warning::UnusedVariable), WarningState::Warning);
+        let later = &["all", "no-unused-variable"];
+        assert_eq!(state(later, Warning::UnusedVariable), WarningState::Ignored);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            state(&["all", "error"], Warning::UnusedVariable),
+            WarningState::Error
+        );
+        assert_eq!(options(&["error"]).state(None), WarningState::Error);
+        assert_eq!(options(&[]).state(None), WarningState::Warning);
+        let named = &["error=unused-variable"];
+        assert_eq!(state(named, Warning::UnusedVariable), WarningState::Error);
+        assert_eq!(state(named, Warning::ReturnType), WarningState::Warning);
+        let except = &["error", "no-error=return-type"];
+        assert_eq!(state(except, Warning::ReturnType), WarningState::Warning);
+        assert_eq!(state(except, Warning::ImplicitInt), WarningState::Error);
+        assert!(WarningOptions::default().apply("no-such-warning").is_err());
+    }
+
+    #[test]
+    fn pragmas_change_the_options_from_their_location() {
+        let source = "#pragma GCC diagnostic error \"-Wunused-variable\"\nint x;";
+        let (_, pragmas) = tokenize(source, Standard::C11).unwrap();
+        let (control, diagnostics) = diagnostic_control(&options(&["all"]), &pragmas);
+        assert!(diagnostics.is_empty());
+        let at = |line| Location { line, column: 1 };
+        let before = options_at(&control, at(0));
+        assert_eq!(
+            before.state(Some(Warning::UnusedVariable)),
+            WarningState::Warning
+        );
+        let after = options_at(&control, at(2));
+        assert_eq!(
+            after.state(Some(Warning::UnusedVariable)),
+            WarningState::Error
+        );
+    }
+}